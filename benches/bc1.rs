@@ -0,0 +1,155 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use rand::{Rng, SeedableRng};
+
+use jkl::{bc1, cluster_fit, encoder::EncodeEffort, math::Rgb32F, math::Vec3};
+
+mod common;
+
+fn random_blocks(count: usize) -> Vec<[[Rgb32F; 4]; 4]> {
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(42);
+
+    (0..count)
+        .map(|_| {
+            // Random gradient with some noise, similar to natural images.
+            let base = Vec3::new(rng.gen(), rng.gen(), rng.gen());
+            let step = Vec3::new(rng.gen(), rng.gen(), rng.gen()) * 0.1;
+
+            std::array::from_fn(|i| {
+                std::array::from_fn(|j| {
+                    let noise = Vec3::new(rng.gen(), rng.gen(), rng.gen()) * 0.02;
+                    let c = base + step * (i + j) as f32 + noise;
+                    Rgb32F::new(
+                        c.x().clamp(0.0, 1.0),
+                        c.y().clamp(0.0, 1.0),
+                        c.z().clamp(0.0, 1.0),
+                    )
+                })
+            })
+        })
+        .collect()
+}
+
+/// Solid and two-color blocks, similar to UI atlases.
+fn flat_blocks(count: usize) -> Vec<[[Rgb32F; 4]; 4]> {
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(42);
+
+    (0..count)
+        .map(|i| {
+            let a = Rgb32F::new(rng.gen(), rng.gen(), rng.gen());
+            let b = if i % 2 == 0 {
+                a
+            } else {
+                Rgb32F::new(rng.gen(), rng.gen(), rng.gen())
+            };
+            let mask: u16 = rng.gen();
+
+            std::array::from_fn(|i| {
+                std::array::from_fn(|j| if mask >> (i * 4 + j) & 1 == 0 { a } else { b })
+            })
+        })
+        .collect()
+}
+
+fn bench_fit(c: &mut Criterion) {
+    let points: Vec<[Vec3; 16]> = random_blocks(256)
+        .into_iter()
+        .map(|block| {
+            let mut points = [Vec3::ZERO; 16];
+            for i in 0..4 {
+                for j in 0..4 {
+                    points[i * 4 + j] = block[i][j].into();
+                }
+            }
+            points
+        })
+        .collect();
+
+    c.bench_function("cluster_fit::fit", |b| {
+        b.iter(|| {
+            for p in &points {
+                black_box(cluster_fit::fit(black_box(p), 16, 0.0));
+            }
+        })
+    });
+
+    // Used by default effort, has no SIMD implementation.
+    c.bench_function("cluster_fit::cluster_fit", |b| {
+        b.iter(|| {
+            for p in &points {
+                black_box(cluster_fit::cluster_fit::<Vec3, 4, 16>(
+                    black_box(p),
+                    |a, b| (a, b),
+                    |a, b| {
+                        let d = a - b;
+                        d.dot(d)
+                    },
+                ));
+            }
+        })
+    });
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let blocks = random_blocks(256);
+
+    for effort in [
+        EncodeEffort::Fast,
+        EncodeEffort::Default,
+        EncodeEffort::High,
+    ] {
+        c.bench_function(&format!("bc1::Block::encode {effort:?}"), |b| {
+            b.iter_batched(
+                || blocks.clone(),
+                |blocks| {
+                    for block in blocks {
+                        black_box(bc1::Block::encode_with_effort(block, effort));
+                    }
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
+fn bench_encode_image(c: &mut Criterion) {
+    let blocks = common::test_image_blocks(42);
+
+    for effort in [
+        EncodeEffort::Fast,
+        EncodeEffort::Default,
+        EncodeEffort::High,
+    ] {
+        c.bench_function(&format!("bc1::Block::encode image {effort:?}"), |b| {
+            b.iter(|| {
+                for block in &blocks {
+                    black_box(bc1::Block::encode_with_effort(*block, effort));
+                }
+            })
+        });
+    }
+}
+
+fn bench_encode_flat(c: &mut Criterion) {
+    let blocks = flat_blocks(256);
+
+    c.bench_function("bc1::Block::encode flat", |b| {
+        b.iter_batched(
+            || blocks.clone(),
+            |blocks| {
+                for block in blocks {
+                    black_box(bc1::Block::encode(block));
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_fit,
+    bench_encode,
+    bench_encode_image,
+    bench_encode_flat
+);
+criterion_main!(benches);
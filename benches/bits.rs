@@ -0,0 +1,48 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rand::{Rng, SeedableRng};
+
+use jkl::bits::{ReadBits, WriteBits};
+
+const BYTES: usize = 1 << 16;
+
+fn bench_bits(c: &mut Criterion) {
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(42);
+    let data: Vec<u8> = (0..BYTES).map(|_| rng.gen()).collect();
+
+    let mut group = c.benchmark_group("bits");
+    group.throughput(Throughput::Bytes(BYTES as u64));
+
+    for chunk in [1, 3, 7, 13, 32, 64] {
+        let chunks = BYTES * 8 / chunk;
+
+        group.bench_function(format!("WriteBits {chunk} bits"), |b| {
+            let mut output = Vec::with_capacity(BYTES);
+            b.iter(|| {
+                output.clear();
+                let mut write = WriteBits::new(&mut output);
+                for i in 0..chunks {
+                    write
+                        .write_bits(black_box(&data), i * chunk, chunk)
+                        .unwrap();
+                }
+                write.finish().unwrap();
+            })
+        });
+
+        group.bench_function(format!("ReadBits {chunk} bits"), |b| {
+            let mut buffer = [0u8; 8];
+            b.iter(|| {
+                let mut read = ReadBits::new(black_box(&data[..]));
+                for _ in 0..chunks {
+                    read.read_bits(&mut buffer, 0, chunk).unwrap();
+                }
+                black_box(buffer);
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_bits);
+criterion_main!(benches);
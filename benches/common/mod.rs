@@ -0,0 +1,71 @@
+//! Deterministic corpus shared by benchmarks.
+
+// Each benchmark uses only part of the corpus.
+#![allow(dead_code)]
+
+use rand::{Rng, SeedableRng};
+
+use jkl::math::{Rgb32F, Rgb8U};
+
+pub const IMAGE_SIZE: u32 = 256;
+
+/// Generates 256x256 image resembling a photo with sharp edges.
+///
+/// Same seed produces the same image on every machine.
+pub fn test_image(seed: u64) -> Vec<Rgb8U> {
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+
+    // Few random discs over smooth background.
+    let discs: Vec<(f32, f32, f32, [f32; 3])> = (0..12)
+        .map(|_| {
+            let x = rng.gen::<f32>() * IMAGE_SIZE as f32;
+            let y = rng.gen::<f32>() * IMAGE_SIZE as f32;
+            let r = rng.gen_range(8.0..64.0);
+            (x, y, r, [rng.gen(), rng.gen(), rng.gen()])
+        })
+        .collect();
+
+    let mut pixels = Vec::with_capacity((IMAGE_SIZE * IMAGE_SIZE) as usize);
+
+    for y in 0..IMAGE_SIZE {
+        for x in 0..IMAGE_SIZE {
+            let u = x as f32 / IMAGE_SIZE as f32;
+            let v = y as f32 / IMAGE_SIZE as f32;
+            let mut color = [u, v, 1.0 - u * v];
+
+            for &(cx, cy, r, disc) in &discs {
+                let dx = x as f32 - cx;
+                let dy = y as f32 - cy;
+                if dx * dx + dy * dy < r * r {
+                    color = disc;
+                }
+            }
+
+            let noise = rng.gen_range(-0.02..0.02);
+            let [r, g, b] = color.map(|c: f32| ((c + noise).clamp(0.0, 1.0) * 255.0) as u8);
+            pixels.push(Rgb8U::new(r, g, b));
+        }
+    }
+
+    pixels
+}
+
+/// Splits the test image into 4x4 blocks of colors.
+pub fn test_image_blocks(seed: u64) -> Vec<[[Rgb32F; 4]; 4]> {
+    let pixels = test_image(seed);
+    let blocks = IMAGE_SIZE / 4;
+
+    (0..blocks * blocks)
+        .map(|index| {
+            let bx = index % blocks;
+            let by = index / blocks;
+            std::array::from_fn(|i| {
+                std::array::from_fn(|j| {
+                    let x = bx * 4 + j as u32;
+                    let y = by * 4 + i as u32;
+                    pixels[(x + y * IMAGE_SIZE) as usize].into_f32()
+                })
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,44 @@
+use std::io::Cursor;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use jkl::{
+    encoder::{encode_bc1_rgb8, EncodeEffort},
+    jackal,
+};
+
+mod common;
+
+use common::{test_image, IMAGE_SIZE};
+
+fn bench_jackal(c: &mut Criterion) {
+    let pixels = test_image(42);
+    let (extent, blocks) = encode_bc1_rgb8(IMAGE_SIZE, IMAGE_SIZE, &pixels, EncodeEffort::Fast);
+    let extent = extent.bc_block_extent();
+
+    let mut compressed = Vec::new();
+    jackal::compress_bc1_texture(extent, &blocks, Cursor::new(&mut compressed)).unwrap();
+
+    let mut group = c.benchmark_group("jackal");
+    group.throughput(Throughput::Bytes(
+        jackal::blocks_as_bytes(&blocks).len() as u64
+    ));
+
+    group.bench_function("compress_bc1_texture", |b| {
+        let mut output = Vec::with_capacity(compressed.len());
+        b.iter(|| {
+            output.clear();
+            jackal::compress_bc1_texture(extent, black_box(&blocks), Cursor::new(&mut output))
+                .unwrap();
+        })
+    });
+
+    group.bench_function("decompress_bc1_texture", |b| {
+        b.iter(|| black_box(jackal::decompress_bc1_texture(Cursor::new(&compressed)).unwrap()))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_jackal);
+criterion_main!(benches);
@@ -1,3 +1,306 @@
-fn main() {
-    println!("Hello, world!");
+//! Command line tool to compress, decompress and inspect Jackal textures.
+
+use std::{
+    fmt,
+    fs::File,
+    io::{BufReader, BufWriter, Cursor, Seek, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use jkl::{
+    bc1,
+    encoder::{EncodeEffort, EncodeError, Encoder},
+    image::Image,
+    jackal::{self, CompressOptions, DecodeError, DecompressError, Format, MetaChunk},
+    math::Rgb8U,
+    Extent,
+};
+
+const USAGE: &str = "\
+Usage:
+    jkl compress <input.png|input.dds> <output.jkl> [--format bc1] [--quality 0..2]
+    jkl decompress <input.jkl> <output.png|output.dds>
+    jkl info <input.jkl>
+
+Exit codes:
+    0  success
+    1  I/O or image error
+    2  invalid arguments
+    3  invalid magic number
+    4  invalid header or extent
+    5  invalid data
+    6  checksum mismatch
+    7  unsupported feature";
+
+enum Command {
+    Compress {
+        input: PathBuf,
+        output: PathBuf,
+        effort: EncodeEffort,
+    },
+    Decompress {
+        input: PathBuf,
+        output: PathBuf,
+    },
+    Info {
+        input: PathBuf,
+    },
+}
+
+#[derive(Debug)]
+enum Error {
+    Usage(String),
+    Io(std::io::Error),
+    Image(image::ImageError),
+    Encode(EncodeError),
+    Decompress(DecompressError),
+}
+
+impl Error {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Error::Io(_) | Error::Image(_) => 1,
+            Error::Usage(_) => 2,
+            Error::Encode(EncodeError::Io(_)) => 1,
+            Error::Encode(_) => 2,
+            Error::Decompress(err) => match err.decode_error() {
+                None | Some(DecodeError::Cancelled) => 1,
+                Some(DecodeError::InvalidMagic) => 3,
+                Some(DecodeError::InvalidHeader | DecodeError::InvalidExtent) => 4,
+                Some(DecodeError::InvalidData { .. }) => 5,
+                Some(DecodeError::ChecksumMismatch | DecodeError::ContentHashMismatch) => 6,
+                Some(DecodeError::Unsupported) => 7,
+                // Errors added to the library later get the generic exit code.
+                Some(_) => 1,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Usage(msg) => write!(f, "{msg}\n\n{USAGE}"),
+            Error::Io(err) => write!(f, "I/O error: {err}"),
+            Error::Image(err) => write!(f, "image error: {err}"),
+            Error::Encode(err) => write!(f, "failed to encode: {err}"),
+            Error::Decompress(err) => write!(f, "failed to decompress: {err}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(err: image::ImageError) -> Self {
+        Error::Image(err)
+    }
+}
+
+impl From<EncodeError> for Error {
+    fn from(err: EncodeError) -> Self {
+        Error::Encode(err)
+    }
+}
+
+impl From<DecompressError> for Error {
+    fn from(err: DecompressError) -> Self {
+        Error::Decompress(err)
+    }
+}
+
+fn usage(msg: impl Into<String>) -> Error {
+    Error::Usage(msg.into())
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Command, Error> {
+    let command = args.next().ok_or_else(|| usage("missing command"))?;
+
+    let mut positional = Vec::new();
+    let mut effort = EncodeEffort::Default;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" if command == "compress" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| usage("missing value of --format"))?;
+                // BC1 is the only format the tool compresses into.
+                if !value.eq_ignore_ascii_case("bc1") {
+                    return Err(usage(format!("unsupported format '{value}'")));
+                }
+            }
+            "--quality" if command == "compress" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| usage("missing value of --quality"))?;
+                effort = match value.as_str() {
+                    "0" => EncodeEffort::Fast,
+                    "1" => EncodeEffort::Default,
+                    "2" => EncodeEffort::High,
+                    _ => return Err(usage(format!("quality must be 0, 1 or 2, got '{value}'"))),
+                };
+            }
+            _ if arg.starts_with("--") => return Err(usage(format!("unknown option '{arg}'"))),
+            _ => positional.push(PathBuf::from(arg)),
+        }
+    }
+
+    let expected = match command.as_str() {
+        "compress" | "decompress" => 2,
+        "info" => 1,
+        _ => return Err(usage(format!("unknown command '{command}'"))),
+    };
+
+    if positional.len() != expected {
+        return Err(usage(format!(
+            "'{command}' expects {expected} path(s), got {}",
+            positional.len()
+        )));
+    }
+
+    let mut positional = positional.into_iter();
+    let input = positional.next().unwrap();
+
+    Ok(match command.as_str() {
+        "compress" => Command::Compress {
+            input,
+            output: positional.next().unwrap(),
+            effort,
+        },
+        "decompress" => Command::Decompress {
+            input,
+            output: positional.next().unwrap(),
+        },
+        _ => Command::Info { input },
+    })
+}
+
+fn is_dds(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("dds"))
+}
+
+fn compress(input: &Path, output: &Path, effort: EncodeEffort) -> Result<(), Error> {
+    // Compressed in memory, so that invalid input never clobbers existing output.
+    let mut write = Cursor::new(Vec::new());
+
+    if is_dds(input) {
+        let (extent, format, bytes) = jackal::import_dds(BufReader::new(File::open(input)?))?;
+        if format != Format::BC1 {
+            return Err(Error::Decompress(DecodeError::Unsupported.into()));
+        }
+
+        let blocks = bytes
+            .chunks_exact(8)
+            .map(|chunk| bc1::Block::from_bytes(chunk.try_into().unwrap()))
+            .collect::<Vec<_>>();
+
+        jackal::compress_bc1_texture_with_chunks(
+            extent.bc_block_extent(),
+            &blocks,
+            CompressOptions::default(),
+            &[MetaChunk::pixel_size(extent.width(), extent.height())],
+            &mut write,
+        )?;
+    } else {
+        let rgb = image::open(input)?.into_rgb8();
+        let pixels = rgb.pixels().map(|p| Rgb8U::new(p[0], p[1], p[2])).collect();
+        let image = Image::from_pixels(rgb.width(), rgb.height(), pixels);
+
+        Encoder::new(Format::BC1)
+            .effort(effort)
+            .encode_image(&image, &mut write)?;
+    }
+
+    std::fs::write(output, write.into_inner())?;
+    Ok(())
+}
+
+fn decompress(input: &Path, output: &Path) -> Result<(), Error> {
+    let mut read = BufReader::new(File::open(input)?);
+
+    if is_dds(output) {
+        let header = jackal::read_header(&mut read)?;
+        let pixel_size = jackal::read_meta_chunks(&mut read, &header)?
+            .iter()
+            .find_map(MetaChunk::as_pixel_size);
+        read.rewind()?;
+
+        let (extent, blocks) = jackal::decompress_bc1_texture(read)?;
+
+        // Jackal stores extent in blocks, streams without pixel size cover the whole grid.
+        let [width, height, _] = extent.raw_size();
+        let (width, height) = pixel_size.unwrap_or((width * 4, height * 4));
+        let pixel_extent = Extent::D2 { width, height };
+
+        let bytes = jackal::blocks_as_bytes(&blocks);
+        let mut write = BufWriter::new(File::create(output)?);
+        jackal::export_dds(pixel_extent, Format::BC1, bytes, &mut write)?;
+        write.flush()?;
+    } else {
+        let (width, height, pixels) = jackal::decode_to_rgb8(read)?;
+        let bytes = pixels.iter().flat_map(|p| p.bytes()).collect::<Vec<u8>>();
+        let image =
+            image::RgbImage::from_raw(width, height, bytes).expect("pixels cover the image");
+        image.save(output)?;
+    }
+
+    Ok(())
+}
+
+fn info(input: &Path, mut out: impl Write) -> Result<(), Error> {
+    let mut read = BufReader::new(File::open(input)?);
+    let header = jackal::read_header(&mut read)?;
+    read.rewind()?;
+    let report = jackal::stat_file(&mut read)?;
+
+    let sb = header.super_block_size();
+    writeln!(out, "container:   {:?}", header.version())?;
+    writeln!(out, "format:      {:?}", header.format())?;
+    writeln!(out, "extent:      {:?} (blocks)", header.extent())?;
+    writeln!(out, "super-block: {}x{} blocks", sb.width, sb.height)?;
+    writeln!(out, "mip levels:  {}", header.levels().0)?;
+    writeln!(out, "checksums:   {}", header.checksums())?;
+    writeln!(out, "blocks:      {}", report.total_blocks())?;
+    writeln!(out, "compressed:  {} bytes", report.total_compressed_size())?;
+    write!(out, "{report}")?;
+
+    for sb in &report.super_blocks {
+        let [x, y, z] = sb.position;
+        writeln!(
+            out,
+            "  [{x}, {y}, {z}] {} blocks, {} bytes",
+            sb.blocks, sb.compressed_size
+        )?;
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<(), Error> {
+    match parse_args(std::env::args().skip(1))? {
+        Command::Compress {
+            input,
+            output,
+            effort,
+        } => compress(&input, &output, effort),
+        Command::Decompress { input, output } => decompress(&input, &output),
+        Command::Info { input } => info(&input, std::io::stdout().lock()),
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("jkl: {err}");
+            ExitCode::from(err.exit_code())
+        }
+    }
 }
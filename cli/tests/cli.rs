@@ -0,0 +1,129 @@
+use std::{
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+fn jkl(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_jkl"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Returns path in fresh temporary directory of the test.
+fn temp_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("jkl-cli-{}-{test}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write_fixture(path: &std::path::Path) {
+    let image = image::RgbImage::from_fn(10, 6, |x, y| {
+        image::Rgb([(x * 25) as u8, (y * 40) as u8, ((x + y) * 10) as u8])
+    });
+    image.save(path).unwrap();
+}
+
+#[test]
+fn compress_decompress_info() {
+    let dir = temp_dir("roundtrip");
+    let png = dir.join("input.png");
+    let jkl_path = dir.join("output.jkl");
+    let out_png = dir.join("decoded.png");
+    let out_dds = dir.join("decoded.dds");
+    let again = dir.join("again.jkl");
+    write_fixture(&png);
+
+    let output = jkl(&[
+        "compress",
+        png.to_str().unwrap(),
+        jkl_path.to_str().unwrap(),
+        "--quality",
+        "2",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+
+    let output = jkl(&["info", jkl_path.to_str().unwrap()]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("BC1"));
+    assert!(stdout.contains("blocks:      6"));
+    assert!(stdout.contains("total  "));
+
+    let output = jkl(&[
+        "decompress",
+        jkl_path.to_str().unwrap(),
+        out_png.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    let decoded = image::open(&out_png).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (10, 6));
+
+    let output = jkl(&[
+        "decompress",
+        jkl_path.to_str().unwrap(),
+        out_dds.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{output:?}");
+
+    // DDS blocks are stored without re-encoding.
+    let output = jkl(&[
+        "compress",
+        out_dds.to_str().unwrap(),
+        again.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{output:?}");
+
+    // Pixel size survives the trip through DDS.
+    let output = jkl(&[
+        "decompress",
+        again.to_str().unwrap(),
+        out_png.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    let decoded = image::open(&out_png).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (10, 6));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn errors() {
+    let dir = temp_dir("errors");
+    let garbage = dir.join("garbage.jkl");
+    std::fs::write(&garbage, b"not a jackal file at all, definitely not").unwrap();
+
+    let output = jkl(&[]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = jkl(&["compress", "a.png"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = jkl(&["compress", "a.png", "b.jkl", "--quality", "9"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = jkl(&["compress", "a.png", "b.jkl", "--format", "bc7"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    // Invalid input leaves existing output intact.
+    let existing = dir.join("existing.jkl");
+    std::fs::write(&existing, b"keep me").unwrap();
+    let output = jkl(&[
+        "compress",
+        garbage.to_str().unwrap(),
+        existing.to_str().unwrap(),
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(std::fs::read(&existing).unwrap(), b"keep me");
+
+    let output = jkl(&["info", garbage.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("invalid magic number"), "{stderr}");
+
+    let output = jkl(&["info", dir.join("missing.jkl").to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -8,7 +8,14 @@ use egui_snarl::{
     ui::{self, PinInfo, SnarlViewer, SnarlWidget},
     InPin, OutPin, Snarl,
 };
-use jkl::math::{Rgb32F, Rgb8U, Rgba8U, Vec3, Vec4};
+use jkl::{
+    filter,
+    image::Image,
+    interop::egui::image_to_color_image,
+    lzp::{self, LzpConfig},
+    math::{Rgb8U, Rgba8U},
+    stats::Histogram256,
+};
 use serde::{de, Deserialize};
 
 fn main() {
@@ -232,27 +239,12 @@ impl PixelType {
             PixelType::Rgba8U => "Rgba8U",
         }
     }
-
-    fn default(&self) -> PixelValue {
-        match self {
-            PixelType::Rgb8U => PixelValue::Rgb8U(Rgb8U::BLACK),
-            PixelType::Rgba8U => PixelValue::Rgba8U(Rgba8U::BLACK),
-        }
-    }
-
-    fn bit_size(&self) -> u64 {
-        match self {
-            PixelType::Rgb8U => 24,
-            PixelType::Rgba8U => 32,
-        }
-    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum JackalType {
     Null,
     Uint,
-    Pixel(PixelType),
     Image(PixelType),
 }
 
@@ -261,47 +253,12 @@ impl JackalType {
         match *self {
             JackalType::Null => Color32::PLACEHOLDER,
             JackalType::Uint => Color32::RED,
-            JackalType::Pixel(PixelType::Rgb8U) => Color32::BLUE,
-            JackalType::Pixel(PixelType::Rgba8U) => Color32::LIGHT_BLUE,
             JackalType::Image(PixelType::Rgb8U) => Color32::GREEN,
             JackalType::Image(PixelType::Rgba8U) => Color32::LIGHT_GREEN,
         }
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
-enum PixelValue {
-    Rgb8U(Rgb8U),
-    Rgba8U(Rgba8U),
-}
-
-impl PixelValue {
-    fn pixel_ty(&self) -> PixelType {
-        match *self {
-            PixelValue::Rgb8U(_) => PixelType::Rgb8U,
-            PixelValue::Rgba8U(_) => PixelType::Rgba8U,
-        }
-    }
-
-    fn ty(&self) -> JackalType {
-        JackalType::Pixel(self.pixel_ty())
-    }
-
-    fn hash(&self) -> u64 {
-        match self {
-            PixelValue::Rgb8U(pixel) => {
-                (pixel.r() as u64) * 43 + (pixel.g() as u64) * 31 + (pixel.b() as u64) * 29
-            }
-            PixelValue::Rgba8U(pixel) => {
-                (pixel.r() as u64) * 43
-                    + (pixel.g() as u64) * 31
-                    + (pixel.b() as u64) * 29
-                    + (pixel.a() as u64) * 83
-            }
-        }
-    }
-}
-
 #[derive(Clone)]
 enum ImageValue {
     Rgb8U(Image<Rgb8U>),
@@ -309,13 +266,6 @@ enum ImageValue {
 }
 
 impl ImageValue {
-    pub fn new(width: u32, height: u32, pixel_type: PixelType) -> Self {
-        match pixel_type {
-            PixelType::Rgb8U => ImageValue::Rgb8U(Image::new(width, height, Rgb8U::BLACK)),
-            PixelType::Rgba8U => ImageValue::Rgba8U(Image::new(width, height, Rgba8U::BLACK)),
-        }
-    }
-
     fn pixel_ty(&self) -> PixelType {
         match *self {
             ImageValue::Rgb8U(_) => PixelType::Rgb8U,
@@ -331,39 +281,18 @@ impl ImageValue {
         self.pixel_ty().name()
     }
 
-    fn width(&self) -> u32 {
-        match self {
-            ImageValue::Rgb8U(image) => image.width,
-            ImageValue::Rgba8U(image) => image.width,
-        }
-    }
-
-    fn height(&self) -> u32 {
-        match self {
-            ImageValue::Rgb8U(image) => image.height,
-            ImageValue::Rgba8U(image) => image.height,
-        }
-    }
-
     fn to_egui(&self) -> egui::ColorImage {
         match self {
-            ImageValue::Rgb8U(image) => image.to_egui(),
-            ImageValue::Rgba8U(image) => image.to_egui(),
+            ImageValue::Rgb8U(image) => image_to_color_image(image),
+            ImageValue::Rgba8U(image) => image_to_color_image(image),
         }
     }
 
-    fn get(&self, x: u32, y: u32) -> PixelValue {
+    /// Returns bytes of pixels in row-major order.
+    fn bytes(&self) -> Vec<u8> {
         match self {
-            ImageValue::Rgb8U(image) => PixelValue::Rgb8U(image.get(x, y)),
-            ImageValue::Rgba8U(image) => PixelValue::Rgba8U(image.get(x, y)),
-        }
-    }
-
-    fn set(&mut self, x: u32, y: u32, pixel: PixelValue) {
-        match (self, pixel) {
-            (ImageValue::Rgb8U(image), PixelValue::Rgb8U(pixel)) => image.set(x, y, pixel),
-            (ImageValue::Rgba8U(image), PixelValue::Rgba8U(pixel)) => image.set(x, y, pixel),
-            (_, _) => panic!("Wrong pixel type"),
+            ImageValue::Rgb8U(image) => image.pixels().iter().flat_map(|p| p.bytes()).collect(),
+            ImageValue::Rgba8U(image) => image.pixels().iter().flat_map(|p| p.bytes()).collect(),
         }
     }
 }
@@ -372,7 +301,6 @@ impl ImageValue {
 enum JackalValue {
     Null,
     Uint(u64),
-    Pixel(PixelValue),
     Image(ImageValue),
 }
 
@@ -381,7 +309,6 @@ impl JackalValue {
         match self {
             JackalValue::Null => JackalType::Null,
             JackalValue::Uint(_) => JackalType::Uint,
-            JackalValue::Pixel(pixel) => pixel.ty(),
             JackalValue::Image(image) => image.ty(),
         }
     }
@@ -735,39 +662,7 @@ impl FilterNode {
             _ => unreachable!(),
         };
 
-        let input = self.input.unwrap();
-        let output = self.filter.convert_type(input);
-
-        let output =
-            self.output
-                .get_or_insert(ImageValue::new(image.width(), image.height(), output));
-
-        for x in 0..image.width() {
-            for y in 0..image.height() {
-                let a = if x == 0 {
-                    input.default()
-                } else {
-                    image.get(x - 1, y)
-                };
-
-                let b = if y == 0 {
-                    input.default()
-                } else {
-                    image.get(x, y - 1)
-                };
-
-                let c = if x == 0 || y == 0 {
-                    input.default()
-                } else {
-                    image.get(x - 1, y - 1)
-                };
-
-                let t = image.get(x, y);
-                let r = self.filter.filter(a, b, c, t);
-
-                output.set(x, y, r);
-            }
-        }
+        self.output = Some(self.filter.apply(&image));
     }
 
     fn outputs(&self) -> usize {
@@ -858,173 +753,45 @@ impl Filter {
         }
     }
 
-    fn convert_type(&self, input: PixelType) -> PixelType {
-        match self {
-            Filter::StripAlpha => match input {
-                PixelType::Rgb8U => PixelType::Rgb8U,
-                PixelType::Rgba8U => PixelType::Rgb8U,
-            },
-            Filter::Paeth => match input {
-                PixelType::Rgb8U => PixelType::Rgb8U,
-                PixelType::Rgba8U => PixelType::Rgba8U,
-            },
-        }
-    }
-
-    fn filter(&self, a: PixelValue, b: PixelValue, c: PixelValue, t: PixelValue) -> PixelValue {
-        match self {
-            Filter::StripAlpha => match t {
-                PixelValue::Rgb8U(t) => PixelValue::Rgb8U(t),
-                PixelValue::Rgba8U(t) => PixelValue::Rgb8U(t.rgb()),
-            },
-            Filter::Paeth => match (a, b, c, t) {
-                (
-                    PixelValue::Rgb8U(a),
-                    PixelValue::Rgb8U(b),
-                    PixelValue::Rgb8U(c),
-                    PixelValue::Rgb8U(t),
-                ) => PixelValue::Rgb8U(paeth_rgb(a, b, c, t)),
-                (
-                    PixelValue::Rgba8U(a),
-                    PixelValue::Rgba8U(b),
-                    PixelValue::Rgba8U(c),
-                    PixelValue::Rgba8U(t),
-                ) => PixelValue::Rgba8U(paeth_rgba(a, b, c, t)),
-                _ => unreachable!(),
-            },
+    fn apply(&self, image: &ImageValue) -> ImageValue {
+        match (self, image) {
+            (Filter::StripAlpha, ImageValue::Rgb8U(image)) => ImageValue::Rgb8U(image.clone()),
+            (Filter::StripAlpha, ImageValue::Rgba8U(image)) => {
+                ImageValue::Rgb8U(filter::strip_alpha(image))
+            }
+            (Filter::Paeth, ImageValue::Rgb8U(image)) => {
+                ImageValue::Rgb8U(filter::paeth_forward(image))
+            }
+            (Filter::Paeth, ImageValue::Rgba8U(image)) => {
+                ImageValue::Rgba8U(filter::paeth_forward_rgba(image))
+            }
         }
     }
 }
 
-fn paeth_rgb(a: Rgb8U, b: Rgb8U, c: Rgb8U, t: Rgb8U) -> Rgb8U {
-    let af = Vec3::new(a.r() as f32, a.g() as f32, a.b() as f32);
-    let bf = Vec3::new(b.r() as f32, b.g() as f32, b.b() as f32);
-    let cf = Vec3::new(c.r() as f32, c.g() as f32, c.b() as f32);
-
-    let pf = af + bf - cf;
-
-    let ad = Vec3::dot(pf - af, pf - af);
-    let bd = Vec3::dot(pf - bf, pf - bf);
-    let cd = Vec3::dot(pf - cf, pf - cf);
-
-    let p = if ad <= bd && ad <= cd {
-        a
-    } else if bd <= cd {
-        b
-    } else {
-        c
-    };
-
-    let r = Rgb8U::wrapping_sub(t, p);
-    Rgb8U::wrapping_add(r, Rgb8U::new(128, 128, 128))
-}
-
-fn paeth_rgba(a: Rgba8U, b: Rgba8U, c: Rgba8U, t: Rgba8U) -> Rgba8U {
-    let af = Vec4::new(a.r() as f32, a.g() as f32, a.b() as f32, a.a() as f32);
-    let bf = Vec4::new(b.r() as f32, b.g() as f32, b.b() as f32, b.a() as f32);
-    let cf = Vec4::new(c.r() as f32, c.g() as f32, c.b() as f32, c.a() as f32);
-
-    let pf = af + bf - cf;
-
-    let ad = Vec4::dot(pf - af, pf - af);
-    let bd = Vec4::dot(pf - bf, pf - bf);
-    let cd = Vec4::dot(pf - cf, pf - cf);
-
-    let p = if ad <= bd && ad <= cd {
-        a
-    } else if bd <= cd {
-        b
-    } else {
-        c
-    };
-
-    let r = Rgba8U::wrapping_sub(t, p);
-    Rgba8U::wrapping_add(r, Rgba8U::new(128, 128, 128, 128))
-}
-
-fn rgb8u_to_egui(rgb: Rgb8U) -> egui::Color32 {
-    egui::Color32::from_rgb(rgb.r(), rgb.g(), rgb.b())
-}
-
-fn rgba8u_to_egui(rgba: Rgba8U) -> egui::Color32 {
-    egui::Color32::from_rgba_unmultiplied(rgba.r(), rgba.g(), rgba.b(), rgba.a())
-}
-
-fn rgb32f_to_egui(rgb: Rgb32F) -> egui::Color32 {
-    rgb8u_to_egui(Rgb8U::from_f32(rgb))
-}
-
 fn rgb_image_to_jkl(rgb: image::Rgb<u8>) -> Rgb8U {
     Rgb8U::new(rgb[0], rgb[1], rgb[2])
 }
 
 fn convert_image(image: image::DynamicImage) -> ImageValue {
     match image {
-        image::DynamicImage::ImageRgb8(rgb_image) => ImageValue::Rgb8U(Image {
-            width: rgb_image.width(),
-            height: rgb_image.height(),
-            pixels: rgb_image.pixels().map(|p| rgb_image_to_jkl(*p)).collect(),
-        }),
-        image::DynamicImage::ImageRgba8(rgba_image) => ImageValue::Rgba8U(Image {
-            width: rgba_image.width(),
-            height: rgba_image.height(),
-            pixels: rgba_image
+        image::DynamicImage::ImageRgb8(rgb_image) => ImageValue::Rgb8U(Image::from_pixels(
+            rgb_image.width(),
+            rgb_image.height(),
+            rgb_image.pixels().map(|p| rgb_image_to_jkl(*p)).collect(),
+        )),
+        image::DynamicImage::ImageRgba8(rgba_image) => ImageValue::Rgba8U(Image::from_pixels(
+            rgba_image.width(),
+            rgba_image.height(),
+            rgba_image
                 .pixels()
                 .map(|p| Rgba8U::new(p[0], p[1], p[2], p[3]))
                 .collect(),
-        }),
+        )),
         image => unimplemented!("Unsupported image format: {:?}", image.color()),
     }
 }
 
-#[derive(Clone)]
-struct Image<T> {
-    width: u32,
-    height: u32,
-    pixels: Vec<T>,
-}
-
-impl<T> Image<T>
-where
-    T: Copy,
-{
-    fn new(width: u32, height: u32, fill: T) -> Self {
-        Image {
-            width,
-            height,
-            pixels: vec![fill; (width * height) as usize],
-        }
-    }
-
-    fn get(&self, x: u32, y: u32) -> T {
-        self.pixels[(y * self.width + x) as usize]
-    }
-
-    fn set(&mut self, x: u32, y: u32, value: T) {
-        self.pixels[(y * self.width + x) as usize] = value;
-    }
-}
-
-impl Image<Rgb8U> {
-    fn to_egui(&self) -> egui::ColorImage {
-        egui::ColorImage {
-            size: [self.width as usize, self.height as usize],
-            source_size: egui::Vec2::new(self.width as f32, self.height as f32),
-            pixels: self.pixels.iter().copied().map(rgb8u_to_egui).collect(),
-        }
-    }
-}
-
-impl Image<Rgba8U> {
-    fn to_egui(&self) -> egui::ColorImage {
-        egui::ColorImage {
-            size: [self.width as usize, self.height as usize],
-            source_size: egui::Vec2::new(self.width as f32, self.height as f32),
-            pixels: self.pixels.iter().copied().map(rgba8u_to_egui).collect(),
-        }
-    }
-}
-
 struct ImageWidget {
     texture: Option<TextureHandle>,
     max_size: Vec2,
@@ -1059,10 +826,12 @@ impl ImageWidget {
     }
 }
 
-/// Calculates output size of LZP compression.
+/// Calculates output size of LZP compression of image bytes
+/// and compares it with order-0 entropy estimate.
 struct LZPCalculator {
     input: Option<PixelType>,
     lzp_size: u64,
+    entropy_size: u64,
 }
 
 impl LZPCalculator {
@@ -1070,6 +839,7 @@ impl LZPCalculator {
         LZPCalculator {
             input: None,
             lzp_size: 0,
+            entropy_size: 0,
         }
     }
 
@@ -1115,6 +885,7 @@ impl LZPCalculator {
         assert_eq!(input, 0);
 
         self.lzp_size = 0;
+        self.entropy_size = 0;
 
         let image = match value {
             JackalValue::Null => return,
@@ -1122,34 +893,12 @@ impl LZPCalculator {
             _ => unreachable!(),
         };
 
-        let input = self.input.unwrap();
-
-        let mut window = [input.default(); 3];
-        let mut cache = [input.default(); 0x100];
-
-        let mut total_bits = 0;
-
-        for x in 0..image.width() {
-            for y in 0..image.height() {
-                let t = image.get(x, y);
-
-                let h = window[0].hash() * 7 + window[1].hash() * 13 + window[2].hash() * 17;
-                let p = cache[(h & 0xFF) as usize];
-                cache[(h & 0xFF) as usize] = t;
-
-                if p == t {
-                    total_bits += 1;
-                } else {
-                    total_bits += 1;
-                    total_bits += input.bit_size();
-                }
-
-                window.rotate_left(1);
-                window[2] = t;
-            }
-        }
+        let bytes = image.bytes();
+        let stream = lzp::compress(LzpConfig::default(), &bytes);
+        self.lzp_size = stream.len() as u64 * 8;
 
-        self.lzp_size = total_bits;
+        let histogram = Histogram256::from_bytes(&bytes);
+        self.entropy_size = (histogram.estimated_size_bytes() * 8.0).ceil() as u64;
     }
 
     fn outputs(&self) -> usize {
@@ -1166,6 +915,10 @@ impl LZPCalculator {
         match self.input {
             Some(_) => {
                 ui.label(format!("LZP compressed output is {} bits", self.lzp_size));
+                ui.label(format!(
+                    "Order-0 entropy estimate is {} bits",
+                    self.entropy_size
+                ));
             }
             None => {
                 ui.colored_label(Color32::RED, "No image");
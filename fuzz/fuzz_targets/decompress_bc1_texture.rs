@@ -0,0 +1,17 @@
+#![no_main]
+
+use std::io::Cursor;
+
+use jkl::jackal::{self, DecodeLimits};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Default limits allow allocations above fuzzer memory limit.
+    let limits = DecodeLimits {
+        max_blocks: 1 << 16,
+        max_super_blocks: 1 << 12,
+    };
+
+    let _ = jackal::decompress_bc1_texture_with_limits(Cursor::new(data), &limits);
+    let _ = jackal::stat_file(Cursor::new(data));
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use jkl::jackal::{self, DecodeLimits};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Default limits allow allocations above fuzzer memory limit.
+    let limits = DecodeLimits {
+        max_blocks: 1 << 16,
+        max_super_blocks: 1 << 12,
+    };
+
+    let _ = jackal::import_dds_with_limits(data, &limits);
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use jkl::jackal;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = jackal::read_header(data);
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use jkl::jackal::{self, JackalBlock};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // First byte is the number of table entries, the rest is the table.
+    let Some((&count, table_bytes)) = data.split_first() else {
+        return;
+    };
+
+    let mut table = vec![JackalBlock::default(); count as usize];
+    let _ = jackal::read_jackal_blocks(&mut table, table_bytes);
+});
@@ -1,7 +1,8 @@
 use eframe::egui::{self, Color32, ColorImage, TextureHandle, TextureOptions, Vec2};
 use jkl::{
     bc1,
-    math::{Rgb32F, Yiq32F},
+    encoder::{self, EncodeEffort},
+    math::{Rgb32F, Rgb8U, Yiq32F},
 };
 
 fn main() {
@@ -22,7 +23,7 @@ struct Jackal {
     decompressed_image: Option<TextureHandle>,
     total_error: f32,
 
-    jkl_image: Vec<u8>,
+    jkl_report: Option<jkl::jackal::EvalReport>,
     jkl_image_blocks: Vec<bc1::Block>,
     decompressed_jkl_image: Option<TextureHandle>,
 }
@@ -37,7 +38,7 @@ impl Jackal {
             decompressed_image: None,
             total_error: 0.0,
 
-            jkl_image: Vec::new(),
+            jkl_report: None,
             jkl_image_blocks: Vec::new(),
             decompressed_jkl_image: None,
         }
@@ -56,7 +57,7 @@ impl eframe::App for Jackal {
                     self.original_image = None;
                     self.compressed_image.clear();
                     self.decompressed_image = None;
-                    self.jkl_image.clear();
+                    self.jkl_report = None;
                     self.jkl_image_blocks.clear();
                     self.decompressed_jkl_image = None;
                 }
@@ -79,9 +80,13 @@ impl eframe::App for Jackal {
                 let r = ui.button("Compress into Jackal");
                 compress_into_jackal = r.clicked();
 
-                if !self.jkl_image.is_empty() {
+                if let Some(report) = &self.jkl_report {
                     ui.label("JKL size:");
-                    ui.strong(format!("{:.4}", size_of_val(&self.jkl_image[..])));
+                    ui.strong(format!(
+                        "{} ({:.2} bytes per block)",
+                        report.compressed_size,
+                        report.bytes_per_block()
+                    ));
                 }
             });
         });
@@ -93,7 +98,7 @@ impl eframe::App for Jackal {
                     self.compressed_image.clear();
                     self.original_image = None;
                     self.decompressed_image = None;
-                    self.jkl_image.clear();
+                    self.jkl_report = None;
                     self.jkl_image_blocks.clear();
                     self.decompressed_jkl_image = None;
                 }
@@ -102,130 +107,49 @@ impl eframe::App for Jackal {
 
         if self.original_image.is_none() {
             if let Some(image) = &self.image {
-                self.original_image = Some(ctx.load_texture(
-                    "Original",
-                    ColorImage {
-                        size: [image.width() as usize, image.height() as usize],
-                        source_size: Vec2::new(image.width() as f32, image.height() as f32),
-                        pixels: image.pixels().map(|&p| rgb_image_to_egui(p)).collect(),
-                    },
-                    TextureOptions::NEAREST,
-                ));
+                self.original_image = Some(
+                    ctx.load_texture(
+                        "Original",
+                        ColorImage {
+                            size: [image.width() as usize, image.height() as usize],
+                            source_size: Vec2::new(image.width() as f32, image.height() as f32),
+                            pixels: image
+                                .pixels()
+                                .map(|p| Rgb8U::new(p[0], p[1], p[2]).into())
+                                .collect(),
+                        },
+                        TextureOptions::NEAREST,
+                    ),
+                );
             }
         }
 
         if self.compressed_image.is_empty() {
             self.total_error = 0.0;
             if let Some(image) = &self.image {
-                for y in (0..image.height()).step_by(4) {
-                    for x in (0..image.width()).step_by(4) {
-                        let block = [
-                            [
-                                rgb_image_to_texpak(*image.get_pixel(
-                                    (x + 0).min(image.width() - 1),
-                                    (y + 0).min(image.height() - 1),
-                                )),
-                                rgb_image_to_texpak(*image.get_pixel(
-                                    (x + 1).min(image.width() - 1),
-                                    (y + 0).min(image.height() - 1),
-                                )),
-                                rgb_image_to_texpak(*image.get_pixel(
-                                    (x + 2).min(image.width() - 1),
-                                    (y + 0).min(image.height() - 1),
-                                )),
-                                rgb_image_to_texpak(*image.get_pixel(
-                                    (x + 3).min(image.width() - 1),
-                                    (y + 0).min(image.height() - 1),
-                                )),
-                            ],
-                            [
-                                rgb_image_to_texpak(*image.get_pixel(
-                                    (x + 0).min(image.width() - 1),
-                                    (y + 1).min(image.height() - 1),
-                                )),
-                                rgb_image_to_texpak(*image.get_pixel(
-                                    (x + 1).min(image.width() - 1),
-                                    (y + 1).min(image.height() - 1),
-                                )),
-                                rgb_image_to_texpak(*image.get_pixel(
-                                    (x + 2).min(image.width() - 1),
-                                    (y + 1).min(image.height() - 1),
-                                )),
-                                rgb_image_to_texpak(*image.get_pixel(
-                                    (x + 3).min(image.width() - 1),
-                                    (y + 1).min(image.height() - 1),
-                                )),
-                            ],
-                            [
-                                rgb_image_to_texpak(*image.get_pixel(
-                                    (x + 0).min(image.width() - 1),
-                                    (y + 2).min(image.height() - 1),
-                                )),
-                                rgb_image_to_texpak(*image.get_pixel(
-                                    (x + 1).min(image.width() - 1),
-                                    (y + 2).min(image.height() - 1),
-                                )),
-                                rgb_image_to_texpak(*image.get_pixel(
-                                    (x + 2).min(image.width() - 1),
-                                    (y + 2).min(image.height() - 1),
-                                )),
-                                rgb_image_to_texpak(*image.get_pixel(
-                                    (x + 3).min(image.width() - 1),
-                                    (y + 2).min(image.height() - 1),
-                                )),
-                            ],
-                            [
-                                rgb_image_to_texpak(*image.get_pixel(
-                                    (x + 0).min(image.width() - 1),
-                                    (y + 3).min(image.height() - 1),
-                                )),
-                                rgb_image_to_texpak(*image.get_pixel(
-                                    (x + 1).min(image.width() - 1),
-                                    (y + 3).min(image.height() - 1),
-                                )),
-                                rgb_image_to_texpak(*image.get_pixel(
-                                    (x + 2).min(image.width() - 1),
-                                    (y + 3).min(image.height() - 1),
-                                )),
-                                rgb_image_to_texpak(*image.get_pixel(
-                                    (x + 3).min(image.width() - 1),
-                                    (y + 3).min(image.height() - 1),
-                                )),
-                            ],
-                        ];
-
-                        let block = bc1::Block::encode(block);
-                        self.compressed_image.push(block);
-                    }
-                }
+                let (_, blocks) = encoder::encode_bc1_image(
+                    image.width(),
+                    image.height(),
+                    |x, y| rgb_image_to_texpak(*image.get_pixel(x, y)),
+                    EncodeEffort::Default,
+                );
+                self.compressed_image = blocks;
             }
         }
 
         if self.decompressed_image.is_none() {
             if let Some(image) = &self.image {
                 assert!(!self.compressed_image.is_empty());
-                let mut blocks = self.compressed_image.iter();
-
-                let mut pixels = Vec::new();
-
-                for y in (0..image.height()).step_by(4) {
-                    let mut blocks_row = Vec::new();
-
-                    for _ in (0..image.width()).step_by(4) {
-                        let block = *blocks.next().unwrap();
-                        let block = bc1::Block::decode(block);
-                        blocks_row.push(block);
-                    }
-
-                    for i in 0..4.min(image.height() - y) {
-                        for (x, block) in blocks_row.iter().enumerate() {
-                            let x = x as u32 * 4;
-                            for j in 0..4.min(image.width() - x) {
-                                pixels.push(rgb_texpak_to_egui(block[i as usize][j as usize]));
-                            }
-                        }
-                    }
-                }
+                let pixels: Vec<Color32> = encoder::decode_bc1_image(
+                    jkl::Extent::D2 {
+                        width: image.width(),
+                        height: image.height(),
+                    },
+                    &self.compressed_image,
+                )
+                .into_iter()
+                .map(Color32::from)
+                .collect();
 
                 self.total_error = 0.0;
                 for y in 0..image.height() {
@@ -256,24 +180,24 @@ impl eframe::App for Jackal {
         }
 
         if compress_into_jackal {
-            self.jkl_image.clear();
+            self.jkl_report = None;
             self.jkl_image_blocks.clear();
             self.decompressed_jkl_image = None;
 
             if let Some(image) = &self.image {
                 let blocks = &self.compressed_image[..];
-                let mut output = Vec::new();
-                jkl::jackal::compress_bc1_texture(
+                let report = jkl::jackal::evaluate_bc1(
                     jkl::Extent::D2 {
-                        width: (image.width() + 3) / 4,
-                        height: (image.height() + 3) / 4,
-                    },
+                        width: image.width(),
+                        height: image.height(),
+                    }
+                    .bc_block_extent(),
                     blocks,
-                    std::io::Cursor::new(&mut output),
+                    Default::default(),
                 )
                 .unwrap();
 
-                self.jkl_image = output;
+                self.jkl_report = Some(report);
             }
         }
 
@@ -307,10 +231,6 @@ impl eframe::App for Jackal {
     }
 }
 
-fn rgb_image_to_egui(rgb: image::Rgb<u8>) -> egui::Color32 {
-    egui::Color32::from_rgb(rgb[0], rgb[1], rgb[2])
-}
-
 // fn rgb_image_to_raw(rgb: image::Rgb<u8>) -> [u8; 4] {
 //     [rgb[0], rgb[1], rgb[2], 255]
 // }
@@ -323,14 +243,6 @@ fn rgb_image_to_texpak(rgb: image::Rgb<u8>) -> Rgb32F {
     )
 }
 
-fn rgb_texpak_to_egui(rgb: Rgb32F) -> Color32 {
-    Color32::from_rgb(
-        (rgb.r() * 255.0) as u8,
-        (rgb.g() * 255.0) as u8,
-        (rgb.b() * 255.0) as u8,
-    )
-}
-
 fn rgb_egui_to_texpak(rgb: Color32) -> Rgb32F {
     Rgb32F::new(
         rgb.r() as f32 / 255.0,
@@ -338,20 +250,3 @@ fn rgb_egui_to_texpak(rgb: Color32) -> Rgb32F {
         rgb.b() as f32 / 255.0,
     )
 }
-
-// fn rgba_texpak_to_egui(rgb: Rgba32F) -> Color32 {
-//     Color32::from_rgba_premultiplied(
-//         (rgb.r() * 255.0) as u8,
-//         (rgb.g() * 255.0) as u8,
-//         (rgb.b() * 255.0) as u8,
-//         (rgb.a() * 255.0) as u8,
-//     )
-// }
-
-// fn rgb565_to_egui(rgb: Rgb565) -> Color32 {
-//     Color32::from_rgb(
-//         (rgb.r() as f32 * (255.0 / 31.0)) as u8,
-//         (rgb.g() as f32 * (255.0 / 63.0)) as u8,
-//         (rgb.b() as f32 * (255.0 / 31.0)) as u8,
-//     )
-// }
@@ -2,24 +2,30 @@
 //!
 
 use crate::{
-    cluster_fit::cluster_fit,
-    math::{Rgb32F, Rgb565, Rgba32F, Vec3, Yiq32F},
+    cluster_fit::{self, cluster_fit, fit, range_fit, ClusterFit, Fit},
+    encoder::EncodeEffort,
+    math::{Rgb32F, Rgb565, Rgb8U, Rgba32F, Vec3, Yiq32F},
 };
 
 /// A block of 4x4 texels compressed with BC1.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///
+/// Use [`Block::new`] and accessors instead of fields,
+/// fields are visible only inside the crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[repr(C)]
 pub struct Block {
-    pub color0: Rgb565,
-    pub color1: Rgb565,
-    pub texels: [u8; 4],
+    pub(crate) color0: Rgb565,
+    pub(crate) color1: Rgb565,
+
+    /// Row `y` of 2-bit palette indices, texel `x` in bits `2 * x`.
+    pub(crate) texels: [u8; 4],
 }
 
 impl Block {
     pub const BLACK: Block = Block {
         color0: Rgb565::WHITE,
         color1: Rgb565::BLACK,
-        texels: [0xFF; 4],
+        texels: [0x55; 4],
     };
 
     pub const WHITE: Block = Block {
@@ -34,6 +40,71 @@ impl Block {
         texels: [0xFF; 4],
     };
 
+    /// Creates block from endpoints and palette indices.
+    ///
+    /// `indices[y][x]` is the index of the texel at column `x` and row `y`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any index is greater than 3.
+    pub fn new(color0: Rgb565, color1: Rgb565, indices: [[u8; 4]; 4]) -> Self {
+        let mut block = Block {
+            color0,
+            color1,
+            texels: [0; 4],
+        };
+
+        for (y, row) in indices.iter().enumerate() {
+            for (x, &index) in row.iter().enumerate() {
+                block.set_texel_index(x, y, index);
+            }
+        }
+
+        block
+    }
+
+    #[inline(always)]
+    pub fn color0(&self) -> Rgb565 {
+        self.color0
+    }
+
+    #[inline(always)]
+    pub fn color1(&self) -> Rgb565 {
+        self.color1
+    }
+
+    /// Returns palette index of the texel at column `x` and row `y`.
+    #[inline(always)]
+    pub fn texel_index(&self, x: usize, y: usize) -> u8 {
+        (self.texels[y] >> (2 * x)) & 0b11
+    }
+
+    /// Sets palette index of the texel at column `x` and row `y`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if index is greater than 3.
+    #[inline(always)]
+    pub fn set_texel_index(&mut self, x: usize, y: usize, index: u8) {
+        assert!(index <= 3, "Texel index must be in range 0..=3");
+        self.texels[y] = self.texels[y] & !(0b11 << (2 * x)) | index << (2 * x);
+    }
+
+    /// Returns 32-bit index word of standard DXT1 layout.
+    /// Texel at column `x` and row `y` is in bits `2 * (x + 4 * y)`.
+    #[inline(always)]
+    pub fn indices_word(&self) -> u32 {
+        u32::from_le_bytes(self.texels)
+    }
+
+    /// Same as [`Block::bytes`].
+    #[deprecated(note = "use `Block::bytes` instead")]
+    pub fn to_bytes(&self) -> [u8; 8] {
+        self.bytes()
+    }
+
+    /// Returns block in standard DXT1 layout:
+    /// little-endian `color0` and `color1` followed by 32-bit index word.
     pub fn bytes(&self) -> [u8; 8] {
         let color0 = self.color0.bytes();
         let color1 = self.color1.bytes();
@@ -44,6 +115,7 @@ impl Block {
         ]
     }
 
+    /// Reads block from standard DXT1 layout.
     pub fn from_bytes(bytes: [u8; 8]) -> Block {
         let color0 = Rgb565::from_bytes([bytes[0], bytes[1]]);
         let color1 = Rgb565::from_bytes([bytes[2], bytes[3]]);
@@ -56,75 +128,72 @@ impl Block {
         }
     }
 
-    /// Decodes single BC1 block.
-    pub fn decode(self) -> [[Rgb32F; 4]; 4] {
-        // Decode endpoints.
-        let color0 = self.color0.into_f32();
-        let color1 = self.color1.into_f32();
+    /// Returns palette of the block with 8-bit channels.
+    ///
+    /// Endpoints are expanded by bit replication and intermediate colors
+    /// are computed as `(2 * a + b) / 3` and `(a + b) / 2` with truncation,
+    /// matching integer reference decoders of D3D and Vulkan.
+    /// Transparent entry of the palette is black.
+    pub fn palette_rgb8(self) -> [Rgb8U; 4] {
+        let color0 = self.color0.into_rgb8();
+        let color1 = self.color1.into_rgb8();
 
-        // Prepare local variables.
-        let mut colors = [[Rgb32F::BLACK; 4]; 4];
-        let texels = self.texels;
-
-        // Check mode and build palette.
-        let palette = if self.color0.bits() > self.color1.bits() {
+        if self.color0.bits() > self.color1.bits() {
             // Interpolate two intermediate colors.
             [
                 color0,
-                Rgb32F::lerp(color0, color1, 1.0 / 3.0),
-                Rgb32F::lerp(color0, color1, 2.0 / 3.0),
                 color1,
+                Rgb565::lerp_third(self.color0, self.color1),
+                Rgb565::lerp_third(self.color1, self.color0),
             ]
         } else {
             // Interpolate one intermediate color.
             [
                 color0,
-                Rgb32F::lerp(color0, color1, 1.0 / 2.0),
                 color1,
-                Rgb32F::BLACK,
+                Rgb565::lerp_half(self.color0, self.color1),
+                Rgb8U::BLACK,
             ]
-        };
+        }
+    }
 
-        // Decode texels.
-        for i in 0..4 {
-            for j in 0..4 {
-                let index = (texels[i] >> 2 * j) & 0b11;
+    /// Returns palette of the block.
+    ///
+    /// When `color0 > color1` palette has two interpolated colors,
+    /// otherwise it has one interpolated color and transparent black.
+    pub fn palette(self) -> [Rgba32F; 4] {
+        let palette = self.palette_rgb8().map(|c| c.into_f32().with_alpha(1.0));
 
-                colors[i][j] = palette[index as usize];
-            }
+        if self.color0.bits() > self.color1.bits() {
+            palette
+        } else {
+            [palette[0], palette[1], palette[2], Rgba32F::TRANSPARENT]
         }
+    }
 
-        colors
+    /// Decodes single BC1 block into 8-bit colors.
+    ///
+    /// Uses [`Block::palette_rgb8`], so output is bit-exact with integer reference decoders.
+    /// Transparent texels are decoded as black.
+    pub fn decode_rgb8(self) -> [[Rgb8U; 4]; 4] {
+        let palette = self.palette_rgb8();
+        std::array::from_fn(|y| std::array::from_fn(|x| palette[self.texel_index(x, y) as usize]))
     }
 
     /// Decodes single BC1 block.
-    pub fn decode_with_alpha(self) -> [[Rgba32F; 4]; 4] {
-        // Decode endpoints.
-        let color0 = self.color0.into_f32();
-        let color1 = self.color1.into_f32();
+    ///
+    /// Same as [`Block::decode_rgb8`] with channels scaled to `0.0..=1.0`.
+    /// Transparent texels are decoded as black.
+    pub fn decode(self) -> [[Rgb32F; 4]; 4] {
+        self.decode_rgb8().map(|row| row.map(Rgb8U::into_f32))
+    }
 
-        // Prepare local variables.
-        let mut colors = [[Rgba32F::TRANSPARENT; 4]; 4];
+    /// Decodes single BC1 block with punch-through alpha.
+    pub fn decode_rgba(self) -> [[Rgba32F; 4]; 4] {
+        let palette = self.palette();
         let texels = self.texels;
 
-        // Check mode and build palette.
-        let palette = if self.color0.bits() > self.color1.bits() {
-            // Interpolate two intermediate colors.
-            [
-                color0.with_alpha(1.0),
-                Rgb32F::lerp(color0, color1, 1.0 / 3.0).with_alpha(1.0),
-                Rgb32F::lerp(color0, color1, 2.0 / 3.0).with_alpha(1.0),
-                color1.with_alpha(1.0),
-            ]
-        } else {
-            // Interpolate one intermediate color.
-            [
-                color0.with_alpha(1.0),
-                Rgb32F::lerp(color0, color1, 1.0 / 2.0).with_alpha(1.0),
-                color1.with_alpha(1.0),
-                Rgba32F::TRANSPARENT,
-            ]
-        };
+        let mut colors = [[Rgba32F::TRANSPARENT; 4]; 4];
 
         // Decode texels.
         for i in 0..4 {
@@ -138,46 +207,322 @@ impl Block {
         colors
     }
 
+    /// Decodes single BC1 block with punch-through alpha.
+    pub fn decode_with_alpha(self) -> [[Rgba32F; 4]; 4] {
+        self.decode_rgba()
+    }
+
+    /// Encodes single BC1 block with default effort.
     pub fn encode(colors: [[Rgb32F; 4]; 4]) -> Self {
-        let mut samples = [Vec3::ZERO; 16];
+        Self::encode_with_effort(colors, EncodeEffort::Default)
+    }
+
+    /// Encodes single BC1 block spending specified effort on endpoints search.
+    ///
+    /// Benchmarked with `cargo bench --bench bc1`.
+    pub fn encode_with_effort(colors: [[Rgb32F; 4]; 4], effort: EncodeEffort) -> Self {
+        Self::encode_with_metric(colors, effort, ErrorMetric::default())
+    }
+
+    /// Encodes single BC1 block scoring endpoints with specified error metric.
+    pub fn encode_with_metric(
+        colors: [[Rgb32F; 4]; 4],
+        effort: EncodeEffort,
+        metric: ErrorMetric,
+    ) -> Self {
+        // Flat blocks, common in UI and atlases, need no endpoints search.
+        if let Some(block) = Self::encode_few_colors(&colors, metric) {
+            return block;
+        }
+
+        let mut rgb = [Vec3::ZERO; 16];
 
         for i in 0..4 {
             for j in 0..4 {
-                samples[i * 4 + j] = colors[i][j].into();
+                rgb[i * 4 + j] = colors[i][j].into();
             }
         }
 
-        let cf = cluster_fit::<Vec3, 4, 16>(
-            &samples,
-            |a: Vec3, b: Vec3| {
-                let mut a = Rgb565::from_f32(a.into());
-                let mut b = Rgb565::from_f32(b.into());
+        // Metric is squared distance after transform, convert samples once.
+        let samples = rgb.map(|c| metric.forward(c));
+        let remap = |a, b| metric.remap(a, b, remap_opaque);
+
+        let cf = match effort {
+            EncodeEffort::Fast => range_fit::<Vec3, 4, 16>(&samples, remap, distance_squared),
+            EncodeEffort::Default => cluster_fit::<Vec3, 4, 16>(&samples, remap, distance_squared),
+            EncodeEffort::High => {
+                let clustered =
+                    Self::from_cluster_fit(metric.inverse_fit(cluster_fit::<Vec3, 4, 16>(
+                        &samples,
+                        remap,
+                        distance_squared,
+                    )));
+                let refined = Self::from_fit(fit(&rgb, 16, 0.0));
+
+                let best = if refined.sse(&colors) < clustered.sse(&colors) {
+                    refined
+                } else {
+                    clustered
+                };
+                return best.canonicalize(&colors);
+            }
+        };
+
+        Self::from_cluster_fit(metric.inverse_fit(cf)).canonicalize(&colors)
+    }
+
+    /// Encodes single BC1 block of sRGB-encoded colors fitting endpoints in linear light.
+    ///
+    /// Endpoints are stored sRGB-encoded as usual,
+    /// texel indices are chosen by distance in linear space.
+    pub fn encode_linear(colors: [[Rgb32F; 4]; 4], effort: EncodeEffort) -> Self {
+        let linear = colors.map(|row| row.map(Rgb32F::srgb_to_linear));
+        let fitted = Self::encode_with_metric(linear, effort, ErrorMetric::Rgb);
+
+        let [a, b] = [fitted.color0, fitted.color1]
+            .map(|c| cluster_fit::quantize(c.into_f32().linear_to_srgb().into()));
+
+        if a == b {
+            let block = Block {
+                color0: a,
+                color1: a,
+                texels: [0x00; 4],
+            };
+            return block.canonicalize(&colors);
+        }
+
+        Self::with_nearest_indices(a, b, &colors, |c| c.srgb_to_linear().into())
+    }
+
+    /// Encodes single BC1 block, optionally dithering texel indices.
+    ///
+    /// Endpoints are the same as with [`Block::encode_with_effort`].
+    /// With `dither` quantization error of each texel is diffused to its neighbors
+    /// using serpentine Floyd-Steinberg restricted to the block.
+    pub fn encode_with_dither(
+        colors: [[Rgb32F; 4]; 4],
+        effort: EncodeEffort,
+        dither: bool,
+    ) -> Self {
+        let block = Self::encode_with_effort(colors, effort);
+
+        if dither {
+            block.dither_indices(&colors)
+        } else {
+            block
+        }
+    }
+
+    /// Reassigns texel indices with error diffusion, keeping endpoints.
+    fn dither_indices(self, colors: &[[Rgb32F; 4]; 4]) -> Self {
+        let palette = self.palette();
+
+        // Transparent entry is not a candidate for opaque texels.
+        let candidates = if self.is_three_color_mode() { 3 } else { 4 };
+
+        let mut errors = [[Vec3::ZERO; 4]; 4];
+        let mut texels = [0; 4];
 
-                if a == b {
-                    b = Rgb565::from_bits(!a.bits());
+        for i in 0..4 {
+            let reverse = i % 2 == 1;
+
+            for k in 0..4 {
+                let j = if reverse { 3 - k } else { k };
+
+                let target = Vec3::from(colors[i][j]) + errors[i][j];
+
+                let mut best = (0, f32::MAX);
+                for (idx, color) in palette[..candidates].iter().enumerate() {
+                    let d = target - Vec3::from(color.rgb());
+                    let e = d.dot(d);
+                    if e < best.1 {
+                        best = (idx, e);
+                    }
                 }
-                if a.bits() < b.bits() {
-                    core::mem::swap(&mut a, &mut b);
+
+                texels[i] |= (best.0 as u8) << (j * 2);
+
+                let error = target - Vec3::from(palette[best.0].rgb());
+
+                // Forward is the direction of the row traversal.
+                let forward = if reverse {
+                    j.checked_sub(1)
+                } else {
+                    Some(j + 1).filter(|&j| j < 4)
+                };
+                let backward = if reverse {
+                    Some(j + 1).filter(|&j| j < 4)
+                } else {
+                    j.checked_sub(1)
+                };
+
+                if let Some(f) = forward {
+                    errors[i][f] += error * (7.0 / 16.0);
+                }
+                if i < 3 {
+                    if let Some(b) = backward {
+                        errors[i + 1][b] += error * (3.0 / 16.0);
+                    }
+                    errors[i + 1][j] += error * (5.0 / 16.0);
+                    if let Some(f) = forward {
+                        errors[i + 1][f] += error * (1.0 / 16.0);
+                    }
                 }
+            }
+        }
 
-                (a.into_f32().into(), b.into_f32().into())
-            },
-            |a: Vec3, b: Vec3| {
-                let a = Rgb32F::from(a);
-                let b = Rgb32F::from(b);
+        Block { texels, ..self }
+    }
 
-                let a = Yiq32F::from_rgb(a);
-                let b = Yiq32F::from_rgb(b);
+    /// Returns `true` if block uses 3-color mode,
+    /// with one interpolated color and transparent black.
+    pub fn is_three_color_mode(&self) -> bool {
+        self.color0.bits() <= self.color1.bits()
+    }
 
-                Yiq32F::perceptual_distance(a, b)
-            },
-        );
+    /// Converts opaque block in 3-color mode into 4-color mode.
+    ///
+    /// Endpoints are swapped and texel indices remapped.
+    /// Texels that used the midpoint get the interpolated color closest to `colors`.
+    fn canonicalize(self, colors: &[[Rgb32F; 4]; 4]) -> Self {
+        if !self.is_three_color_mode() {
+            return self;
+        }
+
+        let index = |i: usize, j: usize| (self.texels[i] >> (j * 2)) & 0b11;
+
+        if (0..16).any(|k| index(k / 4, k % 4) == 3) {
+            // Block has transparent texels.
+            return self;
+        }
+
+        if self.color0 == self.color1 {
+            // Single color, pick another endpoint and use index of the color.
+            let color = self.color0;
+            return if color.bits() > 0 {
+                Block {
+                    color0: color,
+                    color1: Rgb565::from_bits(color.bits() - 1),
+                    texels: [0x00; 4],
+                }
+            } else {
+                Block {
+                    color0: Rgb565::from_bits(1),
+                    color1: color,
+                    texels: [0x55; 4],
+                }
+            };
+        }
+
+        let mut block = Block {
+            color0: self.color1,
+            color1: self.color0,
+            texels: [0; 4],
+        };
+
+        let palette = block.palette();
+
+        for (i, row) in colors.iter().enumerate() {
+            for (j, &color) in row.iter().enumerate() {
+                let idx = match index(i, j) {
+                    0 => 1,
+                    1 => 0,
+                    _ => {
+                        let e2 = Rgb32F::distance_squared(palette[2].rgb(), color);
+                        let e3 = Rgb32F::distance_squared(palette[3].rgb(), color);
+                        if e3 < e2 {
+                            3
+                        } else {
+                            2
+                        }
+                    }
+                };
+                block.texels[i] |= idx << (j * 2);
+            }
+        }
+
+        block
+    }
+
+    /// Encodes block with at most two distinct colors, using them as endpoints.
+    ///
+    /// Returns `None` if block has more than two distinct colors.
+    fn encode_few_colors(colors: &[[Rgb32F; 4]; 4], metric: ErrorMetric) -> Option<Self> {
+        let first = colors[0][0];
+        let mut second = None;
+
+        for &color in colors.iter().flatten() {
+            match second {
+                _ if color == first => {}
+                None => second = Some(color),
+                Some(second) if second == color => {}
+                Some(_) => return None,
+            }
+        }
+
+        let a = cluster_fit::quantize(first.into());
+        let b = second.map_or(a, |second| cluster_fit::quantize(second.into()));
+
+        if a == b {
+            // Solid color, or both colors quantize to the same endpoint.
+            let block = Block {
+                color0: a,
+                color1: a,
+                texels: [0x00; 4],
+            };
+            return Some(block.canonicalize(colors));
+        }
+
+        // Interpolated colors may be closer than endpoints after quantization.
+        Some(Self::with_nearest_indices(a, b, colors, |c| {
+            metric.forward(c.into())
+        }))
+    }
+
+    /// Builds 4-color block from distinct endpoints,
+    /// picking for each texel the palette entry nearest after mapping with `space`.
+    fn with_nearest_indices(
+        a: Rgb565,
+        b: Rgb565,
+        colors: &[[Rgb32F; 4]; 4],
+        space: impl Fn(Rgb32F) -> Vec3,
+    ) -> Self {
+        let (color0, color1) = if a.bits() > b.bits() { (a, b) } else { (b, a) };
+        let mut block = Block {
+            color0,
+            color1,
+            texels: [0x00; 4],
+        };
+
+        let palette = block.palette().map(|c| space(c.rgb()));
+
+        for (i, row) in colors.iter().enumerate() {
+            for (j, &color) in row.iter().enumerate() {
+                let color = space(color);
+                let mut best = (0, f32::INFINITY);
+                for (idx, &entry) in palette.iter().enumerate() {
+                    let error = distance_squared(entry, color);
+                    if error < best.1 {
+                        best = (idx as u8, error);
+                    }
+                }
+                block.texels[i] |= best.0 << (j * 2);
+            }
+        }
+
+        block
+    }
+
+    /// Builds 4-color block from result of cluster fit.
+    fn from_cluster_fit(cf: ClusterFit<Vec3, 16>) -> Self {
+        // Fit indices go from color0 to color1, palette has endpoints first.
+        const REMAP: [u8; 4] = [0, 2, 3, 1];
 
         let (color0, color1) = cf.endpoints;
         let mut texels = [0; 4];
         for i in 0..4 {
             for j in 0..4 {
-                let idx = (cf.indices[i * 4 + j] as u8) & 0b11;
+                let idx = REMAP[cf.indices[i * 4 + j]];
                 texels[i] |= idx << (j * 2);
             }
         }
@@ -189,9 +534,68 @@ impl Block {
         }
     }
 
-    /// Encode block into BC1 setting texels to TRANSPARENT if alpha <= threshold.
-    pub fn encode_with_alpha(colors: [[Rgba32F; 4]; 4], threshold: f32) -> Self {
+    /// Builds block from result of iterative fit.
+    fn from_fit(fit: Fit) -> Self {
+        // Lattice indices go from first endpoint to second, palette has endpoints first.
+        const REMAP: [u8; 4] = [0, 2, 3, 1];
+
+        let (a, b) = fit.endpoints;
+
+        if a == b {
+            // Single color, palette starts with it in either mode.
+            return Block {
+                color0: a,
+                color1: b,
+                texels: [0; 4],
+            };
+        }
+
+        let (color0, color1, indices) = if a.bits() > b.bits() {
+            (a, b, fit.indices)
+        } else {
+            (b, a, fit.indices.map(|idx| 3 - idx))
+        };
+
+        let mut texels = [0; 4];
+        for i in 0..4 {
+            for j in 0..4 {
+                texels[i] |= REMAP[indices[i * 4 + j] as usize] << (j * 2);
+            }
+        }
+
+        Block {
+            color0,
+            color1,
+            texels,
+        }
+    }
+
+    /// Returns sum of squared errors of decoded block against colors.
+    fn sse(self, colors: &[[Rgb32F; 4]; 4]) -> f32 {
+        let decoded = self.decode();
+
+        let mut sse = 0.0;
+        for i in 0..4 {
+            for j in 0..4 {
+                sse += Rgb32F::distance_squared(decoded[i][j], colors[i][j]);
+            }
+        }
+        sse
+    }
+
+    /// Encodes single BC1 block with punch-through alpha.
+    ///
+    /// Texels with alpha below `alpha_threshold` become transparent.
+    /// If there are any, block is encoded in 3-color mode
+    /// and endpoints are fitted to the remaining opaque texels.
+    pub fn encode_rgba(
+        colors: [[Rgba32F; 4]; 4],
+        alpha_threshold: f32,
+        effort: EncodeEffort,
+    ) -> Self {
+        let metric = ErrorMetric::default();
         let mut samples = [Vec3::ZERO; 16];
+        let mut positions = [0; 16];
 
         let mut num_samples = 0;
 
@@ -199,57 +603,47 @@ impl Block {
             for j in 0..4 {
                 let c = colors[i][j];
 
-                if c.a() <= threshold {
+                if c.a() < alpha_threshold {
                     continue;
                 }
 
-                samples[num_samples] = c.rgb().into();
+                samples[num_samples] = metric.forward(c.rgb().into());
+                positions[num_samples] = i * 4 + j;
                 num_samples += 1;
             }
         }
 
         match num_samples {
             0 => Self::TRANSPARENT,
-            1..16 => {
-                let cf = cluster_fit::<Vec3, 3, 16>(
-                    &samples[..num_samples],
-                    |a: Vec3, b: Vec3| {
-                        let mut a = Rgb565::from_f32(a.into());
-                        let mut b = Rgb565::from_f32(b.into());
-
-                        if a == b {
-                            b = Rgb565::from_bits(!a.bits());
-                        }
-                        if a.bits() > b.bits() {
-                            core::mem::swap(&mut a, &mut b);
-                        }
+            16 => Self::encode_with_effort(colors.map(|row| row.map(|c| c.rgb())), effort),
+            _ => {
+                let samples = &samples[..num_samples];
+                let remap = |a, b| metric.remap(a, b, remap_punch_through);
 
-                        (a.into_f32().into(), b.into_f32().into())
-                    },
-                    |a: Vec3, b: Vec3| {
-                        let a = Rgb32F::from(a);
-                        let b = Rgb32F::from(b);
+                let cf = match effort {
+                    EncodeEffort::Fast => {
+                        range_fit::<Vec3, 3, 16>(samples, remap, distance_squared)
+                    }
+                    // Iterative fit supports only 4-color mode.
+                    EncodeEffort::Default | EncodeEffort::High => {
+                        cluster_fit::<Vec3, 3, 16>(samples, remap, distance_squared)
+                    }
+                };
+                let cf = metric.inverse_fit(cf);
 
-                        let a = Yiq32F::from_rgb(a);
-                        let b = Yiq32F::from_rgb(b);
+                // Fit indices go from color0 to color1, palette has endpoints first.
+                const REMAP: [u8; 3] = [0, 2, 1];
 
-                        Yiq32F::perceptual_distance(a, b)
-                    },
-                );
+                // Transparent texels stay at index 3.
+                let mut texels = [0xFF; 4];
+                for (k, &pos) in positions[..num_samples].iter().enumerate() {
+                    let (i, j) = (pos / 4, pos % 4);
+                    let idx = REMAP[cf.indices[k]];
+                    texels[i] &= !(0b11 << (j * 2));
+                    texels[i] |= idx << (j * 2);
+                }
 
                 let (color0, color1) = cf.endpoints;
-                let mut texels = [0; 4];
-                for i in 0..4 {
-                    for j in 0..4 {
-                        let c = colors[i][j];
-                        if c.a() < threshold {
-                            texels[i] |= 0b11 << (j * 2);
-                        } else {
-                            let idx = (cf.indices[i * 4 + j] as u8) & 0b11;
-                            texels[i] |= idx << (j * 2);
-                        }
-                    }
-                }
 
                 Block {
                     color0: Rgb565::from_f32(Rgb32F::from(color0)),
@@ -257,49 +651,834 @@ impl Block {
                     texels,
                 }
             }
-            16 => {
-                let cf = cluster_fit::<Vec3, 4, 16>(
-                    &samples[..num_samples],
-                    |a: Vec3, b: Vec3| {
-                        let mut a = Rgb565::from_f32(a.into());
-                        let mut b = Rgb565::from_f32(b.into());
-
-                        if a == b {
-                            b = Rgb565::from_bits(!a.bits());
-                        }
-                        if a.bits() < b.bits() {
-                            core::mem::swap(&mut a, &mut b);
-                        }
+        }
+    }
 
-                        (a.into_f32().into(), b.into_f32().into())
-                    },
-                    |a: Vec3, b: Vec3| {
-                        let a = Rgb32F::from(a);
-                        let b = Rgb32F::from(b);
+    /// Encode block into BC1 setting texels to TRANSPARENT if alpha < threshold.
+    pub fn encode_with_alpha(colors: [[Rgba32F; 4]; 4], threshold: f32) -> Self {
+        Self::encode_rgba(colors, threshold, EncodeEffort::Default)
+    }
+}
 
-                        let a = Yiq32F::from_rgb(a);
-                        let b = Yiq32F::from_rgb(b);
+/// Quantizes endpoints so that `color0 > color1`, selecting 4-color mode.
+fn remap_opaque(a: Vec3, b: Vec3) -> (Vec3, Vec3) {
+    let mut a = Rgb565::from_f32(a.into());
+    let mut b = Rgb565::from_f32(b.into());
 
-                        Yiq32F::perceptual_distance(a, b)
-                    },
-                );
+    if a == b {
+        b = Rgb565::from_bits(!a.bits());
+    }
+    if a.bits() < b.bits() {
+        core::mem::swap(&mut a, &mut b);
+    }
 
-                let (color0, color1) = cf.endpoints;
-                let mut texels = [0; 4];
-                for i in 0..4 {
-                    for j in 0..4 {
-                        let idx = (cf.indices[i * 4 + j] as u8) & 0b11;
-                        texels[i] |= idx << (j * 2);
-                    }
-                }
+    (a.into_f32().into(), b.into_f32().into())
+}
 
-                Block {
-                    color0: Rgb565::from_f32(Rgb32F::from(color0)),
-                    color1: Rgb565::from_f32(Rgb32F::from(color1)),
-                    texels,
+/// Quantizes endpoints so that `color0 <= color1`, selecting 3-color mode.
+fn remap_punch_through(a: Vec3, b: Vec3) -> (Vec3, Vec3) {
+    let mut a = Rgb565::from_f32(a.into());
+    let mut b = Rgb565::from_f32(b.into());
+
+    if a.bits() > b.bits() {
+        core::mem::swap(&mut a, &mut b);
+    }
+
+    (a.into_f32().into(), b.into_f32().into())
+}
+
+fn distance_squared(a: Vec3, b: Vec3) -> f32 {
+    (a - b).length_squared()
+}
+
+/// Error metric used to select block endpoints and texel indices.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ErrorMetric {
+    /// Squared distance in RGB space.
+    Rgb,
+
+    /// Squared distance in YIQ space with chrominance weighted by 1/4.
+    #[default]
+    Yiq,
+
+    /// Squared distance in RGB space with per-channel weights.
+    /// Weights must be positive.
+    WeightedRgb { r: f32, g: f32, b: f32 },
+}
+
+impl ErrorMetric {
+    /// Maps RGB color into space where metric is plain squared distance.
+    ///
+    /// All supported metrics are linear, so palette interpolation commutes with the mapping.
+    fn forward(self, rgb: Vec3) -> Vec3 {
+        match self {
+            ErrorMetric::Rgb => rgb,
+            ErrorMetric::Yiq => {
+                let yiq = Yiq32F::from_rgb(rgb.into());
+                Vec3::new(yiq.y(), yiq.i() * 0.5, yiq.q() * 0.5)
+            }
+            ErrorMetric::WeightedRgb { r, g, b } => {
+                Vec3::new(rgb.x() * r.sqrt(), rgb.y() * g.sqrt(), rgb.z() * b.sqrt())
+            }
+        }
+    }
+
+    /// Maps color from metric space back into RGB.
+    fn inverse(self, v: Vec3) -> Vec3 {
+        match self {
+            ErrorMetric::Rgb => v,
+            ErrorMetric::Yiq => Yiq32F::new(v.x(), v.y() * 2.0, v.z() * 2.0)
+                .into_rgb()
+                .into(),
+            ErrorMetric::WeightedRgb { r, g, b } => {
+                Vec3::new(v.x() / r.sqrt(), v.y() / g.sqrt(), v.z() / b.sqrt())
+            }
+        }
+    }
+
+    /// Quantizes endpoints given in metric space.
+    fn remap(self, a: Vec3, b: Vec3, quantize: fn(Vec3, Vec3) -> (Vec3, Vec3)) -> (Vec3, Vec3) {
+        let (a, b) = quantize(self.snap(a), self.snap(b));
+        (self.forward(a), self.forward(b))
+    }
+
+    /// Maps quantized color from metric space back into RGB.
+    ///
+    /// Inverse mapping is not exact, so color is snapped to the nearest 5:6:5 color
+    /// to keep truncating quantization from stepping down a level.
+    fn snap(self, v: Vec3) -> Vec3 {
+        match self {
+            ErrorMetric::Rgb => v,
+            _ => cluster_fit::quantize(self.inverse(v)).into_f32().into(),
+        }
+    }
+
+    /// Converts endpoints of fit done in metric space back into RGB.
+    fn inverse_fit(self, mut cf: ClusterFit<Vec3, 16>) -> ClusterFit<Vec3, 16> {
+        let (a, b) = cf.endpoints;
+        cf.endpoints = (self.snap(a), self.snap(b));
+        cf
+    }
+}
+
+/// Error of one encoded block split between endpoints and indices.
+///
+/// All errors are squared RGB distances to the original colors.
+/// Maps are indexed as `[y][x]`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockAnalysis {
+    /// Error of the block as decoded.
+    pub actual_sse: f32,
+
+    /// Error with endpoints of the block and the best index of every texel.
+    pub optimal_indices_sse: f32,
+
+    /// Error with indices of the block and least-squares endpoints.
+    ///
+    /// Endpoints are neither quantized nor clamped,
+    /// so no block with these indices can do better.
+    pub optimal_endpoints_sse: f32,
+
+    pub actual_errors: [[f32; 4]; 4],
+    pub optimal_indices_errors: [[f32; 4]; 4],
+    pub optimal_endpoints_errors: [[f32; 4]; 4],
+}
+
+impl BlockAnalysis {
+    /// Part of the error that better indices would remove.
+    pub fn indices_gap(&self) -> f32 {
+        self.actual_sse - self.optimal_indices_sse
+    }
+
+    /// Part of the error that better endpoints would remove.
+    pub fn endpoints_gap(&self) -> f32 {
+        self.actual_sse - self.optimal_endpoints_sse
+    }
+}
+
+/// Analyzes error of `block` encoding `original` colors.
+///
+/// Transparent texels of 3-color blocks are treated as black, like [`Block::decode`] does.
+pub fn analyze_block(original: [[Rgb32F; 4]; 4], block: &Block) -> BlockAnalysis {
+    let decoded = block.decode();
+    let palette = block.palette_rgb8().map(Rgb8U::into_f32);
+
+    let actual_errors = std::array::from_fn(|y| {
+        std::array::from_fn(|x| Rgb32F::distance_squared(decoded[y][x], original[y][x]))
+    });
+
+    let optimal_indices_errors = original.map(|row| {
+        row.map(|color| {
+            palette
+                .iter()
+                .map(|&entry| Rgb32F::distance_squared(entry, color))
+                .fold(f32::INFINITY, f32::min)
+        })
+    });
+
+    // Weights of color0 and color1 for each palette index.
+    let weights: [(f32, f32); 4] = if block.is_three_color_mode() {
+        [(1.0, 0.0), (0.0, 1.0), (0.5, 0.5), (0.0, 0.0)]
+    } else {
+        [
+            (1.0, 0.0),
+            (0.0, 1.0),
+            (2.0 / 3.0, 1.0 / 3.0),
+            (1.0 / 3.0, 2.0 / 3.0),
+        ]
+    };
+    let weight = |x: usize, y: usize| weights[block.texel_index(x, y) as usize];
+
+    // Normal equations of least squares with two unknowns.
+    let (mut aa, mut ab, mut bb) = (0.0, 0.0, 0.0);
+    let (mut ax, mut bx) = (Vec3::ZERO, Vec3::ZERO);
+    let (mut sum, mut count) = (Vec3::ZERO, 0);
+    for (y, row) in original.iter().enumerate() {
+        for (x, &color) in row.iter().enumerate() {
+            let (a, b) = weight(x, y);
+            let color = Vec3::from(color);
+            aa += a * a;
+            ab += a * b;
+            bb += b * b;
+            ax += color * a;
+            bx += color * b;
+            if a + b > 0.0 {
+                sum += color;
+                count += 1;
+            }
+        }
+    }
+
+    let det = aa * bb - ab * ab;
+    let (e0, e1) = if det > 1e-6 {
+        ((ax * bb - bx * ab) / det, (bx * aa - ax * ab) / det)
+    } else {
+        // All weighted texels use the same index and weights sum to one,
+        // so the best is to reproduce their mean.
+        let mean = sum / count.max(1) as f32;
+        (mean, mean)
+    };
+
+    let optimal_endpoints_errors = std::array::from_fn(|y| {
+        std::array::from_fn(|x| {
+            let (a, b) = weight(x, y);
+            distance_squared(e0 * a + e1 * b, Vec3::from(original[y][x]))
+        })
+    });
+
+    let sum = |errors: &[[f32; 4]; 4]| errors.as_flattened().iter().sum();
+
+    BlockAnalysis {
+        actual_sse: sum(&actual_errors),
+        optimal_indices_sse: sum(&optimal_indices_errors),
+        optimal_endpoints_sse: sum(&optimal_endpoints_errors),
+        actual_errors,
+        optimal_indices_errors,
+        optimal_endpoints_errors,
+    }
+}
+
+/// Percentiles of per-block errors.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ErrorPercentiles {
+    pub p50: f32,
+    pub p90: f32,
+    pub p99: f32,
+    pub max: f32,
+}
+
+impl ErrorPercentiles {
+    fn from_values(mut values: Vec<f32>) -> Self {
+        if values.is_empty() {
+            return ErrorPercentiles::default();
+        }
+
+        values.sort_by(f32::total_cmp);
+        let at = |p: usize| values[(values.len() - 1) * p / 100];
+
+        ErrorPercentiles {
+            p50: at(50),
+            p90: at(90),
+            p99: at(99),
+            max: at(100),
+        }
+    }
+}
+
+/// Percentiles of [`BlockAnalysis`] errors over many blocks.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextureAnalysis {
+    pub actual_sse: ErrorPercentiles,
+    pub optimal_indices_sse: ErrorPercentiles,
+    pub optimal_endpoints_sse: ErrorPercentiles,
+    pub indices_gap: ErrorPercentiles,
+    pub endpoints_gap: ErrorPercentiles,
+}
+
+/// Analyzes every block with [`analyze_block`] and aggregates errors into percentiles.
+///
+/// # Panics
+///
+/// Panics if `originals` and `blocks` have different lengths.
+pub fn analyze_texture(originals: &[[[Rgb32F; 4]; 4]], blocks: &[Block]) -> TextureAnalysis {
+    assert_eq!(originals.len(), blocks.len());
+
+    let analyses: Vec<BlockAnalysis> = originals
+        .iter()
+        .zip(blocks)
+        .map(|(&original, block)| analyze_block(original, block))
+        .collect();
+
+    let percentiles = |f: fn(&BlockAnalysis) -> f32| {
+        ErrorPercentiles::from_values(analyses.iter().map(f).collect())
+    };
+
+    TextureAnalysis {
+        actual_sse: percentiles(|a| a.actual_sse),
+        optimal_indices_sse: percentiles(|a| a.optimal_indices_sse),
+        optimal_endpoints_sse: percentiles(|a| a.optimal_endpoints_sse),
+        indices_gap: percentiles(BlockAnalysis::indices_gap),
+        endpoints_gap: percentiles(BlockAnalysis::endpoints_gap),
+    }
+}
+
+// Block is `repr(C)` without padding and any bit pattern is a valid block.
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for Block {}
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for Block {}
+
+#[test]
+fn test_dxt1_known_blocks() {
+    // Solid red: color0 = 0xF800, color1 = 0, all indices 0.
+    let red = Block::from_bytes([0x00, 0xF8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(red.color0, Rgb565::new(31, 0, 0));
+    assert_eq!(red.decode(), [[Rgb32F::new(1.0, 0.0, 0.0); 4]; 4]);
+
+    // White and black stripes: 4-color mode, rows alternate indices 0 and 1.
+    let stripes = Block::from_bytes([0xFF, 0xFF, 0x00, 0x00, 0x00, 0x55, 0x00, 0x55]);
+    let decoded = stripes.decode();
+    assert_eq!(decoded[0], [Rgb32F::WHITE; 4]);
+    assert_eq!(decoded[1], [Rgb32F::BLACK; 4]);
+
+    // First texel transparent in 3-color mode: color0 <= color1, index 3.
+    let punch = Block::from_bytes([0x00, 0x00, 0x1F, 0x00, 0x03, 0x00, 0x00, 0x00]);
+    let decoded = punch.decode_rgba();
+    assert_eq!(decoded[0][0], Rgba32F::TRANSPARENT);
+    assert_eq!(decoded[0][1], Rgba32F::new(0.0, 0.0, 0.0, 1.0));
+    assert_eq!(decoded[3][3], Rgba32F::new(0.0, 0.0, 0.0, 1.0));
+
+    for block in [red, stripes, punch] {
+        assert_eq!(Block::from_bytes(block.bytes()), block);
+    }
+    assert_eq!(
+        stripes.bytes(),
+        [0xFF, 0xFF, 0x00, 0x00, 0x00, 0x55, 0x00, 0x55]
+    );
+}
+
+#[test]
+fn test_decode_rgb8_reference() {
+    // color0 = (20, 40, 10) and color1 = (5, 10, 25), first row uses all indices.
+    let four = Block::from_bytes([0x0A, 0xA5, 0x59, 0x29, 0xE4, 0x00, 0x00, 0x00]);
+    let expected = [
+        Rgb8U::new(165, 162, 82),
+        Rgb8U::new(41, 40, 206),
+        Rgb8U::new(123, 121, 123),
+        Rgb8U::new(82, 80, 164),
+    ];
+    assert_eq!(four.palette_rgb8(), expected);
+    assert_eq!(four.decode_rgb8()[0], expected);
+    assert_eq!(four.decode_rgb8()[1], [expected[0]; 4]);
+
+    // Same endpoints swapped select 3-color mode.
+    let three = Block::from_bytes([0x59, 0x29, 0x0A, 0xA5, 0xE4, 0x00, 0x00, 0x00]);
+    assert_eq!(
+        three.decode_rgb8()[0],
+        [
+            Rgb8U::new(41, 40, 206),
+            Rgb8U::new(165, 162, 82),
+            Rgb8U::new(103, 101, 144),
+            Rgb8U::BLACK,
+        ]
+    );
+
+    for block in [four, three] {
+        let float = block.decode_rgb8().map(|row| row.map(Rgb8U::into_f32));
+        assert_eq!(block.decode(), float);
+    }
+}
+
+#[test]
+fn test_palette_order() {
+    // Standard DXT1 order: endpoints first, then interpolated colors.
+    let color0 = Rgb565::new(31, 0, 0);
+    let color1 = Rgb565::new(0, 0, 31);
+
+    let four = Block::new(color0, color1, [[0, 1, 2, 3]; 4]);
+    assert_eq!(
+        four.palette_rgb8(),
+        [
+            Rgb8U::new(255, 0, 0),
+            Rgb8U::new(0, 0, 255),
+            Rgb8U::new(170, 0, 85),
+            Rgb8U::new(85, 0, 170),
+        ]
+    );
+    assert_eq!(
+        four.bytes(),
+        [0x00, 0xF8, 0x1F, 0x00, 0xE4, 0xE4, 0xE4, 0xE4]
+    );
+
+    let three = Block::new(color1, color0, [[0, 1, 2, 3]; 4]);
+    assert_eq!(
+        three.palette_rgb8(),
+        [
+            Rgb8U::new(0, 0, 255),
+            Rgb8U::new(255, 0, 0),
+            Rgb8U::new(127, 0, 127),
+            Rgb8U::BLACK,
+        ]
+    );
+    assert_eq!(three.palette()[3], Rgba32F::TRANSPARENT);
+
+    // Constants select index 1 for black and index 0 for white.
+    assert_eq!(
+        Block::BLACK.bytes(),
+        [0xFF, 0xFF, 0x00, 0x00, 0x55, 0x55, 0x55, 0x55]
+    );
+    assert_eq!(
+        Block::WHITE.bytes(),
+        [0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
+    );
+    assert_eq!(
+        Block::TRANSPARENT.bytes(),
+        [0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF]
+    );
+
+    // Encoder emits endpoint texels as indices 0 and 1.
+    let split = Block::encode(std::array::from_fn(|_| {
+        [Rgb32F::WHITE, Rgb32F::WHITE, Rgb32F::BLACK, Rgb32F::BLACK]
+    }));
+    assert_eq!(
+        split.bytes(),
+        [0xFF, 0xFF, 0x00, 0x00, 0x50, 0x50, 0x50, 0x50]
+    );
+}
+
+#[test]
+fn test_constants() {
+    assert_eq!(Block::BLACK.decode(), [[Rgb32F::BLACK; 4]; 4]);
+    assert_eq!(Block::WHITE.decode(), [[Rgb32F::WHITE; 4]; 4]);
+    assert_eq!(
+        Block::TRANSPARENT.decode_rgba(),
+        [[Rgba32F::TRANSPARENT; 4]; 4]
+    );
+}
+
+#[test]
+fn test_encode_rgba_opaque() {
+    let red = Rgba32F::new(1.0, 0.0, 0.0, 1.0);
+    let blue = Rgba32F::new(0.0, 0.0, 1.0, 1.0);
+
+    let mut colors = [[red; 4]; 4];
+    colors[1] = [blue; 4];
+
+    for effort in [
+        EncodeEffort::Fast,
+        EncodeEffort::Default,
+        EncodeEffort::High,
+    ] {
+        let block = Block::encode_rgba(colors, 0.5, effort);
+
+        // Opaque blocks use 4-color mode.
+        assert!(block.color0.bits() > block.color1.bits());
+        assert_eq!(block.decode_rgba(), colors);
+    }
+}
+
+#[test]
+fn test_encode_rgba_transparent() {
+    let colors = [[Rgba32F::new(1.0, 1.0, 1.0, 0.25); 4]; 4];
+
+    for effort in [
+        EncodeEffort::Fast,
+        EncodeEffort::Default,
+        EncodeEffort::High,
+    ] {
+        let block = Block::encode_rgba(colors, 0.5, effort);
+        assert_eq!(block.decode_rgba(), [[Rgba32F::TRANSPARENT; 4]; 4]);
+    }
+}
+
+#[test]
+fn test_encode_rgba_mixed() {
+    let red = Rgba32F::new(1.0, 0.0, 0.0, 1.0);
+    let green = Rgba32F::new(0.0, 1.0, 0.0, 0.75);
+    let clear = Rgba32F::new(0.0, 1.0, 0.0, 0.0);
+
+    let colors = [
+        [red, clear, red, green],
+        [clear, clear, green, green],
+        [red, red, clear, clear],
+        [green, clear, red, clear],
+    ];
+
+    for effort in [
+        EncodeEffort::Fast,
+        EncodeEffort::Default,
+        EncodeEffort::High,
+    ] {
+        let block = Block::encode_rgba(colors, 0.5, effort);
+
+        // Transparent texels require 3-color mode.
+        assert!(block.color0.bits() <= block.color1.bits());
+
+        let decoded = block.decode_rgba();
+        for i in 0..4 {
+            for j in 0..4 {
+                let expected = if colors[i][j].a() < 0.5 {
+                    Rgba32F::TRANSPARENT
+                } else {
+                    colors[i][j].rgb().with_alpha(1.0)
+                };
+                assert_eq!(decoded[i][j], expected, "texel {i},{j}");
+            }
+        }
+    }
+}
+
+#[test]
+fn test_canonical_endpoints() {
+    let red = Rgb565::from_f32(Rgb32F::new(1.0, 0.0, 0.0));
+    let blue = Rgb565::from_f32(Rgb32F::new(0.0, 0.0, 1.0));
+    assert!(blue.bits() < red.bits());
+
+    // Reversed endpoints of two-color block.
+    let reversed = Block {
+        color0: blue,
+        color1: red,
+        texels: [0x00, 0x55, 0x11, 0x44],
+    };
+    assert!(reversed.is_three_color_mode());
+
+    let colors = reversed.decode();
+    let block = reversed.canonicalize(&colors);
+    assert!(!block.is_three_color_mode());
+    assert_eq!(block.decode(), colors);
+
+    // Equal endpoints, including black which has no smaller color.
+    for color in [red, blue, Rgb565::BLACK, Rgb565::WHITE] {
+        let reversed = Block {
+            color0: color,
+            color1: color,
+            texels: [0x24, 0x12, 0x09, 0x00],
+        };
+
+        let colors = reversed.decode();
+        let block = reversed.canonicalize(&colors);
+        assert!(!block.is_three_color_mode());
+        assert_eq!(block.decode(), colors);
+    }
+
+    // Transparent texels keep 3-color mode.
+    let transparent = Block {
+        color0: blue,
+        color1: red,
+        texels: [0xC0, 0, 0, 0],
+    };
+    assert_eq!(transparent.canonicalize(&transparent.decode()), transparent);
+
+    // Solid colors are encoded in 4-color mode at every effort.
+    for color in [Rgb32F::BLACK, Rgb32F::WHITE, Rgb32F::new(0.2, 0.5, 0.9)] {
+        for effort in [
+            EncodeEffort::Fast,
+            EncodeEffort::Default,
+            EncodeEffort::High,
+        ] {
+            let block = Block::encode_with_effort([[color; 4]; 4], effort);
+            assert!(!block.is_three_color_mode(), "{color:?} {effort:?}");
+        }
+    }
+}
+
+#[test]
+fn test_dither_gradient() {
+    // Horizontal gradient 16 pixels wide, split into 4 blocks.
+    let gradient = |x: usize| Rgb32F::new(0.3 + x as f32 * 0.01, 0.5, 0.2 + x as f32 * 0.004);
+
+    // Sum over columns of absolute difference between mean decoded color and original.
+    let column_error = |dither: bool| {
+        let mut error = 0.0;
+        for b in 0..4 {
+            let colors = [std::array::from_fn(|j| gradient(b * 4 + j)); 4];
+            let block = Block::encode_with_dither(colors, EncodeEffort::Default, dither);
+            let decoded = block.decode();
+
+            for j in 0..4 {
+                let mut mean = Vec3::ZERO;
+                for row in &decoded {
+                    mean += Vec3::from(row[j]) / 4.0;
                 }
+                let d = mean - Vec3::from(colors[0][j]);
+                error += d.dot(d).sqrt();
+            }
+        }
+        error
+    };
+
+    let plain = column_error(false);
+    let dithered = column_error(true);
+    assert!(dithered < plain, "dithered {dithered} >= plain {plain}");
+}
+
+#[test]
+fn test_high_effort_not_worse() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(7);
+
+    for _ in 0..50 {
+        let colors: [[Rgb32F; 4]; 4] = std::array::from_fn(|_| {
+            std::array::from_fn(|_| Rgb32F::new(rng.gen(), rng.gen(), rng.gen()))
+        });
+
+        let default = Block::encode_with_effort(colors, EncodeEffort::Default);
+        let high = Block::encode_with_effort(colors, EncodeEffort::High);
+
+        assert!(high.sse(&colors) <= default.sse(&colors));
+    }
+}
+
+#[test]
+fn test_error_metric() {
+    let green_a = Rgb32F::new(0.1, 0.6, 0.1);
+    let green_b = Rgb32F::new(0.15, 0.7, 0.05);
+    let red = Rgb32F::new(0.9, 0.1, 0.1);
+
+    let mut colors = [[green_a; 4]; 4];
+    for (i, row) in colors.iter_mut().enumerate() {
+        for (j, color) in row.iter_mut().enumerate() {
+            if (i + j) % 2 == 1 {
+                *color = green_b;
+            }
+        }
+    }
+    colors[3][3] = red;
+
+    let rgb = Block::encode_with_metric(colors, EncodeEffort::Default, ErrorMetric::Rgb);
+    let yiq = Block::encode_with_metric(colors, EncodeEffort::Default, ErrorMetric::Yiq);
+    assert_ne!((rgb.color0, rgb.color1), (yiq.color0, yiq.color1));
+
+    // Both blocks still decode close to the source.
+    assert!(rgb.sse(&colors) < 0.1);
+    assert!(yiq.sse(&colors) < 0.1);
+}
+
+#[test]
+fn test_few_colors() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(11);
+    let exact = |bits: u16| Rgb565::from_bits(bits).into_rgb8().into_f32();
+
+    for _ in 0..100 {
+        let a = exact(rng.gen());
+        let b = exact(rng.gen());
+        let mask: u16 = rng.gen();
+
+        let colors: [[Rgb32F; 4]; 4] = std::array::from_fn(|i| {
+            std::array::from_fn(|j| if mask >> (i * 4 + j) & 1 == 0 { a } else { b })
+        });
+
+        for effort in [
+            EncodeEffort::Fast,
+            EncodeEffort::Default,
+            EncodeEffort::High,
+        ] {
+            let block = Block::encode_with_effort(colors, effort);
+            assert_eq!(block.decode(), colors, "{a:?} {b:?} {effort:?}");
+            assert!(!block.is_three_color_mode());
+        }
+
+        let solid = Block::encode([[a; 4]; 4]);
+        assert_eq!(solid.decode(), [[a; 4]; 4]);
+        assert!(!solid.is_three_color_mode());
+    }
+
+    // Distinct colors that quantize to the same endpoint.
+    let a = Rgb32F::new(0.5, 0.5, 0.5);
+    let b = Rgb32F::new(0.501, 0.5, 0.5);
+    let colors = [[a, b, a, b]; 4];
+    let block = Block::encode(colors);
+    assert!(!block.is_three_color_mode());
+    let decoded = block.decode();
+    assert!(decoded.iter().flatten().all(|&c| c == decoded[0][0]));
+    assert!(Rgb32F::distance(decoded[0][0], a) < 1.0 / 31.0);
+
+    // Three colors take the regular path.
+    let c = Rgb32F::new(0.1, 0.9, 0.3);
+    assert!(Block::encode_few_colors(&[[a, b, c, a]; 4], ErrorMetric::default()).is_none());
+}
+
+#[test]
+fn test_encode_linear() {
+    let black = Rgb32F::BLACK;
+    let white = Rgb32F::WHITE;
+
+    // Fine checkerboard, perceived as linear mid-gray from afar.
+    let colors = std::array::from_fn(|i| {
+        std::array::from_fn(|j| if (i + j) % 2 == 0 { black } else { white })
+    });
+    let block = Block::encode_linear(colors, EncodeEffort::Default);
+    assert_eq!(block.decode(), colors);
+
+    let colors: [[Rgb32F; 4]; 4] = std::array::from_fn(|i| {
+        std::array::from_fn(|j| Rgb32F::new(j as f32 / 3.0, i as f32 / 5.0, 0.3))
+    });
+
+    let linear_sse = |block: Block| {
+        let decoded = block.decode();
+        let mut sse = 0.0;
+        for i in 0..4 {
+            for j in 0..4 {
+                sse += Rgb32F::distance_squared(
+                    decoded[i][j].srgb_to_linear(),
+                    colors[i][j].srgb_to_linear(),
+                );
             }
-            _ => unreachable!(),
+        }
+        sse
+    };
+
+    // Fitting in linear light lowers error measured in linear light.
+    let srgb = linear_sse(Block::encode(colors));
+    for effort in [
+        EncodeEffort::Fast,
+        EncodeEffort::Default,
+        EncodeEffort::High,
+    ] {
+        let linear = Block::encode_linear(colors, effort);
+        assert!(!linear.is_three_color_mode());
+        assert!(linear_sse(linear) < srgb, "{effort:?}");
+    }
+}
+
+#[test]
+fn test_index_packing() {
+    let indices: [[u8; 4]; 4] =
+        std::array::from_fn(|y| std::array::from_fn(|x| ((x + y * 3) % 4) as u8));
+
+    let block = Block::new(Rgb565::WHITE, Rgb565::BLACK, indices);
+    assert_eq!(block.color0(), Rgb565::WHITE);
+    assert_eq!(block.color1(), Rgb565::BLACK);
+
+    for (y, row) in indices.iter().enumerate() {
+        for (x, &index) in row.iter().enumerate() {
+            assert_eq!(block.texel_index(x, y), index);
+            assert_eq!(
+                (block.indices_word() >> (2 * (x + 4 * y))) & 0b11,
+                index as u32
+            );
         }
     }
+
+    // Row 0 is `0, 1, 2, 3` from low bits to high bits.
+    assert_eq!(block.texels[0], 0b11_10_01_00);
+    assert_eq!(block.indices_word().to_le_bytes(), block.texels);
+    assert_eq!(&block.bytes()[4..], &block.texels);
+
+    let mut block = Block::BLACK;
+    block.set_texel_index(2, 1, 3);
+    block.set_texel_index(0, 3, 0);
+    assert_eq!(block.texels, [0x55, 0x75, 0x55, 0x54]);
+    assert_eq!(block.texel_index(2, 1), 3);
+    assert_eq!(block.texel_index(0, 3), 0);
+    assert_eq!(block.texel_index(1, 3), 1);
+}
+
+#[test]
+fn test_analyze_block() {
+    use rand::{Rng, SeedableRng};
+
+    // Decoded colors are reproduced exactly.
+    for block in [
+        Block::new(Rgb565::WHITE, Rgb565::BLACK, [[0, 1, 2, 3]; 4]),
+        Block::new(Rgb565::BLACK, Rgb565::WHITE, [[3, 2, 1, 0]; 4]),
+        Block::new(Rgb565::WHITE, Rgb565::WHITE, [[2; 4]; 4]),
+    ] {
+        let analysis = analyze_block(block.decode(), &block);
+        assert_eq!(analysis.actual_sse, 0.0);
+        assert_eq!(analysis.optimal_indices_sse, 0.0);
+        assert!(analysis.optimal_endpoints_sse < 1e-3);
+    }
+
+    // Texel with the wrong index is attributed to indices.
+    let block = Block::new(Rgb565::WHITE, Rgb565::BLACK, [[0; 4]; 4]);
+    let mut original = block.decode();
+    original[1][2] = Rgb32F::BLACK;
+    let analysis = analyze_block(original, &block);
+    assert_eq!(analysis.actual_errors[1][2], 3.0);
+    assert_eq!(analysis.optimal_indices_sse, 0.0);
+    assert_eq!(analysis.indices_gap(), 3.0);
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(11);
+    for _ in 0..100 {
+        let colors: [[Rgb32F; 4]; 4] = std::array::from_fn(|_| {
+            std::array::from_fn(|_| Rgb32F::new(rng.gen(), rng.gen(), rng.gen()))
+        });
+        let block = Block::from_bytes(rng.gen());
+
+        let analysis = analyze_block(colors, &block);
+        assert!((analysis.actual_sse - block.sse(&colors)).abs() < 1e-4);
+        assert!(analysis.optimal_indices_sse <= analysis.actual_sse);
+        // Palette is interpolated with truncation, so least squares endpoints may be slightly off.
+        assert!(analysis.optimal_endpoints_sse <= analysis.actual_sse + 0.01);
+    }
+}
+
+#[test]
+fn test_encoder_near_optimal_endpoints() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(5);
+
+    let originals: Vec<[[Rgb32F; 4]; 4]> = (0..200)
+        .map(|_| {
+            // Colors scattered around a line, like most natural blocks.
+            let a = Vec3::new(rng.gen(), rng.gen(), rng.gen());
+            let b = Vec3::new(rng.gen(), rng.gen(), rng.gen());
+            std::array::from_fn(|_| {
+                std::array::from_fn(|_| {
+                    let noise = Vec3::new(rng.gen(), rng.gen(), rng.gen()) * 0.1;
+                    let color = a + (b - a) * rng.gen::<f32>() + noise;
+                    Rgb32F::new(color.x().min(1.0), color.y().min(1.0), color.z().min(1.0))
+                })
+            })
+        })
+        .collect();
+    let blocks: Vec<Block> = originals
+        .iter()
+        .map(|&colors| Block::encode(colors))
+        .collect();
+
+    for (&colors, block) in originals.iter().zip(&blocks) {
+        let analysis = analyze_block(colors, block);
+        assert!(
+            analysis.actual_sse <= 2.0 * analysis.optimal_endpoints_sse + 0.01,
+            "{analysis:?}"
+        );
+    }
+
+    let analysis = analyze_texture(&originals, &blocks);
+    for p in [
+        analysis.actual_sse,
+        analysis.optimal_indices_sse,
+        analysis.optimal_endpoints_sse,
+    ] {
+        assert!(p.p50 <= p.p90 && p.p90 <= p.p99 && p.p99 <= p.max);
+    }
+    assert!(analysis.optimal_endpoints_sse.p50 <= analysis.actual_sse.p50);
+
+    assert_eq!(analyze_texture(&[], &[]), TextureAnalysis::default());
 }
@@ -2,8 +2,9 @@
 //!
 
 use crate::{
-    cluster_fit::cluster_fit,
-    math::{Rgb32F, Rgb565, Rgba32F, Vec3, Yiq32F},
+    cluster_fit::{self, cluster_fit, range_fit},
+    encoder::EncodeEffort,
+    math::{Rgb32F, Rgb565, Rgb8U, Rgba32F, Vec3, Yiq32F},
 };
 
 /// A block of 4x4 texels compressed with BC2.
@@ -21,7 +22,7 @@ impl Block {
         alpha: [0xFF; 8],
         color0: Rgb565::WHITE,
         color1: Rgb565::BLACK,
-        texels: [0xFF; 4],
+        texels: [0x55; 4],
     };
 
     pub const WHITE: Block = Block {
@@ -67,133 +68,86 @@ impl Block {
         }
     }
 
-    /// Decodes single BC2 block.
-    pub fn decode(self) -> [[Rgb32F; 4]; 4] {
-        // Decode endpoints.
-        let color0 = self.color0.into_f32();
-        let color1 = self.color1.into_f32();
+    /// Returns palette of the block.
+    ///
+    /// Unlike BC1, BC2 always interpolates two intermediate colors
+    /// regardless of endpoints order.
+    pub fn palette(self) -> [Rgb32F; 4] {
+        self.palette_rgb8().map(Rgb8U::into_f32)
+    }
 
-        // Prepare local variables.
-        let mut colors = [[Rgb32F::BLACK; 4]; 4];
-        let texels = self.texels;
+    /// Returns palette of the block with 8-bit channels.
+    ///
+    /// Intermediate colors are computed as in [`bc1::Block::palette_rgb8`].
+    ///
+    /// [`bc1::Block::palette_rgb8`]: crate::bc1::Block::palette_rgb8
+    pub fn palette_rgb8(self) -> [Rgb8U; 4] {
+        [
+            self.color0.into_rgb8(),
+            self.color1.into_rgb8(),
+            Rgb565::lerp_third(self.color0, self.color1),
+            Rgb565::lerp_third(self.color1, self.color0),
+        ]
+    }
 
-        // Check mode and build palette.
-        let palette = if self.color0.bits() > self.color1.bits() {
-            // Interpolate two intermediate colors.
-            [
-                color0,
-                Rgb32F::lerp(color0, color1, 1.0 / 3.0),
-                Rgb32F::lerp(color0, color1, 2.0 / 3.0),
-                color1,
-            ]
-        } else {
-            // Interpolate one intermediate color.
-            [
-                color0,
-                Rgb32F::lerp(color0, color1, 1.0 / 2.0),
-                color1,
-                Rgb32F::BLACK,
-            ]
-        };
+    /// Returns alpha of the texel in row `i` and column `j`.
+    pub fn alpha(&self, i: usize, j: usize) -> f32 {
+        let alpha = (self.alpha[i * 2 + j / 2] >> (4 * (j % 2))) & 0b1111;
+        alpha as f32 / 15.0
+    }
 
-        // Decode texels.
-        for i in 0..4 {
-            for j in 0..4 {
-                let index = (texels[i] >> 2 * j) & 0b11;
+    /// Decodes single BC2 block ignoring alpha.
+    pub fn decode(self) -> [[Rgb32F; 4]; 4] {
+        self.decode_with_alpha().map(|row| row.map(|c| c.rgb()))
+    }
 
-                colors[i][j] = palette[index as usize];
-            }
-        }
+    /// Decodes single BC2 block into 8-bit colors ignoring alpha.
+    pub fn decode_rgb8(self) -> [[Rgb8U; 4]; 4] {
+        let palette = self.palette_rgb8();
+        let texels = self.texels;
 
-        colors
+        std::array::from_fn(|i| {
+            std::array::from_fn(|j| palette[((texels[i] >> (2 * j)) & 0b11) as usize])
+        })
     }
 
     /// Decodes single BC2 block.
     pub fn decode_with_alpha(self) -> [[Rgba32F; 4]; 4] {
-        // Decode endpoints.
-        let color0 = self.color0.into_f32();
-        let color1 = self.color1.into_f32();
-
-        // Prepare local variables.
-        let mut colors = [[Rgba32F::TRANSPARENT; 4]; 4];
+        let palette = self.palette();
         let texels = self.texels;
 
-        // Check mode and build palette.
-        let palette = [
-            color0,
-            Rgb32F::lerp(color0, color1, 1.0 / 3.0),
-            Rgb32F::lerp(color0, color1, 2.0 / 3.0),
-            color1,
-        ];
+        let mut colors = [[Rgba32F::TRANSPARENT; 4]; 4];
 
         // Decode texels.
         for i in 0..4 {
             for j in 0..4 {
                 let index = (texels[i] >> 2 * j) & 0b11;
-                let alpha = (self.alpha[i * 2 + j / 2] >> 4 * (j % 2)) & 0b1111;
-                let alpha = alpha as f32 / 15.0;
 
-                colors[i][j] = palette[index as usize].with_alpha(alpha);
+                colors[i][j] = palette[index as usize].with_alpha(self.alpha(i, j));
             }
         }
 
         colors
     }
 
+    /// Encodes single opaque BC2 block.
     pub fn encode(colors: [[Rgb32F; 4]; 4]) -> Self {
-        let mut samples = [Vec3::ZERO; 16];
-
-        for i in 0..4 {
-            for j in 0..4 {
-                samples[i * 4 + j] = colors[i][j].into();
-            }
-        }
-
-        let cf = cluster_fit::<Vec3, 4, 16>(
-            &samples,
-            |a: Vec3, b: Vec3| {
-                let mut a = Rgb565::from_f32(a.into());
-                let mut b = Rgb565::from_f32(b.into());
-
-                if a == b {
-                    b = Rgb565::from_bits(!a.bits());
-                }
-                if a.bits() < b.bits() {
-                    core::mem::swap(&mut a, &mut b);
-                }
-
-                (a.into_f32().into(), b.into_f32().into())
-            },
-            |a: Vec3, b: Vec3| {
-                let a = Rgb32F::from(a);
-                let b = Rgb32F::from(b);
-
-                let a = Yiq32F::from_rgb(a);
-                let b = Yiq32F::from_rgb(b);
-
-                Yiq32F::perceptual_distance(a, b)
-            },
-        );
-
-        let (color0, color1) = cf.endpoints;
-        let mut texels = [0; 4];
-        for i in 0..4 {
-            for j in 0..4 {
-                let idx = (cf.indices[i * 4 + j] as u8) & 0b11;
-                texels[i] |= idx << (j * 2);
-            }
-        }
-
-        Block {
-            alpha: [0xFF; 8],
-            color0: Rgb565::from_f32(Rgb32F::from(color0)),
-            color1: Rgb565::from_f32(Rgb32F::from(color1)),
-            texels,
-        }
+        Self::encode_with_alpha(colors.map(|row| row.map(|c| c.into_opaque())))
     }
 
     /// Encode block into BC2 with alpha.
     pub fn encode_with_alpha(colors: [[Rgba32F; 4]; 4]) -> Self {
+        Self::encode_with_effort(colors, EncodeEffort::Default)
+    }
+
+    /// Encodes block into BC2 spending specified effort on color endpoints search.
+    ///
+    /// Alpha is quantized to 4 bits per texel independently of colors,
+    /// so sharp alpha edges are preserved exactly.
+    pub fn encode_with_effort(colors: [[Rgba32F; 4]; 4], effort: EncodeEffort) -> Self {
+        // Fit indices go from color0 to color1, palette has endpoints first.
+        const REMAP: [u8; 4] = [0, 2, 3, 1];
+
         let mut samples = [Vec3::ZERO; 16];
 
         for i in 0..4 {
@@ -202,37 +156,18 @@ impl Block {
             }
         }
 
-        let cf = cluster_fit::<Vec3, 4, 16>(
-            &samples,
-            |a: Vec3, b: Vec3| {
-                let mut a = Rgb565::from_f32(a.into());
-                let mut b = Rgb565::from_f32(b.into());
-
-                if a == b {
-                    b = Rgb565::from_bits(!a.bits());
-                }
-                if a.bits() < b.bits() {
-                    core::mem::swap(&mut a, &mut b);
-                }
-
-                (a.into_f32().into(), b.into_f32().into())
-            },
-            |a: Vec3, b: Vec3| {
-                let a = Rgb32F::from(a);
-                let b = Rgb32F::from(b);
-
-                let a = Yiq32F::from_rgb(a);
-                let b = Yiq32F::from_rgb(b);
-
-                Yiq32F::perceptual_distance(a, b)
-            },
-        );
+        let cf = match effort {
+            EncodeEffort::Fast => range_fit::<Vec3, 4, 16>(&samples, remap, perceptual_distance),
+            EncodeEffort::Default | EncodeEffort::High => {
+                cluster_fit::<Vec3, 4, 16>(&samples, remap, perceptual_distance)
+            }
+        };
 
         let (color0, color1) = cf.endpoints;
         let mut texels = [0; 4];
         for i in 0..4 {
             for j in 0..4 {
-                let idx = (cf.indices[i * 4 + j] as u8) & 0b11;
+                let idx = REMAP[cf.indices[i * 4 + j]];
                 texels[i] |= idx << (j * 2);
             }
         }
@@ -240,16 +175,89 @@ impl Block {
         let mut alpha = [0; 8];
         for i in 0..4 {
             for j in 0..4 {
-                let a = (colors[i][j].a() * 15.0).round() as u8;
-                alpha[i * 2 + j / 2] |= (a & 0b1111) << (4 * (j % 2));
+                let a = (colors[i][j].a().clamp(0.0, 1.0) * 15.0).round() as u8;
+                alpha[i * 2 + j / 2] |= a << (4 * (j % 2));
             }
         }
 
         Block {
             alpha,
-            color0: Rgb565::from_f32(Rgb32F::from(color0)),
-            color1: Rgb565::from_f32(Rgb32F::from(color1)),
+            color0: cluster_fit::quantize(color0),
+            color1: cluster_fit::quantize(color1),
             texels,
         }
     }
 }
+
+/// Quantizes endpoints so that `color0 > color1`.
+///
+/// BC2 decoders ignore endpoints order, this keeps blocks valid for BC1 decoders as well.
+fn remap(a: Vec3, b: Vec3) -> (Vec3, Vec3) {
+    let mut a = cluster_fit::quantize(a);
+    let mut b = cluster_fit::quantize(b);
+
+    if a == b {
+        b = Rgb565::from_bits(!a.bits());
+    }
+    if a.bits() < b.bits() {
+        core::mem::swap(&mut a, &mut b);
+    }
+
+    (a.into_f32().into(), b.into_f32().into())
+}
+
+fn perceptual_distance(a: Vec3, b: Vec3) -> f32 {
+    Yiq32F::perceptual_distance(Yiq32F::from_rgb(a.into()), Yiq32F::from_rgb(b.into()))
+}
+
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for Block {}
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for Block {}
+
+#[test]
+fn test_encode_sharp_alpha() {
+    let red = Rgba32F::new(1.0, 0.0, 0.0, 1.0);
+    let green = Rgba32F::new(0.0, 1.0, 0.0, 1.0);
+
+    let colors: [[Rgba32F; 4]; 4] = std::array::from_fn(|i| {
+        std::array::from_fn(|j| {
+            let color = if i < 2 { red } else { green };
+            // Alpha flips between neighbouring texels, BC3 would blur it.
+            color.rgb().with_alpha(((i + j) % 2) as f32)
+        })
+    });
+
+    for effort in [
+        EncodeEffort::Fast,
+        EncodeEffort::Default,
+        EncodeEffort::High,
+    ] {
+        let block = Block::encode_with_effort(colors, effort);
+        assert_eq!(Block::from_bytes(block.bytes()), block);
+        assert_eq!(block.decode_with_alpha(), colors, "{effort:?}");
+    }
+
+    // Alpha is quantized to 4 bits.
+    let block = Block::encode_with_alpha([[red.rgb().with_alpha(0.5); 4]; 4]);
+    assert!(block.alpha.iter().all(|&a| a == 0x88));
+    assert_eq!(block.alpha(0, 0), 8.0 / 15.0);
+}
+
+#[test]
+fn test_palette() {
+    // BC2 interpolates two colors even when `color0 <= color1`.
+    let block = Block {
+        alpha: [0xFF; 8],
+        color0: Rgb565::BLACK,
+        color1: Rgb565::WHITE,
+        texels: [0b11_10_01_00; 4],
+    };
+
+    let palette = block.palette();
+    assert_eq!(palette[0], Rgb32F::BLACK);
+    assert_eq!(palette[1], Rgb32F::WHITE);
+    assert_eq!(palette[2], Rgb32F::splat(85.0 / 255.0));
+    assert_eq!(palette[3], Rgb32F::splat(170.0 / 255.0));
+    assert_eq!(block.decode()[0], palette);
+}
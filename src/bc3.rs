@@ -3,7 +3,7 @@
 
 use crate::{
     bc1, bc4,
-    math::{Rgb32F, Rgba32F, R32F},
+    math::{Rgb32F, Rgb8U, Rgba32F, R32F},
 };
 
 /// A block of 4x4 texels compressed with BC4.
@@ -59,6 +59,11 @@ impl Block {
         self.rgb.decode()
     }
 
+    /// Decodes color of single BC3 block into 8-bit colors.
+    pub fn decode_rgb8(self) -> [[Rgb8U; 4]; 4] {
+        self.rgb.decode_rgb8()
+    }
+
     /// Decodes single BC1 block.
     pub fn decode_with_alpha(self) -> [[Rgba32F; 4]; 4] {
         let alpha = self.alpha.decode();
@@ -94,3 +99,8 @@ impl Block {
         Block { alpha, rgb }
     }
 }
+
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for Block {}
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for Block {}
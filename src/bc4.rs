@@ -2,7 +2,7 @@
 //!
 
 use crate::{
-    cluster_fit::cluster_fit,
+    cluster_fit::{cluster_fit, range_fit},
     math::{R32F, R8U},
 };
 
@@ -50,59 +50,61 @@ impl Block {
         }
     }
 
-    /// Decodes single BC4 block.
-    pub fn decode(self) -> [[R32F; 4]; 4] {
-        // Decode endpoints.
+    /// Returns palette of the block indexed by texel values.
+    ///
+    /// With `color0 > color1` there are 6 interpolated values between endpoints,
+    /// otherwise 4 interpolated values followed by 0.0 and 1.0.
+    pub fn palette(self) -> [R32F; 8] {
         let color0 = self.color0.into_f32();
         let color1 = self.color1.into_f32();
 
-        // Prepare local variables.
-        let mut colors = [[R32F::BLACK; 4]; 4];
-        let texels = self.texels;
-
-        // Check mode and build palette.
-        let palette = if self.color0.bits() > self.color1.bits() {
+        if self.color0.bits() > self.color1.bits() {
             [
                 color0,
+                color1,
                 R32F::lerp(color0, color1, 1.0 / 7.0),
                 R32F::lerp(color0, color1, 2.0 / 7.0),
                 R32F::lerp(color0, color1, 3.0 / 7.0),
                 R32F::lerp(color0, color1, 4.0 / 7.0),
                 R32F::lerp(color0, color1, 5.0 / 7.0),
                 R32F::lerp(color0, color1, 6.0 / 7.0),
-                color1,
             ]
         } else {
             [
                 color0,
+                color1,
                 R32F::lerp(color0, color1, 1.0 / 5.0),
                 R32F::lerp(color0, color1, 2.0 / 5.0),
                 R32F::lerp(color0, color1, 3.0 / 5.0),
                 R32F::lerp(color0, color1, 4.0 / 5.0),
-                color1,
                 R32F::BLACK,
                 R32F::WHITE,
             ]
-        };
+        }
+    }
 
-        // Decode texels.
-        for i in 0..4 {
-            for j in 0..4 {
-                let start_bit = (i * 4 + j) * 3;
-                let start_byte = start_bit / 8;
+    /// Returns palette index of texel at row `i` and column `j`.
+    pub fn index(&self, i: usize, j: usize) -> u8 {
+        let start_bit = (i * 4 + j) * 3;
+        let start_byte = start_bit / 8;
 
-                let mut index = (texels[start_byte] >> (start_bit & 7)) & 0b111;
-                if start_bit & 7 > 5 {
-                    index |= (texels[start_byte + 1] << (8 - (start_bit & 7))) & 0b111;
-                }
-
-                colors[i][j] = palette[index as usize];
-            }
+        let mut index = (self.texels[start_byte] >> (start_bit & 7)) & 0b111;
+        if start_bit & 7 > 5 {
+            index |= (self.texels[start_byte + 1] << (8 - (start_bit & 7))) & 0b111;
         }
+        index
+    }
 
-        colors
+    /// Decodes single BC4 block.
+    pub fn decode(self) -> [[R32F; 4]; 4] {
+        let palette = self.palette();
+        std::array::from_fn(|i| std::array::from_fn(|j| palette[self.index(i, j) as usize]))
     }
 
+    /// Encodes single BC4 block.
+    ///
+    /// Both modes are tried and the one with lower squared error wins.
+    /// Mode with 0.0 and 1.0 in the palette fits remaining values with finer steps.
     pub fn encode(colors: [[R32F; 4]; 4]) -> Self {
         let mut samples = [0.0; 16];
 
@@ -112,8 +114,20 @@ impl Block {
             }
         }
 
+        let (eight, eight_error) = Self::encode_eight(&samples);
+        let (six, six_error) = Self::encode_six(&samples);
+
+        if six_error < eight_error {
+            six
+        } else {
+            eight
+        }
+    }
+
+    /// Encodes block with `color0 > color1`.
+    fn encode_eight(samples: &[f32; 16]) -> (Self, f32) {
         let cf = cluster_fit::<f32, 8, 16>(
-            &samples,
+            samples,
             |a: f32, b: f32| {
                 let mut a = R8U::from_f32(R32F::new(a));
                 let mut b = R8U::from_f32(R32F::new(b));
@@ -127,34 +141,164 @@ impl Block {
 
                 (a.into_f32().r(), b.into_f32().r())
             },
-            |a: f32, b: f32| {
-                let a = R32F::new(a);
-                let b = R32F::new(b);
-
-                R32F::distance(a, b)
-            },
+            |a: f32, b: f32| R32F::distance_squared(R32F::new(a), R32F::new(b)),
         );
 
         let (color0, color1) = cf.endpoints;
-        let mut texels = [0; 6];
-        for i in 0..4 {
-            for j in 0..4 {
-                let idx = (cf.indices[i * 4 + j] as u8) & 0b111;
+        Self::with_endpoints(
+            R8U::from_f32(R32F::new(color0)),
+            R8U::from_f32(R32F::new(color1)),
+            samples,
+        )
+    }
+
+    /// Encodes block with `color0 <= color1`.
+    ///
+    /// Endpoints are fitted to values that are not served by 0.0 and 1.0 exactly.
+    fn encode_six(samples: &[f32; 16]) -> (Self, f32) {
+        let mut midtones = [0.0; 16];
+        let mut count = 0;
+        for &sample in samples {
+            let bits = R8U::from_f32(R32F::new(sample)).bits();
+            if bits != 0 && bits != 255 {
+                midtones[count] = sample;
+                count += 1;
+            }
+        }
 
-                let start_bit = (i * 4 + j) * 3;
-                let start_byte = start_bit / 8;
+        let remap = |a: f32, b: f32| {
+            let mut a = R8U::from_f32(R32F::new(a));
+            let mut b = R8U::from_f32(R32F::new(b));
 
-                texels[start_byte] |= idx << (start_bit & 7);
-                if start_bit & 7 > 5 {
-                    texels[start_byte + 1] |= idx >> (8 - (start_bit & 7));
-                }
+            if a.bits() > b.bits() {
+                core::mem::swap(&mut a, &mut b);
+            }
+
+            (a.into_f32().r(), b.into_f32().r())
+        };
+        let error = |a: f32, b: f32| R32F::distance_squared(R32F::new(a), R32F::new(b));
+
+        let (color0, color1) = match count {
+            0 => (0.0, 0.0),
+            // Cluster fit needs at least as many values as there are palette entries.
+            1..6 => range_fit::<f32, 6, 16>(&midtones[..count], remap, error).endpoints,
+            _ => cluster_fit::<f32, 6, 16>(&midtones[..count], remap, error).endpoints,
+        };
+
+        Self::with_endpoints(
+            R8U::from_f32(R32F::new(color0)),
+            R8U::from_f32(R32F::new(color1)),
+            samples,
+        )
+    }
+
+    /// Assigns samples to the nearest palette entries.
+    ///
+    /// Returns block and its sum of squared errors.
+    fn with_endpoints(color0: R8U, color1: R8U, samples: &[f32; 16]) -> (Self, f32) {
+        let mut block = Block {
+            color0,
+            color1,
+            texels: [0; 6],
+        };
+        let palette = block.palette();
+
+        let mut total_error = 0.0;
+        for (k, &sample) in samples.iter().enumerate() {
+            let (idx, error) = palette
+                .iter()
+                .map(|p| R32F::distance_squared(*p, R32F::new(sample)))
+                .enumerate()
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap();
+            total_error += error;
+
+            let idx = idx as u8;
+            let start_bit = k * 3;
+            let start_byte = start_bit / 8;
+
+            block.texels[start_byte] |= idx << (start_bit & 7);
+            if start_bit & 7 > 5 {
+                block.texels[start_byte + 1] |= idx >> (8 - (start_bit & 7));
             }
         }
 
-        Block {
-            color0: R8U::from_f32(R32F::new(color0)),
-            color1: R8U::from_f32(R32F::new(color1)),
-            texels,
+        (block, total_error)
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for Block {}
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for Block {}
+
+#[test]
+fn test_palette_order() {
+    let block = Block {
+        color0: R8U::new(200),
+        color1: R8U::new(60),
+        texels: [0; 6],
+    };
+    let palette = block.palette();
+    assert_eq!(palette[0], block.color0.into_f32());
+    assert_eq!(palette[1], block.color1.into_f32());
+    assert!((palette[2].r() - (6.0 * 200.0 + 60.0) / 7.0 / 255.0).abs() < 1e-6);
+
+    let block = Block {
+        color0: R8U::new(60),
+        color1: R8U::new(200),
+        texels: [0; 6],
+    };
+    let palette = block.palette();
+    assert_eq!(palette[1], block.color1.into_f32());
+    assert_eq!(palette[6], R32F::BLACK);
+    assert_eq!(palette[7], R32F::WHITE);
+}
+
+#[test]
+fn test_ramp_error() {
+    // Ramp 0..255 across the block.
+    let colors = std::array::from_fn(|i| {
+        std::array::from_fn(|j| R32F::new((i * 4 + j) as f32 * 17.0 / 255.0))
+    });
+
+    let block = Block::encode(colors);
+    assert!(block.color0.bits() > block.color1.bits());
+
+    // Half of the step between 8 values, plus endpoint rounding.
+    let max_error = 1.0 / 14.0 + 0.5 / 255.0;
+
+    let decoded = block.decode();
+    for i in 0..4 {
+        for j in 0..4 {
+            let error = (decoded[i][j].r() - colors[i][j].r()).abs();
+            assert!(error <= max_error, "{error} at {i}x{j}");
+        }
+    }
+}
+
+#[test]
+fn test_six_value_mode() {
+    // Exact black and white with midtones in narrow range.
+    let values = [
+        0.0, 1.0, 0.4, 0.45, 0.5, 0.55, 0.6, 0.42, 0.0, 1.0, 0.48, 0.52, 0.58, 0.44, 0.0, 1.0,
+    ];
+    let colors = std::array::from_fn(|i| std::array::from_fn(|j| R32F::new(values[i * 4 + j])));
+
+    let block = Block::encode(colors);
+    assert!(block.color0.bits() <= block.color1.bits());
+
+    let decoded = block.decode();
+    for i in 0..4 {
+        for j in 0..4 {
+            let value = values[i * 4 + j];
+            let error = (decoded[i][j].r() - value).abs();
+            if value == 0.0 || value == 1.0 {
+                assert_eq!(error, 0.0);
+            } else {
+                // Half of the step between 6 values spanning midtones, plus endpoint rounding.
+                assert!(error <= 0.2 / 10.0 + 0.5 / 255.0, "{error} at {i}x{j}");
+            }
         }
     }
 }
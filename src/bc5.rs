@@ -1,4 +1,4 @@
-//! BC4 implementation.
+//! BC5 implementation.
 //!
 
 use crate::{
@@ -6,7 +6,7 @@ use crate::{
     math::{Rg32F, R32F},
 };
 
-/// A block of 4x4 texels compressed with BC4.
+/// A block of 4x4 texels compressed with BC5.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(C)]
 pub struct Block {
@@ -49,7 +49,7 @@ impl Block {
         }
     }
 
-    /// Decodes single BC4 block.
+    /// Decodes single BC5 block.
     pub fn decode(self) -> [[Rg32F; 4]; 4] {
         let red = self.red.decode();
         let green = self.green.decode();
@@ -65,6 +65,7 @@ impl Block {
         colors
     }
 
+    /// Encodes red and green channels as independent BC4 blocks.
     pub fn encode(colors: [[Rg32F; 4]; 4]) -> Self {
         let mut red = [[R32F::BLACK; 4]; 4];
         let mut green = [[R32F::BLACK; 4]; 4];
@@ -82,3 +83,8 @@ impl Block {
         Block { red, green }
     }
 }
+
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for Block {}
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for Block {}
@@ -17,6 +17,13 @@ impl<W> WriteBits<W> {
             buffer_len: 0,
         }
     }
+
+    /// Returns underlying writer.
+    ///
+    /// Bits not yet written with [`WriteBits::finish`] are lost.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
 }
 
 impl<W> WriteBits<W>
@@ -30,6 +37,8 @@ where
     /// Returns number of bits written.
     /// It would be the `bit_len`.
     /// Unless writer is exhausted.
+    ///
+    /// Benchmarked with `cargo bench --bench bits`.
     pub fn write_bits(
         &mut self,
         buffer: &[u8],
@@ -55,14 +64,17 @@ where
                 return Ok(total_bits_written);
             }
 
-            if !self.flush()? {
+            if !self.flush_bytes()? {
                 return Ok(total_bits_written);
             }
         }
     }
 
-    fn flush(&mut self) -> std::io::Result<bool> {
-        if self.buffer_len > 8 {
+    /// Writes whole bytes from internal buffer into the writer.
+    ///
+    /// Returns `false` if writer is exhausted.
+    fn flush_bytes(&mut self) -> std::io::Result<bool> {
+        if self.buffer_len >= 8 {
             let write_bytes = self.buffer_len / 8;
 
             let bytes_written = loop {
@@ -89,13 +101,17 @@ where
         Ok(true)
     }
 
+    /// Writes remaining bits padding last byte with zeros.
+    ///
+    /// Returns number of bits written including padding.
     pub fn finish(&mut self) -> std::io::Result<usize> {
         if self.buffer_len > 0 {
-            let write_bytes = (self.buffer_len + 7) / 8;
+            let write_bytes = self.buffer_len.div_ceil(8);
             self.writer
                 .write_all(&self.buffer.to_le_bytes()[..write_bytes as usize])?;
 
             let written_bits = write_bytes * 8;
+            self.buffer = 0;
             self.buffer_len = 0;
 
             Ok(written_bits as usize)
@@ -104,6 +120,30 @@ where
         }
     }
 
+    /// Writes low `bit_len` bits of the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit_len` is greater than 64.
+    pub fn write_u64_bits(&mut self, value: u64, bit_len: usize) -> std::io::Result<()> {
+        assert!(bit_len <= 64, "Cannot write {bit_len} bits from u64");
+
+        if self.write_bits(&value.to_le_bytes(), 0, bit_len)? != bit_len {
+            return Err(std::io::ErrorKind::WriteZero.into());
+        }
+        Ok(())
+    }
+
+    /// Pads stream with zero bits up to the next byte boundary.
+    ///
+    /// Returns number of padding bits written.
+    pub fn align_to_byte(&mut self) -> std::io::Result<usize> {
+        // Only whole bytes leave the buffer, so its length tells position within a byte.
+        let pad = (8 - self.buffer_len as usize % 8) % 8;
+        self.write_u64_bits(0, pad)?;
+        Ok(pad)
+    }
+
     fn copy_from_buffer<'a>(
         &mut self,
         mut buffer: &'a [u8],
@@ -149,7 +189,7 @@ where
 
             let mut copy_bytes = [0; 16];
 
-            let copy_bytes_len = (copy_len + 7) / 8;
+            let copy_bytes_len = copy_len.div_ceil(8);
             copy_bytes[..copy_bytes_len].copy_from_slice(&buffer[..copy_bytes_len]);
 
             let mut copy_bits = u128::from_le_bytes(copy_bytes);
@@ -160,7 +200,7 @@ where
             self.buffer |= copy_bits << self.buffer_len;
             self.buffer_len += copy_len as u8;
 
-            bit_offset += copy_len as usize;
+            bit_offset += copy_len;
             bit_len -= copy_len;
         }
 
@@ -172,16 +212,65 @@ impl<W> Write for WriteBits<W>
 where
     W: Write,
 {
+    /// Either accepts all bytes or fails.
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.write_bits(buf, 0, buf.len() * 8).map(|n| n / 8)
+        let bit_len = buf.len() * 8;
+        if self.write_bits(buf, 0, bit_len)? != bit_len {
+            return Err(std::io::ErrorKind::WriteZero.into());
+        }
+        Ok(buf.len())
     }
 
+    /// Writes all whole bytes into the writer and flushes it.
+    ///
+    /// Bits that do not form a whole byte stay buffered,
+    /// they are written only by [`WriteBits::finish`].
     fn flush(&mut self) -> std::io::Result<()> {
-        self.flush()?;
+        while self.buffer_len >= 8 {
+            if !self.flush_bytes()? {
+                return Err(std::io::ErrorKind::WriteZero.into());
+            }
+        }
         self.writer.flush()
     }
 }
 
+/// Error returned when stream ends in the middle of requested bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TruncatedStream {
+    /// Number of bits read before stream ended.
+    pub bits_read: usize,
+
+    /// Number of bits requested.
+    pub bits_requested: usize,
+}
+
+impl TruncatedStream {
+    /// Returns `true` if the I/O error is caused by truncated stream.
+    pub fn is_truncated(err: &std::io::Error) -> bool {
+        err.get_ref()
+            .is_some_and(|err| err.downcast_ref::<TruncatedStream>().is_some())
+    }
+}
+
+impl std::fmt::Display for TruncatedStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "stream truncated after {} of {} bits",
+            self.bits_read, self.bits_requested
+        )
+    }
+}
+
+impl std::error::Error for TruncatedStream {}
+
+impl From<TruncatedStream> for std::io::Error {
+    fn from(err: TruncatedStream) -> Self {
+        std::io::Error::new(std::io::ErrorKind::UnexpectedEof, err)
+    }
+}
+
 /// Wrapper around reader to read bits.
 pub struct ReadBits<R> {
     reader: R,
@@ -210,21 +299,46 @@ where
     ///
     /// Returns number of bits read.
     /// It would be the `bit_len`.
-    /// Unless reader is exhausted.
+    /// Unless reader is exhausted before any bit is read, then it is `0`.
+    /// Reader exhausted after some bits are read is reported as [`TruncatedStream`] error
+    /// with [`std::io::ErrorKind::UnexpectedEof`] kind.
     ///
     /// # Panics
     ///
     /// The function will panic if `buffer` doesn't fit bits in range `bit_offset..bit_offset+bit_len`.
-    /// This means that `buffer.len()` must be equal or greater than `(bit_offset + bit_len + 7) / 8`.
+    /// This means that `buffer.len()` must be equal or greater than `(bit_offset + bit_len).div_ceil(8)`.
     ///
     /// Function can also panic if internal reader panics on read.
+    ///
+    /// Benchmarked with `cargo bench --bench bits`.
     pub fn read_bits(
         &mut self,
         buffer: &mut [u8],
         bit_offset: usize,
         bit_len: usize,
     ) -> std::io::Result<usize> {
-        assert!(buffer.len() >= (bit_offset + bit_len + 7) / 8);
+        let bits_read = self.read_bits_partial(buffer, bit_offset, bit_len)?;
+
+        if bits_read != 0 && bits_read != bit_len {
+            return Err(TruncatedStream {
+                bits_read,
+                bits_requested: bit_len,
+            }
+            .into());
+        }
+
+        Ok(bits_read)
+    }
+
+    /// Same as [`ReadBits::read_bits`] but returns number of bits read
+    /// if reader is exhausted in the middle.
+    fn read_bits_partial(
+        &mut self,
+        buffer: &mut [u8],
+        bit_offset: usize,
+        bit_len: usize,
+    ) -> std::io::Result<usize> {
+        assert!(buffer.len() >= (bit_offset + bit_len).div_ceil(8));
 
         if bit_len == 0 {
             return Ok(0);
@@ -255,24 +369,124 @@ where
         }
     }
 
-    fn fill_buffer(&mut self, bit_len: usize) -> std::io::Result<bool> {
-        debug_assert_eq!(self.buffer_len, 0);
+    /// Reads low `bit_len` bits of the value.
+    ///
+    /// Reader exhausted before all bits are read is reported as [`TruncatedStream`] error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit_len` is greater than 64.
+    pub fn read_u64_bits(&mut self, bit_len: usize) -> std::io::Result<u64> {
+        assert!(bit_len <= 64, "Cannot read {bit_len} bits into u64");
+
+        let mut bytes = [0; 8];
+        let bits_read = self.read_bits_partial(&mut bytes, 0, bit_len)?;
+        if bits_read != bit_len {
+            return Err(TruncatedStream {
+                bits_read,
+                bits_requested: bit_len,
+            }
+            .into());
+        }
 
-        let mut buffer = [0; 16];
-        let bytes_read = loop {
-            let r = self.reader.read(&mut buffer[..((bit_len + 7) / 8).min(16)]);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    /// Returns next `bit_len` bits without consuming them.
+    ///
+    /// If reader is exhausted, missing bits are zeros.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit_len` is greater than 64.
+    pub fn peek_bits(&mut self, bit_len: usize) -> std::io::Result<u64> {
+        assert!(bit_len <= 64, "Cannot peek {bit_len} bits into u64");
+
+        while (self.buffer_len as usize) < bit_len {
+            if !self.fill_buffer(bit_len)? {
+                break;
+            }
+        }
 
-            match r {
-                Ok(0) => return Ok(false),
-                Ok(n) => break n,
+        let mask = if bit_len < 64 {
+            (1u64 << bit_len) - 1
+        } else {
+            u64::MAX
+        };
+
+        Ok(self.buffer as u64 & mask)
+    }
+
+    /// Consumes `bit_len` bits.
+    ///
+    /// Reader exhausted before all bits are skipped is reported as [`TruncatedStream`] error.
+    pub fn skip_bits(&mut self, bit_len: usize) -> std::io::Result<()> {
+        let mut scratch = [0; 16];
+        let mut bits_skipped = 0;
+
+        while bits_skipped < bit_len {
+            let chunk = (bit_len - bits_skipped).min(128);
+            let bits_read = self.read_bits_partial(&mut scratch, 0, chunk)?;
+            bits_skipped += bits_read;
+
+            if bits_read != chunk {
+                return Err(TruncatedStream {
+                    bits_read: bits_skipped,
+                    bits_requested: bit_len,
+                }
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Skips bits up to the next byte boundary.
+    ///
+    /// Returns number of bits skipped.
+    pub fn align_to_byte(&mut self) -> usize {
+        // Only whole bytes enter the buffer, so its length tells position within a byte.
+        let skip = self.buffer_len as usize % 8;
+        self.buffer >>= skip;
+        self.buffer_len -= skip as u8;
+        skip
+    }
+
+    /// Reads more bytes into internal buffer, up to enough to hold `bit_len` bits
+    /// or until buffer is full.
+    /// Short reads are repeated until enough bytes arrive or reader is exhausted.
+    ///
+    /// Returns `false` if no bytes were read.
+    fn fill_buffer(&mut self, bit_len: usize) -> std::io::Result<bool> {
+        let mut buffer = [0; 16];
+        let free = (128 - self.buffer_len as usize) / 8;
+        let want = bit_len
+            .saturating_sub(self.buffer_len as usize)
+            .div_ceil(8)
+            .min(free);
+
+        let mut bytes_read = 0;
+        let mut eof_count = 0;
+
+        // Reader is considered exhausted after two consecutive empty reads.
+        while bytes_read < want && eof_count < 2 {
+            match self.reader.read(&mut buffer[bytes_read..want]) {
+                Ok(0) => eof_count += 1,
+                Ok(n) => {
+                    bytes_read += n;
+                    eof_count = 0;
+                }
                 Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
                 Err(e) => return Err(e),
             }
-        };
+        }
 
-        self.buffer = u128::from_le_bytes(buffer);
-        self.buffer_len = bytes_read as u8 * 8;
-        Ok(true)
+        if bytes_read > 0 {
+            // At least one byte is free, so shift is less than 128.
+            self.buffer |= u128::from_le_bytes(buffer) << self.buffer_len;
+            self.buffer_len += bytes_read as u8 * 8;
+        }
+        Ok(bytes_read > 0)
     }
 
     fn copy_from_buffer<'a>(
@@ -324,13 +538,13 @@ where
 
             let copy_bytes = copy_bits.to_le_bytes();
 
-            let copy_bytes_len = (copy_len + 7) / 8;
+            let copy_bytes_len = copy_len.div_ceil(8);
             buffer[..copy_bytes_len].copy_from_slice(&copy_bytes[..copy_bytes_len]);
 
-            self.buffer >>= copy_len;
+            self.buffer = self.buffer.checked_shr(copy_len as u32).unwrap_or(0);
             self.buffer_len -= copy_len as u8;
 
-            bit_offset += copy_len as usize;
+            bit_offset += copy_len;
             bit_len -= copy_len;
         }
 
@@ -343,7 +557,19 @@ where
     R: Read,
 {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        self.read_bits(buf, 0, buf.len() * 8).map(|n| n / 8)
+        let bit_len = buf.len() * 8;
+        let bits_read = self.read_bits_partial(buf, 0, bit_len)?;
+
+        // Reader may end with fewer bytes than requested, but not in the middle of a byte.
+        if bits_read % 8 != 0 {
+            return Err(TruncatedStream {
+                bits_read,
+                bits_requested: bit_len,
+            }
+            .into());
+        }
+
+        Ok(bits_read / 8)
     }
 }
 
@@ -410,7 +636,6 @@ fn test_test() {
 
     write.finish().unwrap();
 
-    drop(write);
     let mut read = ReadBits::new(&buffer[..]);
 
     for (bit_len, index, value) in writes {
@@ -422,3 +647,240 @@ fn test_test() {
         assert_eq!(buffer[0], value[0]);
     }
 }
+
+#[test]
+fn test_writer_buf_writer() {
+    use std::io::BufWriter;
+
+    /// Writer that accepts at most one byte per call.
+    struct ByteWriter<'a>(&'a mut Vec<u8>);
+
+    impl Write for ByteWriter<'_> {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.extend(buf.iter().take(1));
+            Ok(buf.len().min(1))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let data: Vec<u8> = (0..1000u32).map(|i| (i * 7 + i / 13) as u8).collect();
+
+    let mut output = Vec::new();
+    let mut write = WriteBits::new(ByteWriter(&mut output));
+
+    // Unaligned prefix makes every following byte straddle byte boundary.
+    write.write_bits(&[0b101], 0, 3).unwrap();
+
+    let mut buf_write = BufWriter::with_capacity(7, write);
+    let mut rest = &data[..];
+    for chunk_len in [1, 3, 5, 13, 2, 17, 31, 9].iter().cycle() {
+        if rest.is_empty() {
+            break;
+        }
+        let (chunk, tail) = rest.split_at((*chunk_len).min(rest.len()));
+        buf_write.write_all(chunk).unwrap();
+        rest = tail;
+    }
+    buf_write.flush().unwrap();
+
+    let mut write = buf_write.into_inner().map_err(|_| ()).unwrap();
+    write.finish().unwrap();
+
+    assert_eq!(output.len(), data.len() + 1);
+
+    let mut read = ReadBits::new(&output[..]);
+    let mut prefix = [0];
+    assert_eq!(read.read_bits(&mut prefix, 0, 3).unwrap(), 3);
+    assert_eq!(prefix[0], 0b101);
+
+    let mut decoded = vec![0; data.len()];
+    assert_eq!(
+        read.read_bits(&mut decoded, 0, data.len() * 8).unwrap(),
+        data.len() * 8
+    );
+    assert_eq!(decoded, data);
+}
+
+#[cfg(test)]
+/// Reader that returns at most one byte per call.
+struct ByteReader<R>(R);
+
+#[cfg(test)]
+impl<R: Read> Read for ByteReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(1);
+        self.0.read(&mut buf[..len])
+    }
+}
+
+#[test]
+fn test_reader_short_reads() {
+    let data: Vec<u8> = (0..300u32).map(|i| (i * 31 + i / 7) as u8).collect();
+
+    // Read with odd bit counts through reader that returns one byte at a time.
+    let mut expected = ReadBits::new(&data[..]);
+    let mut read = ReadBits::new(ByteReader(&data[..]));
+
+    let mut total = 0;
+    for bit_len in [1, 7, 13, 64, 3, 100, 9, 128, 2].iter().cycle() {
+        let bit_len = (*bit_len).min(data.len() * 8 - total);
+        if bit_len == 0 {
+            break;
+        }
+
+        let mut a = [0; 16];
+        let mut b = [0; 16];
+        assert_eq!(expected.read_bits(&mut a, 0, bit_len).unwrap(), bit_len);
+        assert_eq!(read.read_bits(&mut b, 0, bit_len).unwrap(), bit_len);
+        assert_eq!(a, b);
+
+        total += bit_len;
+    }
+
+    // Clean end of stream.
+    assert_eq!(read.read_bits(&mut [0], 0, 8).unwrap(), 0);
+}
+
+#[test]
+fn test_reader_truncated_jackal() {
+    use crate::{bc1, jackal, math::Rgb565};
+
+    let blocks: Vec<bc1::Block> = (0..16u16)
+        .map(|i| bc1::Block {
+            color0: Rgb565::from_bits(i * 1000),
+            color1: Rgb565::from_bits(i),
+            texels: [i as u8; 4],
+        })
+        .collect();
+
+    let mut output = Vec::new();
+    jackal::compress_bc1_texture(
+        jackal::Extent::D2 {
+            width: 4,
+            height: 4,
+        },
+        &blocks,
+        std::io::Cursor::new(&mut output),
+    )
+    .unwrap();
+
+    // Cut payload in the middle of the last 24-bit group.
+    let len = output.len() / 3 * 3 - 1;
+    let truncated = &output[..len];
+
+    let mut read = ReadBits::new(ByteReader(truncated));
+    let mut bytes = [0; 3];
+    for chunk in truncated.chunks_exact(3) {
+        assert_eq!(read.read_bits(&mut bytes, 0, 24).unwrap(), 24);
+        assert_eq!(bytes, chunk);
+    }
+
+    let err = read.read_bits(&mut bytes, 0, 24).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    assert!(TruncatedStream::is_truncated(&err));
+    assert_eq!(
+        err.into_inner()
+            .unwrap()
+            .downcast::<TruncatedStream>()
+            .unwrap(),
+        Box::new(TruncatedStream {
+            bits_read: 16,
+            bits_requested: 24,
+        })
+    );
+
+    // Byte reads return what's left.
+    let mut read = ReadBits::new(ByteReader(truncated));
+    let mut all = Vec::new();
+    read.read_to_end(&mut all).unwrap();
+    assert_eq!(all, truncated);
+}
+
+#[test]
+fn test_u64_peek_align() {
+    let mut buffer = Vec::new();
+    let mut write = WriteBits::new(&mut buffer);
+
+    write.write_u64_bits(0x5, 3).unwrap();
+    assert_eq!(write.align_to_byte().unwrap(), 5);
+    assert_eq!(write.align_to_byte().unwrap(), 0);
+
+    // Fill internal buffer up to 120 bits so next value straddles 128-bit boundary.
+    write.write_u64_bits(0xDEAD_BEEF, 32).unwrap();
+    write.write_u64_bits(u64::MAX, 64).unwrap();
+    write.write_u64_bits(0x3, 16).unwrap();
+    write.write_u64_bits(0x0123_4567_89AB_CDEF, 64).unwrap();
+    write.write_u64_bits(0x1, 1).unwrap();
+    assert_eq!(write.align_to_byte().unwrap(), 7);
+    write.write_bits(&[0xAB], 0, 8).unwrap();
+    write.write_u64_bits(0x7F, 7).unwrap();
+    write.finish().unwrap();
+
+    assert_eq!(buffer.len(), 1 + 4 + 8 + 2 + 8 + 1 + 1 + 1);
+
+    let mut read = ReadBits::new(ByteReader(&buffer[..]));
+
+    assert_eq!(read.peek_bits(3).unwrap(), 0x5);
+    assert_eq!(read.read_u64_bits(3).unwrap(), 0x5);
+    assert_eq!(read.align_to_byte(), 5);
+    assert_eq!(read.align_to_byte(), 0);
+
+    assert_eq!(read.peek_bits(64).unwrap(), 0xFFFF_FFFF_DEAD_BEEF);
+    assert_eq!(read.read_u64_bits(32).unwrap(), 0xDEAD_BEEF);
+    read.skip_bits(64).unwrap();
+    assert_eq!(read.read_u64_bits(16).unwrap(), 0x3);
+
+    // Peek tops up buffer that is partially consumed.
+    assert_eq!(read.read_u64_bits(4).unwrap(), 0xF);
+    assert_eq!(read.peek_bits(60).unwrap(), 0x0012_3456_789A_BCDE);
+    assert_eq!(read.read_u64_bits(60).unwrap(), 0x0012_3456_789A_BCDE);
+    assert_eq!(read.read_u64_bits(1).unwrap(), 0x1);
+    assert_eq!(read.align_to_byte(), 7);
+
+    let mut byte = [0];
+    assert_eq!(read.read_bits(&mut byte, 0, 8).unwrap(), 8);
+    assert_eq!(byte[0], 0xAB);
+
+    // Missing bits are zeros when peeking past the end.
+    assert_eq!(read.peek_bits(64).unwrap(), 0x7F);
+    assert_eq!(read.read_u64_bits(7).unwrap(), 0x7F);
+
+    let err = read.skip_bits(2).unwrap_err();
+    assert!(TruncatedStream::is_truncated(&err));
+    assert_eq!(read.peek_bits(8).unwrap(), 0);
+}
+
+#[test]
+fn test_u64_random() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(11);
+
+    let ops: Vec<(u64, usize)> = (0..1000)
+        .map(|_| {
+            let len = rng.gen_range(0..=64);
+            let value = if len == 64 {
+                rng.gen()
+            } else {
+                rng.gen::<u64>() & ((1 << len) - 1)
+            };
+            (value, len)
+        })
+        .collect();
+
+    let mut buffer = Vec::new();
+    let mut write = WriteBits::new(&mut buffer);
+    for &(value, len) in &ops {
+        write.write_u64_bits(value, len).unwrap();
+    }
+    write.finish().unwrap();
+
+    let mut read = ReadBits::new(&buffer[..]);
+    for &(value, len) in &ops {
+        assert_eq!(read.peek_bits(len).unwrap(), value);
+        assert_eq!(read.read_u64_bits(len).unwrap(), value);
+    }
+}
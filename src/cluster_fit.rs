@@ -1,6 +1,9 @@
 use std::ops::{Add, AddAssign, Mul, Sub};
 
-use crate::math::{pca_axis, Region3, Vec3, Zero};
+use crate::{
+    math::{pca_axis, Region3, Rgb32F, Rgb565, Vec3, Zero},
+    simd,
+};
 
 pub struct ClusterFit<T, const N: usize> {
     pub endpoints: (T, T),
@@ -168,6 +171,156 @@ where
     }
 }
 
+/// Fits endpoints to the extreme projections of samples on the principal axis.
+///
+/// Much faster than [`cluster_fit`] but yields lower quality.
+pub fn range_fit<T, const I: usize, const N: usize>(
+    samples: &[T],
+    remap_endpoints: impl Fn(T, T) -> (T, T),
+    error: impl Fn(T, T) -> f32 + Copy,
+) -> ClusterFit<T, N>
+where
+    T: Sample,
+{
+    assert!(samples.len() <= N);
+
+    let axis = T::principal_axis(samples);
+
+    let mut min = (0, f32::MAX);
+    let mut max = (0, f32::MIN);
+
+    for (i, &sample) in samples.iter().enumerate() {
+        let projection = sample.project(axis);
+        if projection < min.1 {
+            min = (i, projection);
+        }
+        if projection > max.1 {
+            max = (i, projection);
+        }
+    }
+
+    let endpoints = if samples.is_empty() || min.0 == max.0 {
+        T::fallback_endpoints(samples)
+    } else {
+        (samples[min.0], samples[max.0])
+    };
+
+    let endpoints = remap_endpoints(endpoints.0, endpoints.1);
+    let palette = build_palette::<T, I>(endpoints.0, endpoints.1);
+
+    let mut indices = [0; N];
+    let mut total_error = 0.0f32;
+
+    for (i, &sample) in samples.iter().enumerate() {
+        let (idx, e) = index_error(sample, &palette, error);
+        indices[i] = idx;
+        total_error += e;
+    }
+
+    ClusterFit {
+        endpoints,
+        indices,
+        error: total_error,
+    }
+}
+
+/// Result of [`fit`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fit {
+    /// Quantized endpoints.
+    pub endpoints: (Rgb565, Rgb565),
+
+    /// Index of lattice color for each point.
+    /// 0 is the first endpoint, 3 is the second endpoint,
+    /// 1 and 2 are 1/3 and 2/3 of the way between them.
+    pub indices: [u8; 16],
+
+    /// Sum of squared errors of points against assigned lattice colors.
+    pub sse: f32,
+}
+
+/// Quantizes color to the nearest representable in 5:6:5 format.
+pub(crate) fn quantize(v: Vec3) -> Rgb565 {
+    let rgb = Rgb32F::from(v);
+    let (r, g, b) = (rgb.r(), rgb.g(), rgb.b());
+    // Float to integer casts saturate, so out of range channels are clamped.
+    Rgb565::new_clamped(
+        (r * 31.0).round() as u8,
+        (g * 63.0).round() as u8,
+        (b * 31.0).round() as u8,
+    )
+}
+
+/// Assigns points to the nearest of four lattice colors between endpoints.
+fn assign(points: &[Vec3; 16], endpoints: (Rgb565, Rgb565)) -> ([u8; 16], f32) {
+    let c0: Vec3 = endpoints.0.into_f32().into();
+    let c1: Vec3 = endpoints.1.into_f32().into();
+    let lattice = build_palette::<Vec3, 4>(c0, c1);
+
+    simd::nearest4(points, &lattice)
+}
+
+/// Fits BC1 endpoints to 16 points by iterative refinement.
+///
+/// Starts from extremes of the principal axis, then alternates between
+/// assigning points to lattice colors and solving endpoints by least squares.
+/// Stops after `max_iterations`, when SSE is not greater than `target_sse`
+/// or when SSE stops improving.
+pub fn fit(points: &[Vec3; 16], max_iterations: usize, target_sse: f32) -> Fit {
+    let axis = pca_axis(points);
+
+    let mut min = (points[0], f32::MAX);
+    let mut max = (points[0], f32::MIN);
+
+    for &point in points {
+        let projection = axis.dot(point);
+        if projection < min.1 {
+            min = (point, projection);
+        }
+        if projection > max.1 {
+            max = (point, projection);
+        }
+    }
+
+    let endpoints = (quantize(min.0), quantize(max.0));
+    let (indices, sse) = assign(points, endpoints);
+
+    let mut best = Fit {
+        endpoints,
+        indices,
+        sse,
+    };
+
+    for _ in 0..max_iterations {
+        if best.sse <= target_sse {
+            break;
+        }
+
+        let weights = best.indices.map(|idx| idx as f32 / 3.0);
+        let m = simd::least_squares(&weights, points);
+
+        let Some((c0, c1)) = solve_moments(m.a, m.b, m.c, m.x, m.y) else {
+            break;
+        };
+
+        let endpoints = (quantize(c0), quantize(c1));
+        let (indices, sse) = assign(points, endpoints);
+
+        if sse >= best.sse {
+            // Improvement stalled.
+            break;
+        }
+
+        best = Fit {
+            endpoints,
+            indices,
+            sse,
+        };
+    }
+
+    best
+}
+
 fn solve_endpoints<T, const N: usize>(weights: [f32; N], samples: &[T]) -> Option<(T, T)>
 where
     T: Sample,
@@ -196,6 +349,15 @@ where
         Y += s * w;
     }
 
+    solve_moments(A, B, C, X, Y)
+}
+
+/// Solves least squares system for endpoints from accumulated sums.
+#[allow(non_snake_case)]
+fn solve_moments<T>(A: f32, B: f32, C: f32, X: T, Y: T) -> Option<(T, T)>
+where
+    T: Sample,
+{
     let D = A * C - B * B;
 
     if D.abs() < 1e-8 {
@@ -249,3 +411,49 @@ where
 
     (best_index, best_error)
 }
+
+#[test]
+fn test_fit_monotonic() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(42);
+
+    for _ in 0..100 {
+        let points: [Vec3; 16] =
+            std::array::from_fn(|_| Vec3::new(rng.gen(), rng.gen(), rng.gen()));
+
+        let mut last = f32::MAX;
+        for iterations in 0..10 {
+            let fit = fit(&points, iterations, 0.0);
+            assert!(fit.sse <= last);
+            last = fit.sse;
+        }
+    }
+}
+
+#[test]
+fn test_fit_two_colors() {
+    let a = Rgb565::new(31, 10, 3);
+    let b = Rgb565::new(2, 60, 20);
+
+    let points: [Vec3; 16] = std::array::from_fn(|i| {
+        if i % 3 == 0 {
+            a.into_f32().into()
+        } else {
+            b.into_f32().into()
+        }
+    });
+
+    let fit = fit(&points, 8, 0.0);
+    assert_eq!(fit.sse, 0.0);
+
+    let (c0, c1) = fit.endpoints;
+    assert!((c0, c1) == (a, b) || (c0, c1) == (b, a));
+
+    for (i, &idx) in fit.indices.iter().enumerate() {
+        let expected = if i % 3 == 0 { a } else { b };
+        let actual = if idx == 0 { c0 } else { c1 };
+        assert!(idx == 0 || idx == 3);
+        assert_eq!(actual, expected);
+    }
+}
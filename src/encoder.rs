@@ -1 +1,1314 @@
-pub struct Encoder {}
+//! Helpers to encode whole images into arrays of blocks.
+//!
+//! Blocks are stored row-major, x fastest, then y, then z.
+
+use std::{
+    fmt,
+    io::{Seek, Write},
+};
+
+use crate::{
+    bc1, bc4, bc5,
+    image::Image,
+    jackal::{
+        compress_texture_with_chunks, AnyBlock, CompressOptions, Format, MetaChunk,
+        SuperBlockChoice, SuperBlockSize, TextureMeta,
+    },
+    math::{Rg8U, Rgb32F, Rgb8U, Rgba32F, Rgba8U, R8U},
+    Extent,
+};
+
+pub use crate::jackal::choose_super_block_size;
+
+/// Error of image encoding.
+///
+/// New variants may be added, including ones enabled by optional features.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum EncodeError {
+    /// Image size is zero or does not match number of pixels.
+    InvalidExtent,
+
+    /// Super-block size dimensions are not powers of two.
+    InvalidSuperBlockSize,
+
+    /// Encoder does not support the format.
+    UnsupportedFormat(Format),
+
+    /// Encoder does not support color type of the image.
+    #[cfg(feature = "image")]
+    UnsupportedColorType(::image::ColorType),
+
+    /// Progress callback requested cancellation.
+    Cancelled,
+
+    Io(std::io::Error),
+}
+
+impl From<std::io::Error> for EncodeError {
+    #[inline(always)]
+    fn from(err: std::io::Error) -> Self {
+        EncodeError::Io(err)
+    }
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::InvalidExtent => f.write_str("invalid image extent"),
+            EncodeError::InvalidSuperBlockSize => f.write_str("invalid super-block size"),
+            EncodeError::UnsupportedFormat(format) => write!(f, "unsupported format {format:?}"),
+            #[cfg(feature = "image")]
+            EncodeError::UnsupportedColorType(color) => {
+                write!(f, "unsupported color type {color:?}")
+            }
+            EncodeError::Cancelled => f.write_str("cancelled"),
+            EncodeError::Io(err) => write!(f, "I/O error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EncodeError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Summary of encoded texture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncodeStats {
+    /// Extent of the texture in blocks.
+    pub extent: Extent,
+
+    /// Number of encoded blocks.
+    pub blocks_count: usize,
+
+    /// Total number of bytes written.
+    pub compressed_size: u64,
+
+    /// Size of each super-block payload in bytes.
+    pub super_block_sizes: Vec<u32>,
+}
+
+/// Region of the image encoded with its own effort, e.g. important part of a sprite atlas.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct QualityRegion {
+    /// `[x, y, width, height]` of the region in pixels.
+    pub rect_px: [u32; 4],
+
+    /// Effort used for blocks covering any pixel of the region.
+    pub effort: EncodeEffort,
+}
+
+/// Encodes images into Jackal streams.
+///
+/// Tiles image into blocks, encodes them and compresses the blocks.
+#[derive(Clone, Debug)]
+pub struct Encoder {
+    format: Format,
+    effort: EncodeEffort,
+    regions: Vec<QualityRegion>,
+    dither: bool,
+    options: CompressOptions,
+    parallel: bool,
+}
+
+impl Encoder {
+    pub fn new(format: Format) -> Self {
+        Encoder {
+            format,
+            effort: EncodeEffort::Default,
+            regions: Vec::new(),
+            dither: false,
+            options: CompressOptions::default(),
+            parallel: false,
+        }
+    }
+
+    /// Sets effort of the block encoder.
+    pub fn effort(mut self, effort: EncodeEffort) -> Self {
+        self.effort = effort;
+        self
+    }
+
+    /// Sets regions of BC1 images encoded with their own effort instead of the base one.
+    ///
+    /// Where regions overlap the highest effort wins.
+    /// Parts of regions outside of the image are ignored.
+    pub fn quality_regions(mut self, regions: &[QualityRegion]) -> Self {
+        self.regions = regions.to_vec();
+        self
+    }
+
+    /// Enables or disables dithering of texel indices within blocks.
+    pub fn dither(mut self, dither: bool) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    /// Overrides size of super-blocks chosen from the extent.
+    pub fn super_block_override(mut self, super_block_size: Option<SuperBlockSize>) -> Self {
+        self.options.super_block_size =
+            super_block_size.map_or(SuperBlockChoice::FromExtent, SuperBlockChoice::Fixed);
+        self
+    }
+
+    /// Sets how size of super-blocks is chosen.
+    pub fn super_block_choice(mut self, choice: SuperBlockChoice) -> Self {
+        self.options.super_block_size = choice;
+        self
+    }
+
+    /// Sets colorspace and alpha mode recorded in the header.
+    pub fn meta(mut self, meta: TextureMeta) -> Self {
+        self.options.meta = meta;
+        self
+    }
+
+    /// Enables or disables super-block checksums.
+    pub fn checksums(mut self, checksums: bool) -> Self {
+        self.options.checksums = checksums;
+        self
+    }
+
+    /// Encodes blocks on all available threads.
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Encodes row-major RGB8 pixels and writes Jackal stream.
+    pub fn encode_rgb8(
+        &self,
+        width: u32,
+        height: u32,
+        pixels: &[Rgb8U],
+        write: impl Write + Seek,
+    ) -> Result<EncodeStats, EncodeError> {
+        self.validate(Format::BC1, width, height, pixels.len())?;
+
+        let get_pixel =
+            |x: u32, y: u32| pixels[x as usize + y as usize * width as usize].into_f32();
+
+        let efforts = block_efforts(width, height, self.effort, &self.regions);
+        let blocks_width = width.div_ceil(4) as usize;
+        let effort_of = |bx: u32, by: u32| efforts[bx as usize + by as usize * blocks_width];
+
+        let blocks = if self.parallel {
+            encode_bc1_blocks_parallel(width, height, &get_pixel, &effort_of, self.dither)
+        } else {
+            encode_bc1_blocks(width, height, &get_pixel, &effort_of, self.dither)
+        };
+
+        self.write_blocks(width, height, &blocks, write)
+    }
+
+    /// Encodes row-major single channel pixels into BC4 and writes Jackal stream.
+    pub fn encode_r8(
+        &self,
+        width: u32,
+        height: u32,
+        pixels: &[R8U],
+        write: impl Write + Seek,
+    ) -> Result<EncodeStats, EncodeError> {
+        self.validate(Format::BC4, width, height, pixels.len())?;
+
+        let blocks = encode_tiled(
+            width,
+            height,
+            |x, y| pixels[x as usize + y as usize * width as usize].into_f32(),
+            bc4::Block::encode,
+        );
+
+        self.write_blocks(width, height, &blocks, write)
+    }
+
+    /// Encodes row-major two channel pixels, e.g. normal map XY, into BC5 and writes Jackal stream.
+    pub fn encode_rg8(
+        &self,
+        width: u32,
+        height: u32,
+        pixels: &[Rg8U],
+        write: impl Write + Seek,
+    ) -> Result<EncodeStats, EncodeError> {
+        self.validate(Format::BC5, width, height, pixels.len())?;
+
+        let blocks = encode_tiled(
+            width,
+            height,
+            |x, y| pixels[x as usize + y as usize * width as usize].into_f32(),
+            bc5::Block::encode,
+        );
+
+        self.write_blocks(width, height, &blocks, write)
+    }
+
+    /// Checks that encoder format is `format` and size matches number of pixels.
+    fn validate(
+        &self,
+        format: Format,
+        width: u32,
+        height: u32,
+        pixels: usize,
+    ) -> Result<(), EncodeError> {
+        if self.format != format {
+            return Err(EncodeError::UnsupportedFormat(self.format));
+        }
+
+        if width == 0 || height == 0 || pixels as u64 != width as u64 * height as u64 {
+            return Err(EncodeError::InvalidExtent);
+        }
+
+        if let SuperBlockChoice::Fixed(super_block_size) = self.options.super_block_size {
+            if !super_block_size.is_valid() {
+                return Err(EncodeError::InvalidSuperBlockSize);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compresses blocks covering image of given size.
+    ///
+    /// Size of the image is recorded in [`MetaChunk::pixel_size`] chunk.
+    fn write_blocks<B: AnyBlock>(
+        &self,
+        width: u32,
+        height: u32,
+        blocks: &[B],
+        mut write: impl Write + Seek,
+    ) -> Result<EncodeStats, EncodeError> {
+        let extent = Extent::D2 { width, height }.bc_block_extent();
+
+        let start = write.stream_position()?;
+        let chunks = [MetaChunk::pixel_size(width, height)];
+        let (_, table) =
+            compress_texture_with_chunks(extent, blocks, self.options, &chunks, &mut write)?;
+        let end = write.seek(std::io::SeekFrom::End(0))?;
+
+        Ok(EncodeStats {
+            extent,
+            blocks_count: blocks.len(),
+            compressed_size: end - start,
+            super_block_sizes: table.iter().map(|sb| sb.size).collect(),
+        })
+    }
+
+    /// Encodes RGB8 image and writes Jackal stream.
+    pub fn encode_image(
+        &self,
+        image: &Image<Rgb8U>,
+        write: impl Write + Seek,
+    ) -> Result<EncodeStats, EncodeError> {
+        self.encode_rgb8(image.width(), image.height(), image.pixels(), write)
+    }
+}
+
+/// How much time block encoder spends searching for better endpoints.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EncodeEffort {
+    /// Endpoints are extremes of the principal axis.
+    Fast,
+
+    /// Endpoints are found with cluster fit.
+    ///
+    /// Not accelerated by `simd` feature.
+    #[default]
+    Default,
+
+    /// Cluster fit is compared with iterative least squares refinement,
+    /// the one with lower error wins.
+    ///
+    /// Refinement uses SIMD kernels with `simd` feature.
+    High,
+}
+
+/// Encodes image of given size into BC1 blocks.
+///
+/// Returns extent of the image in pixels and blocks covering it.
+///
+/// When size is not a multiple of 4, edge blocks are padded
+/// by repeating the last column and row of the image.
+pub fn encode_bc1_image(
+    width: u32,
+    height: u32,
+    get_pixel: impl Fn(u32, u32) -> Rgb32F,
+    effort: EncodeEffort,
+) -> (Extent, Vec<bc1::Block>) {
+    let blocks = encode_bc1_blocks(width, height, &get_pixel, &|_, _| effort, false);
+    (Extent::D2 { width, height }, blocks)
+}
+
+/// Returns effort of each block covering the image, row-major.
+///
+/// Blocks covering any pixel of a region use effort of the region,
+/// the highest one where regions overlap, and `base` effort otherwise.
+/// Regions are clamped to the image.
+fn block_efforts(
+    width: u32,
+    height: u32,
+    base: EncodeEffort,
+    regions: &[QualityRegion],
+) -> Vec<EncodeEffort> {
+    let blocks_width = width.div_ceil(4);
+    let blocks_height = height.div_ceil(4);
+
+    let mut efforts = vec![None; blocks_width as usize * blocks_height as usize];
+
+    for region in regions {
+        let [x, y, region_width, region_height] = region.rect_px;
+        let x_end = x.saturating_add(region_width).min(width);
+        let y_end = y.saturating_add(region_height).min(height);
+
+        for by in y / 4..y_end.div_ceil(4) {
+            for bx in x / 4..x_end.div_ceil(4) {
+                let effort = &mut efforts[bx as usize + by as usize * blocks_width as usize];
+                *effort = (*effort).max(Some(region.effort));
+            }
+        }
+    }
+
+    efforts
+        .into_iter()
+        .map(|effort| effort.unwrap_or(base))
+        .collect()
+}
+
+/// Encodes blocks covering the image on current thread.
+///
+/// `effort_of` returns effort of the block at column `bx` and row `by` of blocks.
+fn encode_bc1_blocks(
+    width: u32,
+    height: u32,
+    get_pixel: &impl Fn(u32, u32) -> Rgb32F,
+    effort_of: &impl Fn(u32, u32) -> EncodeEffort,
+    dither: bool,
+) -> Vec<bc1::Block> {
+    let blocks_width = width.div_ceil(4);
+    let blocks_height = height.div_ceil(4);
+
+    let mut blocks = vec![bc1::Block::BLACK; blocks_width as usize * blocks_height as usize];
+
+    if !blocks.is_empty() {
+        for (by, row) in blocks.chunks_mut(blocks_width as usize).enumerate() {
+            encode_bc1_block_row(width, height, by as u32, get_pixel, effort_of, dither, row);
+        }
+    }
+
+    blocks
+}
+
+/// Encodes one row of blocks, clamping pixel coordinates to the image.
+fn encode_bc1_block_row(
+    width: u32,
+    height: u32,
+    by: u32,
+    get_pixel: &impl Fn(u32, u32) -> Rgb32F,
+    effort_of: &impl Fn(u32, u32) -> EncodeEffort,
+    dither: bool,
+    row: &mut [bc1::Block],
+) {
+    for (bx, block) in row.iter_mut().enumerate() {
+        let mut colors = [[Rgb32F::BLACK; 4]; 4];
+
+        for (i, colors_row) in colors.iter_mut().enumerate() {
+            for (j, color) in colors_row.iter_mut().enumerate() {
+                let x = (bx as u32 * 4 + j as u32).min(width - 1);
+                let y = (by * 4 + i as u32).min(height - 1);
+                *color = get_pixel(x, y);
+            }
+        }
+
+        *block = bc1::Block::encode_with_dither(colors, effort_of(bx as u32, by), dither);
+    }
+}
+
+/// Same as [`encode_bc1_image`] but splits rows of blocks between threads.
+fn encode_bc1_blocks_parallel(
+    width: u32,
+    height: u32,
+    get_pixel: &(impl Fn(u32, u32) -> Rgb32F + Sync),
+    effort_of: &(impl Fn(u32, u32) -> EncodeEffort + Sync),
+    dither: bool,
+) -> Vec<bc1::Block> {
+    let blocks_width = width.div_ceil(4) as usize;
+    let blocks_height = height.div_ceil(4) as usize;
+
+    let mut blocks = vec![bc1::Block::BLACK; blocks_width * blocks_height];
+
+    if blocks.is_empty() {
+        return blocks;
+    }
+
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let rows_per_thread = blocks_height.div_ceil(threads);
+
+    std::thread::scope(|scope| {
+        for (chunk_index, chunk) in blocks
+            .chunks_mut(rows_per_thread * blocks_width)
+            .enumerate()
+        {
+            scope.spawn(move || {
+                for (row_index, row) in chunk.chunks_mut(blocks_width).enumerate() {
+                    let by = (chunk_index * rows_per_thread + row_index) as u32;
+                    encode_bc1_block_row(width, height, by, get_pixel, effort_of, dither, row);
+                }
+            });
+        }
+    });
+
+    blocks
+}
+
+/// Encodes row-major RGB8 pixels into BC1 blocks.
+///
+/// See [`encode_bc1_image`].
+pub fn encode_bc1_rgb8(
+    width: u32,
+    height: u32,
+    pixels: &[Rgb8U],
+    effort: EncodeEffort,
+) -> (Extent, Vec<bc1::Block>) {
+    assert_eq!(pixels.len(), width as usize * height as usize);
+
+    encode_bc1_image(
+        width,
+        height,
+        |x, y| pixels[x as usize + y as usize * width as usize].into_f32(),
+        effort,
+    )
+}
+
+/// Decodes BC1 blocks into row-major RGB8 pixels.
+///
+/// Texels of edge blocks outside of the extent are discarded.
+pub fn decode_bc1_image(extent: Extent, blocks: &[bc1::Block]) -> Vec<Rgb8U> {
+    decode_image(extent, blocks, |block| block.decode_rgb8())
+}
+
+/// Decodes blocks of any format into row-major RGB8 pixels.
+pub(crate) fn decode_image<B: Copy>(
+    extent: Extent,
+    blocks: &[B],
+    decode: impl Fn(B) -> [[Rgb8U; 4]; 4],
+) -> Vec<Rgb8U> {
+    let [width, height, depth] = extent.raw_size();
+
+    let blocks_width = width.div_ceil(4);
+    let blocks_height = height.div_ceil(4);
+
+    assert_eq!(
+        blocks.len(),
+        blocks_width as usize * blocks_height as usize * depth as usize
+    );
+
+    let mut pixels = vec![Rgb8U::BLACK; width as usize * height as usize * depth as usize];
+
+    for z in 0..depth {
+        for by in 0..blocks_height {
+            for bx in 0..blocks_width {
+                let index = bx as usize
+                    + by as usize * blocks_width as usize
+                    + z as usize * blocks_width as usize * blocks_height as usize;
+
+                let texels = decode(blocks[index]);
+
+                for y in by * 4..(by * 4 + 4).min(height) {
+                    for x in bx * 4..(bx * 4 + 4).min(width) {
+                        let texel = texels[(y % 4) as usize][(x % 4) as usize];
+
+                        pixels[x as usize
+                            + y as usize * width as usize
+                            + z as usize * (width * height) as usize] = texel;
+                    }
+                }
+            }
+        }
+    }
+
+    pixels
+}
+
+/// Filter used to downsample mip levels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MipFilter {
+    /// Average of the source texels covered by the destination texel.
+    #[default]
+    Box,
+
+    /// Tent filter twice as wide as the box, softer than [`MipFilter::Box`].
+    Triangle,
+}
+
+/// Generates mip chain from row-major sRGB pixels.
+///
+/// First level is the source image, each following level halves the size
+/// until 1x1 is reached. Filtering happens in linear light.
+pub fn generate_mips(
+    width: u32,
+    height: u32,
+    pixels: &[Rgb8U],
+    filter: MipFilter,
+) -> Vec<(u32, u32, Vec<Rgb8U>)> {
+    assert_eq!(pixels.len(), width as usize * height as usize);
+
+    let base = pixels
+        .iter()
+        .map(|p| {
+            let linear = p.to_linear_f32();
+            [linear.r(), linear.g(), linear.b(), 1.0]
+        })
+        .collect();
+
+    let levels = generate_linear_mips(width, height, base, filter);
+
+    std::iter::once((width, height, pixels.to_vec()))
+        .chain(levels.map(|(w, h, level)| {
+            let level = level
+                .iter()
+                .map(|&[r, g, b, _]| Rgb32F::new(r, g, b).linear_to_srgb8())
+                .collect();
+            (w, h, level)
+        }))
+        .collect()
+}
+
+/// Same as [`generate_mips`] but for pixels with straight alpha.
+///
+/// Colors are weighted by alpha so fully transparent texels
+/// do not bleed into visible ones. Alpha itself is filtered linearly.
+pub fn generate_mips_rgba(
+    width: u32,
+    height: u32,
+    pixels: &[Rgba8U],
+    filter: MipFilter,
+) -> Vec<(u32, u32, Vec<Rgba8U>)> {
+    assert_eq!(pixels.len(), width as usize * height as usize);
+
+    let base = pixels
+        .iter()
+        .map(|p| {
+            let linear = p.to_linear_f32();
+            let a = linear.a();
+            [linear.r() * a, linear.g() * a, linear.b() * a, a]
+        })
+        .collect();
+
+    let levels = generate_linear_mips(width, height, base, filter);
+
+    std::iter::once((width, height, pixels.to_vec()))
+        .chain(levels.map(|(w, h, level)| {
+            let level = level
+                .iter()
+                .map(|&[r, g, b, a]| {
+                    if a <= 0.0 {
+                        return Rgba8U::TRANSPARENT;
+                    }
+                    Rgba32F::new(r / a, g / a, b / a, a).linear_to_srgb8()
+                })
+                .collect();
+            (w, h, level)
+        }))
+        .collect()
+}
+
+/// Yields levels below the base, each filtered from the unquantized previous one.
+fn generate_linear_mips(
+    width: u32,
+    height: u32,
+    base: Vec<[f32; 4]>,
+    filter: MipFilter,
+) -> impl Iterator<Item = (u32, u32, Vec<[f32; 4]>)> {
+    let mut level = (width, height, base);
+
+    std::iter::from_fn(move || {
+        let (w, h, ref pixels) = level;
+        if w <= 1 && h <= 1 {
+            return None;
+        }
+
+        let dw = (w / 2).max(1);
+        let dh = (h / 2).max(1);
+
+        // Horizontal pass, then vertical.
+        let kx = mip_kernel(w, dw, filter);
+        let mut rows = vec![[0.0; 4]; dw as usize * h as usize];
+        for y in 0..h as usize {
+            for (x, taps) in kx.iter().enumerate() {
+                rows[x + y * dw as usize] = apply_taps(taps, |i| pixels[i + y * w as usize]);
+            }
+        }
+
+        let ky = mip_kernel(h, dh, filter);
+        let mut next = vec![[0.0; 4]; dw as usize * dh as usize];
+        for (y, taps) in ky.iter().enumerate() {
+            for x in 0..dw as usize {
+                next[x + y * dw as usize] = apply_taps(taps, |i| rows[x + i * dw as usize]);
+            }
+        }
+
+        level = (dw, dh, next);
+        Some(level.clone())
+    })
+}
+
+/// Returns normalized taps of the 1D kernel for each destination texel.
+///
+/// Destination texel `i` covers source interval `[i * scale, (i + 1) * scale)`,
+/// so odd sizes get fractional weights at the edges instead of shifting content.
+fn mip_kernel(src: u32, dst: u32, filter: MipFilter) -> Vec<Vec<(usize, f32)>> {
+    let scale = src as f32 / dst as f32;
+
+    (0..dst)
+        .map(|i| {
+            let start = i as f32 * scale;
+            let end = start + scale;
+            let center = start + scale * 0.5;
+
+            let mut taps = Vec::new();
+            for j in 0..src as usize {
+                let weight = match filter {
+                    MipFilter::Box => (end.min(j as f32 + 1.0) - start.max(j as f32)).max(0.0),
+                    MipFilter::Triangle => (1.0 - (j as f32 + 0.5 - center).abs() / scale).max(0.0),
+                };
+                if weight > 0.0 {
+                    taps.push((j, weight));
+                }
+            }
+
+            let total: f32 = taps.iter().map(|&(_, w)| w).sum();
+            for tap in &mut taps {
+                tap.1 /= total;
+            }
+            taps
+        })
+        .collect()
+}
+
+fn apply_taps(taps: &[(usize, f32)], get: impl Fn(usize) -> [f32; 4]) -> [f32; 4] {
+    let mut sum = [0.0; 4];
+    for &(i, weight) in taps {
+        let value = get(i);
+        for c in 0..4 {
+            sum[c] += value[c] * weight;
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+fn gradient(width: u32, height: u32) -> Vec<Rgb8U> {
+    let mut pixels = Vec::new();
+    for _ in 0..height {
+        for x in 0..width {
+            pixels.push(Rgb8U::new((x * 40) as u8, (x * 20) as u8, 0));
+        }
+    }
+    pixels
+}
+
+/// Alpha below this value makes texel transparent in BC1.
+#[cfg(feature = "image")]
+const PUNCH_THROUGH_THRESHOLD: f32 = 0.5;
+
+/// Encodes image from `image` crate into blocks of the format.
+///
+/// Returns extent of the image in pixels and raw bytes of blocks covering it.
+/// 16-bit channels are rounded to nearest 8-bit value,
+/// grayscale images are expanded to RGB, BC4 takes red channel.
+/// BC1 uses punch-through alpha for texels with alpha below one half.
+///
+/// BC1, BC2, BC3 and BC4 formats are supported.
+/// Floating-point images are rejected with [`EncodeError::UnsupportedColorType`].
+#[cfg(feature = "image")]
+pub fn from_dynamic_image(
+    img: &::image::DynamicImage,
+    format: Format,
+    effort: EncodeEffort,
+) -> Result<(Extent, Vec<u8>), EncodeError> {
+    use crate::{bc2, bc3, math::R32F};
+    use ::image::DynamicImage;
+
+    let pixels: Vec<Rgba8U> = match img {
+        DynamicImage::ImageLuma8(img) => img
+            .pixels()
+            .map(|p| Rgba8U::new(p[0], p[0], p[0], 255))
+            .collect(),
+        DynamicImage::ImageLumaA8(img) => img
+            .pixels()
+            .map(|p| Rgba8U::new(p[0], p[0], p[0], p[1]))
+            .collect(),
+        DynamicImage::ImageRgb8(img) => img
+            .pixels()
+            .map(|p| Rgba8U::new(p[0], p[1], p[2], 255))
+            .collect(),
+        DynamicImage::ImageRgba8(img) => img.pixels().map(|p| Rgba8U::from_bytes(p.0)).collect(),
+        DynamicImage::ImageLuma16(img) => img
+            .pixels()
+            .map(|p| {
+                let l = unorm16_to_unorm8(p[0]);
+                Rgba8U::new(l, l, l, 255)
+            })
+            .collect(),
+        DynamicImage::ImageLumaA16(img) => img
+            .pixels()
+            .map(|p| {
+                let l = unorm16_to_unorm8(p[0]);
+                Rgba8U::new(l, l, l, unorm16_to_unorm8(p[1]))
+            })
+            .collect(),
+        DynamicImage::ImageRgb16(img) => img
+            .pixels()
+            .map(|p| {
+                let [r, g, b] = p.0.map(unorm16_to_unorm8);
+                Rgba8U::new(r, g, b, 255)
+            })
+            .collect(),
+        DynamicImage::ImageRgba16(img) => img
+            .pixels()
+            .map(|p| Rgba8U::from_bytes(p.0.map(unorm16_to_unorm8)))
+            .collect(),
+        img => return Err(EncodeError::UnsupportedColorType(img.color())),
+    };
+
+    let (width, height) = (img.width(), img.height());
+    if width == 0 || height == 0 {
+        return Err(EncodeError::InvalidExtent);
+    }
+
+    let get_pixel = |x: u32, y: u32| pixels[x as usize + y as usize * width as usize].into_f32();
+
+    let bytes = match format {
+        Format::BC1 => encode_tiled(width, height, get_pixel, |colors| {
+            bc1::Block::encode_rgba(colors, PUNCH_THROUGH_THRESHOLD, effort).bytes()
+        })
+        .concat(),
+        Format::BC2 => encode_tiled(width, height, get_pixel, |colors| {
+            bc2::Block::encode_with_effort(colors, effort).bytes()
+        })
+        .concat(),
+        Format::BC3 => encode_tiled(width, height, get_pixel, |colors| {
+            bc3::Block::encode_with_alpha(colors).bytes()
+        })
+        .concat(),
+        Format::BC4 => encode_tiled(width, height, get_pixel, |colors| {
+            bc4::Block::encode(colors.map(|row| row.map(|c| R32F::new(c.r())))).bytes()
+        })
+        .concat(),
+        _ => return Err(EncodeError::UnsupportedFormat(format)),
+    };
+
+    Ok((Extent::D2 { width, height }, bytes))
+}
+
+/// Rounds 16-bit normalized value to nearest 8-bit one.
+#[cfg(feature = "image")]
+fn unorm16_to_unorm8(value: u16) -> u8 {
+    ((value as u32 * 255 + 32767) / 65535) as u8
+}
+
+/// Encodes blocks covering the image on current thread.
+///
+/// Edge blocks are padded by repeating the last column and row of the image.
+fn encode_tiled<T: Copy, B>(
+    width: u32,
+    height: u32,
+    get_pixel: impl Fn(u32, u32) -> T,
+    encode: impl Fn([[T; 4]; 4]) -> B,
+) -> Vec<B> {
+    let blocks_width = width.div_ceil(4);
+    let blocks_height = height.div_ceil(4);
+
+    let mut blocks = Vec::with_capacity(blocks_width as usize * blocks_height as usize);
+
+    for by in 0..blocks_height {
+        for bx in 0..blocks_width {
+            let colors = std::array::from_fn(|i| {
+                std::array::from_fn(|j| {
+                    let x = (bx * 4 + j as u32).min(width - 1);
+                    let y = (by * 4 + i as u32).min(height - 1);
+                    get_pixel(x, y)
+                })
+            });
+            blocks.push(encode(colors));
+        }
+    }
+
+    blocks
+}
+
+#[test]
+fn test_edge_clamping() {
+    for width in [5, 6] {
+        let pixels = gradient(width, 3);
+
+        let captured = std::cell::RefCell::new(Vec::new());
+        let (extent, blocks) = encode_bc1_image(
+            width,
+            3,
+            |x, y| {
+                captured.borrow_mut().push((x, y));
+                pixels[(x + y * width) as usize].into_f32()
+            },
+            EncodeEffort::Fast,
+        );
+
+        assert_eq!(extent, Extent::D2 { width, height: 3 });
+        assert_eq!(blocks.len(), 2);
+
+        // Second block repeats last column and row.
+        let captured = captured.into_inner();
+        let second = &captured[16..];
+        for i in 0..4 {
+            for j in 0..4 {
+                let x = (4 + j).min(width - 1);
+                let y = i.min(2);
+                assert_eq!(second[(i * 4 + j) as usize], (x, y));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_roundtrip_cropped() {
+    for width in [5, 6] {
+        let pixels = vec![Rgb8U::WHITE; width as usize * 7];
+
+        let (extent, blocks) = encode_bc1_rgb8(width, 7, &pixels, EncodeEffort::Default);
+        assert_eq!(blocks.len(), 4);
+
+        let decoded = decode_bc1_image(extent, &blocks);
+        assert_eq!(decoded, pixels);
+    }
+}
+
+#[test]
+fn test_gradient_error() {
+    let pixels = gradient(6, 6);
+
+    for effort in [
+        EncodeEffort::Fast,
+        EncodeEffort::Default,
+        EncodeEffort::High,
+    ] {
+        let (extent, blocks) = encode_bc1_rgb8(6, 6, &pixels, effort);
+        let decoded = decode_bc1_image(extent, &blocks);
+
+        assert_eq!(decoded.len(), pixels.len());
+        for (a, b) in pixels.iter().zip(&decoded) {
+            assert!(Rgb8U::distance(*a, *b) < 24.0, "{a:?} vs {b:?}");
+        }
+    }
+}
+
+#[test]
+fn test_encoder_end_to_end() {
+    let width = 67;
+    let height = 33;
+
+    let pixels: Vec<Rgb8U> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| Rgb8U::new((x * 3) as u8, (y * 7) as u8, 128)))
+        .collect();
+
+    let mut sequential = Vec::new();
+    let stats = Encoder::new(Format::BC1)
+        .effort(EncodeEffort::Fast)
+        .encode_rgb8(
+            width,
+            height,
+            &pixels,
+            std::io::Cursor::new(&mut sequential),
+        )
+        .unwrap();
+
+    assert_eq!(
+        stats.extent,
+        Extent::D2 {
+            width: 17,
+            height: 9
+        }
+    );
+    assert_eq!(stats.blocks_count, 17 * 9);
+    assert_eq!(stats.compressed_size, sequential.len() as u64);
+    // 17 blocks wide image is split into two 16x16 super-blocks.
+    assert_eq!(stats.super_block_sizes.len(), 2);
+
+    let (extent, blocks) =
+        crate::jackal::decompress_bc1_texture(std::io::Cursor::new(&sequential)).unwrap();
+    assert_eq!(extent, stats.extent);
+    assert_eq!(
+        blocks,
+        encode_bc1_rgb8(width, height, &pixels, EncodeEffort::Fast).1
+    );
+
+    let mut parallel = Vec::new();
+    Encoder::new(Format::BC1)
+        .effort(EncodeEffort::Fast)
+        .parallel(true)
+        .encode_rgb8(width, height, &pixels, std::io::Cursor::new(&mut parallel))
+        .unwrap();
+    assert_eq!(parallel, sequential);
+
+    let mut small = Vec::new();
+    let stats = Encoder::new(Format::BC1)
+        .effort(EncodeEffort::Fast)
+        .super_block_override(Some(SuperBlockSize {
+            width: 4,
+            height: 4,
+        }))
+        .encode_rgb8(width, height, &pixels, std::io::Cursor::new(&mut small))
+        .unwrap();
+    assert_eq!(stats.super_block_sizes.len(), 5 * 3);
+
+    let (_, small_blocks) =
+        crate::jackal::decompress_bc1_texture(std::io::Cursor::new(&small)).unwrap();
+    assert_eq!(small_blocks, blocks);
+
+    // Dithering changes only texel indices, same in parallel.
+    let encode_dithered = |parallel| {
+        let mut output = Vec::new();
+        Encoder::new(Format::BC1)
+            .effort(EncodeEffort::Fast)
+            .dither(true)
+            .parallel(parallel)
+            .encode_rgb8(width, height, &pixels, std::io::Cursor::new(&mut output))
+            .unwrap();
+        output
+    };
+    let dithered = encode_dithered(false);
+    assert_eq!(encode_dithered(true), dithered);
+
+    let (_, dithered_blocks) =
+        crate::jackal::decompress_bc1_texture(std::io::Cursor::new(&dithered)).unwrap();
+    assert_ne!(dithered_blocks, blocks);
+    for (d, b) in dithered_blocks.iter().zip(&blocks) {
+        assert_eq!((d.color0(), d.color1()), (b.color0(), b.color1()));
+    }
+}
+
+#[test]
+fn test_encoder_validation() {
+    let pixels = vec![Rgb8U::BLACK; 15];
+
+    let result =
+        Encoder::new(Format::BC1).encode_rgb8(4, 4, &pixels, std::io::Cursor::new(Vec::new()));
+    assert!(matches!(result, Err(EncodeError::InvalidExtent)));
+
+    let result = Encoder::new(Format::BC1).encode_rgb8(0, 0, &[], std::io::Cursor::new(Vec::new()));
+    assert!(matches!(result, Err(EncodeError::InvalidExtent)));
+
+    let result = Encoder::new(Format::BC1)
+        .super_block_override(Some(SuperBlockSize {
+            width: 3,
+            height: 4,
+        }))
+        .encode_rgb8(3, 5, &pixels, std::io::Cursor::new(Vec::new()));
+    assert!(matches!(result, Err(EncodeError::InvalidSuperBlockSize)));
+
+    let result =
+        Encoder::new(Format::BC4).encode_rgb8(3, 5, &pixels, std::io::Cursor::new(Vec::new()));
+    assert!(matches!(
+        result,
+        Err(EncodeError::UnsupportedFormat(Format::BC4))
+    ));
+}
+
+#[test]
+fn test_encoder_bc4_bc5() {
+    use crate::jackal::{decompress_block_texture, DecodeLimits};
+
+    let (width, height) = (10, 6);
+    let value = |x: u32, y: u32| (x * 20 + y * 7) as u8;
+
+    let pixels: Vec<R8U> = (0..width * height)
+        .map(|i| R8U::new(value(i % width, i / width)))
+        .collect();
+
+    let mut output = Vec::new();
+    let stats = Encoder::new(Format::BC4)
+        .encode_r8(width, height, &pixels, std::io::Cursor::new(&mut output))
+        .unwrap();
+    assert_eq!(stats.blocks_count, 3 * 2);
+
+    let texture: crate::jackal::BlockTexture<bc4::Block> =
+        decompress_block_texture(std::io::Cursor::new(&output), &DecodeLimits::default()).unwrap();
+    for y in 0..height {
+        for x in 0..width {
+            let block = texture.get(x / 4, y / 4, 0).unwrap();
+            let decoded = block.decode()[(y % 4) as usize][(x % 4) as usize].r();
+            assert!((decoded - value(x, y) as f32 / 255.0).abs() < 0.05);
+        }
+    }
+
+    // Normal map XY in red and green.
+    let pixels: Vec<Rg8U> = (0..width * height)
+        .map(|i| {
+            Rg8U::new(
+                value(i % width, i / width),
+                255 - value(i % width, i / width),
+            )
+        })
+        .collect();
+
+    let mut output = Vec::new();
+    Encoder::new(Format::BC5)
+        .encode_rg8(width, height, &pixels, std::io::Cursor::new(&mut output))
+        .unwrap();
+
+    let texture: crate::jackal::BlockTexture<bc5::Block> =
+        decompress_block_texture(std::io::Cursor::new(&output), &DecodeLimits::default()).unwrap();
+    for y in 0..height {
+        for x in 0..width {
+            let block = texture.get(x / 4, y / 4, 0).unwrap();
+            let decoded = block.decode()[(y % 4) as usize][(x % 4) as usize];
+            let expected = pixels[(x + y * width) as usize].into_f32();
+            assert!((decoded.r() - expected.r()).abs() < 0.05);
+            assert!((decoded.g() - expected.g()).abs() < 0.05);
+        }
+    }
+
+    assert!(matches!(
+        Encoder::new(Format::BC4).encode_rg8(
+            1,
+            1,
+            &[Rg8U::BLACK],
+            std::io::Cursor::new(Vec::new())
+        ),
+        Err(EncodeError::UnsupportedFormat(Format::BC4))
+    ));
+    assert!(matches!(
+        Encoder::new(Format::BC5).encode_r8(1, 1, &[R8U::BLACK], std::io::Cursor::new(Vec::new())),
+        Err(EncodeError::UnsupportedFormat(Format::BC5))
+    ));
+}
+
+#[test]
+fn test_mips_checkerboard() {
+    let pixels = [Rgb8U::BLACK, Rgb8U::WHITE, Rgb8U::WHITE, Rgb8U::BLACK];
+
+    for filter in [MipFilter::Box, MipFilter::Triangle] {
+        let mips = generate_mips(2, 2, &pixels, filter);
+        assert_eq!(mips.len(), 2);
+        assert_eq!(mips[0], (2, 2, pixels.to_vec()));
+
+        // Linear midpoint between black and white.
+        let (w, h, level) = &mips[1];
+        assert_eq!((*w, *h), (1, 1));
+        assert_eq!(level[0], Rgb8U::new(188, 188, 188));
+    }
+}
+
+#[test]
+fn test_mips_odd_size() {
+    let row = [10, 60, 200, 60, 10].map(|v| Rgb8U::new(v, v, v));
+    let pixels: Vec<_> = row.iter().chain(&row).chain(&row).copied().collect();
+
+    for filter in [MipFilter::Box, MipFilter::Triangle] {
+        let mips = generate_mips(5, 3, &pixels, filter);
+        let sizes: Vec<_> = mips.iter().map(|(w, h, _)| (*w, *h)).collect();
+        assert_eq!(sizes, [(5, 3), (2, 1), (1, 1)]);
+
+        // Symmetric content stays symmetric.
+        let (_, _, level) = &mips[1];
+        assert_eq!(level[0], level[1]);
+    }
+}
+
+#[test]
+fn test_mips_alpha_weighted() {
+    let red = Rgba8U::new(255, 0, 0, 255);
+    let pixels = [
+        red,
+        Rgba8U::new(0, 255, 0, 0),
+        Rgba8U::new(0, 0, 255, 0),
+        Rgba8U::new(255, 255, 255, 0),
+    ];
+
+    for filter in [MipFilter::Box, MipFilter::Triangle] {
+        let mips = generate_mips_rgba(2, 2, &pixels, filter);
+        assert_eq!(mips[1].2, [Rgba8U::new(255, 0, 0, 64)]);
+    }
+
+    let mips = generate_mips_rgba(1, 1, &[Rgba8U::TRANSPARENT], MipFilter::Box);
+    assert_eq!(mips.len(), 1);
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn test_from_dynamic_image() {
+    use ::image::{DynamicImage, ImageBuffer, Luma, LumaA, Rgb, Rgb32FImage, Rgba, Rgba32FImage};
+
+    assert_eq!(unorm16_to_unorm8(0), 0);
+    assert_eq!(unorm16_to_unorm8(u16::MAX), 255);
+    for value in 0..=255u8 {
+        assert_eq!(unorm16_to_unorm8(value as u16 * 257), value);
+    }
+    assert_eq!(unorm16_to_unorm8(128), 0);
+    assert_eq!(unorm16_to_unorm8(129), 1);
+
+    // Gray gradient in every accepted color type, 16-bit channels are expanded exactly.
+    let (width, height) = (5, 3);
+    let gray = |x: u32, y: u32| (x * 50 + y * 20) as u8;
+    let wide = |v: u8| v as u16 * 257;
+
+    let images = [
+        DynamicImage::from(ImageBuffer::from_fn(width, height, |x, y| {
+            Luma([gray(x, y)])
+        })),
+        DynamicImage::from(ImageBuffer::from_fn(width, height, |x, y| {
+            LumaA([gray(x, y), 255])
+        })),
+        DynamicImage::from(ImageBuffer::from_fn(width, height, |x, y| {
+            Rgb([gray(x, y); 3])
+        })),
+        DynamicImage::from(ImageBuffer::from_fn(width, height, |x, y| {
+            Rgba([gray(x, y), gray(x, y), gray(x, y), 255])
+        })),
+        DynamicImage::from(ImageBuffer::from_fn(width, height, |x, y| {
+            Luma([wide(gray(x, y))])
+        })),
+        DynamicImage::from(ImageBuffer::from_fn(width, height, |x, y| {
+            LumaA([wide(gray(x, y)), u16::MAX])
+        })),
+        DynamicImage::from(ImageBuffer::from_fn(width, height, |x, y| {
+            Rgb([wide(gray(x, y)); 3])
+        })),
+        DynamicImage::from(ImageBuffer::from_fn(width, height, |x, y| {
+            Rgba([
+                wide(gray(x, y)),
+                wide(gray(x, y)),
+                wide(gray(x, y)),
+                u16::MAX,
+            ])
+        })),
+    ];
+
+    for format in [Format::BC1, Format::BC2, Format::BC3, Format::BC4] {
+        let (extent, expected) =
+            from_dynamic_image(&images[0], format, EncodeEffort::Default).unwrap();
+        assert_eq!(extent, Extent::D2 { width, height });
+        assert_eq!(expected.len(), 2 * format.block_byte_size());
+
+        for image in &images[1..] {
+            let (_, bytes) = from_dynamic_image(image, format, EncodeEffort::Default).unwrap();
+            assert_eq!(bytes, expected, "{:?} {format:?}", image.color());
+        }
+    }
+
+    // BC4 encodes luminance.
+    let (_, bytes) = from_dynamic_image(&images[0], Format::BC4, EncodeEffort::Default).unwrap();
+    let texels = std::array::from_fn(|y| {
+        std::array::from_fn(|x| {
+            crate::math::R32F::new(gray(x as u32, y.min(2) as u32) as f32 / 255.0)
+        })
+    });
+    assert_eq!(bytes[..8], crate::bc4::Block::encode(texels).bytes());
+
+    // Transparent texels use punch-through alpha in BC1.
+    let image = DynamicImage::from(ImageBuffer::from_fn(4, 4, |x, y| {
+        Rgba([200u8, 100, 50, if x == y { 0 } else { 255 }])
+    }));
+    let (_, bytes) = from_dynamic_image(&image, Format::BC1, EncodeEffort::Default).unwrap();
+    let block = bc1::Block::from_bytes(bytes.try_into().unwrap());
+    assert!(block.is_three_color_mode());
+    let texels = block.decode_with_alpha();
+    for (y, row) in texels.iter().enumerate() {
+        for (x, texel) in row.iter().enumerate() {
+            assert_eq!(texel.a(), if x == y { 0.0 } else { 1.0 });
+        }
+    }
+
+    let image = DynamicImage::from(Rgb32FImage::new(4, 4));
+    assert!(matches!(
+        from_dynamic_image(&image, Format::BC1, EncodeEffort::Default),
+        Err(EncodeError::UnsupportedColorType(
+            ::image::ColorType::Rgb32F
+        ))
+    ));
+    let image = DynamicImage::from(Rgba32FImage::new(4, 4));
+    assert!(matches!(
+        from_dynamic_image(&image, Format::BC3, EncodeEffort::Default),
+        Err(EncodeError::UnsupportedColorType(
+            ::image::ColorType::Rgba32F
+        ))
+    ));
+    assert!(matches!(
+        from_dynamic_image(&images[0], Format::BC7, EncodeEffort::Default),
+        Err(EncodeError::UnsupportedFormat(Format::BC7))
+    ));
+}
+
+#[test]
+fn test_block_efforts() {
+    use EncodeEffort::{Default, Fast, High};
+
+    // 10x6 image is covered by 3x2 blocks.
+    let efforts = block_efforts(
+        10,
+        6,
+        Default,
+        &[
+            QualityRegion {
+                rect_px: [3, 0, 2, 1],
+                effort: High,
+            },
+            QualityRegion {
+                rect_px: [0, 0, 1, 1],
+                effort: Fast,
+            },
+            QualityRegion {
+                rect_px: [8, 4, 100, 100],
+                effort: Fast,
+            },
+            QualityRegion {
+                rect_px: [20, 20, 5, 5],
+                effort: High,
+            },
+            QualityRegion {
+                rect_px: [0, 0, 0, 5],
+                effort: High,
+            },
+            QualityRegion {
+                rect_px: [u32::MAX - 1, u32::MAX - 1, 10, 10],
+                effort: High,
+            },
+        ],
+    );
+
+    assert_eq!(efforts, [High, High, Default, Default, Default, Fast]);
+}
+
+#[test]
+fn test_quality_regions() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+    let pixels: Vec<Rgb8U> = (0..8 * 4)
+        .map(|_| Rgb8U::new(rng.gen(), rng.gen(), rng.gen()))
+        .collect();
+
+    let (_, fast) = encode_bc1_rgb8(8, 4, &pixels, EncodeEffort::Fast);
+    let (_, high) = encode_bc1_rgb8(8, 4, &pixels, EncodeEffort::High);
+    assert_ne!(fast[0], high[0]);
+    assert_ne!(fast[1], high[1]);
+
+    for parallel in [false, true] {
+        let mut output = Vec::new();
+        Encoder::new(Format::BC1)
+            .effort(EncodeEffort::Fast)
+            .quality_regions(&[QualityRegion {
+                rect_px: [5, 1, 100, 2],
+                effort: EncodeEffort::High,
+            }])
+            .parallel(parallel)
+            .encode_rgb8(8, 4, &pixels, std::io::Cursor::new(&mut output))
+            .unwrap();
+
+        let (_, blocks) =
+            crate::jackal::decompress_bc1_texture(std::io::Cursor::new(&output)).unwrap();
+        assert_eq!(blocks, [fast[0], high[1]]);
+    }
+}
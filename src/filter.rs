@@ -1,5 +1,10 @@
 use std::ops::{Add, Sub};
 
+use crate::{
+    image::{Image, Pixel},
+    math::{avg_floor, Rgb8U, Rgba8U},
+};
+
 pub trait Filterable: Add<Output = Self> + Sub<Output = Self> + Copy + Sized {
     type Distance: Ord + Copy + Sized;
     fn distance(lhs: &Self, rhs: &Self) -> Self::Distance;
@@ -31,3 +36,453 @@ where
 {
     filter_paeth(a, b, c)
 }
+
+/// Returns left, top and top-left neighbors of the pixel.
+///
+/// Neighbors outside of the image are [`Pixel::ZERO`].
+pub fn neighbors<P>(image: &Image<P>, x: u32, y: u32) -> (P, P, P)
+where
+    P: Pixel,
+{
+    let get = |x: Option<u32>, y: Option<u32>| match (x, y) {
+        (Some(x), Some(y)) => image.get_checked(x, y).unwrap_or(P::ZERO),
+        _ => P::ZERO,
+    };
+
+    let left = get(x.checked_sub(1), Some(y));
+    let top = get(Some(x), y.checked_sub(1));
+    let top_left = get(x.checked_sub(1), y.checked_sub(1));
+
+    (left, top, top_left)
+}
+
+/// Predicts each pixel of the image from its neighbors.
+///
+/// See [`neighbors`].
+pub fn predict_image<T>(image: &Image<T>) -> Image<T>
+where
+    T: Filterable + Pixel,
+{
+    Image::from_fn(image.width(), image.height(), |x, y| {
+        let (a, b, c) = neighbors(image, x, y);
+        filter(a, b, c)
+    })
+}
+
+/// Bias added to residuals so that zero residual is stored as 128.
+const RESIDUAL_BIAS: u8 = 128;
+
+/// Predictor used to compute residuals of pixels.
+///
+/// Same set of predictors as in PNG.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FilterKind {
+    /// Zero prediction, pixels are stored as is.
+    None,
+
+    /// Left neighbor.
+    Sub,
+
+    /// Top neighbor.
+    Up,
+
+    /// Average of left and top neighbors.
+    Average,
+
+    /// Paeth predictor, see [`paeth_forward`].
+    Paeth,
+}
+
+impl FilterKind {
+    pub const ALL: [FilterKind; 5] = [
+        FilterKind::None,
+        FilterKind::Sub,
+        FilterKind::Up,
+        FilterKind::Average,
+        FilterKind::Paeth,
+    ];
+}
+
+/// Pixels with 8 bit channels.
+trait BytePixel<const N: usize>: Pixel {
+    fn to_bytes(self) -> [u8; N];
+    fn from_bytes(bytes: [u8; N]) -> Self;
+}
+
+impl BytePixel<3> for Rgb8U {
+    #[inline(always)]
+    fn to_bytes(self) -> [u8; 3] {
+        self.bytes()
+    }
+
+    #[inline(always)]
+    fn from_bytes(bytes: [u8; 3]) -> Self {
+        Rgb8U::from_bytes(bytes)
+    }
+}
+
+impl BytePixel<4> for Rgba8U {
+    #[inline(always)]
+    fn to_bytes(self) -> [u8; 4] {
+        self.bytes()
+    }
+
+    #[inline(always)]
+    fn from_bytes(bytes: [u8; 4]) -> Self {
+        Rgba8U::from_bytes(bytes)
+    }
+}
+
+/// Selects Paeth predictor for pixel channels.
+///
+/// Unlike PNG, single neighbor is chosen for all channels
+/// by squared distance to the gradient estimate.
+fn paeth_bytes<const N: usize>(a: [u8; N], b: [u8; N], c: [u8; N]) -> [u8; N] {
+    let mut pa = 0;
+    let mut pb = 0;
+    let mut pc = 0;
+
+    for i in 0..N {
+        let (a, b, c) = (a[i] as i32, b[i] as i32, c[i] as i32);
+        let p = a + b - c;
+
+        pa += (p - a) * (p - a);
+        pb += (p - b) * (p - b);
+        pc += (p - c) * (p - c);
+    }
+
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Predicts pixel at the coordinates from its neighbors.
+fn predict<P, const N: usize>(image: &Image<P>, x: u32, y: u32, kind: FilterKind) -> [u8; N]
+where
+    P: BytePixel<N>,
+{
+    let (a, b, c) = neighbors(image, x, y);
+    let (a, b, c) = (a.to_bytes(), b.to_bytes(), c.to_bytes());
+
+    match kind {
+        FilterKind::None => [0; N],
+        FilterKind::Sub => a,
+        FilterKind::Up => b,
+        FilterKind::Average => std::array::from_fn(|i| avg_floor(a[i], b[i])),
+        FilterKind::Paeth => paeth_bytes(a, b, c),
+    }
+}
+
+/// Writes residuals of one row into `residuals`.
+fn forward_row<P, const N: usize>(
+    image: &Image<P>,
+    y: u32,
+    kind: FilterKind,
+    residuals: &mut Image<P>,
+) where
+    P: BytePixel<N>,
+{
+    for x in 0..image.width() {
+        let p = predict(image, x, y, kind);
+        let t = image.get(x, y).to_bytes();
+
+        let r = std::array::from_fn(|i| t[i].wrapping_sub(p[i]).wrapping_add(RESIDUAL_BIAS));
+        residuals.set(x, y, P::from_bytes(r));
+    }
+}
+
+/// Reconstructs one row of `image` from residuals.
+///
+/// Previous rows must be already reconstructed.
+fn inverse_row<P, const N: usize>(
+    residuals: &Image<P>,
+    y: u32,
+    kind: FilterKind,
+    image: &mut Image<P>,
+) where
+    P: BytePixel<N>,
+{
+    for x in 0..residuals.width() {
+        let p = predict(image, x, y, kind);
+        let r = residuals.get(x, y).to_bytes();
+
+        let t = std::array::from_fn(|i| r[i].wrapping_sub(RESIDUAL_BIAS).wrapping_add(p[i]));
+        image.set(x, y, P::from_bytes(t));
+    }
+}
+
+/// Sum of absolute values of residuals in the row.
+fn row_cost<P, const N: usize>(residuals: &Image<P>, y: u32) -> u64
+where
+    P: BytePixel<N>,
+{
+    (0..residuals.width())
+        .flat_map(|x| residuals.get(x, y).to_bytes())
+        .map(|r| r.wrapping_sub(RESIDUAL_BIAS) as i8 as i32)
+        .map(|r| r.unsigned_abs() as u64)
+        .sum()
+}
+
+fn forward_image<P, const N: usize>(image: &Image<P>, kinds: &[FilterKind]) -> Image<P>
+where
+    P: BytePixel<N>,
+{
+    assert_eq!(kinds.len(), image.height() as usize);
+
+    let mut residuals = Image::new(image.width(), image.height(), P::ZERO);
+    for (y, &kind) in kinds.iter().enumerate() {
+        forward_row(image, y as u32, kind, &mut residuals);
+    }
+    residuals
+}
+
+fn inverse_image<P, const N: usize>(residuals: &Image<P>, kinds: &[FilterKind]) -> Image<P>
+where
+    P: BytePixel<N>,
+{
+    assert_eq!(kinds.len(), residuals.height() as usize);
+
+    let mut image = Image::new(residuals.width(), residuals.height(), P::ZERO);
+    for (y, &kind) in kinds.iter().enumerate() {
+        inverse_row(residuals, y as u32, kind, &mut image);
+    }
+    image
+}
+
+fn best_per_row_image<P, const N: usize>(image: &Image<P>) -> (Vec<FilterKind>, Image<P>)
+where
+    P: BytePixel<N>,
+{
+    let mut kinds = Vec::with_capacity(image.height() as usize);
+    let mut residuals = Image::new(image.width(), image.height(), P::ZERO);
+
+    for y in 0..image.height() {
+        let mut best = (FilterKind::None, u64::MAX);
+
+        for kind in FilterKind::ALL {
+            forward_row(image, y, kind, &mut residuals);
+            let cost = row_cost(&residuals, y);
+
+            // On ties, simpler filter wins.
+            if cost < best.1 {
+                best = (kind, cost);
+            }
+        }
+
+        forward_row(image, y, best.0, &mut residuals);
+        kinds.push(best.0);
+    }
+
+    (kinds, residuals)
+}
+
+/// Replaces pixels with residuals of prediction.
+///
+/// Residuals are biased by 128, so perfectly predicted pixels become gray.
+/// Neighbors outside of the image are predicted as zero.
+/// Use [`inverse`] to restore the image.
+pub fn forward(image: &Image<Rgb8U>, kind: FilterKind) -> Image<Rgb8U> {
+    forward_image(image, &vec![kind; image.height() as usize])
+}
+
+/// Restores image from residuals produced by [`forward`].
+pub fn inverse(residuals: &Image<Rgb8U>, kind: FilterKind) -> Image<Rgb8U> {
+    inverse_image(residuals, &vec![kind; residuals.height() as usize])
+}
+
+/// Same as [`forward`] for RGBA images.
+pub fn forward_rgba(image: &Image<Rgba8U>, kind: FilterKind) -> Image<Rgba8U> {
+    forward_image(image, &vec![kind; image.height() as usize])
+}
+
+/// Restores image from residuals produced by [`forward_rgba`].
+pub fn inverse_rgba(residuals: &Image<Rgba8U>, kind: FilterKind) -> Image<Rgba8U> {
+    inverse_image(residuals, &vec![kind; residuals.height() as usize])
+}
+
+/// Replaces pixels with residuals of Paeth prediction.
+///
+/// See [`forward`].
+pub fn paeth_forward(image: &Image<Rgb8U>) -> Image<Rgb8U> {
+    forward(image, FilterKind::Paeth)
+}
+
+/// Restores image from residuals produced by [`paeth_forward`].
+pub fn paeth_inverse(residuals: &Image<Rgb8U>) -> Image<Rgb8U> {
+    inverse(residuals, FilterKind::Paeth)
+}
+
+/// Same as [`paeth_forward`] for RGBA images.
+pub fn paeth_forward_rgba(image: &Image<Rgba8U>) -> Image<Rgba8U> {
+    forward_rgba(image, FilterKind::Paeth)
+}
+
+/// Restores image from residuals produced by [`paeth_forward_rgba`].
+pub fn paeth_inverse_rgba(residuals: &Image<Rgba8U>) -> Image<Rgba8U> {
+    inverse_rgba(residuals, FilterKind::Paeth)
+}
+
+/// Chooses filter for each row like PNG encoders do.
+///
+/// Filter with the smallest sum of absolute residuals is chosen.
+/// Returns chosen filters and residuals.
+pub fn best_per_row(image: &Image<Rgb8U>) -> (Vec<FilterKind>, Image<Rgb8U>) {
+    best_per_row_image(image)
+}
+
+/// Restores image from residuals produced by [`best_per_row`].
+///
+/// # Panics
+///
+/// Panics if number of filters does not match image height.
+pub fn inverse_per_row(kinds: &[FilterKind], residuals: &Image<Rgb8U>) -> Image<Rgb8U> {
+    inverse_image(residuals, kinds)
+}
+
+/// Same as [`best_per_row`] for RGBA images.
+pub fn best_per_row_rgba(image: &Image<Rgba8U>) -> (Vec<FilterKind>, Image<Rgba8U>) {
+    best_per_row_image(image)
+}
+
+/// Restores image from residuals produced by [`best_per_row_rgba`].
+pub fn inverse_per_row_rgba(kinds: &[FilterKind], residuals: &Image<Rgba8U>) -> Image<Rgba8U> {
+    inverse_image(residuals, kinds)
+}
+
+/// Drops alpha channel of the image.
+pub fn strip_alpha(image: &Image<Rgba8U>) -> Image<Rgb8U> {
+    image.map(|p| p.rgb())
+}
+
+#[cfg(test)]
+fn random_image<P: Pixel>(
+    rng: &mut impl rand::Rng,
+    pixel: impl Fn(&mut dyn rand::RngCore) -> P,
+) -> Image<P> {
+    let width = rng.gen_range(0..20);
+    let height = rng.gen_range(0..20);
+
+    // Smooth images exercise all predictors, noisy ones exercise wrapping.
+    let smooth = rng.gen();
+    let mut image = Image::new(width, height, P::ZERO);
+    for y in 0..height {
+        for x in 0..width {
+            let p = if smooth && x > 0 && rng.gen_ratio(3, 4) {
+                image.get(x - 1, y)
+            } else {
+                pixel(rng)
+            };
+            image.set(x, y, p);
+        }
+    }
+    image
+}
+
+#[test]
+fn test_paeth_roundtrip() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(11);
+
+    for _ in 0..200 {
+        let image = random_image(&mut rng, |rng| Rgb8U::from_bytes(rng.gen()));
+        let residuals = paeth_forward(&image);
+        assert_eq!(paeth_inverse(&residuals), image);
+
+        let image = random_image(&mut rng, |rng| Rgba8U::from_bytes(rng.gen()));
+        let residuals = paeth_forward_rgba(&image);
+        assert_eq!(paeth_inverse_rgba(&residuals), image);
+    }
+}
+
+#[test]
+fn test_paeth_constant() {
+    let image = Image::new(5, 4, Rgb8U::new(10, 20, 30));
+    let residuals = paeth_forward(&image);
+
+    // First pixel is predicted from zero, the rest are predicted exactly.
+    assert_eq!(residuals.get(0, 0), Rgb8U::new(138, 148, 158));
+    assert!(residuals
+        .pixels()
+        .iter()
+        .skip(1)
+        .all(|&p| p == Rgb8U::new(128, 128, 128)));
+}
+
+#[test]
+fn test_filters_roundtrip() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(12);
+
+    for _ in 0..100 {
+        let image = random_image(&mut rng, |rng| Rgb8U::from_bytes(rng.gen()));
+        for kind in FilterKind::ALL {
+            assert_eq!(inverse(&forward(&image, kind), kind), image);
+        }
+
+        let (kinds, residuals) = best_per_row(&image);
+        assert_eq!(inverse_per_row(&kinds, &residuals), image);
+
+        let image = random_image(&mut rng, |rng| Rgba8U::from_bytes(rng.gen()));
+        for kind in FilterKind::ALL {
+            assert_eq!(inverse_rgba(&forward_rgba(&image, kind), kind), image);
+        }
+
+        let (kinds, residuals) = best_per_row_rgba(&image);
+        assert_eq!(inverse_per_row_rgba(&kinds, &residuals), image);
+    }
+}
+
+#[test]
+fn test_filters_known() {
+    let image = Image::from_fn(4, 2, |x, y| {
+        Rgb8U::new(x as u8 * 10 + y as u8, 200, 100 - y as u8 * 3)
+    });
+
+    let sub = forward(&image, FilterKind::Sub);
+    assert_eq!(sub.get(0, 1), Rgb8U::new(129, 72, 225));
+    assert_eq!(sub.get(2, 1), Rgb8U::new(138, 128, 128));
+
+    let up = forward(&image, FilterKind::Up);
+    assert_eq!(up.get(3, 0), Rgb8U::new(158, 72, 228));
+    assert_eq!(up.get(3, 1), Rgb8U::new(129, 128, 125));
+
+    // (20 + 11) / 2 = 15, (200 + 200) / 2 = 200, (97 + 100) / 2 = 98.
+    let average = forward(&image, FilterKind::Average);
+    assert_eq!(average.get(2, 1), Rgb8U::new(134, 128, 127));
+}
+
+#[test]
+fn test_best_per_row_gradient() {
+    // Horizontal gradient with unrelated rows is best predicted from the left.
+    let image = Image::from_fn(64, 8, |x, y| {
+        let offset = (y % 2 * 40) as u8;
+        Rgb8U::new(offset + x as u8 * 3, offset + x as u8 * 2, offset)
+    });
+
+    let (kinds, residuals) = best_per_row(&image);
+    assert_eq!(kinds, vec![FilterKind::Sub; 8]);
+    assert_eq!(residuals, forward(&image, FilterKind::Sub));
+
+    // Vertical gradient is best predicted from the top.
+    let image = Image::from_fn(16, 8, |x, y| {
+        Rgb8U::new((x * 37 % 256) as u8, y as u8 * 5, (x * 11) as u8)
+    });
+
+    let (kinds, _) = best_per_row(&image);
+    assert!(kinds[1..].iter().all(|&kind| kind == FilterKind::Up));
+}
+
+#[test]
+fn test_strip_alpha() {
+    let image = Image::from_fn(3, 2, |x, y| Rgba8U::new(x as u8, y as u8, 7, (x * y) as u8));
+    let stripped = strip_alpha(&image);
+    assert_eq!(stripped.get(2, 1), Rgb8U::new(2, 1, 7));
+    assert_eq!(stripped.width(), 3);
+    assert_eq!(stripped.height(), 2);
+}
@@ -0,0 +1,540 @@
+//! Upload of Jackal textures to GPU.
+//!
+//! Super-blocks are decoded on CPU worker threads into a staging buffer
+//! where blocks of each super-block are stored contiguously.
+//! [`SCATTER_WGSL`] compute shader then moves blocks into row-major order
+//! that can be copied into BC1 texture.
+
+use std::io::Cursor;
+
+use wgpu::util::DeviceExt;
+
+use crate::{
+    bc1,
+    jackal::{
+        self, blocks_as_bytes, AnyBlock, DecodeError, DecodeStage, DecompressError, Extent,
+        JackalBlock, JackalHeader,
+    },
+};
+
+/// Compute shader scattering blocks of the staging buffer into row-major order.
+///
+/// Each workgroup moves blocks of one super-block.
+/// Blocks are read and written as pairs of 32-bit words.
+///
+/// Bindings of group 0:
+/// 0. staging blocks, see [`SuperBlockUploader::staging`].
+/// 1. super-blocks, see [`GpuSuperBlock`].
+/// 2. output blocks, `row_pitch` blocks per row and `row_pitch * height` blocks per slice.
+/// 3. uniform with `row_pitch` and `slice_pitch` followed by two padding words.
+pub const SCATTER_WGSL: &str = r#"
+struct SuperBlock {
+    origin_x: u32,
+    origin_y: u32,
+    origin_z: u32,
+    width: u32,
+    height: u32,
+    base: u32,
+}
+
+struct Params {
+    row_pitch: u32,
+    slice_pitch: u32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+@group(0) @binding(0) var<storage, read> staging: array<vec2<u32>>;
+@group(0) @binding(1) var<storage, read> super_blocks: array<SuperBlock>;
+@group(0) @binding(2) var<storage, read_write> blocks: array<vec2<u32>>;
+@group(0) @binding(3) var<uniform> params: Params;
+
+@compute @workgroup_size(64)
+fn scatter(
+    @builtin(workgroup_id) group: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+    @builtin(local_invocation_index) local: u32,
+) {
+    let index = group.x + group.y * groups.x;
+    if index >= arrayLength(&super_blocks) {
+        return;
+    }
+
+    let sb = super_blocks[index];
+    let count = sb.width * sb.height;
+
+    for (var i = local; i < count; i += 64u) {
+        let x = sb.origin_x + i % sb.width;
+        let y = sb.origin_y + i / sb.width;
+        blocks[x + y * params.row_pitch + sb.origin_z * params.slice_pitch] = staging[sb.base + i];
+    }
+}
+"#;
+
+/// Entry point of [`SCATTER_WGSL`].
+pub const SCATTER_ENTRY_POINT: &str = "scatter";
+
+/// Maximum number of workgroups in one dimension of a dispatch.
+const MAX_WORKGROUPS: u32 = 65535;
+
+/// Super-block as seen by [`SCATTER_WGSL`].
+///
+/// Stored in GPU buffer as six little-endian `u32` words in field order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GpuSuperBlock {
+    /// Position of the first block of the super-block in the texture.
+    pub origin: [u32; 3],
+
+    /// Size of the super-block clipped to the extent.
+    pub width: u32,
+    pub height: u32,
+
+    /// Index of the first block of the super-block in the staging buffer.
+    pub base: u32,
+}
+
+impl GpuSuperBlock {
+    pub fn bytes(&self) -> [u8; 24] {
+        let words = [
+            self.origin[0],
+            self.origin[1],
+            self.origin[2],
+            self.width,
+            self.height,
+            self.base,
+        ];
+
+        let mut bytes = [0; 24];
+        for (chunk, word) in bytes.chunks_exact_mut(4).zip(words) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// BC1 texture decoded per super-block and ready for upload.
+#[derive(Debug)]
+pub struct SuperBlockUploader {
+    extent: Extent,
+    grid_extent: [u32; 3],
+    staging: Vec<bc1::Block>,
+    super_blocks: Vec<GpuSuperBlock>,
+}
+
+impl SuperBlockUploader {
+    /// Decodes super-blocks listed in `table` on up to `threads` worker threads.
+    ///
+    /// `bytes` is the whole container the `header` and `table` were read from.
+    pub fn decode(
+        header: &JackalHeader,
+        table: &[JackalBlock],
+        bytes: &[u8],
+        threads: usize,
+    ) -> Result<Self, DecompressError> {
+        if header.format() != bc1::Block::FORMAT {
+            return Err(DecompressError::from(DecodeError::Unsupported).at(DecodeStage::Header));
+        }
+
+        let grid_extent = header.jackal_blocks_extent();
+        if header.checked_jackal_blocks_count() != Some(table.len()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "super-block table does not match header",
+            )
+            .into());
+        }
+
+        let mut super_blocks = Vec::with_capacity(table.len());
+        let mut base = 0;
+        for z in 0..grid_extent[2] {
+            for y in 0..grid_extent[1] {
+                for x in 0..grid_extent[0] {
+                    let bounds = jackal::super_block_bounds(header, [x, y, z])?;
+
+                    let super_block = GpuSuperBlock {
+                        origin: [bounds.x_start, bounds.y_start, z],
+                        width: bounds.width(),
+                        height: bounds.height(),
+                        base,
+                    };
+                    base += super_block.width * super_block.height;
+                    super_blocks.push(super_block);
+                }
+            }
+        }
+
+        let mut staging = vec![bc1::Block::EMPTY; header.blocks_count()];
+
+        // Split staging buffer into regions of super-blocks.
+        let mut regions = Vec::with_capacity(table.len());
+        let mut rest = &mut staging[..];
+        for (index, super_block) in super_blocks.iter().enumerate() {
+            let count = (super_block.width * super_block.height) as usize;
+            let (region, tail) = rest.split_at_mut(count);
+            regions.push((index, region));
+            rest = tail;
+        }
+
+        let per_thread = regions.len().div_ceil(threads.max(1)).max(1);
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = regions
+                .chunks_mut(per_thread)
+                .map(|chunk| {
+                    scope.spawn(move || -> Result<(), DecompressError> {
+                        for (index, region) in chunk {
+                            let index = *index;
+                            let super_pos = [
+                                index as u32 % grid_extent[0],
+                                index as u32 / grid_extent[0] % grid_extent[1],
+                                index as u32 / grid_extent[0] / grid_extent[1],
+                            ];
+
+                            jackal::decompress_super_block(
+                                header,
+                                super_pos,
+                                table[index],
+                                region,
+                                Cursor::new(bytes),
+                            )
+                            .map_err(|err| err.at(DecodeStage::SuperBlock(index)))?;
+                        }
+                        Ok(())
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .try_for_each(|worker| worker.join().expect("decode worker panicked"))
+        })?;
+
+        Ok(SuperBlockUploader {
+            extent: header.extent(),
+            grid_extent,
+            staging,
+            super_blocks,
+        })
+    }
+
+    /// Returns extent of the texture in blocks.
+    pub fn extent(&self) -> Extent {
+        self.extent
+    }
+
+    /// Returns number of super-blocks along each axis.
+    pub fn grid_extent(&self) -> [u32; 3] {
+        self.grid_extent
+    }
+
+    /// Returns decoded blocks, super-blocks one after another in table order,
+    /// blocks of each super-block row by row.
+    pub fn staging(&self) -> &[bc1::Block] {
+        &self.staging
+    }
+
+    /// Returns super-blocks in table order.
+    pub fn super_blocks(&self) -> &[GpuSuperBlock] {
+        &self.super_blocks
+    }
+
+    /// Returns number of blocks in a row of the scattered buffer.
+    ///
+    /// Rows are padded to [`wgpu::COPY_BYTES_PER_ROW_ALIGNMENT`],
+    /// so the buffer can be copied into texture directly.
+    pub fn row_pitch(&self) -> u32 {
+        let [width, _, _] = self.extent.raw_size();
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT / 8;
+        width.div_ceil(align) * align
+    }
+
+    /// Returns layout of the scattered buffer for copying into BC1 texture.
+    pub fn copy_layout(&self) -> wgpu::TexelCopyBufferLayout {
+        let [_, height, _] = self.extent.raw_size();
+        wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(self.row_pitch() * 8),
+            rows_per_image: Some(height),
+        }
+    }
+
+    /// Uploads staging blocks and scatters them into a new buffer in row-major order.
+    ///
+    /// Returned buffer has `STORAGE | COPY_SRC` usage and [`SuperBlockUploader::copy_layout`].
+    pub fn scatter(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> wgpu::Buffer {
+        let [_, height, depth] = self.extent.raw_size();
+        let row_pitch = self.row_pitch();
+        let slice_pitch = row_pitch * height;
+
+        let staging = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("jkl staging blocks"),
+            contents: blocks_as_bytes(&self.staging),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let super_blocks: Vec<u8> = self
+            .super_blocks
+            .iter()
+            .flat_map(GpuSuperBlock::bytes)
+            .collect();
+        let super_blocks = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("jkl super-blocks"),
+            contents: &super_blocks,
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let mut params = [0; 16];
+        params[0..4].copy_from_slice(&row_pitch.to_le_bytes());
+        params[4..8].copy_from_slice(&slice_pitch.to_le_bytes());
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("jkl scatter params"),
+            contents: &params,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let output = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("jkl blocks"),
+            size: slice_pitch as u64 * depth as u64 * 8,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("jkl scatter"),
+            source: wgpu::ShaderSource::Wgsl(SCATTER_WGSL.into()),
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("jkl scatter"),
+            layout: None,
+            module: &module,
+            entry_point: Some(SCATTER_ENTRY_POINT),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("jkl scatter"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: staging.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: super_blocks.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: output.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: params.as_entire_binding(),
+                },
+            ],
+        });
+
+        let count = self.super_blocks.len() as u32;
+        let groups_x = count.min(MAX_WORKGROUPS);
+        let groups_y = count.div_ceil(MAX_WORKGROUPS);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("jkl scatter"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("jkl scatter"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(groups_x, groups_y, 1);
+        }
+        queue.submit([encoder.finish()]);
+
+        output
+    }
+}
+
+/// Scatters staging blocks on CPU the same way [`SCATTER_WGSL`] does, without row padding.
+#[cfg(test)]
+fn scatter_on_cpu(uploader: &SuperBlockUploader) -> Vec<bc1::Block> {
+    let [width, height, depth] = uploader.extent.raw_size();
+    let mut blocks = vec![bc1::Block::EMPTY; (width * height * depth) as usize];
+
+    for sb in &uploader.super_blocks {
+        for i in 0..sb.width * sb.height {
+            let x = sb.origin[0] + i % sb.width;
+            let y = sb.origin[1] + i / sb.width;
+            blocks[(x + y * width + sb.origin[2] * width * height) as usize] =
+                uploader.staging[(sb.base + i) as usize];
+        }
+    }
+
+    blocks
+}
+
+#[cfg(test)]
+fn test_texture(extent: Extent) -> Vec<u8> {
+    use crate::jackal::{CompressOptions, SuperBlockChoice, SuperBlockSize};
+
+    let [width, height, depth] = extent.raw_size();
+    let blocks: Vec<bc1::Block> = (0..width * height * depth)
+        .map(|i| {
+            bc1::Block::from_bytes([
+                i as u8,
+                (i >> 8) as u8,
+                !i as u8,
+                0x5A,
+                (i * 7) as u8,
+                0xE4,
+                (i % 13) as u8,
+                0x1B,
+            ])
+        })
+        .collect();
+
+    let options = CompressOptions {
+        super_block_size: SuperBlockChoice::Fixed(SuperBlockSize {
+            width: 4,
+            height: 2,
+        }),
+        ..CompressOptions::default()
+    };
+
+    let mut output = Vec::new();
+    jackal::compress_bc1_texture_opts(extent, &blocks, options, Cursor::new(&mut output)).unwrap();
+    output
+}
+
+#[cfg(test)]
+fn read_table(bytes: &[u8]) -> (JackalHeader, Vec<JackalBlock>) {
+    let header = jackal::read_header(bytes).unwrap();
+    let mut read = Cursor::new(bytes);
+    read.set_position(header.bytes_size() as u64);
+    let table = jackal::read_jackal_blocks_vec(&header, read).unwrap();
+    (header, table)
+}
+
+#[test]
+fn test_staging_layout() {
+    let extents = [
+        Extent::D2 {
+            width: 11,
+            height: 5,
+        },
+        Extent::D2Array {
+            width: 6,
+            height: 3,
+            layers: 3,
+        },
+    ];
+
+    for extent in extents {
+        let bytes = test_texture(extent);
+        let (header, table) = read_table(&bytes);
+        let (_, expected) = jackal::decompress_bc1_texture(Cursor::new(&bytes)).unwrap();
+
+        for threads in [1, 3, 64] {
+            let uploader = SuperBlockUploader::decode(&header, &table, &bytes, threads).unwrap();
+            assert_eq!(uploader.grid_extent(), header.jackal_blocks_extent());
+            assert_eq!(uploader.super_blocks().len(), table.len());
+            assert_eq!(scatter_on_cpu(&uploader), expected, "{extent:?} {threads}");
+        }
+    }
+
+    // Corrupted payload is reported with the super-block index.
+    let mut bytes = test_texture(extents[0]);
+    let (header, table) = read_table(&bytes);
+    bytes[table[2].offset as usize] ^= 0xFF;
+    let err = SuperBlockUploader::decode(&header, &table, &bytes, 2).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::ChecksumMismatch));
+}
+
+#[test]
+fn test_scatter_shader_is_valid() {
+    use wgpu::naga::{
+        front::wgsl,
+        valid::{Capabilities, ValidationFlags, Validator},
+    };
+
+    let module = wgsl::parse_str(SCATTER_WGSL).unwrap();
+    Validator::new(ValidationFlags::all(), Capabilities::empty())
+        .validate(&module)
+        .unwrap();
+    assert!(module
+        .entry_points
+        .iter()
+        .any(|entry| entry.name == SCATTER_ENTRY_POINT));
+}
+
+/// Scatters on the fallback adapter and compares with CPU decode.
+///
+/// Skipped when no fallback adapter is available.
+#[test]
+fn test_scatter_on_gpu() {
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        loop {
+            if let std::task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            std::thread::yield_now();
+        }
+    }
+
+    let instance = wgpu::Instance::default();
+    let adapter = match block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        force_fallback_adapter: true,
+        ..Default::default()
+    })) {
+        Ok(adapter) => adapter,
+        Err(err) => {
+            eprintln!("skipping GPU scatter test, no fallback adapter: {err}");
+            return;
+        }
+    };
+    let (device, queue) = block_on(adapter.request_device(&Default::default())).unwrap();
+
+    let extent = Extent::D2Array {
+        width: 11,
+        height: 5,
+        layers: 2,
+    };
+    let bytes = test_texture(extent);
+    let (header, table) = read_table(&bytes);
+    let (_, expected) = jackal::decompress_bc1_texture(Cursor::new(&bytes)).unwrap();
+
+    let uploader = SuperBlockUploader::decode(&header, &table, &bytes, 4).unwrap();
+    let output = uploader.scatter(&device, &queue);
+
+    let readback = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: output.size(),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&Default::default());
+    encoder.copy_buffer_to_buffer(&output, 0, &readback, 0, output.size());
+    queue.submit([encoder.finish()]);
+
+    readback
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, |result| result.unwrap());
+    device.poll(wgpu::PollType::Wait).unwrap();
+
+    let data = readback.slice(..).get_mapped_range();
+    let [width, height, _] = extent.raw_size();
+    let row_pitch = uploader.row_pitch() as usize;
+    for (i, block) in expected.iter().enumerate() {
+        let (x, y, z) = (
+            i % width as usize,
+            i / width as usize % height as usize,
+            i / (width * height) as usize,
+        );
+        let offset = (x + (y + z * height as usize) * row_pitch) * 8;
+        assert_eq!(&data[offset..offset + 8], &block.bytes(), "block {i}");
+    }
+}
@@ -0,0 +1,246 @@
+//! Simple image container.
+//!
+//! Pixels are stored row-major, x fastest.
+
+use crate::math::{Rgb32F, Rgb8U, Rgba32F, Rgba8U, R8U};
+
+/// Pixel type that can be stored in [`Image`].
+pub trait Pixel: Copy + PartialEq + std::fmt::Debug + 'static {
+    /// Pixel with all channels set to zero.
+    const ZERO: Self;
+
+    /// Number of channels in the pixel.
+    const CHANNELS: usize;
+}
+
+impl Pixel for R8U {
+    const ZERO: Self = R8U::BLACK;
+    const CHANNELS: usize = 1;
+}
+
+impl Pixel for Rgb8U {
+    const ZERO: Self = Rgb8U::BLACK;
+    const CHANNELS: usize = 3;
+}
+
+impl Pixel for Rgba8U {
+    const ZERO: Self = Rgba8U::TRANSPARENT;
+    const CHANNELS: usize = 4;
+}
+
+impl Pixel for Rgb32F {
+    const ZERO: Self = Rgb32F::BLACK;
+    const CHANNELS: usize = 3;
+}
+
+impl Pixel for Rgba32F {
+    const ZERO: Self = Rgba32F::TRANSPARENT;
+    const CHANNELS: usize = 4;
+}
+
+/// 2D image with pixels of type `P`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image<P> {
+    width: u32,
+    height: u32,
+    pixels: Vec<P>,
+}
+
+impl<P> Image<P>
+where
+    P: Pixel,
+{
+    /// Creates image filled with the pixel.
+    pub fn new(width: u32, height: u32, fill: P) -> Self {
+        Image {
+            width,
+            height,
+            pixels: vec![fill; width as usize * height as usize],
+        }
+    }
+
+    /// Creates image from row-major pixels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if number of pixels does not match the size.
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<P>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            width as usize * height as usize,
+            "Number of pixels must match image size"
+        );
+
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Creates image calling `f` with coordinates of each pixel.
+    pub fn from_fn(width: u32, height: u32, mut f: impl FnMut(u32, u32) -> P) -> Self {
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(f(x, y));
+            }
+        }
+
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    #[inline(always)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline(always)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns all pixels in row-major order.
+    #[inline(always)]
+    pub fn pixels(&self) -> &[P] {
+        &self.pixels
+    }
+
+    /// Returns all pixels in row-major order.
+    #[inline(always)]
+    pub fn into_pixels(self) -> Vec<P> {
+        self.pixels
+    }
+
+    #[inline(always)]
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some(x as usize + y as usize * self.width as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Returns pixel at the coordinates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if coordinates are out of bounds.
+    #[inline(always)]
+    pub fn get(&self, x: u32, y: u32) -> P {
+        match self.get_checked(x, y) {
+            Some(pixel) => pixel,
+            None => panic!("Pixel ({x}, {y}) is out of bounds"),
+        }
+    }
+
+    /// Sets pixel at the coordinates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if coordinates are out of bounds.
+    #[inline(always)]
+    pub fn set(&mut self, x: u32, y: u32, pixel: P) {
+        if self.set_checked(x, y, pixel).is_none() {
+            panic!("Pixel ({x}, {y}) is out of bounds");
+        }
+    }
+
+    /// Returns pixel at the coordinates or `None` if they are out of bounds.
+    #[inline(always)]
+    pub fn get_checked(&self, x: u32, y: u32) -> Option<P> {
+        let index = self.index(x, y)?;
+        Some(self.pixels[index])
+    }
+
+    /// Sets pixel at the coordinates and returns previous one.
+    ///
+    /// Returns `None` if coordinates are out of bounds.
+    #[inline(always)]
+    pub fn set_checked(&mut self, x: u32, y: u32, pixel: P) -> Option<P> {
+        let index = self.index(x, y)?;
+        Some(std::mem::replace(&mut self.pixels[index], pixel))
+    }
+
+    /// Returns iterator over rows of pixels.
+    pub fn rows(&self) -> impl ExactSizeIterator<Item = &[P]> + '_ {
+        // Image without columns yields `height` empty rows.
+        let width = self.width as usize;
+        (0..self.height as usize).map(move |y| &self.pixels[y * width..][..width])
+    }
+
+    /// Converts each pixel with `f`.
+    pub fn map<Q>(&self, f: impl FnMut(P) -> Q) -> Image<Q>
+    where
+        Q: Pixel,
+    {
+        Image {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter().copied().map(f).collect(),
+        }
+    }
+}
+
+#[test]
+fn test_image_map_roundtrip() {
+    let image = Image::from_fn(7, 5, |x, y| {
+        Rgb8U::new((x * 36) as u8, (y * 60) as u8, (x * y * 7) as u8)
+    });
+
+    let float: Image<Rgb32F> = image.map(Rgb8U::into_f32);
+    assert_eq!(float.width(), 7);
+    assert_eq!(float.height(), 5);
+
+    let back = float.map(Rgb8U::from_f32);
+    assert_eq!(back, image);
+
+    // All 8 bit values survive conversion.
+    let all = Image::from_fn(256, 1, |x, _| R8U::new(x as u8));
+    let back = all
+        .map(|p| Rgb8U::new(p.r(), p.r(), p.r()).into_f32())
+        .map(|p| R8U::new(Rgb8U::from_f32(p).r()));
+    assert_eq!(back, all);
+}
+
+#[test]
+fn test_image_edges() {
+    let mut image = Image::new(3, 2, Rgba8U::TRANSPARENT);
+
+    image.set(2, 1, Rgba8U::WHITE);
+    assert_eq!(image.get(2, 1), Rgba8U::WHITE);
+    assert_eq!(image.pixels()[5], Rgba8U::WHITE);
+
+    assert_eq!(image.get_checked(3, 0), None);
+    assert_eq!(image.get_checked(0, 2), None);
+    assert_eq!(image.get_checked(u32::MAX, u32::MAX), None);
+    assert_eq!(image.set_checked(3, 1, Rgba8U::BLACK), None);
+    assert_eq!(
+        image.set_checked(0, 0, Rgba8U::BLACK),
+        Some(Rgba8U::TRANSPARENT)
+    );
+
+    let rows: Vec<&[Rgba8U]> = image.rows().collect();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(
+        rows[0],
+        &[Rgba8U::BLACK, Rgba8U::TRANSPARENT, Rgba8U::TRANSPARENT]
+    );
+    assert_eq!(rows[1][2], Rgba8U::WHITE);
+
+    let empty = Image::<Rgb8U>::new(0, 4, Rgb8U::BLACK);
+    assert_eq!(empty.rows().count(), 4);
+    assert!(empty.rows().all(|row| row.is_empty()));
+    assert_eq!(empty.get_checked(0, 0), None);
+}
+
+#[test]
+#[should_panic]
+fn test_image_get_out_of_bounds() {
+    let image = Image::new(2, 2, R8U::BLACK);
+    image.get(2, 0);
+}
@@ -0,0 +1,105 @@
+//! Conversions into [`egui`] colors and images.
+
+use ::egui::{Color32, ColorImage, Vec2};
+
+use crate::{
+    image::{Image, Pixel},
+    math::{Rgb32F, Rgb565, Rgb8U, Rgba32F, Rgba8U},
+};
+
+impl From<Rgb8U> for Color32 {
+    #[inline(always)]
+    fn from(rgb: Rgb8U) -> Self {
+        Color32::from_rgb(rgb.r(), rgb.g(), rgb.b())
+    }
+}
+
+impl From<Rgba8U> for Color32 {
+    #[inline(always)]
+    fn from(rgba: Rgba8U) -> Self {
+        Color32::from_rgba_unmultiplied(rgba.r(), rgba.g(), rgba.b(), rgba.a())
+    }
+}
+
+/// Expands channels replicating high bits, same as BC1 decoders do.
+impl From<Rgb565> for Color32 {
+    #[inline(always)]
+    fn from(rgb: Rgb565) -> Self {
+        rgb.into_rgb8().into()
+    }
+}
+
+/// Channels are clamped to `0.0..=1.0`.
+impl From<Rgb32F> for Color32 {
+    #[inline(always)]
+    fn from(rgb: Rgb32F) -> Self {
+        Rgb8U::from_f32(rgb).into()
+    }
+}
+
+/// Channels are clamped to `0.0..=1.0`.
+impl From<Rgba32F> for Color32 {
+    #[inline(always)]
+    fn from(rgba: Rgba32F) -> Self {
+        Rgba8U::from_f32(rgba).into()
+    }
+}
+
+/// Converts image into [`ColorImage`] for uploading as egui texture.
+pub fn image_to_color_image<P>(image: &Image<P>) -> ColorImage
+where
+    P: Pixel + Into<Color32>,
+{
+    ColorImage {
+        size: [image.width() as usize, image.height() as usize],
+        source_size: Vec2::new(image.width() as f32, image.height() as f32),
+        pixels: image.pixels().iter().map(|&p| p.into()).collect(),
+    }
+}
+
+#[test]
+fn color_conversions() {
+    assert_eq!(
+        Color32::from(Rgb8U::new(0, 128, 255)),
+        Color32::from_rgb(0, 128, 255)
+    );
+    assert_eq!(
+        Color32::from(Rgba8U::new(255, 255, 255, 0)),
+        Color32::from_rgba_unmultiplied(255, 255, 255, 0)
+    );
+    assert_eq!(
+        Color32::from(Rgba8U::new(10, 20, 30, 255)),
+        Color32::from_rgb(10, 20, 30)
+    );
+
+    assert_eq!(Color32::from(Rgb565::WHITE), Color32::WHITE);
+    assert_eq!(Color32::from(Rgb565::BLACK), Color32::BLACK);
+    // 5-bit 16 expands to 132, not 128.
+    assert_eq!(
+        Color32::from(Rgb565::new(16, 32, 1)),
+        Color32::from_rgb(132, 130, 8)
+    );
+
+    assert_eq!(
+        Color32::from(Rgb32F::new(-1.0, 0.5, 2.0)),
+        Color32::from_rgb(0, 127, 255)
+    );
+    assert_eq!(
+        Color32::from(Rgba32F::new(1.5, 0.0, -0.5, 1.0)),
+        Color32::from_rgb(255, 0, 0)
+    );
+    assert_eq!(
+        Color32::from(Rgba32F::new(0.0, 0.0, 0.0, -1.0)),
+        Color32::TRANSPARENT
+    );
+}
+
+#[test]
+fn color_image() {
+    let image = Image::from_fn(3, 2, |x, y| Rgb8U::new(x as u8, y as u8, 7));
+    let color_image = image_to_color_image(&image);
+
+    assert_eq!(color_image.size, [3, 2]);
+    assert_eq!(color_image.pixels.len(), 6);
+    assert_eq!(color_image.pixels[5], Color32::from_rgb(2, 1, 7));
+}
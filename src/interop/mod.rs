@@ -0,0 +1,6 @@
+//! Conversions into types of other crates.
+//!
+//! Each module is enabled by the feature of the same name.
+
+#[cfg(feature = "egui")]
+pub mod egui;
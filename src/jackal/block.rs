@@ -1,75 +1,683 @@
 use std::io::{Read, Write};
 
-use crate::{bc1, jackal::DecompressError, math::Rgb565};
+use crate::{
+    bc1, bc2, bc3, bc4, bc5,
+    jackal::{DecompressError, Extent, Format, JackalHeader, MipLevels},
+    math::{Rgb565, R8U},
+};
+
+pub trait AnyBlock: Copy + PartialEq + 'static + Sized {
+    /// Format recorded in the header of compressed stream.
+    const FORMAT: Format;
 
-pub trait AnyBlock: Copy + 'static + Sized {
     const ASPECTS: usize;
 
+    /// Block that fills storage before it is decoded.
+    const EMPTY: Self;
+
+    /// Returns number of bytes written by [`AnyBlock::compress`] for the aspect.
+    fn aspect_bytes(header: &JackalHeader, aspect: usize) -> usize;
+
     /// Compress specific block aspect.
     ///
-    /// Writes compressed data into `writer`
-    fn compress<'a, const ASPECT: usize>(&self, writer: impl Write) -> std::io::Result<()>;
+    /// Writes compressed data into `writer`.
+    /// `aspect` must be less than [`AnyBlock::ASPECTS`].
+    fn compress(
+        &self,
+        aspect: usize,
+        header: &JackalHeader,
+        writer: impl Write,
+    ) -> std::io::Result<()>;
 
     /// Decompress one block aspect.
     ///
-    /// Reads compressed data from `reader`
-    fn decompress<'a, const ASPECT: usize>(
+    /// Reads compressed data from `reader`.
+    /// `aspect` must be less than [`AnyBlock::ASPECTS`].
+    fn decompress(
         &mut self,
+        aspect: usize,
+        header: &JackalHeader,
         reader: impl Read,
     ) -> Result<(), DecompressError>;
+
+    /// Replaces endpoints with wrapping differences from endpoints of `reference`.
+    fn predict_endpoints(&mut self, reference: &Self);
+
+    /// Restores endpoints replaced by [`AnyBlock::predict_endpoints`].
+    fn restore_endpoints(&mut self, reference: &Self);
 }
 
-impl AnyBlock for bc1::Block {
-    const ASPECTS: usize = 3;
+/// Returns number of bytes of color endpoint aspect.
+/// With interleaved colors the other byte is stored in separate aspect.
+fn color_bytes(header: &JackalHeader) -> usize {
+    if header.interleaved_colors() {
+        1
+    } else {
+        2
+    }
+}
 
-    fn compress<'a, const ASPECT: usize>(&self, mut writer: impl Write) -> std::io::Result<()> {
-        match ASPECT {
-            0 => {
-                // Color0
-                let bytes = self.color0.bits().to_le_bytes();
-                writer.write_all(&bytes)?;
-            }
-            1 => {
-                // Color1
-                let bytes = self.color1.bits().to_le_bytes();
-                writer.write_all(&bytes)?;
-            }
-            2 => {
-                // Texels
-                writer.write_all(&self.texels)?;
+/// Writes color endpoint, or its high byte with interleaved colors.
+fn compress_color(color: Rgb565, interleaved: bool, mut writer: impl Write) -> std::io::Result<()> {
+    if interleaved {
+        writer.write_all(&[(color.bits_interleaved() >> 8) as u8])
+    } else {
+        writer.write_all(&color.bits().to_le_bytes())
+    }
+}
+
+/// Writes low byte of interleaved color endpoint.
+fn compress_color_low(
+    color: Rgb565,
+    interleaved: bool,
+    mut writer: impl Write,
+) -> std::io::Result<()> {
+    if interleaved {
+        writer.write_all(&[color.bits_interleaved() as u8])?;
+    }
+    Ok(())
+}
+
+/// Reads color endpoint written by [`compress_color`].
+///
+/// With interleaved colors, high byte of interleaved bits is kept in color
+/// until low byte is read in later aspect.
+fn decompress_color(interleaved: bool, mut reader: impl Read) -> std::io::Result<Rgb565> {
+    if interleaved {
+        let mut byte = [0; 1];
+        reader.read_exact(&mut byte)?;
+
+        Ok(Rgb565::from_bits((byte[0] as u16) << 8))
+    } else {
+        let mut bytes = [0; 2];
+        reader.read_exact(&mut bytes)?;
+
+        Ok(Rgb565::from_bits(u16::from_le_bytes(bytes)))
+    }
+}
+
+/// Reads low byte of interleaved color endpoint written by [`compress_color_low`].
+fn decompress_color_low(
+    color: &mut Rgb565,
+    interleaved: bool,
+    mut reader: impl Read,
+) -> std::io::Result<()> {
+    if interleaved {
+        let mut byte = [0; 1];
+        reader.read_exact(&mut byte)?;
+
+        let bits = color.bits() | byte[0] as u16;
+        *color = Rgb565::from_bits_interleaved(bits);
+    }
+    Ok(())
+}
+
+/// Writes 2-bit texel indices of BC1 color block.
+///
+/// With canonical indices, all indices are XORed with the index of the top-left texel,
+/// which becomes zero, and its bits store that index instead.
+/// Swapping endpoints of a block XORs its indices with 1,
+/// so such blocks produce the same bytes except for the top-left texel.
+fn compress_texels(
+    texels: [u8; 4],
+    canonical: bool,
+    mut writer: impl Write,
+) -> std::io::Result<()> {
+    let mut word = u32::from_le_bytes(texels);
+    if canonical {
+        let top_left = word & 0b11;
+        word ^= top_left * 0x5555_5555;
+        word |= top_left;
+    }
+    writer.write_all(&word.to_le_bytes())
+}
+
+/// Reads texel indices written by [`compress_texels`].
+fn decompress_texels(canonical: bool, mut reader: impl Read) -> std::io::Result<[u8; 4]> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+
+    let mut word = u32::from_le_bytes(bytes);
+    if canonical {
+        let top_left = word & 0b11;
+        word = (word ^ (top_left * 0x5555_5555)) & !0b11 | top_left;
+    }
+    Ok(word.to_le_bytes())
+}
+
+/// Writes endpoints or indices of BC4 block.
+fn compress_bc4(block: &bc4::Block, aspect: usize, mut writer: impl Write) -> std::io::Result<()> {
+    match aspect {
+        0 => writer.write_all(&[block.color0.bits()]),
+        1 => writer.write_all(&[block.color1.bits()]),
+        2 => writer.write_all(&block.texels),
+        _ => unreachable!(),
+    }
+}
+
+/// Reads endpoints or indices of BC4 block written by [`compress_bc4`].
+fn decompress_bc4(
+    block: &mut bc4::Block,
+    aspect: usize,
+    mut decoder: impl Read,
+) -> Result<(), DecompressError> {
+    match aspect {
+        0 | 1 => {
+            let mut byte = [0; 1];
+            decoder.read_exact(&mut byte)?;
+            if aspect == 0 {
+                block.color0 = R8U::new(byte[0]);
+            } else {
+                block.color1 = R8U::new(byte[0]);
             }
+        }
+        2 => decoder.read_exact(&mut block.texels)?,
+        _ => unreachable!(),
+    }
+
+    Ok(())
+}
+
+// BC1 aspects:
+// 0 - color0
+// 1 - color1
+// 2 - texels
+// 3 - low byte of interleaved color0
+// 4 - low byte of interleaved color1
+//
+// With interleaved colors, aspects 0 and 1 contain high bytes of interleaved bits.
+// Otherwise aspects 0 and 1 contain little-endian bytes and aspects 3 and 4 are empty.
+impl AnyBlock for bc1::Block {
+    const FORMAT: Format = Format::BC1;
+    const ASPECTS: usize = 5;
+    const EMPTY: Self = bc1::Block::BLACK;
+
+    fn aspect_bytes(header: &JackalHeader, aspect: usize) -> usize {
+        match aspect {
+            0 | 1 => color_bytes(header),
+            2 => 4,
+            3 | 4 => 2 - color_bytes(header),
+            _ => unreachable!(),
+        }
+    }
+
+    fn compress(
+        &self,
+        aspect: usize,
+        header: &JackalHeader,
+        writer: impl Write,
+    ) -> std::io::Result<()> {
+        let interleaved = header.interleaved_colors();
+
+        match aspect {
+            0 => compress_color(self.color0, interleaved, writer),
+            1 => compress_color(self.color1, interleaved, writer),
+            2 => compress_texels(self.texels, header.canonical_indices(), writer),
+            3 => compress_color_low(self.color0, interleaved, writer),
+            4 => compress_color_low(self.color1, interleaved, writer),
+            _ => unreachable!(),
+        }
+    }
+
+    fn decompress(
+        &mut self,
+        aspect: usize,
+        header: &JackalHeader,
+        decoder: impl Read,
+    ) -> Result<(), DecompressError> {
+        let interleaved = header.interleaved_colors();
+
+        match aspect {
+            0 => self.color0 = decompress_color(interleaved, decoder)?,
+            1 => self.color1 = decompress_color(interleaved, decoder)?,
+            2 => self.texels = decompress_texels(header.canonical_indices(), decoder)?,
+            3 => decompress_color_low(&mut self.color0, interleaved, decoder)?,
+            4 => decompress_color_low(&mut self.color1, interleaved, decoder)?,
             _ => unreachable!(),
         }
 
         Ok(())
     }
 
-    fn decompress<'a, const ASPECT: usize>(
+    fn predict_endpoints(&mut self, reference: &Self) {
+        self.color0 = Rgb565::wrapping_sub(self.color0, reference.color0);
+        self.color1 = Rgb565::wrapping_sub(self.color1, reference.color1);
+    }
+
+    fn restore_endpoints(&mut self, reference: &Self) {
+        self.color0 = Rgb565::wrapping_add(self.color0, reference.color0);
+        self.color1 = Rgb565::wrapping_add(self.color1, reference.color1);
+    }
+}
+
+// BC2 aspects:
+// 0 - explicit alpha, 4 bits per texel
+// 1 - color0
+// 2 - color1
+// 3 - texels
+// 4 - low byte of interleaved color0
+// 5 - low byte of interleaved color1
+//
+// Colors are stored the same way as in BC1.
+impl AnyBlock for bc2::Block {
+    const FORMAT: Format = Format::BC2;
+    const ASPECTS: usize = 6;
+    const EMPTY: Self = bc2::Block::BLACK;
+
+    fn aspect_bytes(header: &JackalHeader, aspect: usize) -> usize {
+        match aspect {
+            0 => 8,
+            1 | 2 => color_bytes(header),
+            3 => 4,
+            4 | 5 => 2 - color_bytes(header),
+            _ => unreachable!(),
+        }
+    }
+
+    fn compress(
+        &self,
+        aspect: usize,
+        header: &JackalHeader,
+        mut writer: impl Write,
+    ) -> std::io::Result<()> {
+        let interleaved = header.interleaved_colors();
+
+        match aspect {
+            0 => writer.write_all(&self.alpha),
+            1 => compress_color(self.color0, interleaved, writer),
+            2 => compress_color(self.color1, interleaved, writer),
+            3 => compress_texels(self.texels, header.canonical_indices(), writer),
+            4 => compress_color_low(self.color0, interleaved, writer),
+            5 => compress_color_low(self.color1, interleaved, writer),
+            _ => unreachable!(),
+        }
+    }
+
+    fn decompress(
         &mut self,
+        aspect: usize,
+        header: &JackalHeader,
         mut decoder: impl Read,
     ) -> Result<(), DecompressError> {
-        match ASPECT {
-            0 => {
-                // Color0
-                let mut bytes = [0; 2];
-                decoder.read_exact(&mut bytes)?;
+        let interleaved = header.interleaved_colors();
 
-                self.color0 = Rgb565::from_bits(u16::from_le_bytes(bytes));
-            }
-            1 => {
-                // Color1
-                let mut bytes = [0; 2];
-                decoder.read_exact(&mut bytes)?;
+        match aspect {
+            0 => decoder.read_exact(&mut self.alpha)?,
+            1 => self.color0 = decompress_color(interleaved, decoder)?,
+            2 => self.color1 = decompress_color(interleaved, decoder)?,
+            3 => self.texels = decompress_texels(header.canonical_indices(), decoder)?,
+            4 => decompress_color_low(&mut self.color0, interleaved, decoder)?,
+            5 => decompress_color_low(&mut self.color1, interleaved, decoder)?,
+            _ => unreachable!(),
+        }
 
-                self.color1 = Rgb565::from_bits(u16::from_le_bytes(bytes));
-            }
-            2 => {
-                // Texels
-                decoder.read_exact(&mut self.texels)?;
-            }
+        Ok(())
+    }
+
+    fn predict_endpoints(&mut self, reference: &Self) {
+        self.color0 = Rgb565::wrapping_sub(self.color0, reference.color0);
+        self.color1 = Rgb565::wrapping_sub(self.color1, reference.color1);
+    }
+
+    fn restore_endpoints(&mut self, reference: &Self) {
+        self.color0 = Rgb565::wrapping_add(self.color0, reference.color0);
+        self.color1 = Rgb565::wrapping_add(self.color1, reference.color1);
+    }
+}
+
+// BC3 aspects:
+// 0 - alpha0
+// 1 - alpha1
+// 2 - alpha indices, 3 bits per texel
+// 3 - color0
+// 4 - color1
+// 5 - texels
+// 6 - low byte of interleaved color0
+// 7 - low byte of interleaved color1
+//
+// Alpha endpoints and indices are stored raw, colors are stored the same way as in BC1.
+impl AnyBlock for bc3::Block {
+    const FORMAT: Format = Format::BC3;
+    const ASPECTS: usize = 8;
+    const EMPTY: Self = bc3::Block::BLACK;
+
+    fn aspect_bytes(header: &JackalHeader, aspect: usize) -> usize {
+        match aspect {
+            0 | 1 => 1,
+            2 => 6,
+            3 | 4 => color_bytes(header),
+            5 => 4,
+            6 | 7 => 2 - color_bytes(header),
+            _ => unreachable!(),
+        }
+    }
+
+    fn compress(
+        &self,
+        aspect: usize,
+        header: &JackalHeader,
+        writer: impl Write,
+    ) -> std::io::Result<()> {
+        let interleaved = header.interleaved_colors();
+
+        match aspect {
+            0..3 => compress_bc4(&self.alpha, aspect, writer),
+            3 => compress_color(self.rgb.color0, interleaved, writer),
+            4 => compress_color(self.rgb.color1, interleaved, writer),
+            5 => compress_texels(self.rgb.texels, header.canonical_indices(), writer),
+            6 => compress_color_low(self.rgb.color0, interleaved, writer),
+            7 => compress_color_low(self.rgb.color1, interleaved, writer),
+            _ => unreachable!(),
+        }
+    }
+
+    fn decompress(
+        &mut self,
+        aspect: usize,
+        header: &JackalHeader,
+        decoder: impl Read,
+    ) -> Result<(), DecompressError> {
+        let interleaved = header.interleaved_colors();
+
+        match aspect {
+            0..3 => decompress_bc4(&mut self.alpha, aspect, decoder)?,
+            3 => self.rgb.color0 = decompress_color(interleaved, decoder)?,
+            4 => self.rgb.color1 = decompress_color(interleaved, decoder)?,
+            5 => self.rgb.texels = decompress_texels(header.canonical_indices(), decoder)?,
+            6 => decompress_color_low(&mut self.rgb.color0, interleaved, decoder)?,
+            7 => decompress_color_low(&mut self.rgb.color1, interleaved, decoder)?,
             _ => unreachable!(),
         }
 
         Ok(())
     }
+
+    fn predict_endpoints(&mut self, reference: &Self) {
+        self.alpha.predict_endpoints(&reference.alpha);
+        self.rgb.predict_endpoints(&reference.rgb);
+    }
+
+    fn restore_endpoints(&mut self, reference: &Self) {
+        self.alpha.restore_endpoints(&reference.alpha);
+        self.rgb.restore_endpoints(&reference.rgb);
+    }
+}
+
+// BC4 aspects:
+// 0 - color0
+// 1 - color1
+// 2 - texels, 3 bits per texel
+impl AnyBlock for bc4::Block {
+    const FORMAT: Format = Format::BC4;
+    const ASPECTS: usize = 3;
+    const EMPTY: Self = bc4::Block::BLACK;
+
+    fn aspect_bytes(_header: &JackalHeader, aspect: usize) -> usize {
+        match aspect {
+            0 | 1 => 1,
+            2 => 6,
+            _ => unreachable!(),
+        }
+    }
+
+    fn compress(
+        &self,
+        aspect: usize,
+        _header: &JackalHeader,
+        writer: impl Write,
+    ) -> std::io::Result<()> {
+        compress_bc4(self, aspect, writer)
+    }
+
+    fn decompress(
+        &mut self,
+        aspect: usize,
+        _header: &JackalHeader,
+        decoder: impl Read,
+    ) -> Result<(), DecompressError> {
+        decompress_bc4(self, aspect, decoder)
+    }
+
+    fn predict_endpoints(&mut self, reference: &Self) {
+        self.color0 = self.color0.wrapping_sub(reference.color0);
+        self.color1 = self.color1.wrapping_sub(reference.color1);
+    }
+
+    fn restore_endpoints(&mut self, reference: &Self) {
+        self.color0 = self.color0.wrapping_add(reference.color0);
+        self.color1 = self.color1.wrapping_add(reference.color1);
+    }
+}
+
+// BC5 aspects:
+// 0..3 - red channel as BC4 aspects
+// 3..6 - green channel as BC4 aspects
+impl AnyBlock for bc5::Block {
+    const FORMAT: Format = Format::BC5;
+    const ASPECTS: usize = 6;
+    const EMPTY: Self = bc5::Block::BLACK;
+
+    fn aspect_bytes(header: &JackalHeader, aspect: usize) -> usize {
+        bc4::Block::aspect_bytes(header, aspect % 3)
+    }
+
+    fn compress(
+        &self,
+        aspect: usize,
+        _header: &JackalHeader,
+        writer: impl Write,
+    ) -> std::io::Result<()> {
+        match aspect {
+            0..3 => compress_bc4(&self.red, aspect, writer),
+            3..6 => compress_bc4(&self.green, aspect - 3, writer),
+            _ => unreachable!(),
+        }
+    }
+
+    fn decompress(
+        &mut self,
+        aspect: usize,
+        _header: &JackalHeader,
+        decoder: impl Read,
+    ) -> Result<(), DecompressError> {
+        match aspect {
+            0..3 => decompress_bc4(&mut self.red, aspect, decoder),
+            3..6 => decompress_bc4(&mut self.green, aspect - 3, decoder),
+            _ => unreachable!(),
+        }
+    }
+
+    fn predict_endpoints(&mut self, reference: &Self) {
+        self.red.predict_endpoints(&reference.red);
+        self.green.predict_endpoints(&reference.green);
+    }
+
+    fn restore_endpoints(&mut self, reference: &Self) {
+        self.red.restore_endpoints(&reference.red);
+        self.green.restore_endpoints(&reference.green);
+    }
+}
+
+/// Returns header without transforms, so that aspects of a block written with it
+/// form raw block layout.
+fn raw_header<B>() -> JackalHeader
+where
+    B: AnyBlock,
+{
+    JackalHeader::new(B::FORMAT, Extent::D1 { width: 1 }, MipLevels(1))
+}
+
+/// Returns number of bytes written by [`write_raw_block`].
+pub(crate) fn raw_block_bytes<B>() -> usize
+where
+    B: AnyBlock,
+{
+    let header = raw_header::<B>();
+    (0..B::ASPECTS)
+        .map(|aspect| B::aspect_bytes(&header, aspect))
+        .sum()
+}
+
+/// Writes all aspects of the block without any transforms.
+pub(crate) fn write_raw_block<B>(block: &B, mut write: impl Write) -> std::io::Result<()>
+where
+    B: AnyBlock,
+{
+    let header = raw_header::<B>();
+    for aspect in 0..B::ASPECTS {
+        block.compress(aspect, &header, &mut write)?;
+    }
+    Ok(())
+}
+
+/// Reads block written by [`write_raw_block`].
+pub(crate) fn read_raw_block<B>(mut read: impl Read) -> Result<B, DecompressError>
+where
+    B: AnyBlock,
+{
+    let header = raw_header::<B>();
+    let mut block = B::EMPTY;
+    for aspect in 0..B::ASPECTS {
+        block.decompress(aspect, &header, &mut read)?;
+    }
+    Ok(block)
+}
+
+/// Views BC1 blocks as raw bytes, e.g. for upload into GPU buffer.
+///
+/// Colors are stored in native byte order, which matches BC1 layout on little-endian targets.
+pub fn blocks_as_bytes(blocks: &[bc1::Block]) -> &[u8] {
+    // SAFETY: Block is `repr(C)` without padding, so every byte is initialized.
+    unsafe {
+        std::slice::from_raw_parts(blocks.as_ptr().cast::<u8>(), std::mem::size_of_val(blocks))
+    }
+}
+
+/// Views raw bytes as BC1 blocks.
+///
+/// Returns `None` if length of `bytes` is not a multiple of block size
+/// or `bytes` are not aligned for [`bc1::Block`].
+pub fn blocks_from_bytes(bytes: &[u8]) -> Option<&[bc1::Block]> {
+    let size = std::mem::size_of::<bc1::Block>();
+    let align = std::mem::align_of::<bc1::Block>();
+
+    if !bytes.len().is_multiple_of(size) || !(bytes.as_ptr() as usize).is_multiple_of(align) {
+        return None;
+    }
+
+    // SAFETY: Length and alignment are checked above and any bit pattern is a valid block.
+    Some(unsafe { std::slice::from_raw_parts(bytes.as_ptr().cast(), bytes.len() / size) })
+}
+
+#[test]
+fn test_blocks_as_bytes() {
+    assert_eq!(std::mem::size_of::<bc1::Block>(), 8);
+    assert_eq!(std::mem::align_of::<bc1::Block>(), 2);
+
+    let blocks = [
+        bc1::Block::BLACK,
+        bc1::Block {
+            color0: Rgb565::new(31, 2, 3),
+            color1: Rgb565::new(4, 5, 6),
+            texels: [1, 2, 3, 4],
+        },
+    ];
+
+    let bytes = blocks_as_bytes(&blocks);
+    assert_eq!(bytes.len(), 16);
+    if cfg!(target_endian = "little") {
+        assert_eq!(&bytes[..8], &bc1::Block::BLACK.bytes());
+        assert_eq!(&bytes[8..], &blocks[1].bytes());
+    }
+    assert_eq!(blocks_from_bytes(bytes), Some(&blocks[..]));
+
+    assert_eq!(blocks_from_bytes(&bytes[..12]), None);
+    assert_eq!(blocks_from_bytes(&bytes[1..9]), None);
+}
+
+#[cfg(feature = "bytemuck")]
+#[test]
+fn test_blocks_pod() {
+    let blocks = [bc1::Block::WHITE, bc1::Block::TRANSPARENT];
+    let bytes: &[u8] = bytemuck::cast_slice(&blocks);
+    assert_eq!(bytes, blocks_as_bytes(&blocks));
+    assert_eq!(bytemuck::cast_slice::<u8, bc1::Block>(bytes), &blocks[..]);
+}
+
+#[test]
+fn test_canonical_texels() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(7);
+
+    for _ in 0..10000 {
+        let texels: [u8; 4] = rng.gen();
+
+        let mut bytes = Vec::new();
+        compress_texels(texels, true, &mut bytes).unwrap();
+        assert_eq!(decompress_texels(true, &bytes[..]).unwrap(), texels);
+
+        // Swapped endpoints differ only in the top-left texel.
+        let swapped = texels.map(|byte| byte ^ 0x55);
+        let mut swapped_bytes = Vec::new();
+        compress_texels(swapped, true, &mut swapped_bytes).unwrap();
+        assert_eq!(swapped_bytes[1..], bytes[1..]);
+        assert_eq!(swapped_bytes[0] & !0b11, bytes[0] & !0b11);
+    }
+
+    let mut bytes = Vec::new();
+    compress_texels([0x1B; 4], false, &mut bytes).unwrap();
+    assert_eq!(bytes, [0x1B; 4]);
+}
+
+#[test]
+fn test_random_residuals() {
+    use rand::{Rng, SeedableRng};
+
+    use crate::jackal::{Extent, MipLevels, Predictor};
+
+    fn decode_random<B: AnyBlock>(header: &JackalHeader, rng: &mut impl Rng) -> B {
+        let mut block = B::EMPTY;
+        for aspect in 0..B::ASPECTS {
+            let bytes: Vec<u8> = (0..B::aspect_bytes(header, aspect))
+                .map(|_| rng.gen())
+                .collect();
+            block.decompress(aspect, header, &bytes[..]).unwrap();
+        }
+        block
+    }
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(11);
+    let extent = Extent::D2 {
+        width: 1,
+        height: 1,
+    };
+
+    for flags in 0..8 {
+        let header = JackalHeader::new(Format::BC1, extent, MipLevels(1))
+            .with_interleaved_colors(flags & 1 != 0)
+            .with_canonical_indices(flags & 2 != 0)
+            .with_predictor(if flags & 4 != 0 {
+                Predictor::Previous
+            } else {
+                Predictor::None
+            });
+
+        for _ in 0..1000 {
+            let reference = decode_random::<bc1::Block>(&header, &mut rng);
+            let mut block = decode_random::<bc1::Block>(&header, &mut rng);
+            block.restore_endpoints(&reference);
+            block.decode_rgba();
+
+            let reference = decode_random::<bc2::Block>(&header, &mut rng);
+            let mut block = decode_random::<bc2::Block>(&header, &mut rng);
+            block.restore_endpoints(&reference);
+            block.decode();
+
+            let reference = decode_random::<bc3::Block>(&header, &mut rng);
+            let mut block = decode_random::<bc3::Block>(&header, &mut rng);
+            block.restore_endpoints(&reference);
+            block.decode();
+        }
+    }
 }
@@ -0,0 +1,252 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::jackal::{ContainerVersion, DecodeError, DecompressError, JackalBlock, JackalHeader};
+
+/// Application metadata stored between the header and the super-block table.
+///
+/// Decoders skip chunks, so any data may be attached without breaking other readers.
+/// Chunks are stored only in [`ContainerVersion::V5`] and later containers.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MetaChunk {
+    /// Tag identifying the kind of the chunk.
+    pub tag: [u8; 4],
+
+    pub data: Vec<u8>,
+}
+
+impl MetaChunk {
+    /// Size of the tag and length preceding chunk data.
+    pub const HEADER_BYTES_SIZE: usize = 8;
+
+    /// Tag of the chunk holding size of the encoded image in pixels.
+    pub const PIXEL_SIZE_TAG: [u8; 4] = *b"PXSZ";
+
+    pub fn new(tag: [u8; 4], data: impl Into<Vec<u8>>) -> Self {
+        MetaChunk {
+            tag,
+            data: data.into(),
+        }
+    }
+
+    /// Creates chunk recording width and height of the image in pixels.
+    ///
+    /// Texture extent is measured in blocks, so this chunk lets decoders
+    /// crop partially used edge blocks.
+    pub fn pixel_size(width: u32, height: u32) -> Self {
+        let mut data = Vec::with_capacity(8);
+        data.extend_from_slice(&width.to_le_bytes());
+        data.extend_from_slice(&height.to_le_bytes());
+        MetaChunk::new(Self::PIXEL_SIZE_TAG, data)
+    }
+
+    /// Returns width and height in pixels if this is a well-formed pixel size chunk.
+    pub fn as_pixel_size(&self) -> Option<(u32, u32)> {
+        if self.tag != Self::PIXEL_SIZE_TAG {
+            return None;
+        }
+        let data: &[u8; 8] = self.data.as_slice().try_into().ok()?;
+        let (width, height) = data.split_at(4);
+        Some((
+            u32::from_le_bytes(width.try_into().unwrap()),
+            u32::from_le_bytes(height.try_into().unwrap()),
+        ))
+    }
+}
+
+/// Returns size of the chunk section in bytes for the container version.
+pub fn meta_chunks_bytes_size(version: ContainerVersion, chunks: &[MetaChunk]) -> usize {
+    if !has_chunk_section(version) {
+        return 0;
+    }
+
+    size_of::<u16>()
+        + chunks
+            .iter()
+            .map(|chunk| MetaChunk::HEADER_BYTES_SIZE + chunk.data.len())
+            .sum::<usize>()
+}
+
+/// Returns `true` if container version has chunk section after the header.
+fn has_chunk_section(version: ContainerVersion) -> bool {
+    match version {
+        ContainerVersion::Legacy
+        | ContainerVersion::V2
+        | ContainerVersion::V3
+        | ContainerVersion::V4 => false,
+        ContainerVersion::V5
+        | ContainerVersion::V6
+        | ContainerVersion::V7
+        | ContainerVersion::V8
+        | ContainerVersion::V9 => true,
+    }
+}
+
+/// Writes chunk section of the header's container version.
+///
+/// Containers older than [`ContainerVersion::V5`] have no chunk section,
+/// writing chunks into them is an error.
+pub(super) fn write_meta_chunks(
+    header: &JackalHeader,
+    chunks: &[MetaChunk],
+    mut write: impl Write,
+) -> std::io::Result<()> {
+    let invalid = |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidInput, message);
+
+    if !has_chunk_section(header.version()) {
+        if chunks.is_empty() {
+            return Ok(());
+        }
+        return Err(invalid(
+            "container version does not support metadata chunks",
+        ));
+    }
+
+    let count = u16::try_from(chunks.len()).map_err(|_| invalid("too many metadata chunks"))?;
+    write.write_all(&count.to_le_bytes())?;
+
+    for chunk in chunks {
+        let len =
+            u32::try_from(chunk.data.len()).map_err(|_| invalid("metadata chunk is too large"))?;
+        write.write_all(&chunk.tag)?;
+        write.write_all(&len.to_le_bytes())?;
+        write.write_all(&chunk.data)?;
+    }
+
+    Ok(())
+}
+
+/// Reads metadata chunks that follow the header.
+///
+/// `read` must be positioned right after the header, as left by [`JackalHeader::read_from`],
+/// and is left at the start of the super-block table, which can be read with [`read_jackal_blocks`].
+/// Containers older than [`ContainerVersion::V5`] have no chunks.
+///
+/// Chunk extending past the end of the stream or leaving no room
+/// for the super-block table is rejected with [`DecodeError::InvalidData`].
+///
+/// [`read_jackal_blocks`]: crate::jackal::read_jackal_blocks
+pub fn read_meta_chunks(
+    read: impl Read + Seek,
+    header: &JackalHeader,
+) -> Result<Vec<MetaChunk>, DecompressError> {
+    let mut chunks = Vec::new();
+    read_chunk_section(read, header, Some(&mut chunks))?;
+    Ok(chunks)
+}
+
+/// Skips metadata chunks that follow the header, leaving `read` at the start of the table.
+pub(super) fn skip_meta_chunks(
+    read: impl Read + Seek,
+    header: &JackalHeader,
+) -> Result<(), DecompressError> {
+    read_chunk_section(read, header, None)
+}
+
+/// Reads chunk section pushing chunks into `chunks` or seeking over them if it is `None`.
+fn read_chunk_section(
+    mut read: impl Read + Seek,
+    header: &JackalHeader,
+    mut chunks: Option<&mut Vec<MetaChunk>>,
+) -> Result<(), DecompressError> {
+    if !has_chunk_section(header.version()) {
+        return Ok(());
+    }
+
+    let start = read.stream_position()?;
+    let stream_len = read.seek(SeekFrom::End(0))?;
+    read.seek(SeekFrom::Start(start))?;
+
+    let mut count_bytes = [0; 2];
+    read.read_exact(&mut count_bytes)?;
+    let count = u16::from_le_bytes(count_bytes);
+
+    let mut pos = start + count_bytes.len() as u64;
+
+    for _ in 0..count {
+        let mut bytes = [0; MetaChunk::HEADER_BYTES_SIZE];
+        read.read_exact(&mut bytes)?;
+        pos += bytes.len() as u64;
+
+        let mut tag = [0; 4];
+        tag.copy_from_slice(&bytes[0..4]);
+        let mut len_bytes = [0; 4];
+        len_bytes.copy_from_slice(&bytes[4..8]);
+        let len = u32::from_le_bytes(len_bytes) as u64;
+
+        // Checked before allocation, so bogus length cannot exhaust memory.
+        if pos + len > stream_len {
+            return Err(DecodeError::INVALID_DATA.into());
+        }
+
+        match &mut chunks {
+            Some(chunks) => {
+                let mut data = Vec::new();
+                (&mut read).take(len).read_to_end(&mut data)?;
+                chunks.push(MetaChunk { tag, data });
+            }
+            None => {
+                read.seek(SeekFrom::Start(pos + len))?;
+            }
+        }
+        pos += len;
+    }
+
+    let table_len = header
+        .checked_jackal_blocks_count()
+        .and_then(|count| (count as u64).checked_mul(JackalBlock::BYTES_SIZE as u64));
+    if table_len.is_none_or(|table_len| pos + table_len > stream_len) {
+        return Err(DecodeError::INVALID_DATA.into());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_chunks_roundtrip() {
+    use crate::jackal::{Extent, Format, MipLevels};
+
+    let header = JackalHeader::new(Format::BC1, Extent::D1 { width: 2 }, MipLevels(1));
+    let chunks = [
+        MetaChunk::new(*b"GUID", [7; 16]),
+        MetaChunk::new(*b"NONE", []),
+        MetaChunk::new(*b"AUTH", "someone"),
+    ];
+
+    let mut bytes = Vec::new();
+    write_meta_chunks(&header, &chunks, &mut bytes).unwrap();
+    assert_eq!(
+        bytes.len(),
+        meta_chunks_bytes_size(ContainerVersion::V5, &chunks)
+    );
+
+    // Room for the table.
+    bytes.extend_from_slice(&[0; JackalBlock::BYTES_SIZE]);
+
+    let mut read = std::io::Cursor::new(&bytes);
+    assert_eq!(read_meta_chunks(&mut read, &header).unwrap(), chunks);
+    assert_eq!(
+        read.position() as usize,
+        bytes.len() - JackalBlock::BYTES_SIZE
+    );
+
+    // Older containers have no chunk section.
+    let v4 = header.with_version(ContainerVersion::V4);
+    let mut empty = Vec::new();
+    write_meta_chunks(&v4, &[], &mut empty).unwrap();
+    assert!(empty.is_empty());
+    assert!(write_meta_chunks(&v4, &chunks, &mut empty).is_err());
+    assert_eq!(
+        read_meta_chunks(std::io::Cursor::new(&bytes), &v4).unwrap(),
+        []
+    );
+}
+
+#[test]
+fn test_pixel_size_chunk() {
+    let chunk = MetaChunk::pixel_size(5, 3);
+    assert_eq!(chunk.tag, *b"PXSZ");
+    assert_eq!(chunk.as_pixel_size(), Some((5, 3)));
+
+    assert_eq!(MetaChunk::new(*b"PXSZ", [0; 7]).as_pixel_size(), None);
+    assert_eq!(MetaChunk::new(*b"AUTH", [0; 8]).as_pixel_size(), None);
+}
@@ -0,0 +1,382 @@
+//! Minimal DDS container support.
+//!
+//! Only 2D textures with a single mip level are exported.
+//! On import, only the top mip level is read.
+
+use std::io::{Read, Write};
+
+use crate::jackal::{DecodeError, DecodeLimits, DecompressError, Extent, Format};
+
+const DDS_MAGIC: u32 = 0x2053_4444; // "DDS "
+const HEADER_SIZE: usize = 124;
+const PIXEL_FORMAT_SIZE: usize = 32;
+const DX10_HEADER_SIZE: usize = 20;
+
+const DDSD_CAPS: u32 = 0x1;
+const DDSD_HEIGHT: u32 = 0x2;
+const DDSD_WIDTH: u32 = 0x4;
+const DDSD_PIXELFORMAT: u32 = 0x1000;
+const DDSD_LINEARSIZE: u32 = 0x80000;
+
+const DDPF_FOURCC: u32 = 0x4;
+const DDSCAPS_TEXTURE: u32 = 0x1000;
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_VOLUME: u32 = 0x200000;
+
+const D3D10_RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+const D3D10_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+
+const fn four_cc(code: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*code)
+}
+
+/// Returns DXGI format used in DX10 header for the format.
+fn dxgi_format(format: Format) -> u32 {
+    match format {
+        Format::BC1 => 71,
+        Format::BC2 => 74,
+        Format::BC3 => 77,
+        Format::BC4 => 80,
+        Format::BC5 => 83,
+        Format::BC6 => 95,
+        Format::BC7 => 98,
+    }
+}
+
+fn format_from_dxgi(dxgi: u32) -> Option<Format> {
+    match dxgi {
+        70..=72 => Some(Format::BC1),
+        73..=75 => Some(Format::BC2),
+        76..=78 => Some(Format::BC3),
+        79..=81 => Some(Format::BC4),
+        82..=84 => Some(Format::BC5),
+        94..=96 => Some(Format::BC6),
+        97..=99 => Some(Format::BC7),
+        _ => None,
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut word = [0; 4];
+    word.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(word)
+}
+
+fn write_u32(bytes: &mut [u8], offset: usize, value: u32) {
+    bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Writes blocks into DDS container.
+///
+/// `extent` is the size of the image in pixels,
+/// `blocks_bytes` contains blocks covering it in row-major order.
+///
+/// Only `Extent::D2` is supported.
+pub fn export_dds(
+    extent: Extent,
+    format: Format,
+    blocks_bytes: &[u8],
+    mut write: impl Write,
+) -> std::io::Result<()> {
+    let Extent::D2 { width, height } = extent else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "only 2D extents can be exported to DDS",
+        ));
+    };
+
+    let linear_size = format
+        .blocks_for_extent(extent)
+        .and_then(|blocks| blocks.checked_mul(format.block_byte_size()));
+
+    if linear_size != Some(blocks_bytes.len()) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "blocks size does not match extent",
+        ));
+    }
+
+    let linear_size = u32::try_from(blocks_bytes.len()).map_err(|_| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "texture is too large")
+    })?;
+
+    let mut header = [0; 4 + HEADER_SIZE];
+
+    write_u32(&mut header, 0, DDS_MAGIC);
+    write_u32(&mut header, 4, HEADER_SIZE as u32);
+    write_u32(
+        &mut header,
+        8,
+        DDSD_CAPS | DDSD_HEIGHT | DDSD_WIDTH | DDSD_PIXELFORMAT | DDSD_LINEARSIZE,
+    );
+    write_u32(&mut header, 12, height);
+    write_u32(&mut header, 16, width);
+    write_u32(&mut header, 20, linear_size);
+
+    // Pixel format.
+    write_u32(&mut header, 76, PIXEL_FORMAT_SIZE as u32);
+    write_u32(&mut header, 80, DDPF_FOURCC);
+
+    let fourcc = match format {
+        Format::BC1 => four_cc(b"DXT1"),
+        Format::BC2 => four_cc(b"DXT3"),
+        Format::BC3 => four_cc(b"DXT5"),
+        _ => four_cc(b"DX10"),
+    };
+    write_u32(&mut header, 84, fourcc);
+
+    write_u32(&mut header, 108, DDSCAPS_TEXTURE);
+
+    write.write_all(&header)?;
+
+    if fourcc == four_cc(b"DX10") {
+        let mut dx10 = [0; DX10_HEADER_SIZE];
+        write_u32(&mut dx10, 0, dxgi_format(format));
+        write_u32(&mut dx10, 4, D3D10_RESOURCE_DIMENSION_TEXTURE2D);
+        write_u32(&mut dx10, 12, 1);
+        write.write_all(&dx10)?;
+    }
+
+    write.write_all(blocks_bytes)
+}
+
+/// Reads blocks of the top mip level from DDS container.
+///
+/// Returns extent of the image in pixels, format of blocks and blocks bytes in row-major order.
+///
+/// Volume, cube-map and array textures are not supported.
+/// Extent is checked against default [`DecodeLimits`].
+pub fn import_dds(read: impl Read) -> Result<(Extent, Format, Vec<u8>), DecompressError> {
+    import_dds_with_limits(read, &DecodeLimits::default())
+}
+
+/// Reads blocks of the top mip level from DDS container rejecting extents that exceed `limits`.
+pub fn import_dds_with_limits(
+    mut read: impl Read,
+    limits: &DecodeLimits,
+) -> Result<(Extent, Format, Vec<u8>), DecompressError> {
+    let mut header = [0; 4 + HEADER_SIZE];
+    read.read_exact(&mut header)?;
+
+    if read_u32(&header, 0) != DDS_MAGIC {
+        return Err(DecodeError::InvalidMagic.into());
+    }
+
+    if read_u32(&header, 4) != HEADER_SIZE as u32
+        || read_u32(&header, 76) != PIXEL_FORMAT_SIZE as u32
+    {
+        return Err(DecodeError::InvalidHeader.into());
+    }
+
+    let height = read_u32(&header, 12);
+    let width = read_u32(&header, 16);
+    let depth = read_u32(&header, 24);
+    let caps2 = read_u32(&header, 112);
+
+    if caps2 & (DDSCAPS2_CUBEMAP | DDSCAPS2_VOLUME) != 0 || depth > 1 {
+        return Err(DecodeError::Unsupported.into());
+    }
+
+    if read_u32(&header, 80) & DDPF_FOURCC == 0 {
+        return Err(DecodeError::Unsupported.into());
+    }
+
+    let fourcc = read_u32(&header, 84);
+
+    let format = if fourcc == four_cc(b"DXT1") {
+        Format::BC1
+    } else if fourcc == four_cc(b"DXT2") || fourcc == four_cc(b"DXT3") {
+        Format::BC2
+    } else if fourcc == four_cc(b"DXT4") || fourcc == four_cc(b"DXT5") {
+        Format::BC3
+    } else if fourcc == four_cc(b"ATI1") || fourcc == four_cc(b"BC4U") {
+        Format::BC4
+    } else if fourcc == four_cc(b"ATI2") || fourcc == four_cc(b"BC5U") {
+        Format::BC5
+    } else if fourcc == four_cc(b"DX10") {
+        let mut dx10 = [0; DX10_HEADER_SIZE];
+        read.read_exact(&mut dx10)?;
+
+        let resource_dimension = read_u32(&dx10, 4);
+        let misc_flag = read_u32(&dx10, 8);
+        let array_size = read_u32(&dx10, 12);
+
+        if resource_dimension != D3D10_RESOURCE_DIMENSION_TEXTURE2D
+            || misc_flag & D3D10_RESOURCE_MISC_TEXTURECUBE != 0
+            || array_size > 1
+        {
+            return Err(DecodeError::Unsupported.into());
+        }
+
+        format_from_dxgi(read_u32(&dx10, 0)).ok_or(DecodeError::Unsupported)?
+    } else {
+        return Err(DecodeError::Unsupported.into());
+    };
+
+    let extent = Extent::D2 { width, height };
+    let size = limits
+        .check_pixel_extent(extent)?
+        .checked_mul(format.block_byte_size())
+        .ok_or(DecodeError::InvalidExtent)?;
+
+    // Buffer grows as bytes are read, so that bogus extent fails on short input
+    // before allocating.
+    let mut blocks_bytes = Vec::new();
+    read.take(size as u64).read_to_end(&mut blocks_bytes)?;
+
+    if blocks_bytes.len() != size {
+        return Err(DecodeError::INVALID_DATA.into());
+    }
+
+    Ok((extent, format, blocks_bytes))
+}
+
+#[test]
+fn test_dds_bc1_roundtrip() {
+    use crate::bc1;
+
+    let blocks = [
+        bc1::Block::BLACK,
+        bc1::Block::WHITE,
+        bc1::Block::TRANSPARENT,
+        bc1::Block::WHITE,
+    ];
+    let bytes: Vec<u8> = blocks.iter().flat_map(|b| b.bytes()).collect();
+
+    let extent = Extent::D2 {
+        width: 5,
+        height: 7,
+    };
+
+    let mut output = Vec::new();
+    export_dds(extent, Format::BC1, &bytes, &mut output).unwrap();
+
+    // Check header fields directly.
+    assert_eq!(&output[0..4], b"DDS ");
+    assert_eq!(read_u32(&output, 4), 124);
+    assert_eq!(read_u32(&output, 12), 7);
+    assert_eq!(read_u32(&output, 16), 5);
+    assert_eq!(read_u32(&output, 20), 32);
+    assert_eq!(read_u32(&output, 76), 32);
+    assert_eq!(&output[84..88], b"DXT1");
+    assert_eq!(output.len(), 128 + 32);
+    assert_eq!(&output[128..], &bytes[..]);
+
+    let (imported_extent, format, imported) = import_dds(&output[..]).unwrap();
+    assert_eq!(imported_extent, extent);
+    assert_eq!(format, Format::BC1);
+    assert_eq!(imported, bytes);
+}
+
+#[test]
+fn test_dds_dx10_roundtrip() {
+    let extent = Extent::D2 {
+        width: 8,
+        height: 4,
+    };
+
+    for (format, dxgi) in [(Format::BC4, 80), (Format::BC5, 83)] {
+        let bytes: Vec<u8> = (0..2 * format.block_byte_size()).map(|i| i as u8).collect();
+
+        let mut output = Vec::new();
+        export_dds(extent, format, &bytes, &mut output).unwrap();
+
+        assert_eq!(&output[84..88], b"DX10");
+        assert_eq!(read_u32(&output, 128), dxgi);
+        assert_eq!(read_u32(&output, 132), 3);
+        assert_eq!(read_u32(&output, 140), 1);
+        assert_eq!(&output[148..], &bytes[..]);
+
+        let (imported_extent, imported_format, imported) = import_dds(&output[..]).unwrap();
+        assert_eq!(imported_extent, extent);
+        assert_eq!(imported_format, format);
+        assert_eq!(imported, bytes);
+    }
+}
+
+#[test]
+fn test_dds_dxt3_roundtrip() {
+    let extent = Extent::D2 {
+        width: 4,
+        height: 8,
+    };
+    let bytes: Vec<u8> = (0..32).collect();
+
+    let mut output = Vec::new();
+    export_dds(extent, Format::BC2, &bytes, &mut output).unwrap();
+    assert_eq!(&output[84..88], b"DXT3");
+
+    let (imported_extent, imported_format, imported) = import_dds(&output[..]).unwrap();
+    assert_eq!(imported_extent, extent);
+    assert_eq!(imported_format, Format::BC2);
+    assert_eq!(imported, bytes);
+}
+
+#[test]
+fn test_dds_unsupported() {
+    let extent = Extent::D2Array {
+        width: 4,
+        height: 4,
+        layers: 2,
+    };
+    let err = export_dds(extent, Format::BC1, &[0; 16], std::io::sink()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    let mut output = Vec::new();
+    export_dds(
+        Extent::D2 {
+            width: 4,
+            height: 4,
+        },
+        Format::BC1,
+        &[0; 8],
+        &mut output,
+    )
+    .unwrap();
+
+    // Mark as volume texture.
+    write_u32(&mut output, 112, DDSCAPS2_VOLUME);
+
+    assert!(matches!(
+        import_dds(&output[..]),
+        Err(DecompressError::Decode(DecodeError::Unsupported))
+    ));
+}
+
+#[test]
+fn test_dds_huge_extent() {
+    let mut output = Vec::new();
+    export_dds(
+        Extent::D2 {
+            width: 4,
+            height: 4,
+        },
+        Format::BC1,
+        &[0; 8],
+        &mut output,
+    )
+    .unwrap();
+
+    for size in [u32::MAX, 0x7FFF_FFFF, 0] {
+        let mut header = output[..128].to_vec();
+        write_u32(&mut header, 12, size);
+        write_u32(&mut header, 16, size);
+
+        let err = import_dds(&header[..]).unwrap_err();
+        assert_eq!(err.decode_error(), Some(DecodeError::InvalidExtent));
+    }
+
+    // Extent within limits with missing blocks.
+    let mut header = output[..128].to_vec();
+    write_u32(&mut header, 12, 4096);
+    write_u32(&mut header, 16, 4096);
+    let err = import_dds(&header[..]).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::INVALID_DATA));
+
+    let limits = DecodeLimits {
+        max_blocks: 0,
+        ..DecodeLimits::default()
+    };
+    let err = import_dds_with_limits(&output[..], &limits).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::InvalidExtent));
+}
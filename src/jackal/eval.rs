@@ -0,0 +1,192 @@
+use std::{
+    fmt,
+    io::Cursor,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    bc1,
+    jackal::{compress_texture, decompress_bc1_texture, CompressOptions, DecompressError, Extent},
+};
+
+/// Result of compressing blocks and decompressing them back.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalReport {
+    /// Number of evaluated blocks.
+    pub blocks: usize,
+
+    /// Size of the compressed stream in bytes.
+    pub compressed_size: u64,
+
+    pub compress_time: Duration,
+    pub decompress_time: Duration,
+}
+
+impl EvalReport {
+    /// Returns average compressed size of one block in bytes.
+    pub fn bytes_per_block(&self) -> f64 {
+        self.compressed_size as f64 / self.blocks as f64
+    }
+
+    /// Returns compressed size relative to size of the uncompressed BC1 blocks.
+    pub fn ratio(&self) -> f64 {
+        self.compressed_size as f64 / (self.blocks * size_of::<bc1::Block>()) as f64
+    }
+}
+
+#[derive(Debug)]
+pub enum EvalError {
+    /// Blocks were rejected by the compressor.
+    Compress(std::io::Error),
+
+    /// Compressed stream failed to decompress.
+    Decompress(DecompressError),
+
+    /// Decompressed blocks differ from the compressed ones.
+    /// This is a bug in the codec.
+    Mismatch {
+        /// Index of the first differing block.
+        first: usize,
+
+        /// Number of differing blocks.
+        count: usize,
+    },
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::Compress(err) => write!(f, "compression failed: {err}"),
+            EvalError::Decompress(err) => write!(f, "decompression failed: {err}"),
+            EvalError::Mismatch { first, count } => write!(
+                f,
+                "{count} decompressed blocks differ from compressed ones, first at {first}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EvalError::Compress(err) => Some(err),
+            EvalError::Decompress(err) => Some(err),
+            EvalError::Mismatch { .. } => None,
+        }
+    }
+}
+
+/// Compresses BC1 blocks in memory, decompresses them back and checks that they match.
+///
+/// `extent` is measured in blocks.
+/// Returns compressed size and time spent in each stage.
+pub fn evaluate_bc1(
+    extent: Extent,
+    blocks: &[bc1::Block],
+    options: CompressOptions,
+) -> Result<EvalReport, EvalError> {
+    let start = Instant::now();
+    let mut stream = Vec::new();
+    compress_texture(extent, blocks, options, Cursor::new(&mut stream))
+        .map_err(EvalError::Compress)?;
+    let compress_time = start.elapsed();
+
+    let decompress_time = verify_bc1(extent, blocks, &stream)?;
+
+    Ok(EvalReport {
+        blocks: blocks.len(),
+        compressed_size: stream.len() as u64,
+        compress_time,
+        decompress_time,
+    })
+}
+
+/// Decompresses `stream` and compares result with `blocks`.
+///
+/// Returns time spent decompressing.
+fn verify_bc1(extent: Extent, blocks: &[bc1::Block], stream: &[u8]) -> Result<Duration, EvalError> {
+    let start = Instant::now();
+    let (decoded_extent, decoded) =
+        decompress_bc1_texture(Cursor::new(stream)).map_err(EvalError::Decompress)?;
+    let decompress_time = start.elapsed();
+
+    if decoded_extent != extent || decoded.len() != blocks.len() {
+        return Err(EvalError::Mismatch {
+            first: 0,
+            count: blocks.len(),
+        });
+    }
+
+    let mut differing = blocks
+        .iter()
+        .zip(&decoded)
+        .enumerate()
+        .filter(|(_, (a, b))| a != b)
+        .map(|(index, _)| index);
+
+    if let Some(first) = differing.next() {
+        return Err(EvalError::Mismatch {
+            first,
+            count: 1 + differing.count(),
+        });
+    }
+
+    Ok(decompress_time)
+}
+
+#[test]
+fn test_evaluate() {
+    use crate::jackal::{compress_bc1_texture_opts, SuperBlockChoice, SuperBlockSize};
+
+    let extent = Extent::D2 {
+        width: 8,
+        height: 4,
+    };
+    let blocks: Vec<_> = (0..32)
+        .map(|i| {
+            bc1::Block::new(
+                crate::math::Rgb565::from_bits(i * 97),
+                crate::math::Rgb565::from_bits(i),
+                [[(i % 4) as u8; 4]; 4],
+            )
+        })
+        .collect();
+    let options = CompressOptions {
+        super_block_size: SuperBlockChoice::Fixed(SuperBlockSize {
+            width: 4,
+            height: 4,
+        }),
+        ..CompressOptions::default()
+    };
+
+    let report = evaluate_bc1(extent, &blocks, options).unwrap();
+
+    let mut stream = Vec::new();
+    compress_bc1_texture_opts(extent, &blocks, options, Cursor::new(&mut stream)).unwrap();
+    assert_eq!(report.compressed_size, stream.len() as u64);
+    assert_eq!(report.blocks, 32);
+    assert_eq!(report.bytes_per_block(), stream.len() as f64 / 32.0);
+
+    // Swapping table entries of two equally sized super-blocks keeps the stream valid,
+    // but moves their blocks.
+    let (header, _) =
+        crate::jackal::read_container(Cursor::new(&stream), &Default::default()).unwrap();
+    let table_start =
+        header.bytes_size() + crate::jackal::meta_chunks_bytes_size(header.version(), &[]);
+    let entry = crate::jackal::JackalBlock::BYTES_SIZE;
+    let (first, second) = stream[table_start..].split_at_mut(entry);
+    first.swap_with_slice(&mut second[..entry]);
+
+    match verify_bc1(extent, &blocks, &stream) {
+        Err(EvalError::Mismatch { first, count }) => {
+            assert_eq!(first, 0);
+            assert_eq!(count, 32);
+        }
+        result => panic!("mismatch is not detected: {result:?}"),
+    }
+
+    assert!(matches!(
+        evaluate_bc1(extent, &blocks[1..], options),
+        Err(EvalError::Compress(_))
+    ));
+}
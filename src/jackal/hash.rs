@@ -0,0 +1,103 @@
+use std::io::Write;
+
+use crate::{
+    bc1,
+    jackal::{block::write_raw_block, AnyBlock, Extent},
+};
+
+/// 64-bit FNV-1a hash.
+struct Fnv1a64(u64);
+
+impl Fnv1a64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    fn new() -> Self {
+        Fnv1a64(Self::OFFSET_BASIS)
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+}
+
+impl Write for Fnv1a64 {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Returns content hash of BC1 blocks stored in the header by compression.
+///
+/// Hash is 64-bit FNV-1a of width, height and depth of the extent as little-endian `u32`,
+/// followed by 8 bytes of each block in row-major order.
+/// It does not depend on compression options, so it identifies texture content.
+pub fn hash_blocks(extent: Extent, blocks: &[bc1::Block]) -> u64 {
+    hash_any_blocks(extent, blocks)
+}
+
+/// Returns content hash of blocks of any format.
+///
+/// Blocks are hashed as aspects written without any transforms,
+/// which is the raw block layout for BC1.
+pub(crate) fn hash_any_blocks<B>(extent: Extent, blocks: &[B]) -> u64
+where
+    B: AnyBlock,
+{
+    let mut hasher = Fnv1a64::new();
+    for size in extent.raw_size() {
+        hasher.update(&size.to_le_bytes());
+    }
+
+    for block in blocks {
+        // Writing into the hasher never fails.
+        let _ = write_raw_block(block, &mut hasher);
+    }
+
+    hasher.0
+}
+
+#[test]
+fn test_fnv1a64() {
+    let hash = |bytes: &[u8]| {
+        let mut hasher = Fnv1a64::new();
+        hasher.update(bytes);
+        hasher.0
+    };
+
+    // Reference values of FNV-1a 64.
+    assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+    assert_eq!(hash(b"foobar"), 0x8594_4171_f739_67e8);
+}
+
+#[test]
+fn test_hash_blocks_layout() {
+    use crate::jackal::blocks_as_bytes;
+
+    let extent = Extent::D2 {
+        width: 3,
+        height: 1,
+    };
+    let blocks = [bc1::Block::BLACK, bc1::Block::WHITE, bc1::Block::BLACK];
+
+    let mut hasher = Fnv1a64::new();
+    hasher.update(&[3, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0]);
+    hasher.update(blocks_as_bytes(&blocks));
+    assert_eq!(hash_blocks(extent, &blocks), hasher.0);
+
+    // Same blocks in other shape have other hash.
+    let column = Extent::D2 {
+        width: 1,
+        height: 3,
+    };
+    assert_ne!(hash_blocks(column, &blocks), hash_blocks(extent, &blocks));
+}
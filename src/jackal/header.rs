@@ -1,9 +1,15 @@
-use std::io::{Read, Write};
+use std::{
+    io::{Read, Write},
+    num::NonZeroU32,
+};
 
 use crate::jackal::{DecodeError, DecompressError};
 
 /// Size of the super-block in number of blocks.
+///
+/// Serialized as a struct with `width` and `height` fields.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SuperBlockSize {
     pub width: u16,
     pub height: u16,
@@ -15,12 +21,16 @@ fn super_block_from_extent(extent: u32) -> u16 {
         64..128 => 64,
         128..256 => 128,
         256..512 => 256,
-        512..1024 => 512,
         _ => 512,
     }
 }
 
 impl SuperBlockSize {
+    /// Returns true if both dimensions are powers of two that can be encoded.
+    pub fn is_valid(&self) -> bool {
+        self.width.is_power_of_two() && self.height.is_power_of_two()
+    }
+
     pub fn encode(&self) -> [u8; 2] {
         debug_assert!(self.width.is_power_of_two());
         debug_assert!(self.height.is_power_of_two());
@@ -48,6 +58,9 @@ impl SuperBlockSize {
         Ok(SuperBlockSize { width, height })
     }
 
+    /// Chooses super-block size for the extent in blocks.
+    ///
+    /// Large extents are split into many super-blocks so they can be decoded in parallel.
     pub fn from_size(width: u32, height: u32) -> Self {
         SuperBlockSize {
             width: super_block_from_extent(width),
@@ -56,10 +69,15 @@ impl SuperBlockSize {
     }
 }
 
+/// Block compression format.
+///
+/// Serialized as variant name, e.g. `"BC1"`.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u16)]
 pub enum Format {
     BC1,
+    BC2,
     BC3,
     BC4,
     BC5,
@@ -68,6 +86,20 @@ pub enum Format {
 }
 
 impl Format {
+    /// Returns number of bytes in one 4x4 block of the format.
+    pub fn block_byte_size(&self) -> usize {
+        match self {
+            Format::BC1 | Format::BC4 => 8,
+            Format::BC2 | Format::BC3 | Format::BC5 | Format::BC6 | Format::BC7 => 16,
+        }
+    }
+
+    /// Returns number of 4x4 blocks covering all texels of the pixel extent
+    /// or `None` if it overflows `usize`.
+    pub fn blocks_for_extent(&self, extent: Extent) -> Option<usize> {
+        usize::try_from(extent.block_count_bc()?).ok()
+    }
+
     pub fn encode(&self) -> [u8; 2] {
         match self {
             Format::BC1 => 0u16.to_le_bytes(),
@@ -76,6 +108,8 @@ impl Format {
             Format::BC5 => 3u16.to_le_bytes(),
             Format::BC6 => 4u16.to_le_bytes(),
             Format::BC7 => 5u16.to_le_bytes(),
+            // Added after BC7, ids are never reordered to keep old streams decodable.
+            Format::BC2 => 6u16.to_le_bytes(),
         }
     }
 
@@ -87,6 +121,7 @@ impl Format {
             3 => Ok(Format::BC5),
             4 => Ok(Format::BC6),
             5 => Ok(Format::BC7),
+            6 => Ok(Format::BC2),
             _ => Err(DecodeError::InvalidHeader),
         }
     }
@@ -143,30 +178,479 @@ impl MipLevels {
     }
 }
 
-const MAGIC_NUMBER: u32 = 0x494C4B4Au32; // "JKLI"
+const MAGIC_NUMBER: u32 = 0x394C4B4Au32; // "JKL9"
+
+/// Magic number of the container with super-block table always after the header.
+const V8_MAGIC_NUMBER: u32 = 0x384C4B4Au32; // "JKL8"
+
+/// Magic number of the container without payload mode byte.
+const V7_MAGIC_NUMBER: u32 = 0x374C4B4Au32; // "JKL7"
+
+/// Magic number of the container without content hash in the header.
+const V6_MAGIC_NUMBER: u32 = 0x364C4B4Au32; // "JKL6"
+
+/// Magic number of the container without payload alignment in the header.
+const V5_MAGIC_NUMBER: u32 = 0x354C4B4Au32; // "JKL5"
+
+/// Magic number of the container without metadata chunks.
+const V4_MAGIC_NUMBER: u32 = 0x344C4B4Au32; // "JKL4"
+
+/// Magic number of the container without texture metadata in the header.
+const V3_MAGIC_NUMBER: u32 = 0x334C4B4Au32; // "JKL3"
+
+/// Magic number of the container with single compressed stream per super-block.
+const V2_MAGIC_NUMBER: u32 = 0x324C4B4Au32; // "JKL2"
+
+/// Magic number of the container written before flags and payload sizes were added.
+const LEGACY_MAGIC_NUMBER: u32 = 0x494C4B4Au32; // "JKLI"
+
+/// Version of Jackal container.
+///
+/// Writers always emit [`ContainerVersion::V9`].
+/// Readers accept all versions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ContainerVersion {
+    /// Magic "JKLI".
+    /// Super-block table holds only payload offsets, header has no flags.
+    Legacy,
+
+    /// Magic "JKL2".
+    /// Super-block table holds payload offsets, sizes and checksums, header has flags.
+    V2,
+
+    /// Magic "JKL3".
+    /// Same header and super-block table as [`ContainerVersion::V2`].
+    /// Super-block payload starts with varint sizes of block aspects,
+    /// followed by each aspect compressed separately, so aspects can be decoded selectively.
+    V3,
+
+    /// Magic "JKL4".
+    /// Same as [`ContainerVersion::V3`] with [`TextureMeta`] appended to the header.
+    V4,
+
+    /// Magic "JKL5".
+    /// Same as [`ContainerVersion::V4`] with section of [`MetaChunk`]s
+    /// between the header and the super-block table.
+    ///
+    /// [`MetaChunk`]: crate::jackal::MetaChunk
+    V5,
+
+    /// Magic "JKL6".
+    /// Same as [`ContainerVersion::V5`] with payload alignment appended to the header.
+    V6,
+
+    /// Magic "JKL7".
+    /// Same as [`ContainerVersion::V6`] with content hash of the blocks appended to the header.
+    V7,
+
+    /// Magic "JKL8".
+    /// Same header as [`ContainerVersion::V7`].
+    /// Super-block payload starts with mode byte, so that super-block
+    /// of identical blocks is stored as a single raw block.
+    V8,
+
+    /// Magic "JKL9".
+    /// Same as [`ContainerVersion::V8`] with flag that moves super-block table
+    /// after the payloads, see [`TableLocation::Trailer`].
+    V9,
+}
+
+impl ContainerVersion {
+    pub fn decode(magic: [u8; 4]) -> Result<Self, DecodeError> {
+        match u32::from_le_bytes(magic) {
+            MAGIC_NUMBER => Ok(ContainerVersion::V9),
+            V8_MAGIC_NUMBER => Ok(ContainerVersion::V8),
+            V7_MAGIC_NUMBER => Ok(ContainerVersion::V7),
+            V6_MAGIC_NUMBER => Ok(ContainerVersion::V6),
+            V5_MAGIC_NUMBER => Ok(ContainerVersion::V5),
+            V4_MAGIC_NUMBER => Ok(ContainerVersion::V4),
+            V3_MAGIC_NUMBER => Ok(ContainerVersion::V3),
+            V2_MAGIC_NUMBER => Ok(ContainerVersion::V2),
+            LEGACY_MAGIC_NUMBER => Ok(ContainerVersion::Legacy),
+            _ => Err(DecodeError::InvalidMagic),
+        }
+    }
+
+    /// Returns size of super-block table entry in bytes.
+    pub fn jackal_block_bytes_size(&self) -> usize {
+        match self {
+            ContainerVersion::Legacy => size_of::<u64>(),
+            ContainerVersion::V2
+            | ContainerVersion::V3
+            | ContainerVersion::V4
+            | ContainerVersion::V5
+            | ContainerVersion::V6
+            | ContainerVersion::V7
+            | ContainerVersion::V8
+            | ContainerVersion::V9 => JackalBlock::BYTES_SIZE,
+        }
+    }
+
+    /// Returns size of the header in bytes.
+    pub const fn header_bytes_size(&self) -> usize {
+        match self {
+            ContainerVersion::Legacy | ContainerVersion::V2 | ContainerVersion::V3 => 26,
+            ContainerVersion::V4 | ContainerVersion::V5 => 30,
+            ContainerVersion::V6 => 34,
+            ContainerVersion::V7 | ContainerVersion::V8 | ContainerVersion::V9 => {
+                JackalHeader::BYTES_SIZE
+            }
+        }
+    }
+}
+
+/// Colorspace of texel colors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Colorspace {
+    #[default]
+    Unspecified,
+    Srgb,
+    Linear,
+}
+
+/// How alpha channel relates to colors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AlphaMode {
+    /// Alpha is ignored, texture is fully opaque.
+    Opaque,
+
+    /// Colors are not multiplied by alpha.
+    #[default]
+    Straight,
+
+    /// Colors are multiplied by alpha.
+    Premultiplied,
+}
+
+/// Texture metadata stored in [`ContainerVersion::V4`] header.
+///
+/// Bits unknown to this version are preserved when header is read and written back.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TextureMeta {
+    // Bits 0-1 are colorspace, bits 2-3 are alpha mode, the rest is reserved.
+    // High 16 bits are reserved for future flags.
+    // Zero bits are the defaults, unspecified colorspace and straight alpha.
+    bits: u32,
+}
+
+impl TextureMeta {
+    pub const BYTES_SIZE: usize = 4;
+
+    const COLORSPACE_SHIFT: u32 = 0;
+    const ALPHA_SHIFT: u32 = 2;
+    const FIELD_MASK: u32 = 0b11;
+
+    pub fn new(colorspace: Colorspace, alpha: AlphaMode) -> Self {
+        TextureMeta::default()
+            .with_colorspace(colorspace)
+            .with_alpha(alpha)
+    }
 
-#[derive(Clone, Copy)]
+    pub fn colorspace(&self) -> Colorspace {
+        match (self.bits >> Self::COLORSPACE_SHIFT) & Self::FIELD_MASK {
+            1 => Colorspace::Srgb,
+            2 => Colorspace::Linear,
+            _ => Colorspace::Unspecified,
+        }
+    }
+
+    pub fn alpha(&self) -> AlphaMode {
+        match (self.bits >> Self::ALPHA_SHIFT) & Self::FIELD_MASK {
+            1 => AlphaMode::Opaque,
+            2 => AlphaMode::Premultiplied,
+            _ => AlphaMode::Straight,
+        }
+    }
+
+    pub fn with_colorspace(self, colorspace: Colorspace) -> Self {
+        let value = match colorspace {
+            Colorspace::Unspecified => 0,
+            Colorspace::Srgb => 1,
+            Colorspace::Linear => 2,
+        };
+        self.with_field(Self::COLORSPACE_SHIFT, value)
+    }
+
+    pub fn with_alpha(self, alpha: AlphaMode) -> Self {
+        let value = match alpha {
+            AlphaMode::Straight => 0,
+            AlphaMode::Opaque => 1,
+            AlphaMode::Premultiplied => 2,
+        };
+        self.with_field(Self::ALPHA_SHIFT, value)
+    }
+
+    /// Returns reserved bits, zero unless written by a newer version.
+    pub fn reserved_bits(&self) -> u32 {
+        self.bits
+            & !(Self::FIELD_MASK << Self::COLORSPACE_SHIFT | Self::FIELD_MASK << Self::ALPHA_SHIFT)
+    }
+
+    fn with_field(mut self, shift: u32, value: u32) -> Self {
+        self.bits = self.bits & !(Self::FIELD_MASK << shift) | value << shift;
+        self
+    }
+
+    pub fn encode(&self) -> [u8; 4] {
+        self.bits.to_le_bytes()
+    }
+
+    /// Decodes metadata rejecting values of known fields that are not defined.
+    pub fn decode(bytes: [u8; 4]) -> Result<Self, DecodeError> {
+        let meta = TextureMeta {
+            bits: u32::from_le_bytes(bytes),
+        };
+
+        if (meta.bits >> Self::COLORSPACE_SHIFT) & Self::FIELD_MASK == 3
+            || (meta.bits >> Self::ALPHA_SHIFT) & Self::FIELD_MASK == 3
+        {
+            return Err(DecodeError::InvalidHeader);
+        }
+
+        Ok(meta)
+    }
+}
+
+/// Prediction of block color endpoints.
+///
+/// Predicted endpoints are stored as wrapping differences from the prediction.
+/// Since [`ContainerVersion::V9`] payload mode of each super-block may override
+/// the header's predictor and store endpoints as is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Predictor {
+    /// Endpoints are stored as is.
+    #[default]
+    None,
+
+    /// Endpoints are predicted from the previous block in traversal order of the super-block.
+    /// First block of each super-block is predicted from [`AnyBlock::EMPTY`].
+    ///
+    /// [`AnyBlock::EMPTY`]: crate::jackal::AnyBlock::EMPTY
+    Previous,
+}
+
+/// Location of the super-block table in the stream.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TableLocation {
+    /// Table follows the header and metadata chunks, payloads follow the table.
+    /// Writing requires seeking back to fill the table.
+    #[default]
+    AfterHeader,
+
+    /// Table follows the payloads and 16-byte trailer at the end of the stream points to it,
+    /// so the stream is written front to back without seeking.
+    /// Supported since [`ContainerVersion::V9`].
+    Trailer,
+}
+
+/// Super-block payloads are followed by CRC-32 in the super-block table.
+const FLAG_CHECKSUMS: u16 = 0x1;
+
+/// Block colors are stored as interleaved bits split into high and low bytes.
+const FLAG_INTERLEAVED_COLORS: u16 = 0x2;
+
+/// Block color endpoints are stored as differences from the previous block.
+/// Defined since [`ContainerVersion::V4`].
+const FLAG_PREDICT_PREVIOUS: u16 = 0x4;
+
+/// Texel indices of blocks are stored relative to the index of the top-left texel.
+/// Defined since [`ContainerVersion::V4`].
+const FLAG_CANONICAL_INDICES: u16 = 0x8;
+
+/// Header holds content hash of the blocks.
+/// Defined since [`ContainerVersion::V7`].
+const FLAG_CONTENT_HASH: u16 = 0x10;
+
+/// Super-block table follows payloads and is located by the trailer.
+/// Defined since [`ContainerVersion::V9`].
+const FLAG_TABLE_TRAILER: u16 = 0x20;
+
+const KNOWN_FLAGS: u16 = FLAG_CHECKSUMS
+    | FLAG_INTERLEAVED_COLORS
+    | FLAG_PREDICT_PREVIOUS
+    | FLAG_CANONICAL_INDICES
+    | FLAG_CONTENT_HASH
+    | FLAG_TABLE_TRAILER;
+
+/// Flags defined since [`ContainerVersion::V4`].
+const V4_FLAGS: u16 = FLAG_PREDICT_PREVIOUS | FLAG_CANONICAL_INDICES;
+
+/// Flags defined since [`ContainerVersion::V7`].
+const V7_FLAGS: u16 = FLAG_CONTENT_HASH;
+
+/// Flags defined since [`ContainerVersion::V9`].
+const V9_FLAGS: u16 = FLAG_TABLE_TRAILER;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct JackalHeader {
+    // Version of the container the header was read from.
+    version: ContainerVersion,
+
     // Number of texture mip levels.
-    pub levels: MipLevels,
+    levels: MipLevels,
 
     // Format of the blocks.
-    pub format: Format,
+    format: Format,
 
     // SuperBlockSize of super-blocks.
-    pub super_block_size: SuperBlockSize,
+    super_block_size: SuperBlockSize,
+
+    /// Extent of the image in blocks. Decoded based on dimensions.
+    extent: Extent,
+
+    /// Whether super-block payloads are checksummed.
+    checksums: bool,
+
+    /// Whether block colors are stored with interleaved bits.
+    interleaved_colors: bool,
 
-    /// Extent of the image. Decoded based on dimensions.
-    pub extent: Extent,
+    /// Prediction of block color endpoints.
+    predictor: Predictor,
+
+    /// Whether texel indices are stored relative to the top-left texel.
+    canonical_indices: bool,
+
+    /// Colorspace and alpha mode of the texture.
+    meta: TextureMeta,
+
+    /// Alignment of super-block payload offsets.
+    payload_alignment: NonZeroU32,
+
+    /// Hash of the blocks computed by [`hash_blocks`].
+    ///
+    /// [`hash_blocks`]: crate::jackal::hash_blocks
+    content_hash: Option<u64>,
+
+    /// Location of the super-block table.
+    table_location: TableLocation,
 }
 
+// Header is read and written through `BYTES_SIZE` buffer, which must hold the current version.
+const _: () = assert!(JackalHeader::BYTES_SIZE == ContainerVersion::V9.header_bytes_size());
+
 impl JackalHeader {
-    pub const BYTES_SIZE: usize = 26;
+    /// Size of the header of the current container version.
+    pub const BYTES_SIZE: usize = 42;
 
+    /// Creates header for the texture.
+    ///
+    /// Super-block size is chosen based on the extent.
+    pub fn new(format: Format, extent: Extent, levels: MipLevels) -> Self {
+        let [width, height, _] = extent.raw_size();
+
+        JackalHeader {
+            version: ContainerVersion::V9,
+            levels,
+            format,
+            super_block_size: SuperBlockSize::from_size(width, height),
+            extent,
+            checksums: true,
+            interleaved_colors: false,
+            predictor: Predictor::None,
+            canonical_indices: false,
+            meta: TextureMeta::default(),
+            payload_alignment: NonZeroU32::MIN,
+            content_hash: None,
+            table_location: TableLocation::AfterHeader,
+        }
+    }
+
+    /// Returns header with specified super-block size.
+    ///
+    /// Both dimensions must be powers of two.
+    pub fn with_super_block_size(mut self, super_block_size: SuperBlockSize) -> Self {
+        self.super_block_size = super_block_size;
+        self
+    }
+
+    /// Returns header of specified container version.
+    /// Used to produce streams of older versions in tests.
+    #[cfg(test)]
+    pub(crate) fn with_version(mut self, version: ContainerVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Returns header with checksums enabled or disabled.
+    pub fn with_checksums(mut self, checksums: bool) -> Self {
+        self.checksums = checksums;
+        self
+    }
+
+    /// Returns header with specified texture metadata.
+    ///
+    /// Metadata is not stored in containers older than [`ContainerVersion::V4`].
+    pub fn with_meta(mut self, meta: TextureMeta) -> Self {
+        self.meta = meta;
+        self
+    }
+
+    /// Returns header with specified alignment of super-block payload offsets.
+    ///
+    /// Alignment is not stored in containers older than [`ContainerVersion::V6`].
+    pub fn with_payload_alignment(mut self, payload_alignment: NonZeroU32) -> Self {
+        self.payload_alignment = payload_alignment;
+        self
+    }
+
+    /// Returns header with specified content hash of the blocks.
+    ///
+    /// Content hash is not stored in containers older than [`ContainerVersion::V7`].
+    pub fn with_content_hash(mut self, content_hash: Option<u64>) -> Self {
+        self.content_hash = content_hash;
+        self
+    }
+
+    /// Returns header with specified location of the super-block table.
+    ///
+    /// Containers older than [`ContainerVersion::V9`] always store the table after the header.
+    pub fn with_table_location(mut self, table_location: TableLocation) -> Self {
+        self.table_location = table_location;
+        self
+    }
+
+    /// Returns header with interleaved colors enabled or disabled.
+    pub fn with_interleaved_colors(mut self, interleaved_colors: bool) -> Self {
+        self.interleaved_colors = interleaved_colors;
+        self
+    }
+
+    /// Returns header with specified endpoint predictor.
+    ///
+    /// Prediction is not supported by containers older than [`ContainerVersion::V4`].
+    pub fn with_predictor(mut self, predictor: Predictor) -> Self {
+        self.predictor = predictor;
+        self
+    }
+
+    /// Returns header with canonical texel indices enabled or disabled.
+    ///
+    /// Not supported by containers older than [`ContainerVersion::V4`].
+    pub fn with_canonical_indices(mut self, canonical_indices: bool) -> Self {
+        self.canonical_indices = canonical_indices;
+        self
+    }
+
+    /// Writes header of the container version header was read from.
+    ///
+    /// [`ContainerVersion::Legacy`] headers are written as [`ContainerVersion::V2`],
+    /// which has the same payload layout.
+    /// Writes [`JackalHeader::bytes_size`] bytes.
     pub fn write_to(&self, mut write: impl Write) -> std::io::Result<()> {
         let mut bytes = [0; Self::BYTES_SIZE];
+        let size = self.bytes_size();
 
-        bytes[0..4].copy_from_slice(&MAGIC_NUMBER.to_le_bytes());
+        let magic = match self.version {
+            ContainerVersion::Legacy | ContainerVersion::V2 => V2_MAGIC_NUMBER,
+            ContainerVersion::V3 => V3_MAGIC_NUMBER,
+            ContainerVersion::V4 => V4_MAGIC_NUMBER,
+            ContainerVersion::V5 => V5_MAGIC_NUMBER,
+            ContainerVersion::V6 => V6_MAGIC_NUMBER,
+            ContainerVersion::V7 => V7_MAGIC_NUMBER,
+            ContainerVersion::V8 => V8_MAGIC_NUMBER,
+            ContainerVersion::V9 => MAGIC_NUMBER,
+        };
+        bytes[0..4].copy_from_slice(&magic.to_le_bytes());
 
         bytes[4..6].copy_from_slice(&self.levels.encode());
         bytes[6..8].copy_from_slice(&self.format.encode());
@@ -178,25 +662,68 @@ impl JackalHeader {
         bytes[16..20].copy_from_slice(&raw_size[1].to_le_bytes());
         bytes[20..24].copy_from_slice(&raw_size[2].to_le_bytes());
 
-        write.write_all(&bytes)?;
+        let mut flags = 0u16;
+        if self.checksums {
+            flags |= FLAG_CHECKSUMS;
+        }
+        if self.interleaved_colors {
+            flags |= FLAG_INTERLEAVED_COLORS;
+        }
+        if self.predictor == Predictor::Previous {
+            flags |= FLAG_PREDICT_PREVIOUS;
+        }
+        if self.canonical_indices {
+            flags |= FLAG_CANONICAL_INDICES;
+        }
+        if self.content_hash.is_some()
+            && matches!(
+                self.version,
+                ContainerVersion::V7 | ContainerVersion::V8 | ContainerVersion::V9
+            )
+        {
+            flags |= FLAG_CONTENT_HASH;
+        }
+        if self.table_location() == TableLocation::Trailer {
+            flags |= FLAG_TABLE_TRAILER;
+        }
+        bytes[24..26].copy_from_slice(&flags.to_le_bytes());
+        bytes[26..30].copy_from_slice(&self.meta.encode());
+        bytes[30..34].copy_from_slice(&self.payload_alignment.get().to_le_bytes());
+        bytes[34..42].copy_from_slice(&self.content_hash.unwrap_or(0).to_le_bytes());
+
+        write.write_all(&bytes[..size])?;
         Ok(())
     }
 
-    pub fn read_from(mut read: impl Read) -> Result<Self, DecompressError> {
-        let mut bytes = [0; 24];
-        read.read_exact(&mut bytes)?;
+    /// Reads header rejecting extents that exceed [`DecodeLimits::default`].
+    pub fn read_from(read: impl Read) -> Result<Self, DecompressError> {
+        Self::read_from_with_limits(read, &DecodeLimits::default())
+    }
+
+    /// Reads header rejecting extents that exceed `limits`.
+    pub fn read_from_with_limits(
+        mut read: impl Read,
+        limits: &DecodeLimits,
+    ) -> Result<Self, DecompressError> {
+        let mut bytes = [0; Self::BYTES_SIZE];
+        read.read_exact(&mut bytes[..4])?;
 
         let mut magic_bytes = [0; 4];
         magic_bytes.copy_from_slice(&bytes[0..4]);
-        let magic = u32::from_le_bytes(magic_bytes);
-        if magic != MAGIC_NUMBER {
-            return Err(DecodeError::InvalidMagic.into());
-        }
+        let version = ContainerVersion::decode(magic_bytes)?;
+
+        read.read_exact(&mut bytes[4..version.header_bytes_size()])?;
 
         let mut levels_bytes = [0; 2];
         levels_bytes.copy_from_slice(&bytes[4..6]);
         let levels = MipLevels::decode(levels_bytes)?;
 
+        // No container version stores mip levels beyond the first one yet,
+        // decoding only the first level would silently drop the rest.
+        if levels.0 > 1 {
+            return Err(DecodeError::InvalidHeader.into());
+        }
+
         let mut format_bytes = [0; 2];
         format_bytes.copy_from_slice(&bytes[6..8]);
         let format = Format::decode(format_bytes)?;
@@ -220,33 +747,196 @@ impl JackalHeader {
         let raw_size = [width, height, depth];
         let extent = Extent::from_raw_size(raw_size, dimensions)?;
 
-        Ok(JackalHeader {
+        let mut flags_bytes = [0; 2];
+        flags_bytes.copy_from_slice(&bytes[24..26]);
+        let flags = u16::from_le_bytes(flags_bytes);
+
+        // Legacy writer left flags bytes zeroed.
+        let known_flags = match version {
+            ContainerVersion::Legacy => 0,
+            ContainerVersion::V2 | ContainerVersion::V3 => {
+                KNOWN_FLAGS & !V4_FLAGS & !V7_FLAGS & !V9_FLAGS
+            }
+            ContainerVersion::V4 | ContainerVersion::V5 | ContainerVersion::V6 => {
+                KNOWN_FLAGS & !V7_FLAGS & !V9_FLAGS
+            }
+            ContainerVersion::V7 | ContainerVersion::V8 => KNOWN_FLAGS & !V9_FLAGS,
+            ContainerVersion::V9 => KNOWN_FLAGS,
+        };
+        if flags & !known_flags != 0 {
+            return Err(DecodeError::InvalidHeader.into());
+        }
+
+        // Older versions leave metadata bytes zeroed, which decode to defaults.
+        let mut meta_bytes = [0; 4];
+        meta_bytes.copy_from_slice(&bytes[26..30]);
+        let meta = TextureMeta::decode(meta_bytes)?;
+
+        // Older versions have no alignment, which is the same as alignment of 1.
+        let payload_alignment = match version {
+            ContainerVersion::V6
+            | ContainerVersion::V7
+            | ContainerVersion::V8
+            | ContainerVersion::V9 => {
+                let mut alignment_bytes = [0; 4];
+                alignment_bytes.copy_from_slice(&bytes[30..34]);
+                NonZeroU32::new(u32::from_le_bytes(alignment_bytes))
+                    .ok_or(DecodeError::InvalidHeader)?
+            }
+            _ => NonZeroU32::MIN,
+        };
+
+        // Flag is rejected for older versions above.
+        let content_hash = if flags & FLAG_CONTENT_HASH != 0 {
+            let mut hash_bytes = [0; 8];
+            hash_bytes.copy_from_slice(&bytes[34..42]);
+            Some(u64::from_le_bytes(hash_bytes))
+        } else {
+            None
+        };
+
+        let header = JackalHeader {
+            version,
             levels,
             format,
             super_block_size,
             extent,
-        })
+            checksums: flags & FLAG_CHECKSUMS != 0,
+            interleaved_colors: flags & FLAG_INTERLEAVED_COLORS != 0,
+            predictor: if flags & FLAG_PREDICT_PREVIOUS != 0 {
+                Predictor::Previous
+            } else {
+                Predictor::None
+            },
+            canonical_indices: flags & FLAG_CANONICAL_INDICES != 0,
+            meta,
+            payload_alignment,
+            content_hash,
+            table_location: if flags & FLAG_TABLE_TRAILER != 0 {
+                TableLocation::Trailer
+            } else {
+                TableLocation::AfterHeader
+            },
+        };
+
+        header.check_limits(limits)?;
+        Ok(header)
+    }
+
+    /// Returns version of the container header was read from.
+    /// Newly created headers are [`ContainerVersion::V9`].
+    pub fn version(&self) -> ContainerVersion {
+        self.version
+    }
+
+    /// Returns size of the header in bytes for its container version.
+    ///
+    /// In [`ContainerVersion::V5`] metadata chunks follow the header,
+    /// so the super-block table does not start right after it.
+    pub fn bytes_size(&self) -> usize {
+        self.version.header_bytes_size()
+    }
+
+    pub fn meta(&self) -> TextureMeta {
+        self.meta
+    }
+
+    pub fn levels(&self) -> MipLevels {
+        self.levels
     }
 
     pub fn format(&self) -> Format {
         self.format
     }
 
+    pub fn super_block_size(&self) -> SuperBlockSize {
+        self.super_block_size
+    }
+
+    pub fn checksums(&self) -> bool {
+        self.checksums
+    }
+
+    pub fn interleaved_colors(&self) -> bool {
+        self.interleaved_colors
+    }
+
+    pub fn predictor(&self) -> Predictor {
+        self.predictor
+    }
+
+    pub fn canonical_indices(&self) -> bool {
+        self.canonical_indices
+    }
+
+    /// Returns alignment of super-block payload offsets.
+    ///
+    /// Decoders do not rely on it, offsets in the super-block table are absolute.
+    pub fn payload_alignment(&self) -> NonZeroU32 {
+        self.payload_alignment
+    }
+
+    /// Returns hash of the blocks computed by [`hash_blocks`] during compression.
+    ///
+    /// `None` for containers older than [`ContainerVersion::V7`].
+    ///
+    /// [`hash_blocks`]: crate::jackal::hash_blocks
+    pub fn content_hash(&self) -> Option<u64> {
+        self.content_hash
+    }
+
+    /// Returns location of the super-block table.
+    /// Always [`TableLocation::AfterHeader`] for containers older than [`ContainerVersion::V9`].
+    pub fn table_location(&self) -> TableLocation {
+        match self.version {
+            ContainerVersion::V9 => self.table_location,
+            _ => TableLocation::AfterHeader,
+        }
+    }
+
+    /// Returns extent of the texture in blocks.
     pub fn extent(&self) -> Extent {
         self.extent
     }
 
     pub fn jackal_blocks_count(&self) -> usize {
         let [width, height, depth] = self.jackal_blocks_extent();
-        (width * height * depth) as usize
+        width as usize * height as usize * depth as usize
+    }
+
+    /// Returns number of super-blocks or `None` if it overflows `usize`.
+    pub fn checked_jackal_blocks_count(&self) -> Option<usize> {
+        let [width, height, depth] = self.jackal_blocks_extent();
+        (width as usize)
+            .checked_mul(height as usize)?
+            .checked_mul(depth as usize)
     }
 
     pub fn jackal_blocks_extent(&self) -> [u32; 3] {
-        let raw_size = self.extent.raw_size();
-        let jackal_blocks_width = (raw_size[0] + self.super_block_size.width as u32 - 1)
-            / self.super_block_size.width as u32;
-        let jackal_blocks_height = (raw_size[1] + self.super_block_size.height as u32 - 1)
-            / self.super_block_size.height as u32;
+        self.super_blocks_extent(self.extent)
+    }
+
+    /// Returns number of super-blocks of mip `level`.
+    ///
+    /// Exact only with `pixel_size`, see [`JackalHeader::level_extent`].
+    /// Returns `None` if [`JackalHeader::level_extent`] does or count overflows `usize`.
+    pub fn level_jackal_blocks_count(
+        &self,
+        level: u16,
+        pixel_size: Option<(u32, u32)>,
+    ) -> Option<usize> {
+        let [width, height, depth] =
+            self.super_blocks_extent(self.level_extent(level, pixel_size)?);
+        (width as usize)
+            .checked_mul(height as usize)?
+            .checked_mul(depth as usize)
+    }
+
+    /// Returns number of super-blocks along each axis covering `extent`.
+    fn super_blocks_extent(&self, extent: Extent) -> [u32; 3] {
+        let raw_size = extent.raw_size();
+        let jackal_blocks_width = raw_size[0].div_ceil(self.super_block_size.width as u32);
+        let jackal_blocks_height = raw_size[1].div_ceil(self.super_block_size.height as u32);
         let jackal_blocks_depth = raw_size[2];
 
         [
@@ -256,33 +946,224 @@ impl JackalHeader {
         ]
     }
 
+    /// Returns extent of mip `level` in blocks.
+    ///
+    /// `pixel_size` is width and height of the texture in pixels as stored in
+    /// [`MetaChunk::pixel_size`] chunk. Levels are computed by halving it.
+    /// Without it texture is assumed to fill its blocks,
+    /// so the result is an upper bound of the real extent of the level.
+    ///
+    /// Returns `None` if texture has no such level or `pixel_size` does not match the extent.
+    ///
+    /// [`MetaChunk::pixel_size`]: super::MetaChunk::pixel_size
+    pub fn level_extent(&self, level: u16, pixel_size: Option<(u32, u32)>) -> Option<Extent> {
+        if level >= self.levels.0 {
+            return None;
+        }
+
+        let level = level as u32;
+        let planar = |blocks: u32, pixels: Option<u32>| {
+            let pixels = match pixels {
+                Some(pixels) if pixels.div_ceil(4) != blocks => return None,
+                Some(pixels) => pixels as u64,
+                None => blocks as u64 * 4,
+            };
+            let pixels = pixels.checked_shr(level).unwrap_or(0).max(1);
+            Some(pixels.div_ceil(4) as u32)
+        };
+        let shrink = |depth: u32| depth.checked_shr(level).unwrap_or(0).max(1);
+        let (pixel_width, pixel_height) = pixel_size.unzip();
+
+        Some(match self.extent {
+            Extent::D1 { width } => Extent::D1 {
+                width: planar(width, pixel_width)?,
+            },
+            Extent::D2 { width, height } => Extent::D2 {
+                width: planar(width, pixel_width)?,
+                height: planar(height, pixel_height)?,
+            },
+            Extent::D3 {
+                width,
+                height,
+                depth,
+            } => Extent::D3 {
+                width: planar(width, pixel_width)?,
+                height: planar(height, pixel_height)?,
+                depth: shrink(depth),
+            },
+            Extent::D1Array { width, layers } => Extent::D1Array {
+                width: planar(width, pixel_width)?,
+                layers,
+            },
+            Extent::D2Array {
+                width,
+                height,
+                layers,
+            } => Extent::D2Array {
+                width: planar(width, pixel_width)?,
+                height: planar(height, pixel_height)?,
+                layers,
+            },
+        })
+    }
+
+    /// Returns number of blocks of mip `level`.
+    ///
+    /// Exact only with `pixel_size`, see [`JackalHeader::level_extent`].
+    /// Returns `None` if [`JackalHeader::level_extent`] does or count overflows `usize`.
+    pub fn level_blocks_count(&self, level: u16, pixel_size: Option<(u32, u32)>) -> Option<usize> {
+        let [width, height, depth] = self.level_extent(level, pixel_size)?.raw_size();
+        (width as usize)
+            .checked_mul(height as usize)?
+            .checked_mul(depth as usize)
+    }
+
+    /// Returns number of blocks of the first mip level.
     pub fn blocks_count(&self) -> usize {
         let raw_size = self.extent.raw_size();
         raw_size[0] as usize * raw_size[1] as usize * raw_size[2] as usize
     }
+
+    /// Returns number of blocks or `None` if it overflows `usize`.
+    pub fn checked_blocks_count(&self) -> Option<usize> {
+        let raw_size = self.extent.raw_size();
+        (raw_size[0] as usize)
+            .checked_mul(raw_size[1] as usize)?
+            .checked_mul(raw_size[2] as usize)
+    }
+
+    /// Checks that block and super-block counts fit into `limits`.
+    pub fn check_limits(&self, limits: &DecodeLimits) -> Result<(), DecodeError> {
+        match (
+            self.checked_blocks_count(),
+            self.checked_jackal_blocks_count(),
+        ) {
+            (Some(blocks), Some(super_blocks))
+                if blocks <= limits.max_blocks && super_blocks <= limits.max_super_blocks =>
+            {
+                Ok(())
+            }
+            _ => Err(DecodeError::InvalidExtent),
+        }
+    }
+}
+
+/// Limits on texture size accepted by decoder.
+///
+/// Protects from allocating memory for absurd extents of corrupted or malicious streams.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Maximum number of blocks in the texture.
+    pub max_blocks: usize,
+
+    /// Maximum number of super-blocks in the texture.
+    pub max_super_blocks: usize,
+}
+
+impl Default for DecodeLimits {
+    /// Allows up to 2<sup>28</sup> blocks, enough for 65536x65536 texture,
+    /// and 2<sup>20</sup> super-blocks.
+    fn default() -> Self {
+        DecodeLimits {
+            max_blocks: 1 << 28,
+            max_super_blocks: 1 << 20,
+        }
+    }
 }
 
-#[derive(Clone, Copy)]
+impl DecodeLimits {
+    /// Checks pixel extent of imported texture and returns number of 4x4 blocks covering it.
+    ///
+    /// Fails with [`DecodeError::InvalidExtent`] if extent is empty or blocks exceed the limits.
+    pub fn check_pixel_extent(&self, extent: Extent) -> Result<usize, DecodeError> {
+        let blocks = extent
+            .block_count_bc()
+            .and_then(|blocks| usize::try_from(blocks).ok());
+
+        match blocks {
+            Some(blocks) if !extent.is_empty() && blocks <= self.max_blocks => Ok(blocks),
+            _ => Err(DecodeError::InvalidExtent),
+        }
+    }
+}
+
+/// Entry of super-block table.
+///
+/// Default entry refers to empty payload at the start of the stream.
+/// It never describes valid super-block, as every super-block has non-empty payload
+/// and the start of the stream is occupied by the header.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct JackalBlock {
+    /// Offset of the super-block payload in the stream.
     pub offset: u64,
+
+    /// Size of the super-block payload in bytes.
+    pub size: u32,
+
+    /// CRC-32 of the payload. Zero if checksums are disabled.
+    pub checksum: u32,
 }
 
 impl JackalBlock {
-    pub const BYTES_SIZE: usize = size_of::<u64>();
+    pub const BYTES_SIZE: usize = size_of::<u64>() + size_of::<u32>() * 2;
+
+    /// Returns size of the super-block payload in bytes.
+    ///
+    /// Payload occupies `offset..offset + len` range of the stream,
+    /// which allows exact-size reads of single super-block.
+    pub fn len(&self) -> u64 {
+        self.size as u64
+    }
+
+    /// Returns `true` if payload is empty, which is never the case for valid super-block.
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Returns end of the payload in the stream or `None` on overflow.
+    pub fn end(&self) -> Option<u64> {
+        self.offset.checked_add(self.len())
+    }
 
     pub fn write_to(&self, mut write: impl Write) -> std::io::Result<()> {
-        write.write_all(&self.offset.to_le_bytes())
+        let mut bytes = [0; Self::BYTES_SIZE];
+        bytes[0..8].copy_from_slice(&self.offset.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.size.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.checksum.to_le_bytes());
+        write.write_all(&bytes)
     }
 
     pub fn read_from(mut read: impl Read) -> Result<Self, DecompressError> {
         let mut bytes = [0; Self::BYTES_SIZE];
         read.read_exact(&mut bytes)?;
-        let offset = u64::from_le_bytes(bytes);
-        Ok(JackalBlock { offset })
+
+        let mut offset_bytes = [0; 8];
+        offset_bytes.copy_from_slice(&bytes[0..8]);
+        let mut size_bytes = [0; 4];
+        size_bytes.copy_from_slice(&bytes[8..12]);
+        let mut checksum_bytes = [0; 4];
+        checksum_bytes.copy_from_slice(&bytes[12..16]);
+
+        Ok(JackalBlock {
+            offset: u64::from_le_bytes(offset_bytes),
+            size: u32::from_le_bytes(size_bytes),
+            checksum: u32::from_le_bytes(checksum_bytes),
+        })
     }
 }
 
+/// Size of the texture along each dimension and number of layers.
+///
+/// Unit of width, height and depth depends on context:
+/// jackal containers measure them in blocks,
+/// DDS and KTX2 containers and image encoders in pixels.
+///
+/// Serialized as externally tagged variant,
+/// e.g. `{"D2":{"width":4,"height":8}}`.
+/// Deserialization rejects empty extents, see [`Extent::validate`].
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawExtent"))]
 pub enum Extent {
     D1 {
         width: u32,
@@ -307,6 +1188,65 @@ pub enum Extent {
     },
 }
 
+/// Unvalidated [`Extent`] as deserialized.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+enum RawExtent {
+    D1 {
+        width: u32,
+    },
+    D2 {
+        width: u32,
+        height: u32,
+    },
+    D3 {
+        width: u32,
+        height: u32,
+        depth: u32,
+    },
+    D1Array {
+        width: u32,
+        layers: u32,
+    },
+    D2Array {
+        width: u32,
+        height: u32,
+        layers: u32,
+    },
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawExtent> for Extent {
+    type Error = DecodeError;
+
+    fn try_from(raw: RawExtent) -> Result<Self, DecodeError> {
+        let extent = match raw {
+            RawExtent::D1 { width } => Extent::D1 { width },
+            RawExtent::D2 { width, height } => Extent::D2 { width, height },
+            RawExtent::D3 {
+                width,
+                height,
+                depth,
+            } => Extent::D3 {
+                width,
+                height,
+                depth,
+            },
+            RawExtent::D1Array { width, layers } => Extent::D1Array { width, layers },
+            RawExtent::D2Array {
+                width,
+                height,
+                layers,
+            } => Extent::D2Array {
+                width,
+                height,
+                layers,
+            },
+        };
+        extent.validate()
+    }
+}
+
 impl Extent {
     pub fn width(&self) -> u32 {
         match *self {
@@ -376,7 +1316,86 @@ impl Extent {
         }
     }
 
+    /// Returns extent of 4x4 blocks covering the pixel extent.
+    ///
+    /// Width and height are divided by 4 rounding up, depth and layers are kept.
+    pub fn bc_block_extent(&self) -> Extent {
+        self.map_planar(|v| v.div_ceil(4), |depth| depth)
+    }
+
+    /// Returns pixel extent of the mip level.
+    ///
+    /// Width, height and depth are halved per level down to 1, layers are kept.
+    pub fn mip_level(&self, level: u32) -> Extent {
+        let shrink = |v: u32| v.checked_shr(level).unwrap_or(0).max(1);
+        self.map_planar(shrink, shrink)
+    }
+
+    /// Returns total number of texels in all layers or `None` if it overflows `u64`.
+    pub fn texel_count(&self) -> Option<u64> {
+        (self.width() as u64 * self.height() as u64)
+            .checked_mul(self.depth() as u64)?
+            .checked_mul(self.layers() as u64)
+    }
+
+    /// Returns total number of 4x4 blocks covering all layers or `None` if it overflows `u64`.
+    pub fn block_count_bc(&self) -> Option<u64> {
+        self.bc_block_extent().texel_count()
+    }
+
+    /// Maps width and height with `planar` and depth with `depth`.
+    fn map_planar(&self, planar: impl Fn(u32) -> u32, depth: impl Fn(u32) -> u32) -> Extent {
+        match *self {
+            Extent::D1 { width } => Extent::D1 {
+                width: planar(width),
+            },
+            Extent::D2 { width, height } => Extent::D2 {
+                width: planar(width),
+                height: planar(height),
+            },
+            Extent::D3 {
+                width,
+                height,
+                depth: d,
+            } => Extent::D3 {
+                width: planar(width),
+                height: planar(height),
+                depth: depth(d),
+            },
+            Extent::D1Array { width, layers } => Extent::D1Array {
+                width: planar(width),
+                layers,
+            },
+            Extent::D2Array {
+                width,
+                height,
+                layers,
+            } => Extent::D2Array {
+                width: planar(width),
+                height: planar(height),
+                layers,
+            },
+        }
+    }
+
+    /// Returns true if any dimension of the extent is zero.
+    pub fn is_empty(&self) -> bool {
+        self.raw_size().contains(&0)
+    }
+
+    /// Checks that all dimensions of the extent are non-zero.
+    pub fn validate(self) -> Result<Self, DecodeError> {
+        if self.is_empty() {
+            return Err(DecodeError::InvalidExtent);
+        }
+        Ok(self)
+    }
+
     fn from_raw_size(value: [u32; 3], dimensions: Dimensions) -> Result<Self, DecodeError> {
+        if value.contains(&0) {
+            return Err(DecodeError::InvalidExtent);
+        }
+
         match dimensions {
             Dimensions::D1 => {
                 if value[1] != 1 || value[2] != 1 {
@@ -415,3 +1434,582 @@ impl Extent {
         }
     }
 }
+
+#[test]
+fn test_header_roundtrip() {
+    let headers = [
+        JackalHeader::new(
+            Format::BC1,
+            Extent::D2 {
+                width: 100,
+                height: 30,
+            },
+            MipLevels(1),
+        ),
+        JackalHeader::new(
+            Format::BC5,
+            Extent::D2Array {
+                width: 600,
+                height: 2,
+                layers: 3,
+            },
+            MipLevels(1),
+        )
+        .with_interleaved_colors(true)
+        .with_predictor(Predictor::Previous)
+        .with_canonical_indices(true),
+        JackalHeader::new(
+            Format::BC4,
+            Extent::D3 {
+                width: 7,
+                height: 200,
+                depth: 5,
+            },
+            MipLevels(1),
+        )
+        .with_checksums(false),
+    ];
+
+    for header in headers {
+        let mut bytes = Vec::new();
+        header.write_to(&mut bytes).unwrap();
+
+        let read = JackalHeader::read_from(&bytes[..]).unwrap();
+        assert_eq!(read, header);
+        assert_eq!(read.levels(), header.levels());
+        assert_eq!(read.super_block_size(), header.super_block_size());
+        assert_eq!(read.checksums(), header.checksums());
+        assert_eq!(read.interleaved_colors(), header.interleaved_colors());
+        assert_eq!(read.predictor(), header.predictor());
+        assert_eq!(read.canonical_indices(), header.canonical_indices());
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_roundtrip() {
+    let extent = Extent::D2 {
+        width: 4,
+        height: 8,
+    };
+    let json = serde_json::to_string(&extent).unwrap();
+    assert_eq!(json, r#"{"D2":{"width":4,"height":8}}"#);
+    assert_eq!(serde_json::from_str::<Extent>(&json).unwrap(), extent);
+
+    // Empty extents are rejected like in decoded headers.
+    for json in [
+        r#"{"D2":{"width":0,"height":8}}"#,
+        r#"{"D1Array":{"width":4,"layers":0}}"#,
+        r#"{"D3":{"width":4,"height":4,"depth":0}}"#,
+    ] {
+        let err = serde_json::from_str::<Extent>(json).unwrap_err();
+        assert!(err.to_string().contains("extent"), "{err}");
+    }
+
+    let json = serde_json::to_string(&Format::BC1).unwrap();
+    assert_eq!(json, r#""BC1""#);
+    assert_eq!(serde_json::from_str::<Format>(&json).unwrap(), Format::BC1);
+
+    let size = SuperBlockSize {
+        width: 64,
+        height: 16,
+    };
+    let json = serde_json::to_string(&size).unwrap();
+    assert_eq!(json, r#"{"width":64,"height":16}"#);
+    assert_eq!(serde_json::from_str::<SuperBlockSize>(&json).unwrap(), size);
+}
+
+#[test]
+fn test_format_block_sizes() {
+    assert_eq!(Format::BC1.block_byte_size(), 8);
+    assert_eq!(Format::BC4.block_byte_size(), 8);
+    assert_eq!(Format::BC3.block_byte_size(), 16);
+    assert_eq!(Format::BC7.block_byte_size(), 16);
+
+    let extent = Extent::D2 {
+        width: 5,
+        height: 8,
+    };
+    assert_eq!(Format::BC1.blocks_for_extent(extent), Some(4));
+
+    let extent = Extent::D2Array {
+        width: 1,
+        height: 9,
+        layers: 3,
+    };
+    assert_eq!(Format::BC5.blocks_for_extent(extent), Some(9));
+
+    let extent = Extent::D3 {
+        width: 8,
+        height: 4,
+        depth: 3,
+    };
+    assert_eq!(Format::BC4.blocks_for_extent(extent), Some(6));
+}
+
+#[test]
+fn test_zero_extent() {
+    let extent = Extent::D2 {
+        width: 0,
+        height: 4,
+    };
+    assert!(extent.is_empty());
+    assert_eq!(extent.validate(), Err(DecodeError::InvalidExtent));
+
+    let mut bytes = Vec::new();
+    JackalHeader::new(Format::BC1, extent, MipLevels(1))
+        .write_to(&mut bytes)
+        .unwrap();
+
+    let err = JackalHeader::read_from(&bytes[..]).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::InvalidExtent));
+
+    let extent = Extent::D1Array {
+        width: 3,
+        layers: 1,
+    };
+    assert_eq!(extent.validate(), Ok(extent));
+}
+
+#[test]
+fn test_extent_helpers() {
+    for (size, blocks) in [(1, 1), (2, 1), (3, 1), (5, 2), (1023, 256)] {
+        let extent = Extent::D2 {
+            width: size,
+            height: 4,
+        };
+        assert_eq!(
+            extent.bc_block_extent(),
+            Extent::D2 {
+                width: blocks,
+                height: 1,
+            }
+        );
+        assert_eq!(extent.block_count_bc(), Some(blocks as u64));
+        assert_eq!(extent.texel_count(), Some(size as u64 * 4));
+    }
+
+    let extent = Extent::D2 {
+        width: 1023,
+        height: 5,
+    };
+    assert_eq!(
+        extent.mip_level(1),
+        Extent::D2 {
+            width: 511,
+            height: 2,
+        }
+    );
+    assert_eq!(
+        extent.mip_level(3),
+        Extent::D2 {
+            width: 127,
+            height: 1,
+        }
+    );
+    assert_eq!(
+        extent.mip_level(40),
+        Extent::D2 {
+            width: 1,
+            height: 1,
+        }
+    );
+
+    let extent = Extent::D3 {
+        width: 8,
+        height: 3,
+        depth: 5,
+    };
+    assert_eq!(
+        extent.mip_level(1),
+        Extent::D3 {
+            width: 4,
+            height: 1,
+            depth: 2,
+        }
+    );
+    assert_eq!(
+        extent.bc_block_extent(),
+        Extent::D3 {
+            width: 2,
+            height: 1,
+            depth: 5,
+        }
+    );
+
+    // Layers are never halved nor divided into blocks.
+    let extent = Extent::D2Array {
+        width: 5,
+        height: 2,
+        layers: 3,
+    };
+    assert_eq!(
+        extent.mip_level(2),
+        Extent::D2Array {
+            width: 1,
+            height: 1,
+            layers: 3,
+        }
+    );
+    assert_eq!(
+        extent.bc_block_extent(),
+        Extent::D2Array {
+            width: 2,
+            height: 1,
+            layers: 3,
+        }
+    );
+    assert_eq!(extent.texel_count(), Some(30));
+    assert_eq!(extent.block_count_bc(), Some(6));
+
+    let extent = Extent::D1Array {
+        width: 1023,
+        layers: 7,
+    };
+    assert_eq!(
+        extent.mip_level(1),
+        Extent::D1Array {
+            width: 511,
+            layers: 7,
+        }
+    );
+    assert_eq!(extent.block_count_bc(), Some(256 * 7));
+
+    // Counts of huge extents do not fit into `u64`.
+    let extent = Extent::D2Array {
+        width: u32::MAX,
+        height: u32::MAX,
+        layers: u32::MAX,
+    };
+    assert_eq!(extent.texel_count(), None);
+    assert_eq!(extent.block_count_bc(), None);
+    assert_eq!(Format::BC1.blocks_for_extent(extent), None);
+    assert_eq!(
+        DecodeLimits::default().check_pixel_extent(extent),
+        Err(DecodeError::InvalidExtent)
+    );
+
+    let extent = Extent::D2 {
+        width: 4096,
+        height: 4096,
+    };
+    assert_eq!(
+        DecodeLimits::default().check_pixel_extent(extent),
+        Ok(1 << 20)
+    );
+}
+
+#[test]
+fn test_super_block_size() {
+    for w in 0..16 {
+        for h in 0..16 {
+            let size = SuperBlockSize {
+                width: 1 << w,
+                height: 1 << h,
+            };
+            assert!(size.is_valid());
+            assert_eq!(SuperBlockSize::decode(size.encode()), Ok(size));
+        }
+    }
+    assert_eq!(
+        SuperBlockSize::decode([16, 0]),
+        Err(DecodeError::InvalidHeader)
+    );
+
+    let header = JackalHeader::new(
+        Format::BC1,
+        Extent::D2 {
+            width: 4096,
+            height: 4096,
+        },
+        MipLevels(1),
+    );
+    assert_eq!(
+        header.super_block_size(),
+        SuperBlockSize {
+            width: 512,
+            height: 512,
+        }
+    );
+    assert_eq!(header.jackal_blocks_count(), 64);
+
+    let header = header.with_super_block_size(SuperBlockSize {
+        width: 1024,
+        height: 256,
+    });
+    assert_eq!(header.jackal_blocks_extent(), [4, 16, 1]);
+}
+
+#[test]
+fn test_texture_meta_roundtrip() {
+    let extent = Extent::D2 {
+        width: 3,
+        height: 5,
+    };
+
+    for colorspace in [
+        Colorspace::Unspecified,
+        Colorspace::Srgb,
+        Colorspace::Linear,
+    ] {
+        for alpha in [
+            AlphaMode::Opaque,
+            AlphaMode::Straight,
+            AlphaMode::Premultiplied,
+        ] {
+            let meta = TextureMeta::new(colorspace, alpha);
+            assert_eq!(meta.colorspace(), colorspace);
+            assert_eq!(meta.alpha(), alpha);
+            assert_eq!(meta.reserved_bits(), 0);
+
+            let header = JackalHeader::new(Format::BC1, extent, MipLevels(1)).with_meta(meta);
+
+            let mut bytes = Vec::new();
+            header.write_to(&mut bytes).unwrap();
+            assert_eq!(bytes.len(), JackalHeader::BYTES_SIZE);
+
+            let read = JackalHeader::read_from(&bytes[..]).unwrap();
+            assert_eq!(read, header);
+            assert_eq!(read.meta(), meta);
+
+            // Older containers have no room for metadata.
+            let mut bytes = Vec::new();
+            header
+                .with_version(ContainerVersion::V3)
+                .write_to(&mut bytes)
+                .unwrap();
+            assert_eq!(bytes.len(), ContainerVersion::V3.header_bytes_size());
+            let read = JackalHeader::read_from(&bytes[..]).unwrap();
+            assert_eq!(read.version(), ContainerVersion::V3);
+            assert_eq!(read.meta(), TextureMeta::default());
+        }
+    }
+}
+
+#[test]
+fn test_texture_meta_reserved_bits() {
+    let header = JackalHeader::new(Format::BC1, Extent::D1 { width: 7 }, MipLevels(1));
+
+    let mut bytes = Vec::new();
+    header.write_to(&mut bytes).unwrap();
+
+    // Reserved bits set by a newer writer, sRGB and premultiplied alpha.
+    bytes[26..30].copy_from_slice(&0xABCD_0F09u32.to_le_bytes());
+
+    let read = JackalHeader::read_from(&bytes[..]).unwrap();
+    assert_eq!(read.meta().colorspace(), Colorspace::Srgb);
+    assert_eq!(read.meta().alpha(), AlphaMode::Premultiplied);
+    assert_eq!(read.meta().reserved_bits(), 0xABCD_0F00);
+
+    // Changing known fields keeps reserved bits.
+    let meta = read.meta().with_colorspace(Colorspace::Linear);
+    let mut rewritten = Vec::new();
+    read.with_meta(meta).write_to(&mut rewritten).unwrap();
+    assert_eq!(&rewritten[26..30], &0xABCD_0F0Au32.to_le_bytes());
+
+    // Undefined values of known fields are rejected.
+    for bits in [0x3u32, 0xC] {
+        bytes[26..30].copy_from_slice(&bits.to_le_bytes());
+        assert!(JackalHeader::read_from(&bytes[..]).is_err());
+    }
+}
+
+#[test]
+fn test_invalid_ids() {
+    let samples = |first: u16| [first, first + 1, 0xFF, 0x100, 0x7FFF, 0x8000, u16::MAX];
+
+    for id in samples(7) {
+        assert_eq!(
+            Format::decode(id.to_le_bytes()),
+            Err(DecodeError::InvalidHeader)
+        );
+    }
+    for id in samples(5) {
+        assert_eq!(
+            Dimensions::decode(id.to_le_bytes()),
+            Err(DecodeError::InvalidHeader)
+        );
+    }
+    assert_eq!(MipLevels::decode([0, 0]), Err(DecodeError::InvalidHeader));
+    assert_eq!(MipLevels::decode([0xFF, 0xFF]), Ok(MipLevels(u16::MAX)));
+
+    // Corrupted fields of a complete file are rejected, not decoded.
+    let extent = Extent::D2 {
+        width: 2,
+        height: 2,
+    };
+    let mut file = Vec::new();
+    crate::jackal::compress_bc1_texture(
+        extent,
+        &[crate::bc1::Block::BLACK; 4],
+        std::io::Cursor::new(&mut file),
+    )
+    .unwrap();
+
+    // Mip levels, format and dimensions.
+    for (offset, bytes) in [
+        (4, [0, 0]),
+        (4, [3, 0]),
+        (6, [7, 0]),
+        (6, [0xFF, 0xFF]),
+        (10, [5, 0]),
+    ] {
+        let mut corrupted = file.clone();
+        corrupted[offset..offset + 2].copy_from_slice(&bytes);
+
+        let err =
+            crate::jackal::decompress_bc1_texture(std::io::Cursor::new(&corrupted)).unwrap_err();
+        assert_eq!(
+            err.decode_error(),
+            Some(DecodeError::InvalidHeader),
+            "{offset} {bytes:?}"
+        );
+    }
+}
+
+#[test]
+fn test_mip_levels() {
+    let header = JackalHeader::new(
+        Format::BC1,
+        Extent::D2Array {
+            width: 10,
+            height: 3,
+            layers: 2,
+        },
+        MipLevels(3),
+    )
+    .with_super_block_size(SuperBlockSize {
+        width: 4,
+        height: 4,
+    });
+
+    assert_eq!(header.level_extent(0, None), Some(header.extent()));
+    assert_eq!(
+        header.level_extent(2, None),
+        Some(Extent::D2Array {
+            width: 3,
+            height: 1,
+            layers: 2,
+        })
+    );
+    assert_eq!(header.level_extent(3, None), None);
+    assert_eq!(
+        header.level_blocks_count(0, None),
+        Some(header.blocks_count())
+    );
+    assert_eq!(header.level_blocks_count(1, None), Some(5 * 2 * 2));
+    assert_eq!(header.level_blocks_count(3, None), None);
+    assert_eq!(
+        header.level_jackal_blocks_count(0, None),
+        Some(header.jackal_blocks_count())
+    );
+    assert_eq!(header.level_jackal_blocks_count(1, None), Some(2 * 2));
+
+    // Without pixel size levels are upper bounds, pixel size makes them exact.
+    for (pixels, upper) in [(17, 3), (20, 3), (33, 5), (64, 8)] {
+        let pixel_extent = Extent::D2 {
+            width: pixels,
+            height: 4,
+        };
+        let header = JackalHeader::new(Format::BC1, pixel_extent.bc_block_extent(), MipLevels(3));
+
+        for level in 0..3 {
+            assert_eq!(
+                header.level_extent(level, Some((pixels, 4))),
+                Some(pixel_extent.mip_level(level as u32).bc_block_extent()),
+                "{pixels} px, level {level}"
+            );
+        }
+        assert_eq!(
+            header.level_extent(1, None).map(|extent| extent.width()),
+            Some(upper),
+            "{pixels} px"
+        );
+    }
+
+    let header = JackalHeader::new(
+        Format::BC1,
+        Extent::D2 {
+            width: 5,
+            height: 1,
+        },
+        MipLevels(2),
+    );
+    assert_eq!(
+        header.level_extent(1, Some((17, 3))),
+        Some(Extent::D2 {
+            width: 2,
+            height: 1,
+        })
+    );
+    assert_eq!(header.level_blocks_count(1, Some((17, 3))), Some(2));
+    assert_eq!(header.level_blocks_count(1, None), Some(3));
+
+    // Pixel size not covered by the extent.
+    assert_eq!(header.level_extent(0, Some((21, 3))), None);
+    assert_eq!(header.level_extent(0, Some((17, 5))), None);
+
+    // No writer stores more than one level, header claiming more is rejected.
+    let mut bytes = Vec::new();
+    header.write_to(&mut bytes).unwrap();
+    assert_eq!(
+        JackalHeader::read_from(&bytes[..])
+            .unwrap_err()
+            .decode_error(),
+        Some(DecodeError::InvalidHeader)
+    );
+}
+
+#[test]
+fn test_header_stream_position() {
+    use std::io::Cursor;
+
+    let header = JackalHeader::new(
+        Format::BC1,
+        Extent::D2 {
+            width: 9,
+            height: 5,
+        },
+        MipLevels(1),
+    )
+    .with_checksums(false);
+
+    for version in [
+        ContainerVersion::Legacy,
+        ContainerVersion::V2,
+        ContainerVersion::V3,
+        ContainerVersion::V4,
+        ContainerVersion::V5,
+        ContainerVersion::V6,
+        ContainerVersion::V7,
+        ContainerVersion::V8,
+        ContainerVersion::V9,
+    ] {
+        let header = header.with_version(version);
+        let size = version.header_bytes_size();
+
+        let mut write = Cursor::new(Vec::new());
+        header.write_to(&mut write).unwrap();
+        assert_eq!(write.position() as usize, size, "{version:?}");
+
+        // Legacy headers are written as V2, they differ only in magic.
+        let mut bytes = write.into_inner();
+        if version == ContainerVersion::Legacy {
+            bytes[0..4].copy_from_slice(b"JKLI");
+        }
+
+        // Trailing bytes must stay unread.
+        bytes.extend_from_slice(&[0xFF; 8]);
+        let mut read = Cursor::new(&bytes);
+        let read_header = JackalHeader::read_from(&mut read).unwrap();
+        assert_eq!(read.position() as usize, size, "{version:?}");
+        assert_eq!(read_header.version(), version);
+        assert_eq!(read_header.extent(), header.extent());
+    }
+
+    // Unknown magic is rejected right after it.
+    let mut read = Cursor::new(b"JKL0 and more bytes than any header has in total");
+    let err = JackalHeader::read_from(&mut read).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::InvalidMagic));
+    assert_eq!(read.position(), 4);
+}
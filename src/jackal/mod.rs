@@ -8,23 +8,58 @@
 // Jackal format compresses super-blocks (blocks of blocks) independently.
 // This allows parallel processing of super-blocks on multi-core CPU and GPU.
 // Although small textures may have just one super-block.
+//
+// Each super-block payload may be protected with CRC-32 stored in the super-block table.
 
 use std::{
+    borrow::Cow,
+    fmt,
     io::{Read, Seek, SeekFrom, Write},
+    num::NonZeroU32,
+    ops::ControlFlow,
     u32,
 };
 
-use crate::bc1;
+use crate::{
+    bc1, bc2, bc3,
+    encoder::{decode_image, EncodeError},
+    lzp,
+    math::Rgb8U,
+    stats::Histogram256,
+    z_curve::BoundZCurve,
+};
 
 pub use self::{
-    block::AnyBlock,
-    header::{Extent, Format, JackalBlock, JackalHeader, MipLevels, SuperBlockSize},
+    block::{blocks_as_bytes, blocks_from_bytes, AnyBlock},
+    chunks::{meta_chunks_bytes_size, read_meta_chunks, MetaChunk},
+    dds::{export_dds, import_dds, import_dds_with_limits},
+    eval::{evaluate_bc1, EvalError, EvalReport},
+    hash::hash_blocks,
+    header::{
+        AlphaMode, Colorspace, ContainerVersion, DecodeLimits, Extent, Format, JackalBlock,
+        JackalHeader, MipLevels, Predictor, SuperBlockSize, TableLocation, TextureMeta,
+    },
+    preview::{decode_endpoints_preview, endpoints_image},
+    report::{stat_file, CompressReport, Progress, SuperBlockReport},
+    texture::BlockTexture,
 };
 
 mod block;
+mod chunks;
+mod crc;
+mod dds;
+mod eval;
+mod hash;
 mod header;
+mod preview;
+mod report;
+mod texture;
 
-#[derive(Clone, Copy, Debug)]
+/// Error of Jackal stream decoding.
+///
+/// New variants may be added as the container evolves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum DecodeError {
     /// Magic number invalid.
     InvalidMagic,
@@ -33,499 +68,856 @@ pub enum DecodeError {
     /// Data is corrupted or comes from other version with extended capabilities.
     InvalidHeader,
 
-    // Data is invalid.
-    // Such as position is out of bounds.
-    InvalidData,
-}
+    /// Data is invalid.
+    /// Such as position is out of bounds or payload is truncated.
+    InvalidData {
+        /// Position of the super-block, if known.
+        super_block: Option<[u32; 3]>,
 
-pub fn compress_bc1_texture(
-    extent: Extent,
-    blocks: &[bc1::Block],
-    write: impl Write + Seek,
-) -> std::io::Result<()> {
-    compress_texture(extent, blocks, write)
-}
+        /// Index of the block aspect, if known.
+        aspect: Option<usize>,
+    },
 
-fn compress_texture<B>(
-    extent: Extent,
-    blocks: &[B],
-    mut write: impl Write + Seek,
-) -> std::io::Result<()>
-where
-    B: AnyBlock,
-{
-    let raw_size = extent.raw_size();
+    /// Data is valid but uses features that are not supported.
+    Unsupported,
 
-    assert_eq!(blocks.len() as u32, raw_size[0] * raw_size[1] * raw_size[2]);
+    /// Checksum of super-block payload does not match.
+    /// Data is corrupted.
+    ChecksumMismatch,
 
-    let super_block_size = SuperBlockSize::from_size(raw_size[0], raw_size[1]);
+    /// Extent is empty or exceeds decode limits.
+    InvalidExtent,
 
-    let header = JackalHeader {
-        levels: MipLevels(1),
-        format: Format::BC1,
-        super_block_size,
-        extent,
+    /// Progress callback requested cancellation.
+    Cancelled,
+
+    /// Content hash of decompressed blocks does not match the one stored in the header.
+    /// Data is corrupted.
+    ContentHashMismatch,
+}
+
+impl DecodeError {
+    /// Invalid data without position information.
+    pub const INVALID_DATA: Self = DecodeError::InvalidData {
+        super_block: None,
+        aspect: None,
     };
+}
 
-    let start = write.seek(SeekFrom::Current(0))?;
-    header.write_to(&mut write)?;
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            DecodeError::InvalidMagic => f.write_str("invalid magic number"),
+            DecodeError::InvalidHeader => f.write_str("invalid header"),
+            DecodeError::InvalidData {
+                super_block,
+                aspect,
+            } => {
+                f.write_str("invalid data")?;
+                if let Some([x, y, z]) = super_block {
+                    write!(f, " in super-block [{x}, {y}, {z}]")?;
+                }
+                if let Some(aspect) = aspect {
+                    write!(f, " at aspect {aspect}")?;
+                }
+                Ok(())
+            }
+            DecodeError::Unsupported => f.write_str("unsupported feature"),
+            DecodeError::ChecksumMismatch => f.write_str("checksum mismatch"),
+            DecodeError::InvalidExtent => f.write_str("invalid extent"),
+            DecodeError::Cancelled => f.write_str("cancelled"),
+            DecodeError::ContentHashMismatch => f.write_str("content hash mismatch"),
+        }
+    }
+}
 
-    let jackal_blocks_width =
-        (raw_size[0] + super_block_size.width as u32 - 1) / super_block_size.width as u32;
-    let jackal_blocks_height =
-        (raw_size[1] + super_block_size.height as u32 - 1) / super_block_size.height as u32;
-    let jackal_blocks_depth = raw_size[2];
+impl std::error::Error for DecodeError {}
 
-    let jackal_blocks_count = jackal_blocks_width * jackal_blocks_height * jackal_blocks_depth;
+/// Options for texture compression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompressOptions {
+    /// Store CRC-32 of each super-block payload.
+    /// Enabled by default.
+    pub checksums: bool,
 
-    let jackal_blocks_start = start + JackalHeader::BYTES_SIZE as u64;
-    let jackal_blocks_end =
-        jackal_blocks_start + JackalBlock::BYTES_SIZE as u64 * jackal_blocks_count as u64;
+    /// How size of super-blocks is chosen.
+    /// By default size is chosen based on the extent.
+    pub super_block_size: SuperBlockChoice,
 
-    let mut next_jackal_block_pos = jackal_blocks_start;
-    let mut next_data_pos = jackal_blocks_end;
+    /// Store block colors as interleaved bits of channels,
+    /// with high and low bytes in separate aspects.
+    /// Disabled by default.
+    pub interleaved_colors: bool,
 
-    for z in 0..raw_size[2] {
-        for y_start in (0..raw_size[1]).step_by(super_block_size.height as usize) {
-            let y_end = if raw_size[1] - y_start < header.super_block_size.height as u32 {
-                raw_size[1]
-            } else {
-                y_start + header.super_block_size.height as u32
-            };
+    /// Prediction of block color endpoints.
+    /// Super-blocks where prediction does not make payload smaller store endpoints as is.
+    /// Disabled by default.
+    pub predictor: Predictor,
 
-            for x_start in (0..raw_size[0]).step_by(super_block_size.width as usize) {
-                let x_end = if raw_size[0] - x_start < header.super_block_size.width as u32 {
-                    raw_size[0]
-                } else {
-                    x_start + header.super_block_size.width as u32
-                };
+    /// Store texel indices of each block relative to the index of its top-left texel,
+    /// so blocks with the same pattern and swapped endpoints differ only in the top-left texel.
+    /// Disabled by default.
+    pub canonical_indices: bool,
 
-                write.seek(SeekFrom::Start(next_jackal_block_pos))?;
+    /// Colorspace and alpha mode recorded in the header.
+    pub meta: TextureMeta,
 
-                // Write a jackal_block.
-                let sb = JackalBlock {
-                    offset: next_data_pos,
-                };
-                sb.write_to(&mut write)?;
-                next_jackal_block_pos += JackalBlock::BYTES_SIZE as u64;
+    /// Super-block payloads start at offsets that are multiples of the alignment,
+    /// with zero bytes padding between payloads.
+    /// Offsets are positions in the writer, so the stream itself should start aligned.
+    /// By default payloads are tightly packed.
+    pub payload_alignment: NonZeroU32,
 
-                write.seek(SeekFrom::Start(next_data_pos))?;
-                compress_any_block::<B>(
-                    x_start, x_end, y_start, y_end, z, raw_size, blocks, &mut write,
-                )?;
-                next_data_pos = write.seek(SeekFrom::Current(0))?;
-            }
+    /// Where super-block table is written.
+    /// With [`TableLocation::Trailer`] the writer is never seeked,
+    /// so the stream must start at the beginning of the writer,
+    /// and [`compress_bc1_texture_to_stream`] accepts writers without [`Seek`].
+    /// By default table follows the header.
+    pub table_location: TableLocation,
+}
+
+impl Default for CompressOptions {
+    fn default() -> Self {
+        CompressOptions {
+            checksums: true,
+            super_block_size: SuperBlockChoice::FromExtent,
+            interleaved_colors: false,
+            predictor: Predictor::None,
+            canonical_indices: false,
+            meta: TextureMeta::default(),
+            payload_alignment: NonZeroU32::MIN,
+            table_location: TableLocation::AfterHeader,
         }
     }
+}
+
+/// Options for texture decompression.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DecompressOptions {
+    /// Limits checked before allocating memory for the texture.
+    pub limits: DecodeLimits,
+
+    /// Compare hash of decompressed blocks with [`JackalHeader::content_hash`].
+    /// Streams without content hash are not verified.
+    /// Disabled by default.
+    pub verify: bool,
+}
 
+/// How size of super-blocks is chosen during compression.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SuperBlockChoice {
+    /// Size is chosen based on the extent.
+    #[default]
+    FromExtent,
+
+    /// Size is chosen by analyzing block content, see [`choose_super_block_size`].
+    Auto,
+
+    /// Explicit size. Both dimensions must be powers of two.
+    Fixed(SuperBlockSize),
+}
+
+/// Compresses BC1 blocks into Jackal stream.
+///
+/// Stream is written in [`ContainerVersion::V9`] container.
+///
+/// `extent` is measured in blocks.
+/// Blocks are stored row-major, x fastest, then y, then depth slice or array layer.
+/// Each slice or layer is split into its own super-blocks.
+///
+/// Output is deterministic: identical blocks and options produce identical bytes.
+/// Any change to the written bytes requires new container version.
+/// Golden files in `tests/fixtures` pin the output.
+///
+/// Benchmarked with `cargo bench --bench jackal`.
+pub fn compress_bc1_texture(
+    extent: Extent,
+    blocks: &[bc1::Block],
+    write: impl Write + Seek,
+) -> std::io::Result<()> {
+    compress_texture(extent, blocks, CompressOptions::default(), write)?;
     Ok(())
 }
 
-pub fn compress_bc1_blocks(
-    header: &JackalHeader,
-    super_pos: [u32; 3],
-    jackal_block: JackalBlock,
+/// Compresses BC1 blocks into Jackal stream with specified options.
+///
+/// `extent` is measured in blocks.
+pub fn compress_bc1_texture_opts(
+    extent: Extent,
     blocks: &[bc1::Block],
-    mut write: impl Write + Seek,
+    options: CompressOptions,
+    write: impl Write + Seek,
 ) -> std::io::Result<()> {
-    let raw_size = header.extent.raw_size();
+    compress_texture(extent, blocks, options, write)?;
+    Ok(())
+}
 
-    let x_start = super_pos[0] * header.super_block_size.width as u32;
-    let x_end = if raw_size[0] - x_start < header.super_block_size.width as u32 {
-        raw_size[0]
-    } else {
-        x_start + header.super_block_size.width as u32
-    };
+/// Compresses BC1 blocks into Jackal stream in a single pass over writer that cannot seek.
+///
+/// `extent` is measured in blocks.
+/// Only [`TableLocation::Trailer`] layout can be written without seeking,
+/// other table locations fail with [`std::io::ErrorKind::InvalidInput`].
+pub fn compress_bc1_texture_to_stream(
+    extent: Extent,
+    blocks: &[bc1::Block],
+    options: CompressOptions,
+    write: impl Write,
+) -> std::io::Result<()> {
+    if options.table_location != TableLocation::Trailer {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "only trailer table can be written without seeking",
+        ));
+    }
 
-    let y_start = super_pos[1] * header.super_block_size.height as u32;
-    let y_end = if raw_size[1] - y_start < header.super_block_size.height as u32 {
-        raw_size[1]
-    } else {
-        y_start + header.super_block_size.height as u32
-    };
+    let header = texture_header(extent, blocks, options)?;
+    write_sink(&header, blocks, &[], &mut StreamSink { write, pos: 0 })?;
+    Ok(())
+}
 
-    let z = super_pos[2];
+/// Compresses BC1 blocks into Jackal stream with specified options.
+/// Returns compressed size of each super-block.
+///
+/// `extent` is measured in blocks.
+pub fn compress_bc1_texture_report(
+    extent: Extent,
+    blocks: &[bc1::Block],
+    options: CompressOptions,
+    write: impl Write + Seek,
+) -> std::io::Result<CompressReport> {
+    let header = texture_header(extent, blocks, options)?;
 
-    write.seek(SeekFrom::Start(jackal_block.offset))?;
-    compress_any_block(x_start, x_end, y_start, y_end, z, raw_size, blocks, write)
+    match write_texture_with_progress(&header, blocks, &[], &mut SeekSink(write), &mut |_| {
+        ControlFlow::Continue(())
+    })? {
+        ControlFlow::Continue((table, aspect_bytes)) => {
+            Ok(CompressReport::new(&header, &table, &aspect_bytes))
+        }
+        ControlFlow::Break(()) => unreachable!("compression is cancelled only by the callback"),
+    }
 }
 
-fn compress_any_block<B>(
-    x_start: u32,
-    x_end: u32,
-    y_start: u32,
-    y_end: u32,
-    z: u32,
-    raw_size: [u32; 3],
+/// Compresses BC1 blocks into Jackal stream with metadata chunks after the header.
+///
+/// `extent` is measured in blocks.
+/// Chunks are returned by [`read_meta_chunks`] and skipped by decoders.
+pub fn compress_bc1_texture_with_chunks(
+    extent: Extent,
+    blocks: &[bc1::Block],
+    options: CompressOptions,
+    chunks: &[MetaChunk],
+    write: impl Write + Seek,
+) -> std::io::Result<()> {
+    let header = texture_header(extent, blocks, options)?;
+    write_texture(&header, blocks, chunks, write)?;
+    Ok(())
+}
+
+/// Compresses BC1 blocks into Jackal stream calling `progress` after each super-block.
+///
+/// `extent` is measured in blocks.
+///
+/// When `progress` returns [`ControlFlow::Break`] compression stops before the next super-block
+/// and [`EncodeError::Cancelled`] is returned.
+/// Writer is then left with the header, part of the super-block table and payloads
+/// of the super-blocks compressed so far, which is not a valid stream.
+pub fn compress_bc1_texture_with_progress(
+    extent: Extent,
+    blocks: &[bc1::Block],
+    options: CompressOptions,
+    write: impl Write + Seek,
+    mut progress: impl FnMut(Progress) -> ControlFlow<()>,
+) -> Result<CompressReport, EncodeError> {
+    let header = texture_header(extent, blocks, options)?;
+
+    match write_texture_with_progress(&header, blocks, &[], &mut SeekSink(write), &mut progress)? {
+        ControlFlow::Continue((table, aspect_bytes)) => {
+            Ok(CompressReport::new(&header, &table, &aspect_bytes))
+        }
+        ControlFlow::Break(()) => Err(EncodeError::Cancelled),
+    }
+}
+
+/// Compresses blocks and returns header and written super-block table.
+pub(crate) fn compress_texture<B>(
+    extent: Extent,
     blocks: &[B],
-    write: impl Write,
-) -> std::io::Result<()>
+    options: CompressOptions,
+    write: impl Write + Seek,
+) -> std::io::Result<(JackalHeader, Vec<JackalBlock>)>
 where
     B: AnyBlock,
 {
-    // let mut encoder = lzw::Encoder::<B::EncoderElement>::new();
-    // let mut write = WriteBits::new(write);
-    let mut encoder = brotli::CompressorWriter::new(write, 4096, 11, 22);
-
-    compress_any_block_aspect::<B, 0>(
-        x_start,
-        x_end,
-        y_start,
-        y_end,
-        z,
-        blocks,
-        raw_size,
-        &mut encoder,
-        // &mut write,
-    )?;
+    compress_texture_with_chunks(extent, blocks, options, &[], write)
+}
 
-    compress_any_block_aspect::<B, 1>(
-        x_start,
-        x_end,
-        y_start,
-        y_end,
-        z,
-        blocks,
-        raw_size,
-        &mut encoder,
-        // &mut write,
-    )?;
+/// Compresses blocks with metadata chunks and returns header and written super-block table.
+pub(crate) fn compress_texture_with_chunks<B>(
+    extent: Extent,
+    blocks: &[B],
+    options: CompressOptions,
+    chunks: &[MetaChunk],
+    write: impl Write + Seek,
+) -> std::io::Result<(JackalHeader, Vec<JackalBlock>)>
+where
+    B: AnyBlock,
+{
+    let header = texture_header(extent, blocks, options)?;
+    let table = write_texture(&header, blocks, chunks, write)?;
+    Ok((header, table))
+}
 
-    compress_any_block_aspect::<B, 2>(
-        x_start,
-        x_end,
-        y_start,
-        y_end,
-        z,
-        blocks,
-        raw_size,
-        &mut encoder,
-        // &mut write,
-    )?;
+/// Checks that extent is not empty and `len` blocks cover it exactly.
+fn check_blocks_count(extent: Extent, len: usize) -> std::io::Result<()> {
+    if extent.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "extent must not be empty",
+        ));
+    }
 
-    compress_any_block_aspect::<B, 3>(
-        x_start,
-        x_end,
-        y_start,
-        y_end,
-        z,
-        blocks,
-        raw_size,
-        &mut encoder,
-        // &mut write,
-    )?;
+    let raw_size = extent.raw_size();
 
-    compress_any_block_aspect::<B, 4>(
-        x_start,
-        x_end,
-        y_start,
-        y_end,
-        z,
-        blocks,
-        raw_size,
-        &mut encoder,
-        // &mut write,
-    )?;
+    // Computed in `u64` so that huge extents cannot wrap around.
+    let expected = raw_size[0] as u64 * raw_size[1] as u64 * raw_size[2] as u64;
+    if len as u64 != expected {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("blocks count does not match extent: expected {expected}, got {len}"),
+        ));
+    }
 
-    compress_any_block_aspect::<B, 5>(
-        x_start,
-        x_end,
-        y_start,
-        y_end,
-        z,
-        blocks,
-        raw_size,
-        &mut encoder,
-        // &mut write,
-    )?;
+    Ok(())
+}
 
-    compress_any_block_aspect::<B, 6>(
-        x_start,
-        x_end,
-        y_start,
-        y_end,
-        z,
-        blocks,
-        raw_size,
-        &mut encoder,
-        // &mut write,
-    )?;
+/// Validates blocks against the extent and builds header for the options.
+fn texture_header<B>(
+    extent: Extent,
+    blocks: &[B],
+    options: CompressOptions,
+) -> std::io::Result<JackalHeader>
+where
+    B: AnyBlock,
+{
+    check_blocks_count(extent, blocks.len())?;
 
-    compress_any_block_aspect::<B, 7>(
-        x_start,
-        x_end,
-        y_start,
-        y_end,
-        z,
-        blocks,
-        raw_size,
-        &mut encoder,
-        // &mut write,
-    )?;
+    let mut header = JackalHeader::new(B::FORMAT, extent, MipLevels(1))
+        .with_checksums(options.checksums)
+        .with_interleaved_colors(options.interleaved_colors)
+        .with_predictor(options.predictor)
+        .with_canonical_indices(options.canonical_indices)
+        .with_meta(options.meta)
+        .with_payload_alignment(options.payload_alignment)
+        .with_table_location(options.table_location)
+        .with_content_hash(Some(hash::hash_any_blocks(extent, blocks)));
 
-    // encoder.finish(&mut write)?;
-    // write.finish()?;
+    match options.super_block_size {
+        SuperBlockChoice::FromExtent => {}
+        SuperBlockChoice::Auto => {
+            header = header.with_super_block_size(estimate_super_block_size(&header, blocks)?);
+        }
+        SuperBlockChoice::Fixed(super_block_size) => {
+            header = header.with_super_block_size(super_block_size);
+        }
+    }
 
-    encoder.flush()?;
+    Ok(header)
+}
 
-    Ok(())
+/// Writes header, metadata chunks, super-block table and payloads.
+///
+/// Payload layout follows version of the `header`.
+fn write_texture<B>(
+    header: &JackalHeader,
+    blocks: &[B],
+    chunks: &[MetaChunk],
+    write: impl Write + Seek,
+) -> std::io::Result<Vec<JackalBlock>>
+where
+    B: AnyBlock,
+{
+    write_sink(header, blocks, chunks, &mut SeekSink(write))
 }
 
-fn compress_any_block_aspect<B, const ASPECT: usize>(
-    x_start: u32,
-    x_end: u32,
-    y_start: u32,
-    y_end: u32,
-    z: u32,
+/// Writes header, metadata chunks, super-block table and payloads into the sink.
+fn write_sink<B>(
+    header: &JackalHeader,
     blocks: &[B],
-    raw_size: [u32; 3],
-    // encoder: &mut lzw::Encoder<B::EncoderElement>,
-    // write: &mut WriteBits<impl Write>,
-    encoder: &mut brotli::CompressorWriter<impl Write>,
-) -> std::io::Result<()>
+    chunks: &[MetaChunk],
+    sink: &mut impl TextureSink,
+) -> std::io::Result<Vec<JackalBlock>>
 where
     B: AnyBlock,
 {
-    if B::ASPECTS <= ASPECT {
-        return Ok(());
+    match write_texture_with_progress(header, blocks, chunks, sink, &mut |_| {
+        ControlFlow::Continue(())
+    })? {
+        ControlFlow::Continue((table, _)) => Ok(table),
+        ControlFlow::Break(()) => unreachable!("compression is cancelled only by the callback"),
     }
+}
 
-    let width = x_end - x_start;
-    let height = y_end - y_start;
+/// Destination of compressed texture.
+///
+/// Only layouts with table after the header seek.
+trait TextureSink: Write {
+    /// Returns current position in the stream.
+    fn position(&mut self) -> std::io::Result<u64>;
 
-    debug_assert!(width <= u16::MAX as u32);
-    debug_assert!(height <= u16::MAX as u32);
+    /// Moves to `pos` in the stream.
+    fn seek_to(&mut self, pos: u64) -> std::io::Result<()>;
+}
 
-    // let bound_curve = BoundZCurve::new(width as u16, height as u16);
-    let bound_curve = (0..height * width).map(|index| {
-        let x = index % width;
-        let y = index / width;
-        (x, y)
-    });
+/// Sink over seekable writer.
+struct SeekSink<W>(W);
 
-    for (x0, y0) in bound_curve {
-        let x = x_start + x0 as u32;
-        let y = y_start + y0 as u32;
-        let width = raw_size[0] as usize;
-        let height = raw_size[1] as usize;
-        let index = x as usize + y as usize * width + z as usize * width * height;
-        let block = &blocks[index as usize];
+impl<W> Write for SeekSink<W>
+where
+    W: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write(buf)
+    }
 
-        block.compress::<ASPECT>(&mut *encoder)?;
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
     }
+}
 
-    Ok(())
+impl<W> TextureSink for SeekSink<W>
+where
+    W: Write + Seek,
+{
+    fn position(&mut self) -> std::io::Result<u64> {
+        self.0.stream_position()
+    }
+
+    fn seek_to(&mut self, pos: u64) -> std::io::Result<()> {
+        self.0.seek(SeekFrom::Start(pos))?;
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
-pub enum DecompressError {
-    Io(std::io::Error),
-    Decode(DecodeError),
+/// Sink over writer that cannot seek, position is tracked by counting written bytes.
+struct StreamSink<W> {
+    write: W,
+    pos: u64,
 }
 
-impl From<std::io::Error> for DecompressError {
-    #[inline(always)]
-    fn from(err: std::io::Error) -> Self {
-        DecompressError::Io(err)
+impl<W> Write for StreamSink<W>
+where
+    W: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.write.write(buf)?;
+        self.pos += written as u64;
+        Ok(written)
     }
-}
 
-impl From<DecodeError> for DecompressError {
-    #[inline(always)]
-    fn from(err: DecodeError) -> Self {
-        DecompressError::Decode(err)
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.write.flush()
     }
 }
 
-/// Read Jackal header from the stream.
-pub fn read_header(read: impl Read) -> Result<JackalHeader, DecompressError> {
-    JackalHeader::read_from(read)
-}
+impl<W> TextureSink for StreamSink<W>
+where
+    W: Write,
+{
+    fn position(&mut self) -> std::io::Result<u64> {
+        Ok(self.pos)
+    }
 
-/// Read super-blocks from the stream.
-pub fn read_jackal_blocks(
-    jackal_blocks: &mut [JackalBlock],
-    mut read: impl Read,
-) -> Result<(), DecompressError> {
-    for sb in jackal_blocks.iter_mut() {
-        *sb = JackalBlock::read_from(&mut read)?;
+    fn seek_to(&mut self, pos: u64) -> std::io::Result<()> {
+        if pos != self.pos {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "writer cannot seek",
+            ));
+        }
+        Ok(())
     }
-    Ok(())
 }
 
-/// Read blocks of one jackal_block.
-///
-/// `header` is Jackal header.
-/// `super_x` is x coordinate of the jackal_block.
-/// `super_y` is y coordinate of the jackal_block.
-/// `jackal_block` is jackal_block data.
-/// `blocks` array of pre-allocated blocks of all jackal_blocks.
-pub fn decompress_bc1_blocks(
-    header: &JackalHeader,
-    super_pos: [u32; 3],
-    jackal_block: JackalBlock,
-    blocks: &mut [bc1::Block],
-    read: impl Read + Seek,
-) -> Result<(), DecompressError> {
-    decompress_any_block(header, super_pos, jackal_block, blocks, read)
-}
+/// Written super-block table and sizes of aspect streams of each super-block.
+type WrittenTable = (Vec<JackalBlock>, Vec<[u64; MAX_ASPECTS]>);
 
-fn decompress_any_block<B>(
+/// Writes header, super-block table and payloads calling `progress` after each super-block.
+///
+/// Table of [`TableLocation::Trailer`] layout is written after all payloads
+/// and `write` is not seeked.
+///
+/// Returns [`ControlFlow::Break`] as soon as `progress` does.
+fn write_texture_with_progress<B>(
     header: &JackalHeader,
-    super_pos: [u32; 3],
-    jackal_block: JackalBlock,
-    blocks: &mut [B],
-    mut read: impl Read + Seek,
-) -> Result<(), DecompressError>
+    blocks: &[B],
+    chunks: &[MetaChunk],
+    write: &mut impl TextureSink,
+    progress: &mut dyn FnMut(Progress) -> ControlFlow<()>,
+) -> std::io::Result<ControlFlow<(), WrittenTable>>
 where
     B: AnyBlock,
 {
-    let raw_size = header.extent.raw_size();
+    let raw_size = header.extent().raw_size();
+    let super_block_size = header.super_block_size();
 
-    let x_start = super_pos[0] * header.super_block_size.width as u32;
-    let x_end = if raw_size[0] - x_start < header.super_block_size.width as u32 {
-        raw_size[0]
-    } else {
-        x_start + header.super_block_size.width as u32
-    };
+    // Trailer layout is written front to back, offsets start at the first written byte.
+    let trailer = header.table_location() == TableLocation::Trailer;
+    let start = if trailer { 0 } else { write.position()? };
+    header.write_to(&mut *write)?;
+    chunks::write_meta_chunks(header, chunks, &mut *write)?;
+
+    let jackal_blocks_width =
+        (raw_size[0] + super_block_size.width as u32 - 1) / super_block_size.width as u32;
+    let jackal_blocks_height =
+        (raw_size[1] + super_block_size.height as u32 - 1) / super_block_size.height as u32;
+    let jackal_blocks_depth = raw_size[2];
+
+    let jackal_blocks_count = jackal_blocks_width * jackal_blocks_height * jackal_blocks_depth;
+
+    let jackal_blocks_start = start
+        + header.bytes_size() as u64
+        + meta_chunks_bytes_size(header.version(), chunks) as u64;
+    let jackal_blocks_end =
+        jackal_blocks_start + JackalBlock::BYTES_SIZE as u64 * jackal_blocks_count as u64;
 
-    let y_start = super_pos[1] * header.super_block_size.height as u32;
-    let y_end = if raw_size[1] - y_start < header.super_block_size.height as u32 {
-        raw_size[1]
+    let mut next_jackal_block_pos = jackal_blocks_start;
+    let mut next_data_pos = if trailer {
+        jackal_blocks_start
     } else {
-        y_start + header.super_block_size.height as u32
+        jackal_blocks_end
     };
+    let alignment = header.payload_alignment().get() as u64;
 
-    let z = super_pos[2];
+    let mut table = Vec::with_capacity(jackal_blocks_count as usize);
+    let mut aspect_bytes = Vec::with_capacity(jackal_blocks_count as usize);
+    let mut blocks_done = 0;
 
-    read.seek(SeekFrom::Start(jackal_block.offset))?;
+    for z in 0..jackal_blocks_depth {
+        for y in 0..jackal_blocks_height {
+            for x in 0..jackal_blocks_width {
+                let bounds = SuperBlockBounds::new(header, [x, y, z]);
 
-    // let mut decoder = lzw::Decoder::<B::EncoderElement>::new();
-    // let mut read = ReadBits::new(read);
-    let mut decoder = brotli::reader::Decompressor::new(read, 4096);
-
-    decompress_any_block_aspect::<B, 0>(
-        x_start,
-        x_end,
-        y_start,
-        y_end,
-        z,
-        blocks,
-        raw_size,
-        &mut decoder,
-        // &mut read,
-    )?;
+                let mut payload = Vec::new();
+                compress_any_block::<B>(bounds, header, blocks, &mut payload)?;
 
-    decompress_any_block_aspect::<B, 1>(
-        x_start,
-        x_end,
-        y_start,
-        y_end,
-        z,
-        blocks,
-        raw_size,
-        &mut decoder,
-        // &mut read,
-    )?;
+                let data_pos = next_data_pos.next_multiple_of(alignment);
+                let sb = jackal_block_for_payload(header, data_pos, &payload)?;
 
-    decompress_any_block_aspect::<B, 2>(
-        x_start,
-        x_end,
-        y_start,
-        y_end,
-        z,
-        blocks,
-        raw_size,
-        &mut decoder,
-        // &mut read,
-    )?;
+                if !trailer {
+                    // Write a jackal_block.
+                    write.seek_to(next_jackal_block_pos)?;
+                    sb.write_to(&mut *write)?;
+                    next_jackal_block_pos += JackalBlock::BYTES_SIZE as u64;
 
-    decompress_any_block_aspect::<B, 3>(
-        x_start,
-        x_end,
-        y_start,
-        y_end,
-        z,
-        blocks,
-        raw_size,
-        &mut decoder,
-        // &mut read,
-    )?;
+                    write.seek_to(next_data_pos)?;
+                }
 
-    decompress_any_block_aspect::<B, 4>(
-        x_start,
-        x_end,
-        y_start,
-        y_end,
-        z,
-        blocks,
-        raw_size,
-        &mut decoder,
-        // &mut read,
-    )?;
+                // Padding is written explicitly, writer may hold stale bytes.
+                std::io::copy(
+                    &mut std::io::repeat(0).take(data_pos - next_data_pos),
+                    &mut *write,
+                )?;
+                write.write_all(&payload)?;
+                next_data_pos = data_pos + payload.len() as u64;
 
-    decompress_any_block_aspect::<B, 5>(
-        x_start,
-        x_end,
-        y_start,
-        y_end,
-        z,
-        blocks,
-        raw_size,
-        &mut decoder,
-        // &mut read,
-    )?;
+                table.push(sb);
+                aspect_bytes.push(payload_aspect_bytes(header, &payload));
+                blocks_done += bounds.count();
 
-    decompress_any_block_aspect::<B, 6>(
-        x_start,
-        x_end,
-        y_start,
-        y_end,
-        z,
-        blocks,
-        raw_size,
-        &mut decoder,
-        // &mut read,
-    )?;
+                let flow = progress(Progress {
+                    super_blocks_done: table.len(),
+                    super_blocks_total: jackal_blocks_count as usize,
+                    blocks_done,
+                    bytes: next_data_pos - start,
+                });
+                if flow.is_break() {
+                    return Ok(ControlFlow::Break(()));
+                }
+            }
+        }
+    }
 
-    decompress_any_block_aspect::<B, 7>(
-        x_start,
-        x_end,
-        y_start,
-        y_end,
-        z,
-        blocks,
-        raw_size,
-        &mut decoder,
-        // &mut read,
-    )?;
+    if trailer {
+        for sb in &table {
+            sb.write_to(&mut *write)?;
+        }
+        write.write_all(&TABLE_TRAILER_MAGIC)?;
+        write.write_all(&next_data_pos.to_le_bytes())?;
+    }
+
+    Ok(ControlFlow::Continue((table, aspect_bytes)))
+}
+
+/// Builds super-block table entry for the payload written at `offset`.
+fn jackal_block_for_payload(
+    header: &JackalHeader,
+    offset: u64,
+    payload: &[u8],
+) -> std::io::Result<JackalBlock> {
+    let size = u32::try_from(payload.len()).map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "super-block payload is too large",
+        )
+    })?;
+
+    let checksum = if header.checksums() {
+        crc::crc32(payload)
+    } else {
+        0
+    };
+
+    Ok(JackalBlock {
+        offset,
+        size,
+        checksum,
+    })
+}
+
+/// Compresses blocks of one super-block at `jackal_block.offset`.
+///
+/// Returns super-block table entry with size and checksum of written payload.
+pub fn compress_bc1_blocks(
+    header: &JackalHeader,
+    super_pos: [u32; 3],
+    jackal_block: JackalBlock,
+    blocks: &[bc1::Block],
+    mut write: impl Write + Seek,
+) -> std::io::Result<JackalBlock> {
+    let bounds = SuperBlockBounds::new(header, super_pos);
+
+    let mut payload = Vec::new();
+    compress_any_block(bounds, header, blocks, &mut payload)?;
+
+    write.seek(SeekFrom::Start(jackal_block.offset))?;
+    write.write_all(&payload)?;
+
+    jackal_block_for_payload(header, jackal_block.offset, &payload)
+}
+
+/// Chooses super-block size for the blocks by analyzing their content.
+///
+/// Each candidate size is scored with the size of the super-block table
+/// plus estimated compressed size of the payloads, and the cheapest one wins.
+/// Payload size is estimated from order-0 entropy of each aspect,
+/// so regions with different statistics are kept in separate super-blocks
+/// while uniform content gets the largest size covering the extent.
+///
+/// `extent` is measured in blocks.
+pub fn choose_super_block_size<B>(extent: Extent, blocks: &[B]) -> std::io::Result<SuperBlockSize>
+where
+    B: AnyBlock,
+{
+    check_blocks_count(extent, blocks.len())?;
+
+    let header = JackalHeader::new(B::FORMAT, extent, MipLevels(1));
+    estimate_super_block_size(&header, blocks)
+}
+
+fn estimate_super_block_size<B>(
+    header: &JackalHeader,
+    blocks: &[B],
+) -> std::io::Result<SuperBlockSize>
+where
+    B: AnyBlock,
+{
+    // Compressed stream adds a few bytes even for tiny inputs.
+    const STREAM_OVERHEAD: f64 = 4.0;
+
+    let [width, height, depth] = header.extent().raw_size();
+
+    // Sizes beyond the extent only add padding.
+    let cover = |v: u32| v.next_power_of_two().clamp(1, 1 << 15) as u16;
+    let max = SuperBlockSize {
+        width: cover(width),
+        height: cover(height),
+    };
+
+    let mut best: Option<(f64, SuperBlockSize)> = None;
 
-    // decoder.finish();
+    let mut side = max.width.max(max.height);
+    loop {
+        let candidate = SuperBlockSize {
+            width: side.min(max.width),
+            height: side.min(max.height),
+        };
+        let header = header.with_super_block_size(candidate);
+
+        let mut cost = header.jackal_blocks_count() as f64 * JackalBlock::BYTES_SIZE as f64;
+
+        let [super_width, super_height, _] = header.jackal_blocks_extent();
+        for z in 0..depth {
+            for y in 0..super_height {
+                for x in 0..super_width {
+                    let bounds = SuperBlockBounds::new(&header, [x, y, z]);
+                    let aspects = collect_aspects(bounds, &header, blocks)?;
+
+                    for aspect in aspects.iter().filter(|aspect| !aspect.is_empty()) {
+                        cost += Histogram256::from_bytes(aspect).estimated_size_bytes()
+                            + STREAM_OVERHEAD;
+                    }
+                }
+            }
+        }
+
+        // Larger sizes are tried first and win ties.
+        if best.is_none_or(|(best_cost, _)| cost < best_cost) {
+            best = Some((cost, candidate));
+        }
+
+        if side <= 16 {
+            break;
+        }
+        side /= 2;
+    }
+
+    Ok(best.map_or(max, |(_, size)| size))
+}
+
+/// Returns raw bytes of each aspect across all blocks of the super-block.
+fn collect_aspects<B>(
+    bounds: SuperBlockBounds,
+    header: &JackalHeader,
+    blocks: &[B],
+) -> std::io::Result<[Vec<u8>; MAX_ASPECTS]>
+where
+    B: AnyBlock,
+{
+    debug_assert!(B::ASPECTS <= MAX_ASPECTS);
+
+    let mut aspects: [Vec<u8>; MAX_ASPECTS] = Default::default();
+
+    for (aspect, bytes) in aspects.iter_mut().enumerate().take(B::ASPECTS) {
+        compress_any_block_aspect(bounds, blocks, header, aspect, bytes)?;
+    }
+
+    Ok(aspects)
+}
+
+fn compress_any_block<B>(
+    bounds: SuperBlockBounds,
+    header: &JackalHeader,
+    blocks: &[B],
+    mut write: impl Write,
+) -> std::io::Result<()>
+where
+    B: AnyBlock,
+{
+    if has_payload_mode(header.version()) {
+        if let Some(block) = constant_block(bounds, header, blocks) {
+            write.write_all(&[PAYLOAD_CONSTANT])?;
+            return block::write_raw_block(&block, write);
+        }
+    }
+
+    let aspects = collect_aspects(bounds, header, blocks)?;
+
+    match header.version() {
+        ContainerVersion::V3
+        | ContainerVersion::V4
+        | ContainerVersion::V5
+        | ContainerVersion::V6
+        | ContainerVersion::V7
+        | ContainerVersion::V8
+        | ContainerVersion::V9 => {
+            let mut mode = PAYLOAD_ASPECTS;
+            let mut compressed = compress_aspect_streams::<B>(&aspects)?;
+
+            // Super-block keeps endpoints as is when prediction does not pay off.
+            if has_payload_predictor(header.version()) && header.predictor() != Predictor::None {
+                let header = header.with_predictor(Predictor::None);
+                let aspects = collect_aspects(bounds, &header, blocks)?;
+                let unpredicted = compress_aspect_streams::<B>(&aspects)?;
+
+                if streams_len(&unpredicted) < streams_len(&compressed) {
+                    mode = PAYLOAD_ASPECTS_UNPREDICTED;
+                    compressed = unpredicted;
+                }
+            }
+
+            if has_payload_mode(header.version()) {
+                write.write_all(&[mode])?;
+            }
+
+            for stream in &compressed {
+                write_varint(&mut write, stream.len() as u64)?;
+            }
+            for stream in &compressed {
+                write.write_all(stream)?;
+            }
+        }
+        ContainerVersion::V2 | ContainerVersion::Legacy => {
+            // Older versions keep all aspects in one stream.
+            let mut encoder = brotli::CompressorWriter::new(write, 4096, 11, 22);
+            for aspect in &aspects {
+                encoder.write_all(aspect)?;
+            }
+            encoder.flush()?;
+        }
+    }
 
     Ok(())
 }
 
-fn decompress_any_block_aspect<B, const ASPECT: usize>(
-    x_start: u32,
-    x_end: u32,
-    y_start: u32,
-    y_end: u32,
-    z: u32,
-    blocks: &mut [B],
-    raw_size: [u32; 3],
-    // decoder: &mut lzw::Decoder<B::EncoderElement>,
-    // read: &mut ReadBits<impl Read>,
-    decoder: &mut brotli::reader::Decompressor<impl Read>,
-) -> Result<(), DecompressError>
+/// Compresses each aspect of blocks into separate stream.
+fn compress_aspect_streams<B>(aspects: &[Vec<u8>; MAX_ASPECTS]) -> std::io::Result<Vec<Vec<u8>>>
 where
     B: AnyBlock,
 {
-    if B::ASPECTS <= ASPECT {
-        return Ok(());
+    let mut compressed = Vec::with_capacity(B::ASPECTS);
+    for aspect in &aspects[..B::ASPECTS] {
+        // Empty aspects are stored with zero size and no stream.
+        let mut stream = Vec::new();
+        if !aspect.is_empty() {
+            let mut encoder = brotli::CompressorWriter::new(&mut stream, 4096, 11, 22);
+            encoder.write_all(aspect)?;
+            encoder.flush()?;
+        }
+        compressed.push(stream);
     }
+    Ok(compressed)
+}
+
+/// Returns total size of compressed streams.
+fn streams_len(streams: &[Vec<u8>]) -> usize {
+    streams.iter().map(Vec::len).sum()
+}
+
+/// Returns the block if all blocks of the super-block are identical.
+fn constant_block<B>(bounds: SuperBlockBounds, header: &JackalHeader, blocks: &[B]) -> Option<B>
+where
+    B: AnyBlock,
+{
+    let raw_size = header.extent().raw_size();
+    let z = bounds.z();
+    let first = blocks[texture::block_index(raw_size, bounds.x_start, bounds.y_start, z)];
+
+    (bounds.y_start..bounds.y_end)
+        .all(|y| {
+            let row = texture::block_index(raw_size, bounds.x_start, y, z);
+            blocks[row..row + bounds.width() as usize]
+                .iter()
+                .all(|block| *block == first)
+        })
+        .then_some(first)
+}
+
+fn compress_any_block_aspect<B>(
+    bounds: SuperBlockBounds,
+    blocks: &[B],
+    header: &JackalHeader,
+    aspect: usize,
+    write: &mut Vec<u8>,
+) -> std::io::Result<()>
+where
+    B: AnyBlock,
+{
+    debug_assert!(aspect < B::ASPECTS);
+
+    let raw_size = header.extent().raw_size();
 
-    let width = x_end - x_start;
-    let height = y_end - y_start;
+    let width = bounds.width();
+    let height = bounds.height();
 
     debug_assert!(width <= u16::MAX as u32);
     debug_assert!(height <= u16::MAX as u32);
@@ -537,94 +929,4001 @@ where
         (x, y)
     });
 
+    let mut previous = B::EMPTY;
+
     for (x0, y0) in bound_curve {
-        let x = x_start + x0 as u32;
-        let y = y_start + y0 as u32;
-        let width = raw_size[0] as usize;
-        let height = raw_size[1] as usize;
-        let index = x as usize + y as usize * width + z as usize * width * height;
+        let x = bounds.x_start + x0 as u32;
+        let y = bounds.y_start + y0 as u32;
+        let index = texture::block_index(raw_size, x, y, bounds.z());
         let mut block = blocks[index];
 
-        block.decompress::<ASPECT>(&mut *decoder)?;
+        if header.predictor() == Predictor::Previous {
+            block.predict_endpoints(&previous);
+            previous = blocks[index];
+        }
 
-        blocks[index as usize] = block;
+        block.compress(aspect, header, &mut *write)?;
     }
 
     Ok(())
 }
 
-pub fn decompress_bc1_texture(
-    mut read: impl Read + Seek,
-) -> Result<(Extent, Vec<bc1::Block>), DecompressError> {
-    let header = read_header(&mut read)?;
-    let mut jackal_blocks = vec![JackalBlock { offset: 0 }; header.jackal_blocks_count()];
-    read_jackal_blocks(&mut jackal_blocks, &mut read)?;
+/// Maximum number of block aspects supported by the container.
+pub const MAX_ASPECTS: usize = 8;
 
-    let mut blocks = vec![bc1::Block::BLACK; header.blocks_count()];
+/// Maximum size of LEB128 varint holding `u64`.
+const MAX_VARINT_BYTES: u64 = 10;
 
-    let jackal_blocks_extent = header.jackal_blocks_extent();
+/// Magic of the trailer that ends stream of [`TableLocation::Trailer`] layout.
+/// It is followed by offset of the super-block table as `u64`.
+const TABLE_TRAILER_MAGIC: [u8; 8] = *b"JKLTABLE";
 
-    for z in 0..jackal_blocks_extent[2] {
-        for y in 0..jackal_blocks_extent[1] {
-            for x in 0..jackal_blocks_extent[0] {
-                decompress_bc1_blocks(
-                    &header,
-                    [x, y, z],
-                    jackal_blocks[(x
-                        + y * jackal_blocks_extent[0]
-                        + z * jackal_blocks_extent[0] * jackal_blocks_extent[1])
-                        as usize],
-                    &mut blocks,
-                    &mut read,
-                )?;
-            }
+/// Size of the table trailer.
+const TABLE_TRAILER_BYTES_SIZE: usize = TABLE_TRAILER_MAGIC.len() + 8;
+
+/// Maximum size of payload mode and stream sizes at the front of super-block payload.
+const MAX_ASPECT_SIZES_BYTES: u64 = 1 + MAX_VARINT_BYTES * MAX_ASPECTS as u64;
+
+/// Payload mode of super-block with separately compressed aspects of blocks.
+/// Endpoints are predicted with [`JackalHeader::predictor`].
+const PAYLOAD_ASPECTS: u8 = 0;
+
+/// Payload mode of super-block with all blocks identical,
+/// followed by the block written with [`block::write_raw_block`].
+const PAYLOAD_CONSTANT: u8 = 1;
+
+/// Payload mode of super-block with separately compressed aspects of blocks
+/// with endpoints stored as is regardless of [`JackalHeader::predictor`].
+/// Defined since [`ContainerVersion::V9`].
+const PAYLOAD_ASPECTS_UNPREDICTED: u8 = 2;
+
+/// Returns `true` if super-block payloads of the container version start with mode byte.
+fn has_payload_mode(version: ContainerVersion) -> bool {
+    match version {
+        ContainerVersion::Legacy
+        | ContainerVersion::V2
+        | ContainerVersion::V3
+        | ContainerVersion::V4
+        | ContainerVersion::V5
+        | ContainerVersion::V6
+        | ContainerVersion::V7 => false,
+        ContainerVersion::V8 | ContainerVersion::V9 => true,
+    }
+}
+
+/// Returns `true` if payload mode of the container version records the predictor.
+fn has_payload_predictor(version: ContainerVersion) -> bool {
+    match version {
+        ContainerVersion::Legacy
+        | ContainerVersion::V2
+        | ContainerVersion::V3
+        | ContainerVersion::V4
+        | ContainerVersion::V5
+        | ContainerVersion::V6
+        | ContainerVersion::V7
+        | ContainerVersion::V8 => false,
+        ContainerVersion::V9 => true,
+    }
+}
+
+/// Returns predictor of aspects payload with `mode` or `None` if mode is not of aspects.
+fn payload_predictor(header: &JackalHeader, mode: u8) -> Option<Predictor> {
+    match mode {
+        PAYLOAD_ASPECTS => Some(header.predictor()),
+        PAYLOAD_ASPECTS_UNPREDICTED if has_payload_predictor(header.version()) => {
+            Some(Predictor::None)
         }
+        _ => None,
     }
+}
 
-    Ok((header.extent(), blocks))
+/// Super-block payload split by its mode.
+enum Payload<'a, B> {
+    /// Compressed aspects of blocks with endpoints predicted by the predictor.
+    Aspects(Predictor, &'a [u8]),
+
+    /// All blocks are identical.
+    Constant(B),
 }
 
-#[test]
-fn roundtrip() {
-    use crate::math::Rgb32F;
+/// Splits mode byte from payload of containers that have it.
+fn parse_payload<'a, B>(
+    header: &JackalHeader,
+    super_pos: [u32; 3],
+    payload: &'a [u8],
+) -> Result<Payload<'a, B>, DecompressError>
+where
+    B: AnyBlock,
+{
+    if !has_payload_mode(header.version()) {
+        return Ok(Payload::Aspects(header.predictor(), payload));
+    }
 
-    let pixels = [
-        [Rgb32F::BLACK, Rgb32F::WHITE, Rgb32F::BLACK, Rgb32F::WHITE],
-        [Rgb32F::WHITE, Rgb32F::BLACK, Rgb32F::WHITE, Rgb32F::BLACK],
-        [Rgb32F::BLACK, Rgb32F::WHITE, Rgb32F::BLACK, Rgb32F::WHITE],
-        [Rgb32F::WHITE, Rgb32F::BLACK, Rgb32F::WHITE, Rgb32F::BLACK],
-    ];
+    let invalid = DecodeError::InvalidData {
+        super_block: Some(super_pos),
+        aspect: None,
+    };
 
-    let block = bc1::Block::encode(pixels);
+    match payload.split_first() {
+        Some((&PAYLOAD_CONSTANT, rest)) if rest.len() == block::raw_block_bytes::<B>() => {
+            Ok(Payload::Constant(block::read_raw_block(rest)?))
+        }
+        Some((&mode, rest)) => match payload_predictor(header, mode) {
+            Some(predictor) => Ok(Payload::Aspects(predictor, rest)),
+            None => Err(invalid.into()),
+        },
+        None => Err(invalid.into()),
+    }
+}
 
-    assert_eq!(block.decode(), pixels);
+/// Returns number of aspects of blocks of the format.
+///
+/// Formats without [`AnyBlock`] implementation have no aspects.
+fn format_aspects(format: Format) -> usize {
+    match format {
+        Format::BC1 => bc1::Block::ASPECTS,
+        Format::BC2 => bc2::Block::ASPECTS,
+        Format::BC3 => bc3::Block::ASPECTS,
+        Format::BC4 => crate::bc4::Block::ASPECTS,
+        Format::BC5 => crate::bc5::Block::ASPECTS,
+        Format::BC6 | Format::BC7 => 0,
+    }
+}
 
-    let blocks = vec![block; 2];
+/// Reads sizes of compressed aspect streams from the front of super-block payload.
+///
+/// Only [`ContainerVersion::V3`] and later store aspects in separate streams.
+/// Sizes are zero for older containers, super-blocks of identical blocks
+/// and payloads too short to hold the sizes.
+fn payload_aspect_bytes(header: &JackalHeader, payload: &[u8]) -> [u64; MAX_ASPECTS] {
+    let mut sizes = [0; MAX_ASPECTS];
 
-    // eprintln!("\n\nCompress");
+    if let ContainerVersion::Legacy | ContainerVersion::V2 = header.version() {
+        return sizes;
+    }
 
-    let mut output = Vec::new();
-    compress_bc1_texture(
-        Extent::D2 {
-            width: 2,
-            height: 1,
-        },
-        &blocks,
-        std::io::Cursor::new(&mut output),
-    )
-    .unwrap();
+    let mut rest = payload;
+    if has_payload_mode(header.version()) {
+        match rest.split_first() {
+            Some((&mode, tail)) if payload_predictor(header, mode).is_some() => rest = tail,
+            _ => return sizes,
+        }
+    }
 
-    // eprintln!("\n\nDecompress");
+    for size in &mut sizes[..format_aspects(header.format())] {
+        match read_varint(&mut rest) {
+            Some(value) => *size = value,
+            None => return [0; MAX_ASPECTS],
+        }
+    }
+    sizes
+}
 
-    let (extent, decompressed) = decompress_bc1_texture(std::io::Cursor::new(&output)).unwrap();
+/// Returns bytes of each aspect of `count` identical blocks as written by [`AnyBlock::compress`].
+///
+/// Endpoints are not predicted.
+fn constant_aspects<B>(header: &JackalHeader, block: B, count: usize) -> [Vec<u8>; MAX_ASPECTS]
+where
+    B: AnyBlock,
+{
+    let mut aspects: [Vec<u8>; MAX_ASPECTS] = Default::default();
 
-    assert_eq!(
-        extent,
-        Extent::D2 {
-            width: 2,
-            height: 1,
+    for (aspect, bytes) in aspects.iter_mut().enumerate().take(B::ASPECTS) {
+        for _ in 0..count {
+            // Writing into a vector never fails.
+            let _ = block.compress(aspect, header, &mut *bytes);
         }
-    );
+    }
 
-    assert_eq!(decompressed[..], blocks[..]);
+    aspects
+}
+
+/// Writes unsigned LEB128 varint.
+fn write_varint(mut write: impl Write, mut value: u64) -> std::io::Result<()> {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            return write.write_all(&[byte]);
+        }
+        write.write_all(&[byte | 0x80])?;
+    }
+}
+
+/// Reads unsigned LEB128 varint from the front of `bytes`.
+///
+/// Returns `None` if varint is truncated or overflows `u64`.
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+
+        value |= ((byte & 0x7F) as u64).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Part of the stream that was being decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeStage {
+    Header,
+    BlockTable,
+
+    /// Super-block with specified index in the super-block table.
+    SuperBlock(usize),
+}
+
+impl fmt::Display for DecodeStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            DecodeStage::Header => f.write_str("header"),
+            DecodeStage::BlockTable => f.write_str("block table"),
+            DecodeStage::SuperBlock(index) => write!(f, "super-block {index}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DecompressError {
+    Io(std::io::Error),
+    Decode(DecodeError),
+
+    /// Error occurred while decoding specific part of the stream.
+    Stage {
+        stage: DecodeStage,
+        source: Box<DecompressError>,
+    },
+}
+
+impl DecompressError {
+    /// Wraps error with the stage it occurred at.
+    pub(crate) fn at(self, stage: DecodeStage) -> Self {
+        DecompressError::Stage {
+            stage,
+            source: Box::new(self),
+        }
+    }
+
+    /// Returns stage at which error occurred, if known.
+    pub fn stage(&self) -> Option<DecodeStage> {
+        match self {
+            DecompressError::Stage { stage, .. } => Some(*stage),
+            _ => None,
+        }
+    }
+
+    /// Returns decode error, looking through stage context.
+    pub fn decode_error(&self) -> Option<DecodeError> {
+        match self {
+            DecompressError::Io(_) => None,
+            DecompressError::Decode(err) => Some(*err),
+            DecompressError::Stage { source, .. } => source.decode_error(),
+        }
+    }
+}
+
+impl fmt::Display for DecompressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecompressError::Io(err) => write!(f, "I/O error: {err}"),
+            DecompressError::Decode(err) => fmt::Display::fmt(err, f),
+            DecompressError::Stage { stage, source } => write!(f, "{stage}: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for DecompressError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecompressError::Io(err) => Some(err),
+            DecompressError::Decode(_) => None,
+            DecompressError::Stage { source, .. } => Some(&**source),
+        }
+    }
+}
+
+impl From<std::io::Error> for DecompressError {
+    #[inline(always)]
+    fn from(err: std::io::Error) -> Self {
+        DecompressError::Io(err)
+    }
+}
+
+impl From<DecodeError> for DecompressError {
+    #[inline(always)]
+    fn from(err: DecodeError) -> Self {
+        DecompressError::Decode(err)
+    }
+}
+
+impl From<lzp::DecodeError> for DecompressError {
+    fn from(err: lzp::DecodeError) -> Self {
+        // Malformed LZP stream inside super-block payload is invalid data.
+        match err {
+            lzp::DecodeError::Io(err) => DecompressError::Io(err),
+            lzp::DecodeError::InvalidConfig | lzp::DecodeError::Truncated => {
+                DecompressError::Decode(DecodeError::INVALID_DATA)
+            }
+        }
+    }
+}
+
+/// Read Jackal header from the stream.
+pub fn read_header(read: impl Read) -> Result<JackalHeader, DecompressError> {
+    JackalHeader::read_from(read)
+}
+
+/// Reads header rejecting extents that exceed `limits`.
+pub fn read_header_with_limits(
+    read: impl Read,
+    limits: &DecodeLimits,
+) -> Result<JackalHeader, DecompressError> {
+    JackalHeader::read_from_with_limits(read, limits)
+}
+
+/// Detects version of the container without consuming the stream.
+pub fn sniff(mut read: impl Read + Seek) -> Result<ContainerVersion, DecompressError> {
+    let start = read.stream_position()?;
+
+    let mut magic = [0; 4];
+    let result = read.read_exact(&mut magic);
+    read.seek(SeekFrom::Start(start))?;
+    result?;
+
+    Ok(ContainerVersion::decode(magic)?)
+}
+
+/// Reads header and super-block table of either container version.
+///
+/// Payload sizes of legacy container are computed from offsets of consecutive payloads,
+/// last one extends to the end of the stream.
+pub(crate) fn read_container(
+    mut read: impl Read + Seek,
+    limits: &DecodeLimits,
+) -> Result<(JackalHeader, Vec<JackalBlock>), DecompressError> {
+    let header =
+        read_header_with_limits(&mut read, limits).map_err(|err| err.at(DecodeStage::Header))?;
+    let table = read_jackal_blocks_vec(&header, &mut read)
+        .map_err(|err| err.at(DecodeStage::BlockTable))?;
+
+    Ok((header, table))
+}
+
+fn read_legacy_jackal_blocks(
+    count: usize,
+    mut read: impl Read + Seek,
+) -> Result<Vec<JackalBlock>, DecompressError> {
+    // Table grows as entries are read, so that bogus count fails on short input
+    // before allocating.
+    let mut jackal_blocks = Vec::new();
+    for _ in 0..count {
+        let mut bytes = [0; 8];
+        read.read_exact(&mut bytes)?;
+        jackal_blocks.push(JackalBlock {
+            offset: u64::from_le_bytes(bytes),
+            ..JackalBlock::default()
+        });
+    }
+
+    let stream_len = read.seek(SeekFrom::End(0))?;
+
+    let mut end = stream_len;
+    for sb in jackal_blocks.iter_mut().rev() {
+        if sb.offset > end {
+            return Err(DecodeError::INVALID_DATA.into());
+        }
+
+        sb.size = u32::try_from(end - sb.offset).map_err(|_| DecodeError::INVALID_DATA)?;
+        end = sb.offset;
+    }
+
+    Ok(jackal_blocks)
+}
+
+/// Reads super-block table of the first mip level that follows the header.
+///
+/// Metadata chunks between the header and the table are skipped.
+/// Reads exactly [`JackalHeader::jackal_blocks_count`] entries of the container version.
+/// Sizes of legacy container payloads are computed from offsets,
+/// which requires seeking to the end of the stream.
+///
+/// No table is returned if it is truncated or invalid.
+pub fn read_jackal_blocks_vec(
+    header: &JackalHeader,
+    read: impl Read + Seek,
+) -> Result<Vec<JackalBlock>, DecompressError> {
+    read_level_jackal_blocks_vec(header, 0, read)
+}
+
+/// Reads super-block table of mip `level`.
+///
+/// Reads exactly [`JackalHeader::level_jackal_blocks_count`] entries.
+/// Containers store only the first level, so other levels are rejected as unsupported.
+pub fn read_level_jackal_blocks_vec(
+    header: &JackalHeader,
+    level: u16,
+    mut read: impl Read + Seek,
+) -> Result<Vec<JackalBlock>, DecompressError> {
+    if level > 0 {
+        return Err(DecodeError::Unsupported.into());
+    }
+
+    // Size of the first level does not depend on pixel size.
+    let count = header
+        .level_jackal_blocks_count(level, None)
+        .ok_or(DecodeError::InvalidExtent)?;
+
+    match header.version() {
+        ContainerVersion::V2
+        | ContainerVersion::V3
+        | ContainerVersion::V4
+        | ContainerVersion::V5
+        | ContainerVersion::V6
+        | ContainerVersion::V7
+        | ContainerVersion::V8
+        | ContainerVersion::V9 => {
+            seek_jackal_blocks(header, &mut read)?;
+
+            // Table grows as entries are read, so that bogus count fails on short input
+            // before allocating.
+            let mut jackal_blocks = Vec::new();
+            for _ in 0..count {
+                jackal_blocks.push(JackalBlock::read_from(&mut read)?);
+            }
+            Ok(jackal_blocks)
+        }
+        ContainerVersion::Legacy => read_legacy_jackal_blocks(count, read),
+    }
+}
+
+/// Moves `read` from the end of the header to the super-block table.
+///
+/// Metadata chunks are skipped and table of [`TableLocation::Trailer`] layout
+/// is located by the trailer at the end of the stream.
+fn seek_jackal_blocks(
+    header: &JackalHeader,
+    mut read: impl Read + Seek,
+) -> Result<(), DecompressError> {
+    chunks::skip_meta_chunks(&mut read, header)?;
+
+    if header.table_location() == TableLocation::Trailer {
+        let stream_len = read.seek(SeekFrom::End(0))?;
+        let trailer_start = stream_len
+            .checked_sub(TABLE_TRAILER_BYTES_SIZE as u64)
+            .ok_or(DecodeError::INVALID_DATA)?;
+        read.seek(SeekFrom::Start(trailer_start))?;
+
+        let mut trailer = [0; TABLE_TRAILER_BYTES_SIZE];
+        read.read_exact(&mut trailer)?;
+        let (magic, offset) = trailer.split_at(TABLE_TRAILER_MAGIC.len());
+        if magic != TABLE_TRAILER_MAGIC {
+            return Err(DecodeError::INVALID_DATA.into());
+        }
+
+        let offset = u64::from_le_bytes(offset.try_into().unwrap());
+        if offset > trailer_start {
+            return Err(DecodeError::INVALID_DATA.into());
+        }
+        read.seek(SeekFrom::Start(offset))?;
+    }
+    Ok(())
+}
+
+/// Read super-blocks from the stream.
+///
+/// Reads one entry of [`ContainerVersion::V2`] or later table for each element of `jackal_blocks`
+/// without allocating.
+/// If an error is returned, some entries may already be overwritten
+/// and contents of `jackal_blocks` must not be used.
+/// Prefer [`read_jackal_blocks_vec`] which never exposes partially read table.
+pub fn read_jackal_blocks(
+    jackal_blocks: &mut [JackalBlock],
+    mut read: impl Read,
+) -> Result<(), DecompressError> {
+    for sb in jackal_blocks.iter_mut() {
+        *sb = JackalBlock::read_from(&mut read)?;
+    }
+    Ok(())
+}
+
+/// Read blocks of one jackal_block.
+///
+/// `header` is Jackal header.
+/// `super_x` is x coordinate of the jackal_block.
+/// `super_y` is y coordinate of the jackal_block.
+/// `jackal_block` is jackal_block data.
+/// `blocks` array of pre-allocated blocks of all jackal_blocks.
+pub fn decompress_bc1_blocks(
+    header: &JackalHeader,
+    super_pos: [u32; 3],
+    jackal_block: JackalBlock,
+    blocks: &mut [bc1::Block],
+    read: impl Read + Seek,
+) -> Result<(), DecompressError> {
+    let payload = read_payload(header, super_pos, jackal_block, read)?;
+    decompress_any_block(
+        header,
+        super_pos,
+        BlockLayout::RowMajor,
+        0,
+        blocks,
+        &payload,
+    )
+}
+
+/// Decompresses blocks of one super-block with payload stored in `data`.
+///
+/// Same as [`decompress_bc1_blocks`], but payload is decoded in place
+/// at `jackal_block.offset` of `data` without seeking or copying.
+/// Payload outside of `data` is rejected with [`DecodeError::InvalidData`].
+pub fn decompress_bc1_blocks_from_slice(
+    header: &JackalHeader,
+    super_pos: [u32; 3],
+    jackal_block: JackalBlock,
+    data: &[u8],
+    blocks: &mut [bc1::Block],
+) -> Result<(), DecompressError> {
+    let payload = slice_payload(header, super_pos, jackal_block, data)?;
+    decompress_any_block(header, super_pos, BlockLayout::RowMajor, 0, blocks, payload)
+}
+
+/// Decodes single aspect of all BC1 blocks of one super-block.
+///
+/// Returns bytes of the aspect as written by [`AnyBlock::compress`] for each block
+/// in row-major order within the super-block.
+/// `N` must be equal to [`AnyBlock::aspect_bytes`] of the aspect.
+///
+/// Only the requested aspect is decompressed in [`ContainerVersion::V3`] and later containers.
+/// Older containers are decompressed up to the aspect.
+///
+/// Endpoints are returned as is, not as residuals of [`Predictor::Previous`].
+/// To restore them all aspects of the super-block are decompressed.
+pub fn decompress_bc1_aspect<const N: usize>(
+    header: &JackalHeader,
+    super_pos: [u32; 3],
+    jackal_block: JackalBlock,
+    aspect: usize,
+    read: impl Read + Seek,
+) -> Result<Vec<[u8; N]>, DecompressError> {
+    decompress_any_aspect::<bc1::Block, N>(header, super_pos, jackal_block, aspect, read)
+}
+
+fn decompress_any_aspect<B, const N: usize>(
+    header: &JackalHeader,
+    super_pos: [u32; 3],
+    jackal_block: JackalBlock,
+    aspect: usize,
+    read: impl Read + Seek,
+) -> Result<Vec<[u8; N]>, DecompressError>
+where
+    B: AnyBlock,
+{
+    if aspect >= B::ASPECTS || B::aspect_bytes(header, aspect) != N {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "aspect size does not match",
+        )
+        .into());
+    }
+
+    let bounds = super_block_bounds(header, super_pos)?;
+    let count = bounds.count();
+
+    let payload = read_payload(header, super_pos, jackal_block, read)?;
+    let (predictor, mut aspects) = match parse_payload::<B>(header, super_pos, &payload)? {
+        Payload::Aspects(predictor, payload) => {
+            // Predicted endpoints depend on other aspects of preceding blocks.
+            let only = (predictor == Predictor::None).then_some(aspect);
+            let aspects = decompress_aspects::<B>(header, super_pos, count, payload, only)?;
+            (predictor, aspects)
+        }
+        Payload::Constant(block) => (Predictor::None, constant_aspects(header, block, count)),
+    };
+
+    let bytes = if predictor == Predictor::Previous {
+        restored_aspect::<B>(header, bounds, &aspects, aspect)?
+    } else {
+        std::mem::take(&mut aspects[aspect])
+    };
+
+    if bytes.len() != count * N {
+        return Err(DecodeError::InvalidData {
+            super_block: Some(super_pos),
+            aspect: Some(aspect),
+        }
+        .into());
+    }
+
+    Ok(bytes
+        .chunks_exact(N)
+        .map(|chunk| chunk.try_into().unwrap())
+        .collect())
+}
+
+/// Decodes blocks of super-block from `aspects`, restores predicted endpoints
+/// and returns bytes of `aspect` of the restored blocks.
+fn restored_aspect<B>(
+    header: &JackalHeader,
+    bounds: SuperBlockBounds,
+    aspects: &[Vec<u8>; MAX_ASPECTS],
+    aspect: usize,
+) -> Result<Vec<u8>, DecompressError>
+where
+    B: AnyBlock,
+{
+    let width = bounds.width();
+    let mut blocks = vec![B::EMPTY; bounds.count()];
+    let index_of = |x0: u32, y0: u32| (x0 + y0 * width) as usize;
+
+    for (block_aspect, bytes) in aspects.iter().enumerate().take(B::ASPECTS) {
+        decompress_any_block_aspect(bounds, &index_of, &mut blocks, header, block_aspect, bytes)?;
+    }
+    restore_super_block_endpoints(header, bounds, &index_of, &mut blocks);
+
+    let mut bytes = Vec::new();
+    for block in &blocks {
+        // Writing into a vector never fails.
+        let _ = block.compress(aspect, header, &mut bytes);
+    }
+    Ok(bytes)
+}
+
+/// Blocks of one super-block clipped to the extent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct SuperBlockBounds {
+    /// Position of the super-block in super-blocks.
+    pub pos: [u32; 3],
+
+    /// Columns `x_start..x_end` of blocks covered by the super-block.
+    pub x_start: u32,
+    pub x_end: u32,
+
+    /// Rows `y_start..y_end` of blocks covered by the super-block.
+    pub y_start: u32,
+    pub y_end: u32,
+}
+
+impl SuperBlockBounds {
+    /// Returns bounds of super-block at `pos`.
+    ///
+    /// `pos` must be inside [`JackalHeader::jackal_blocks_extent`].
+    fn new(header: &JackalHeader, pos: [u32; 3]) -> Self {
+        let raw_size = header.extent().raw_size();
+        let size = header.super_block_size();
+
+        let x_start = pos[0] * size.width as u32;
+        let y_start = pos[1] * size.height as u32;
+
+        SuperBlockBounds {
+            pos,
+            x_start,
+            x_end: x_start + (raw_size[0] - x_start).min(size.width as u32),
+            y_start,
+            y_end: y_start + (raw_size[1] - y_start).min(size.height as u32),
+        }
+    }
+
+    /// Returns depth slice or array layer of the super-block.
+    pub fn z(&self) -> u32 {
+        self.pos[2]
+    }
+
+    pub fn width(&self) -> u32 {
+        self.x_end - self.x_start
+    }
+
+    pub fn height(&self) -> u32 {
+        self.y_end - self.y_start
+    }
+
+    /// Returns number of blocks in the super-block.
+    pub fn count(&self) -> usize {
+        (self.width() * self.height()) as usize
+    }
+}
+
+/// Returns bounds of the super-block or error if it is outside of the extent.
+pub(crate) fn super_block_bounds(
+    header: &JackalHeader,
+    super_pos: [u32; 3],
+) -> Result<SuperBlockBounds, DecodeError> {
+    let jackal_blocks_extent = header.jackal_blocks_extent();
+    if (0..3).any(|i| super_pos[i] >= jackal_blocks_extent[i]) {
+        return Err(DecodeError::InvalidData {
+            super_block: Some(super_pos),
+            aspect: None,
+        });
+    }
+
+    Ok(SuperBlockBounds::new(header, super_pos))
+}
+
+/// Reads super-block payload and verifies its checksum.
+///
+/// Memory is allocated as payload is read, so corrupted sizes cannot cause huge allocations.
+fn read_payload(
+    header: &JackalHeader,
+    super_pos: [u32; 3],
+    jackal_block: JackalBlock,
+    mut read: impl Read + Seek,
+) -> Result<Vec<u8>, DecompressError> {
+    let invalid = DecodeError::InvalidData {
+        super_block: Some(super_pos),
+        aspect: None,
+    };
+
+    // Payload must end within the stream.
+    let stream_len = read.seek(SeekFrom::End(0))?;
+    if jackal_block.end().is_none_or(|end| end > stream_len) {
+        return Err(invalid.into());
+    }
+
+    read.seek(SeekFrom::Start(jackal_block.offset))?;
+
+    // Reading is limited to the payload, so that corrupted stream
+    // never consumes bytes of the next super-block.
+    let mut payload = Vec::new();
+    read.take(jackal_block.len()).read_to_end(&mut payload)?;
+
+    if payload.len() as u64 != jackal_block.len() {
+        return Err(invalid.into());
+    }
+
+    verify_payload(header, jackal_block, &payload)?;
+    Ok(payload)
+}
+
+/// Returns super-block payload within `data` and verifies its checksum.
+fn slice_payload<'a>(
+    header: &JackalHeader,
+    super_pos: [u32; 3],
+    jackal_block: JackalBlock,
+    data: &'a [u8],
+) -> Result<&'a [u8], DecompressError> {
+    let invalid = DecodeError::InvalidData {
+        super_block: Some(super_pos),
+        aspect: None,
+    };
+
+    let payload = usize::try_from(jackal_block.offset)
+        .ok()
+        .and_then(|offset| data.get(offset..)?.get(..jackal_block.size as usize))
+        .ok_or(invalid)?;
+
+    verify_payload(header, jackal_block, payload)?;
+    Ok(payload)
+}
+
+/// Checks payload against checksum in the super-block table if the header enables checksums.
+fn verify_payload(
+    header: &JackalHeader,
+    jackal_block: JackalBlock,
+    payload: &[u8],
+) -> Result<(), DecompressError> {
+    if header.checksums() && crc::crc32(payload) != jackal_block.checksum {
+        return Err(DecodeError::ChecksumMismatch.into());
+    }
+    Ok(())
+}
+
+/// Decompresses raw bytes of block aspects of super-block with `count` blocks.
+///
+/// If `only` is specified, other aspects are left empty where container allows to skip them.
+/// Aspects may be shorter than expected if payload is truncated,
+/// which is reported when blocks are decoded.
+fn decompress_aspects<B>(
+    header: &JackalHeader,
+    super_pos: [u32; 3],
+    count: usize,
+    payload: &[u8],
+    only: Option<usize>,
+) -> Result<[Vec<u8>; MAX_ASPECTS], DecompressError>
+where
+    B: AnyBlock,
+{
+    debug_assert!(B::ASPECTS <= MAX_ASPECTS);
+
+    let invalid = |aspect| DecodeError::InvalidData {
+        super_block: Some(super_pos),
+        aspect,
+    };
+
+    let mut aspects: [Vec<u8>; MAX_ASPECTS] = Default::default();
+
+    match header.version() {
+        ContainerVersion::V3
+        | ContainerVersion::V4
+        | ContainerVersion::V5
+        | ContainerVersion::V6
+        | ContainerVersion::V7
+        | ContainerVersion::V8
+        | ContainerVersion::V9 => {
+            let mut rest = payload;
+            let mut sizes = [0; MAX_ASPECTS];
+            for size in &mut sizes[..B::ASPECTS] {
+                *size = read_varint(&mut rest)
+                    .and_then(|size| usize::try_from(size).ok())
+                    .ok_or(invalid(None))?;
+            }
+
+            for aspect in 0..B::ASPECTS {
+                if sizes[aspect] > rest.len() {
+                    return Err(invalid(Some(aspect)).into());
+                }
+                let (stream, tail) = rest.split_at(sizes[aspect]);
+                rest = tail;
+
+                if only.is_some_and(|only| only != aspect) {
+                    continue;
+                }
+
+                aspects[aspect] =
+                    decompress_aspect_stream::<B>(header, super_pos, count, aspect, stream)?;
+            }
+        }
+        ContainerVersion::V2 | ContainerVersion::Legacy => {
+            // All aspects are in one stream, one after another.
+            let last = only.unwrap_or(B::ASPECTS - 1);
+            let expected: usize = (0..=last)
+                .map(|aspect| B::aspect_bytes(header, aspect) * count)
+                .sum();
+
+            let mut bytes = Vec::new();
+            let result = brotli::reader::Decompressor::new(payload, 4096)
+                .take(expected as u64)
+                .read_to_end(&mut bytes);
+
+            // Bytes decoded before an error are kept, so that error refers to the aspect.
+            if let Err(err) = result {
+                if err.kind() != std::io::ErrorKind::UnexpectedEof {
+                    return Err(err.into());
+                }
+            }
+
+            let mut rest = &bytes[..];
+            for (aspect, bytes) in aspects.iter_mut().enumerate().take(last + 1) {
+                let size = (B::aspect_bytes(header, aspect) * count).min(rest.len());
+                let (head, tail) = rest.split_at(size);
+                rest = tail;
+
+                if only.is_none_or(|only| only == aspect) {
+                    *bytes = head.to_vec();
+                }
+            }
+        }
+    }
+
+    Ok(aspects)
+}
+
+/// Decompresses super-block `payload` into `blocks` of the whole texture in `layout` order.
+fn decompress_any_block<B>(
+    header: &JackalHeader,
+    super_pos: [u32; 3],
+    layout: BlockLayout,
+    base: usize,
+    blocks: &mut [B],
+    payload: &[u8],
+) -> Result<(), DecompressError>
+where
+    B: AnyBlock,
+{
+    if header.checked_blocks_count() != Some(blocks.len()) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "blocks count does not match extent",
+        )
+        .into());
+    }
+
+    let raw_size = header.extent().raw_size();
+    let bounds = super_block_bounds(header, super_pos)?;
+
+    debug_assert!(bounds.width() <= u16::MAX as u32);
+    debug_assert!(bounds.height() <= u16::MAX as u32);
+
+    let curve = BoundZCurve::new(bounds.width() as u16, bounds.height() as u16);
+    let index_of = |x0: u32, y0: u32| match layout {
+        BlockLayout::RowMajor => texture::block_index(
+            raw_size,
+            bounds.x_start + x0,
+            bounds.y_start + y0,
+            bounds.z(),
+        ),
+        BlockLayout::ZCurvePerSuperBlock => base + curve.index_of(x0 as u16, y0 as u16) as usize,
+    };
+
+    decompress_super_block_with(header, super_pos, payload, blocks, index_of)
+}
+
+/// Decompresses blocks of one super-block into `blocks` row by row.
+///
+/// `blocks` must hold exactly the blocks of the super-block clipped to the extent.
+#[cfg(feature = "gpu")]
+pub(crate) fn decompress_super_block<B>(
+    header: &JackalHeader,
+    super_pos: [u32; 3],
+    jackal_block: JackalBlock,
+    blocks: &mut [B],
+    read: impl Read + Seek,
+) -> Result<(), DecompressError>
+where
+    B: AnyBlock,
+{
+    let bounds = super_block_bounds(header, super_pos)?;
+    let width = bounds.width();
+
+    if blocks.len() != bounds.count() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "blocks count does not match super-block",
+        )
+        .into());
+    }
+
+    let payload = read_payload(header, super_pos, jackal_block, read)?;
+    decompress_super_block_with(header, super_pos, &payload, blocks, |x0, y0| {
+        (x0 + y0 * width) as usize
+    })
+}
+
+/// Decompresses blocks of one super-block from its verified payload.
+///
+/// `index_of` maps position of a block inside the super-block to its index in `blocks`.
+fn decompress_super_block_with<B>(
+    header: &JackalHeader,
+    super_pos: [u32; 3],
+    payload: &[u8],
+    blocks: &mut [B],
+    index_of: impl Fn(u32, u32) -> usize,
+) -> Result<(), DecompressError>
+where
+    B: AnyBlock,
+{
+    let bounds = super_block_bounds(header, super_pos)?;
+    let width = bounds.width();
+    let count = bounds.count();
+
+    let (predictor, payload) = match parse_payload::<B>(header, super_pos, payload)? {
+        Payload::Aspects(predictor, payload) => (predictor, payload),
+        Payload::Constant(block) => {
+            // Blocks are stored as is, no aspects to decode and no endpoints to restore.
+            for index in 0..width * bounds.height() {
+                blocks[index_of(index % width, index / width)] = block;
+            }
+            return Ok(());
+        }
+    };
+
+    // let mut decoder = lzw::Decoder::<B::EncoderElement>::new();
+    // let mut read = ReadBits::new(read);
+    let aspects = decompress_aspects::<B>(header, super_pos, count, payload, None)?;
+
+    for (aspect, bytes) in aspects.iter().enumerate().take(B::ASPECTS) {
+        decompress_any_block_aspect(bounds, &index_of, blocks, header, aspect, bytes)?;
+    }
+
+    let header = header.with_predictor(predictor);
+    restore_super_block_endpoints(&header, bounds, &index_of, blocks);
+    Ok(())
+}
+
+/// Restores endpoints of super-block blocks replaced by the header's predictor.
+fn restore_super_block_endpoints<B>(
+    header: &JackalHeader,
+    bounds: SuperBlockBounds,
+    index_of: &impl Fn(u32, u32) -> usize,
+    blocks: &mut [B],
+) where
+    B: AnyBlock,
+{
+    let width = bounds.width();
+    let height = bounds.height();
+
+    if header.predictor() == Predictor::Previous {
+        // Blocks are restored in the same order they were predicted in.
+        let mut previous = B::EMPTY;
+        for index in 0..width * height {
+            let block = &mut blocks[index_of(index % width, index / width)];
+            block.restore_endpoints(&previous);
+            previous = *block;
+        }
+    }
+}
+
+/// Decompresses separate stream of one aspect of super-block with `count` blocks.
+///
+/// Empty stream decodes into empty aspect.
+fn decompress_aspect_stream<B>(
+    header: &JackalHeader,
+    super_pos: [u32; 3],
+    count: usize,
+    aspect: usize,
+    stream: &[u8],
+) -> Result<Vec<u8>, DecodeError>
+where
+    B: AnyBlock,
+{
+    let invalid = DecodeError::InvalidData {
+        super_block: Some(super_pos),
+        aspect: Some(aspect),
+    };
+
+    let mut bytes = Vec::new();
+    if stream.is_empty() {
+        return Ok(bytes);
+    }
+
+    // Limit output to detect streams that decode into more bytes than expected.
+    let expected = B::aspect_bytes(header, aspect) * count;
+    brotli::reader::Decompressor::new(stream, 4096)
+        .take(expected as u64 + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|_| invalid)?;
+
+    if bytes.len() > expected {
+        return Err(invalid);
+    }
+
+    Ok(bytes)
+}
+
+/// Decodes one aspect of blocks of a super-block.
+///
+/// `index_of` maps position of a block inside the super-block to its index in `blocks`.
+fn decompress_any_block_aspect<B>(
+    bounds: SuperBlockBounds,
+    index_of: &impl Fn(u32, u32) -> usize,
+    blocks: &mut [B],
+    header: &JackalHeader,
+    aspect: usize,
+    mut decoder: &[u8],
+) -> Result<(), DecompressError>
+where
+    B: AnyBlock,
+{
+    debug_assert!(aspect < B::ASPECTS);
+
+    let width = bounds.width();
+    let height = bounds.height();
+
+    // let bound_curve = BoundZCurve::new(width as u16, height as u16);
+    let bound_curve = (0..height * width).map(|index| {
+        let x = index % width;
+        let y = index / width;
+        (x, y)
+    });
+
+    for (x0, y0) in bound_curve {
+        let index = index_of(x0, y0);
+        let mut block = blocks[index];
+
+        block
+            .decompress(aspect, header, &mut decoder)
+            .map_err(|err| match err {
+                DecompressError::Io(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                    DecodeError::InvalidData {
+                        super_block: Some(bounds.pos),
+                        aspect: Some(aspect),
+                    }
+                    .into()
+                }
+                err => err,
+            })?;
+
+        blocks[index] = block;
+    }
+
+    Ok(())
+}
+
+/// Decompresses BC1 texture from container of any version.
+///
+/// Extents exceeding [`DecodeLimits::default`] are rejected before allocation.
+/// Blocks are returned in the order accepted by [`compress_bc1_texture`].
+///
+/// Benchmarked with `cargo bench --bench jackal`.
+pub fn decompress_bc1_texture(
+    read: impl Read + Seek,
+) -> Result<(Extent, Vec<bc1::Block>), DecompressError> {
+    decompress_bc1_texture_with_limits(read, &DecodeLimits::default())
+}
+
+/// Decompresses BC1 texture rejecting extents that exceed `limits`.
+pub fn decompress_bc1_texture_with_limits(
+    read: impl Read + Seek,
+    limits: &DecodeLimits,
+) -> Result<(Extent, Vec<bc1::Block>), DecompressError> {
+    decompress_texture(
+        read,
+        &DecompressOptions {
+            limits: *limits,
+            verify: false,
+        },
+    )
+}
+
+/// Decompresses BC1 texture with specified options.
+pub fn decompress_bc1_texture_opts(
+    read: impl Read + Seek,
+    options: &DecompressOptions,
+) -> Result<(Extent, Vec<bc1::Block>), DecompressError> {
+    decompress_texture(read, options)
+}
+
+/// Decompresses BC1 texture calling `progress` after each super-block.
+///
+/// When `progress` returns [`ControlFlow::Break`] decompression stops before the next super-block
+/// and [`DecodeError::Cancelled`] is returned.
+pub fn decompress_bc1_texture_with_progress(
+    mut read: impl Read + Seek,
+    mut progress: impl FnMut(Progress) -> ControlFlow<()>,
+) -> Result<(Extent, Vec<bc1::Block>), DecompressError> {
+    let (header, jackal_blocks) = read_container(&mut read, &DecodeLimits::default())?;
+
+    if header.format() != bc1::Block::FORMAT {
+        return Err(DecompressError::from(DecodeError::Unsupported).at(DecodeStage::Header));
+    }
+
+    let (blocks, _) = decompress_blocks_with_progress(
+        &header,
+        &jackal_blocks,
+        BlockLayout::RowMajor,
+        read,
+        &mut progress,
+    )?;
+    Ok((header.extent(), blocks))
+}
+
+/// Decompresses BC1 texture stored in memory, e.g. memory-mapped file.
+///
+/// Payloads are decoded in place without seeking or copying.
+/// Super-blocks with payloads outside of `data` are rejected with [`DecodeError::InvalidData`].
+pub fn decompress_bc1_texture_from_slice(
+    data: &[u8],
+) -> Result<(Extent, Vec<bc1::Block>), DecompressError> {
+    let (header, jackal_blocks) =
+        read_container(std::io::Cursor::new(data), &DecodeLimits::default())?;
+
+    if header.format() != bc1::Block::FORMAT {
+        return Err(DecompressError::from(DecodeError::Unsupported).at(DecodeStage::Header));
+    }
+
+    let (blocks, _) = decompress_blocks_with(
+        &header,
+        &jackal_blocks,
+        BlockLayout::RowMajor,
+        |super_pos, jackal_block| {
+            slice_payload(&header, super_pos, jackal_block, data).map(Cow::Borrowed)
+        },
+        &mut |_| ControlFlow::Continue(()),
+    )?;
+    Ok((header.extent(), blocks))
+}
+
+/// Outcome of decoding one super-block by [`decompress_bc1_texture_partial`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SuperBlockStatus {
+    /// Super-block is decoded.
+    Ok,
+
+    /// Table entry or payload could not be read, e.g. stream is truncated.
+    IoError,
+
+    /// Payload is read but cannot be decoded.
+    Corrupt,
+}
+
+/// Decompresses as much of BC1 texture as can be read, e.g. from interrupted download.
+///
+/// Every super-block is decoded independently and blocks of those that fail are set to `fill`.
+/// Returns status of each super-block in the order of the super-block table.
+/// Fails only if header cannot be read or texture is not BC1.
+pub fn decompress_bc1_texture_partial(
+    mut read: impl Read + Seek,
+    fill: bc1::Block,
+) -> Result<(Extent, Vec<bc1::Block>, Vec<SuperBlockStatus>), DecompressError> {
+    let header = read_header_with_limits(&mut read, &DecodeLimits::default())
+        .map_err(|err| err.at(DecodeStage::Header))?;
+
+    if header.format() != bc1::Block::FORMAT {
+        return Err(DecompressError::from(DecodeError::Unsupported).at(DecodeStage::Header));
+    }
+
+    let jackal_blocks = read_partial_jackal_blocks(&header, &mut read);
+    let stream_len = read.seek(SeekFrom::End(0)).ok();
+
+    let mut blocks = vec![fill; header.blocks_count()];
+    let mut statuses = vec![SuperBlockStatus::IoError; header.jackal_blocks_count()];
+
+    let raw_size = header.extent().raw_size();
+    let jackal_blocks_extent = header.jackal_blocks_extent();
+
+    for z in 0..jackal_blocks_extent[2] {
+        for y in 0..jackal_blocks_extent[1] {
+            for x in 0..jackal_blocks_extent[0] {
+                let index = texture::block_index(jackal_blocks_extent, x, y, z);
+                let Some(&jackal_block) = jackal_blocks.get(index) else {
+                    continue;
+                };
+
+                statuses[index] = match (jackal_block.end(), stream_len) {
+                    (None, _) => SuperBlockStatus::Corrupt,
+                    (Some(end), Some(len)) if end <= len => {
+                        // Payload is read after seeking to its offset,
+                        // so failure of one super-block does not affect the next.
+                        let result = read_payload(&header, [x, y, z], jackal_block, &mut read)
+                            .and_then(|payload| {
+                                decompress_any_block(
+                                    &header,
+                                    [x, y, z],
+                                    BlockLayout::RowMajor,
+                                    0,
+                                    &mut blocks,
+                                    &payload,
+                                )
+                            });
+
+                        match result {
+                            Ok(()) => SuperBlockStatus::Ok,
+                            Err(DecompressError::Io(_)) => SuperBlockStatus::IoError,
+                            Err(_) => SuperBlockStatus::Corrupt,
+                        }
+                    }
+                    _ => SuperBlockStatus::IoError,
+                };
+
+                if statuses[index] != SuperBlockStatus::Ok {
+                    // Failed super-block may be decoded in part.
+                    let bounds = super_block_bounds(&header, [x, y, z])?;
+                    for y in bounds.y_start..bounds.y_end {
+                        for x in bounds.x_start..bounds.x_end {
+                            blocks[texture::block_index(raw_size, x, y, z)] = fill;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok((header.extent(), blocks, statuses))
+}
+
+/// Reads entries of the super-block table until it ends or the stream is cut short.
+///
+/// Legacy table is either read whole or not at all, as sizes depend on the next entries.
+fn read_partial_jackal_blocks(
+    header: &JackalHeader,
+    mut read: impl Read + Seek,
+) -> Vec<JackalBlock> {
+    if header.version() == ContainerVersion::Legacy {
+        return read_jackal_blocks_vec(header, read).unwrap_or_default();
+    }
+
+    let mut jackal_blocks = Vec::new();
+    if seek_jackal_blocks(header, &mut read).is_err() {
+        return jackal_blocks;
+    }
+
+    while jackal_blocks.len() < header.jackal_blocks_count() {
+        match JackalBlock::read_from(&mut read) {
+            Ok(jackal_block) => jackal_blocks.push(jackal_block),
+            Err(_) => break,
+        }
+    }
+    jackal_blocks
+}
+
+/// Order of blocks returned by [`decompress_bc1_texture_layout`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlockLayout {
+    /// Row-major order of the whole texture, same as [`decompress_bc1_texture`].
+    #[default]
+    RowMajor,
+
+    /// Super-blocks one after another in table order, x fastest, then y, then z.
+    /// Blocks of each super-block follow [`BoundZCurve`] over the super-block
+    /// clipped to the extent.
+    ///
+    /// See [`z_curve_block_index`] for the mapping from block position.
+    ZCurvePerSuperBlock,
+}
+
+/// Decompresses BC1 texture with blocks in the `layout` order.
+///
+/// Also returns index of the first block of each super-block in table order.
+/// Indices are returned only for [`BlockLayout::ZCurvePerSuperBlock`].
+pub fn decompress_bc1_texture_layout(
+    mut read: impl Read + Seek,
+    layout: BlockLayout,
+) -> Result<(Extent, Vec<bc1::Block>, Vec<usize>), DecompressError> {
+    let (header, jackal_blocks) = read_container(&mut read, &DecodeLimits::default())?;
+
+    if header.format() != Format::BC1 {
+        return Err(DecompressError::from(DecodeError::Unsupported).at(DecodeStage::Header));
+    }
+
+    let (blocks, bases) = decompress_blocks(&header, &jackal_blocks, layout, read)?;
+    Ok((header.extent(), blocks, bases))
+}
+
+/// Returns index of the block at `(x, y, z)` in blocks decoded
+/// with [`BlockLayout::ZCurvePerSuperBlock`].
+///
+/// `super_block_size` is taken from the header of the texture.
+/// Position of the super-block and inside of it are computed with divisions,
+/// so the mapping is cheap to evaluate in shaders:
+///
+/// ```text
+/// x0 = x / sw * sw, y0 = y / sh * sh
+/// w = min(sw, width - x0), h = min(sh, height - y0)
+/// base = z * width * height + y0 * width + x0 * h
+/// index = base + BoundZCurve(w, h).index_of(x - x0, y - y0)
+/// ```
+///
+/// # Panics
+///
+/// Panics if position is outside of the extent.
+pub fn z_curve_block_index(
+    extent: Extent,
+    super_block_size: SuperBlockSize,
+    x: u32,
+    y: u32,
+    z: u32,
+) -> usize {
+    let [width, height, depth] = extent.raw_size();
+    assert!(
+        x < width && y < height && z < depth,
+        "Block ({x}, {y}, {z}) is out of bounds"
+    );
+
+    let sw = super_block_size.width as u32;
+    let sh = super_block_size.height as u32;
+
+    let x0 = x / sw * sw;
+    let y0 = y / sh * sh;
+    let w = sw.min(width - x0);
+    let h = sh.min(height - y0);
+
+    let base = texture::block_index([width, height, depth], 0, y0, z) + x0 as usize * h as usize;
+    let local = BoundZCurve::new(w as u16, h as u16).index_of((x - x0) as u16, (y - y0) as u16);
+    base + local as usize
+}
+
+/// Compresses BC2 blocks into Jackal stream.
+///
+/// Stream is written in [`ContainerVersion::V9`] container.
+///
+/// `extent` is measured in blocks.
+pub fn compress_bc2_texture(
+    extent: Extent,
+    blocks: &[bc2::Block],
+    write: impl Write + Seek,
+) -> std::io::Result<()> {
+    compress_texture(extent, blocks, CompressOptions::default(), write)?;
+    Ok(())
+}
+
+/// Compresses BC2 blocks into Jackal stream with specified options.
+///
+/// `extent` is measured in blocks.
+pub fn compress_bc2_texture_opts(
+    extent: Extent,
+    blocks: &[bc2::Block],
+    options: CompressOptions,
+    write: impl Write + Seek,
+) -> std::io::Result<()> {
+    compress_texture(extent, blocks, options, write)?;
+    Ok(())
+}
+
+/// Decompresses BC2 texture from container of any version.
+///
+/// Extents exceeding [`DecodeLimits::default`] are rejected before allocation.
+pub fn decompress_bc2_texture(
+    read: impl Read + Seek,
+) -> Result<(Extent, Vec<bc2::Block>), DecompressError> {
+    decompress_texture(read, &DecompressOptions::default())
+}
+
+/// Compresses BC3 blocks into Jackal stream.
+///
+/// Stream is written in [`ContainerVersion::V9`] container.
+///
+/// `extent` is measured in blocks.
+pub fn compress_bc3_texture(
+    extent: Extent,
+    blocks: &[bc3::Block],
+    write: impl Write + Seek,
+) -> std::io::Result<()> {
+    compress_texture(extent, blocks, CompressOptions::default(), write)?;
+    Ok(())
+}
+
+/// Compresses BC3 blocks into Jackal stream with specified options.
+///
+/// `extent` is measured in blocks.
+pub fn compress_bc3_texture_opts(
+    extent: Extent,
+    blocks: &[bc3::Block],
+    options: CompressOptions,
+    write: impl Write + Seek,
+) -> std::io::Result<()> {
+    compress_texture(extent, blocks, options, write)?;
+    Ok(())
+}
+
+/// Decompresses BC3 texture from container of any version.
+///
+/// Extents exceeding [`DecodeLimits::default`] are rejected before allocation.
+pub fn decompress_bc3_texture(
+    read: impl Read + Seek,
+) -> Result<(Extent, Vec<bc3::Block>), DecompressError> {
+    decompress_texture(read, &DecompressOptions::default())
+}
+
+/// Compresses block texture of any supported format into Jackal stream.
+pub fn compress_block_texture<B>(
+    texture: &BlockTexture<B>,
+    options: CompressOptions,
+    write: impl Write + Seek,
+) -> std::io::Result<()>
+where
+    B: AnyBlock,
+{
+    compress_texture(texture.extent(), texture.as_slice(), options, write)?;
+    Ok(())
+}
+
+/// Decompresses Jackal stream into block texture.
+///
+/// Streams of format other than `B`'s are rejected as unsupported.
+pub fn decompress_block_texture<B>(
+    read: impl Read + Seek,
+    limits: &DecodeLimits,
+) -> Result<BlockTexture<B>, DecompressError>
+where
+    B: AnyBlock,
+{
+    let options = DecompressOptions {
+        limits: *limits,
+        verify: false,
+    };
+    let (extent, blocks) = decompress_texture(read, &options)?;
+    BlockTexture::new(extent, blocks)
+        .map_err(|err| DecompressError::from(err).at(DecodeStage::Header))
+}
+
+/// Decompresses all super-blocks of the texture.
+///
+/// Streams of other formats are rejected as unsupported.
+fn decompress_texture<B>(
+    mut read: impl Read + Seek,
+    options: &DecompressOptions,
+) -> Result<(Extent, Vec<B>), DecompressError>
+where
+    B: AnyBlock,
+{
+    let (header, jackal_blocks) = read_container(&mut read, &options.limits)?;
+
+    if header.format() != B::FORMAT {
+        return Err(DecompressError::from(DecodeError::Unsupported).at(DecodeStage::Header));
+    }
+
+    let (blocks, _) = decompress_blocks(&header, &jackal_blocks, BlockLayout::RowMajor, read)?;
+
+    if options.verify {
+        if let Some(content_hash) = header.content_hash() {
+            if hash::hash_any_blocks(header.extent(), &blocks) != content_hash {
+                return Err(DecodeError::ContentHashMismatch.into());
+            }
+        }
+    }
+
+    Ok((header.extent(), blocks))
+}
+
+/// Decompresses all super-blocks listed in the table.
+///
+/// Returns blocks in `layout` order and index of the first block of each super-block.
+/// For [`BlockLayout::RowMajor`] no indices are returned.
+fn decompress_blocks<B>(
+    header: &JackalHeader,
+    jackal_blocks: &[JackalBlock],
+    layout: BlockLayout,
+    read: impl Read + Seek,
+) -> Result<(Vec<B>, Vec<usize>), DecompressError>
+where
+    B: AnyBlock,
+{
+    decompress_blocks_with_progress(header, jackal_blocks, layout, read, &mut |_| {
+        ControlFlow::Continue(())
+    })
+}
+
+/// Decompresses all super-blocks listed in the table calling `progress` after each super-block.
+fn decompress_blocks_with_progress<B>(
+    header: &JackalHeader,
+    jackal_blocks: &[JackalBlock],
+    layout: BlockLayout,
+    mut read: impl Read + Seek,
+    progress: &mut dyn FnMut(Progress) -> ControlFlow<()>,
+) -> Result<(Vec<B>, Vec<usize>), DecompressError>
+where
+    B: AnyBlock,
+{
+    decompress_blocks_with(
+        header,
+        jackal_blocks,
+        layout,
+        |super_pos, jackal_block| {
+            read_payload(header, super_pos, jackal_block, &mut read).map(Cow::Owned)
+        },
+        progress,
+    )
+}
+
+/// Decompresses all super-blocks listed in the table with payloads returned by `payload_of`.
+fn decompress_blocks_with<'a, B>(
+    header: &JackalHeader,
+    jackal_blocks: &[JackalBlock],
+    layout: BlockLayout,
+    mut payload_of: impl FnMut([u32; 3], JackalBlock) -> Result<Cow<'a, [u8]>, DecompressError>,
+    progress: &mut dyn FnMut(Progress) -> ControlFlow<()>,
+) -> Result<(Vec<B>, Vec<usize>), DecompressError>
+where
+    B: AnyBlock,
+{
+    let mut blocks = vec![B::EMPTY; header.blocks_count()];
+    let mut bases = Vec::new();
+    let mut base = 0;
+    let mut blocks_done = 0;
+    let mut bytes = 0;
+
+    let jackal_blocks_extent = header.jackal_blocks_extent();
+
+    for z in 0..jackal_blocks_extent[2] {
+        for y in 0..jackal_blocks_extent[1] {
+            for x in 0..jackal_blocks_extent[0] {
+                let index = texture::block_index(jackal_blocks_extent, x, y, z);
+
+                payload_of([x, y, z], jackal_blocks[index])
+                    .and_then(|payload| {
+                        decompress_any_block(header, [x, y, z], layout, base, &mut blocks, &payload)
+                    })
+                    .map_err(|err| err.at(DecodeStage::SuperBlock(index)))?;
+
+                let count = super_block_bounds(header, [x, y, z])?.count();
+
+                if layout == BlockLayout::ZCurvePerSuperBlock {
+                    bases.push(base);
+                    base += count;
+                }
+
+                blocks_done += count;
+                bytes += jackal_blocks[index].size as u64;
+
+                let flow = progress(Progress {
+                    super_blocks_done: index + 1,
+                    super_blocks_total: jackal_blocks.len(),
+                    blocks_done,
+                    bytes,
+                });
+                if flow.is_break() {
+                    return Err(DecompressError::from(DecodeError::Cancelled)
+                        .at(DecodeStage::SuperBlock(index)));
+                }
+            }
+        }
+    }
+
+    Ok((blocks, bases))
+}
+
+/// Decompresses 2D texture of any supported format straight into RGB8 pixels.
+///
+/// Returns width, height and row-major pixels of the top mip level.
+/// Jackal stores extent in blocks, so pixels are cropped to the size
+/// recorded in [`MetaChunk::pixel_size`] chunk, as written by [`Encoder`].
+/// Without the chunk the size is a multiple of 4 covering the whole block grid.
+/// Alpha is discarded.
+///
+/// [`Encoder`]: crate::encoder::Encoder
+pub fn decode_to_rgb8(
+    mut read: impl Read + Seek,
+) -> Result<(u32, u32, Vec<Rgb8U>), DecompressError> {
+    let start = read.stream_position()?;
+    let (header, jackal_blocks) = read_container(&mut read, &DecodeLimits::default())?;
+
+    let Extent::D2 { width, height } = header.extent() else {
+        return Err(DecompressError::from(DecodeError::Unsupported).at(DecodeStage::Header));
+    };
+
+    read.seek(SeekFrom::Start(start + header.bytes_size() as u64))?;
+    let chunks = read_meta_chunks(&mut read, &header).map_err(|err| err.at(DecodeStage::Header))?;
+
+    let (width, height) = match chunks.iter().find_map(MetaChunk::as_pixel_size) {
+        // Pixel size must be covered by the block grid exactly.
+        Some((pixel_width, pixel_height))
+            if pixel_width.div_ceil(4) == width && pixel_height.div_ceil(4) == height =>
+        {
+            (pixel_width, pixel_height)
+        }
+        Some(_) => {
+            return Err(DecompressError::from(DecodeError::INVALID_DATA).at(DecodeStage::Header));
+        }
+        None => (width * 4, height * 4),
+    };
+    let pixel_extent = Extent::D2 { width, height };
+
+    let pixels = match header.format() {
+        Format::BC1 => {
+            let (blocks, _) = decompress_blocks::<bc1::Block>(
+                &header,
+                &jackal_blocks,
+                BlockLayout::RowMajor,
+                read,
+            )?;
+            decode_image(pixel_extent, &blocks, |block| block.decode_rgb8())
+        }
+        Format::BC2 => {
+            let (blocks, _) = decompress_blocks::<bc2::Block>(
+                &header,
+                &jackal_blocks,
+                BlockLayout::RowMajor,
+                read,
+            )?;
+            decode_image(pixel_extent, &blocks, |block| block.decode_rgb8())
+        }
+        Format::BC3 => {
+            let (blocks, _) = decompress_blocks::<bc3::Block>(
+                &header,
+                &jackal_blocks,
+                BlockLayout::RowMajor,
+                read,
+            )?;
+            decode_image(pixel_extent, &blocks, |block| block.decode_rgb8())
+        }
+        _ => {
+            return Err(DecompressError::from(DecodeError::Unsupported).at(DecodeStage::Header));
+        }
+    };
+
+    Ok((width, height, pixels))
+}
+
+#[test]
+fn roundtrip() {
+    use crate::math::Rgb32F;
+
+    let pixels = [
+        [Rgb32F::BLACK, Rgb32F::WHITE, Rgb32F::BLACK, Rgb32F::WHITE],
+        [Rgb32F::WHITE, Rgb32F::BLACK, Rgb32F::WHITE, Rgb32F::BLACK],
+        [Rgb32F::BLACK, Rgb32F::WHITE, Rgb32F::BLACK, Rgb32F::WHITE],
+        [Rgb32F::WHITE, Rgb32F::BLACK, Rgb32F::WHITE, Rgb32F::BLACK],
+    ];
+
+    let block = bc1::Block::encode(pixels);
+
+    assert_eq!(block.decode(), pixels);
+
+    let blocks = vec![block; 2];
+
+    // eprintln!("\n\nCompress");
+
+    let mut output = Vec::new();
+    compress_bc1_texture(
+        Extent::D2 {
+            width: 2,
+            height: 1,
+        },
+        &blocks,
+        std::io::Cursor::new(&mut output),
+    )
+    .unwrap();
+
+    // eprintln!("\n\nDecompress");
+
+    let (extent, decompressed) = decompress_bc1_texture(std::io::Cursor::new(&output)).unwrap();
+
+    assert_eq!(
+        extent,
+        Extent::D2 {
+            width: 2,
+            height: 1,
+        }
+    );
+
+    assert_eq!(decompressed[..], blocks[..]);
+}
+
+#[cfg(test)]
+fn checkerboard_blocks() -> Vec<bc1::Block> {
+    (0..6 * 5)
+        .map(|i| bc1::Block {
+            color0: crate::math::Rgb565::from_bits(0xF800 | i as u16),
+            color1: crate::math::Rgb565::from_bits(0x001F),
+            texels: [0x1B, 0xE4, (i * 7) as u8, 0x55],
+        })
+        .collect()
+}
+
+#[test]
+fn checksum_mismatch() {
+    let extent = Extent::D2 {
+        width: 6,
+        height: 5,
+    };
+    let blocks = checkerboard_blocks();
+
+    let mut output = Vec::new();
+    compress_bc1_texture(extent, &blocks, std::io::Cursor::new(&mut output)).unwrap();
+
+    // Flip one bit in the last byte of the payload.
+    let last = output.len() - 1;
+    output[last] ^= 0x10;
+
+    let err = decompress_bc1_texture(std::io::Cursor::new(&output)).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::ChecksumMismatch));
+    assert_eq!(err.stage(), Some(DecodeStage::SuperBlock(0)));
+}
+
+#[test]
+fn checksums_disabled() {
+    let extent = Extent::D2 {
+        width: 6,
+        height: 5,
+    };
+    let blocks = checkerboard_blocks();
+
+    let mut output = Vec::new();
+    compress_bc1_texture_opts(
+        extent,
+        &blocks,
+        CompressOptions {
+            checksums: false,
+            ..CompressOptions::default()
+        },
+        std::io::Cursor::new(&mut output),
+    )
+    .unwrap();
+
+    let header = read_header(&output[..]).unwrap();
+    assert!(!header.checksums());
+
+    let (_, decompressed) = decompress_bc1_texture(std::io::Cursor::new(&output)).unwrap();
+    assert_eq!(decompressed, blocks);
+}
+
+#[test]
+fn interleaved_colors() {
+    use crate::math::Rgb565;
+
+    let extent = Extent::D2 {
+        width: 64,
+        height: 64,
+    };
+
+    // Smooth gradient where neighbor colors differ in low bits only.
+    let blocks: Vec<bc1::Block> = (0..64 * 64)
+        .map(|i| {
+            let (x, y) = (i % 64, i / 64);
+            bc1::Block {
+                color0: Rgb565::new((x / 2) as u8, y as u8, ((x + y) / 4) as u8),
+                color1: Rgb565::new((y / 2) as u8, x as u8, ((x + y) / 4) as u8),
+                texels: [0x1B; 4],
+            }
+        })
+        .collect();
+
+    let compress = |interleaved_colors| {
+        let mut output = Vec::new();
+        compress_bc1_texture_opts(
+            extent,
+            &blocks,
+            CompressOptions {
+                interleaved_colors,
+                ..CompressOptions::default()
+            },
+            std::io::Cursor::new(&mut output),
+        )
+        .unwrap();
+        output
+    };
+
+    let plain = compress(false);
+    let interleaved = compress(true);
+
+    assert!(interleaved.len() < plain.len());
+
+    assert!(read_header(&interleaved[..]).unwrap().interleaved_colors());
+
+    let (_, decompressed) = decompress_bc1_texture(std::io::Cursor::new(&interleaved)).unwrap();
+    assert_eq!(decompressed, blocks);
+}
+
+#[test]
+fn error_display() {
+    use std::error::Error;
+
+    let extent = Extent::D2 {
+        width: 6,
+        height: 5,
+    };
+    let blocks = checkerboard_blocks();
+
+    let mut output = Vec::new();
+    compress_bc1_texture(extent, &blocks, std::io::Cursor::new(&mut output)).unwrap();
+
+    let err = decompress_bc1_texture(std::io::Cursor::new(&output[..10])).unwrap_err();
+    assert!(err.to_string().starts_with("header: I/O error:"));
+    assert!(err.source().unwrap().source().is_some());
+
+    let err = decompress_bc1_texture(std::io::Cursor::new(&output[..JackalHeader::BYTES_SIZE]))
+        .unwrap_err();
+    assert!(err.to_string().starts_with("block table: I/O error:"));
+
+    let mut corrupted = output.clone();
+    corrupted[0] = 0;
+    let err = decompress_bc1_texture(std::io::Cursor::new(&corrupted)).unwrap_err();
+    assert_eq!(err.to_string(), "header: invalid magic number");
+
+    let last = output.len() - 1;
+    output[last] ^= 0x10;
+    let err = decompress_bc1_texture(std::io::Cursor::new(&output)).unwrap_err();
+    assert_eq!(err.to_string(), "super-block 0: checksum mismatch");
+
+    let err = DecodeError::InvalidData {
+        super_block: Some([1, 2, 0]),
+        aspect: Some(2),
+    };
+    assert_eq!(
+        err.to_string(),
+        "invalid data in super-block [1, 2, 0] at aspect 2"
+    );
+}
+
+#[test]
+fn compress_report() {
+    let extent = Extent::D3 {
+        width: 70,
+        height: 40,
+        depth: 2,
+    };
+
+    let blocks: Vec<bc1::Block> = (0..70 * 40 * 2)
+        .map(|i: u32| bc1::Block {
+            color0: crate::math::Rgb565::from_bits((i * 37) as u16),
+            color1: crate::math::Rgb565::from_bits((i / 70) as u16),
+            texels: [(i % 7) as u8, 0xE4, (i % 3) as u8, 0x55],
+        })
+        .collect();
+
+    let mut output = Vec::new();
+    let report = compress_bc1_texture_report(
+        extent,
+        &blocks,
+        CompressOptions {
+            super_block_size: SuperBlockChoice::Fixed(SuperBlockSize {
+                width: 32,
+                height: 32,
+            }),
+            ..CompressOptions::default()
+        },
+        std::io::Cursor::new(&mut output),
+    )
+    .unwrap();
+
+    // 3 x 2 super-blocks per slice, last column and row are clipped.
+    assert_eq!(report.super_blocks.len(), 12);
+    assert_eq!(report.total_blocks(), blocks.len() as u64);
+    assert_eq!(report.super_blocks[0].blocks, 32 * 32);
+    assert_eq!(report.super_blocks[2].blocks, 6 * 32);
+    assert_eq!(report.super_blocks[5].position, [2, 1, 0]);
+    assert_eq!(report.super_blocks[5].blocks, 6 * 8);
+    assert_eq!(report.super_blocks[11].position, [2, 1, 1]);
+
+    let header_size = JackalHeader::BYTES_SIZE
+        + meta_chunks_bytes_size(ContainerVersion::V5, &[])
+        + JackalBlock::BYTES_SIZE * 12;
+    assert_eq!(
+        report.total_compressed_size(),
+        (output.len() - header_size) as u64
+    );
+
+    assert_eq!(stat_file(std::io::Cursor::new(&output)).unwrap(), report);
+
+    // Truncated stream cannot hold the last payload.
+    output.pop();
+    let err = stat_file(std::io::Cursor::new(&output)).unwrap_err();
+    assert_eq!(err.stage(), Some(DecodeStage::SuperBlock(11)));
+    assert_eq!(
+        err.decode_error(),
+        Some(DecodeError::InvalidData {
+            super_block: Some([2, 1, 1]),
+            aspect: None,
+        })
+    );
+}
+
+/// Compresses BC1 blocks into container of specified version.
+#[cfg(test)]
+fn compress_with_version(
+    version: ContainerVersion,
+    extent: Extent,
+    blocks: &[bc1::Block],
+    options: CompressOptions,
+) -> Vec<u8> {
+    let mut header = JackalHeader::new(Format::BC1, extent, MipLevels(1))
+        .with_version(version)
+        .with_checksums(options.checksums)
+        .with_interleaved_colors(options.interleaved_colors);
+
+    if let SuperBlockChoice::Fixed(super_block_size) = options.super_block_size {
+        header = header.with_super_block_size(super_block_size);
+    }
+
+    let mut output = Vec::new();
+    write_texture(&header, blocks, &[], std::io::Cursor::new(&mut output)).unwrap();
+    output
+}
+
+/// Converts stream without checksums and interleaved colors into legacy container.
+#[cfg(test)]
+fn to_legacy_container(stream: &[u8]) -> Vec<u8> {
+    let (header, table) =
+        read_container(std::io::Cursor::new(stream), &DecodeLimits::default()).unwrap();
+    assert!(!header.checksums() && !header.interleaved_colors());
+
+    let table_shrink = (JackalBlock::BYTES_SIZE - 8) * table.len();
+
+    let mut legacy = stream[..header.bytes_size()].to_vec();
+    legacy[0..4].copy_from_slice(b"JKLI");
+
+    for sb in &table {
+        legacy.extend_from_slice(&(sb.offset - table_shrink as u64).to_le_bytes());
+    }
+
+    let payload_start = header.bytes_size() + JackalBlock::BYTES_SIZE * table.len();
+    legacy.extend_from_slice(&stream[payload_start..]);
+    legacy
+}
+
+#[test]
+fn legacy_container() {
+    let extent = Extent::D2 {
+        width: 70,
+        height: 40,
+    };
+
+    let blocks: Vec<bc1::Block> = (0..70 * 40)
+        .map(|i: u32| bc1::Block {
+            color0: crate::math::Rgb565::from_bits((i * 37) as u16),
+            color1: crate::math::Rgb565::from_bits((i / 70) as u16),
+            texels: [(i % 7) as u8, 0xE4, (i % 3) as u8, 0x55],
+        })
+        .collect();
+
+    let options = CompressOptions {
+        checksums: false,
+        super_block_size: SuperBlockChoice::Fixed(SuperBlockSize {
+            width: 32,
+            height: 32,
+        }),
+        ..CompressOptions::default()
+    };
+
+    let mut v9 = Vec::new();
+    compress_bc1_texture_opts(extent, &blocks, options, std::io::Cursor::new(&mut v9)).unwrap();
+
+    let v8 = compress_with_version(ContainerVersion::V8, extent, &blocks, options);
+    let v7 = compress_with_version(ContainerVersion::V7, extent, &blocks, options);
+    let v6 = compress_with_version(ContainerVersion::V6, extent, &blocks, options);
+    let v5 = compress_with_version(ContainerVersion::V5, extent, &blocks, options);
+    let v4 = compress_with_version(ContainerVersion::V4, extent, &blocks, options);
+    let v3 = compress_with_version(ContainerVersion::V3, extent, &blocks, options);
+    let v2 = compress_with_version(ContainerVersion::V2, extent, &blocks, options);
+    let legacy = to_legacy_container(&v2);
+
+    for (stream, version) in [
+        (&v9, ContainerVersion::V9),
+        (&v8, ContainerVersion::V8),
+        (&v7, ContainerVersion::V7),
+        (&v6, ContainerVersion::V6),
+        (&v5, ContainerVersion::V5),
+        (&v4, ContainerVersion::V4),
+        (&v3, ContainerVersion::V3),
+        (&v2, ContainerVersion::V2),
+        (&legacy, ContainerVersion::Legacy),
+    ] {
+        let mut cursor = std::io::Cursor::new(stream);
+        assert_eq!(sniff(&mut cursor).unwrap(), version);
+        assert_eq!(cursor.position(), 0);
+
+        let (decoded_extent, decoded) = decompress_bc1_texture(&mut cursor).unwrap();
+        assert_eq!(decoded_extent, extent);
+        assert_eq!(decoded, blocks);
+
+        let report = stat_file(std::io::Cursor::new(stream)).unwrap();
+        assert_eq!(report.total_blocks(), blocks.len() as u64);
+    }
+
+    assert_eq!(
+        stat_file(std::io::Cursor::new(&legacy)).unwrap(),
+        stat_file(std::io::Cursor::new(&v2)).unwrap()
+    );
+
+    // Legacy headers have no flags.
+    let mut flagged = legacy.clone();
+    flagged[24] = 1;
+    let err = decompress_bc1_texture(std::io::Cursor::new(&flagged)).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::InvalidHeader));
+
+    let err = sniff(std::io::Cursor::new(b"JKL0")).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::InvalidMagic));
+}
+
+#[test]
+fn decode_limits() {
+    let header = JackalHeader::new(
+        Format::BC1,
+        Extent::D2 {
+            width: u32::MAX,
+            height: u32::MAX,
+        },
+        MipLevels(1),
+    )
+    .with_super_block_size(SuperBlockSize {
+        width: 16,
+        height: 16,
+    });
+
+    let mut stream = Vec::new();
+    header.write_to(&mut stream).unwrap();
+
+    let err = decompress_bc1_texture(std::io::Cursor::new(&stream)).unwrap_err();
+    assert_eq!(err.stage(), Some(DecodeStage::Header));
+    assert_eq!(err.decode_error(), Some(DecodeError::InvalidExtent));
+
+    // Product of dimensions overflows 64 bits.
+    let header = JackalHeader::new(
+        Format::BC1,
+        Extent::D3 {
+            width: u32::MAX,
+            height: u32::MAX,
+            depth: u32::MAX,
+        },
+        MipLevels(1),
+    );
+    assert_eq!(header.checked_blocks_count(), None);
+    assert_eq!(
+        header.check_limits(&DecodeLimits {
+            max_blocks: usize::MAX,
+            max_super_blocks: usize::MAX,
+        }),
+        Err(DecodeError::InvalidExtent)
+    );
+
+    // Regular texture exceeds tight limits.
+    let mut output = Vec::new();
+    compress_bc1_texture(
+        Extent::D2 {
+            width: 8,
+            height: 8,
+        },
+        &[bc1::Block::BLACK; 64],
+        std::io::Cursor::new(&mut output),
+    )
+    .unwrap();
+
+    let limits = DecodeLimits {
+        max_blocks: 63,
+        ..DecodeLimits::default()
+    };
+    let err =
+        decompress_bc1_texture_with_limits(std::io::Cursor::new(&output), &limits).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::InvalidExtent));
+
+    let limits = DecodeLimits {
+        max_blocks: 64,
+        ..DecodeLimits::default()
+    };
+    let (_, blocks) =
+        decompress_bc1_texture_with_limits(std::io::Cursor::new(&output), &limits).unwrap();
+    assert_eq!(blocks.len(), 64);
+}
+
+#[test]
+fn blocks_count_mismatch() {
+    let extent = Extent::D2 {
+        width: 4,
+        height: 2,
+    };
+
+    for count in [0, 7, 9] {
+        let mut output = Vec::new();
+        let err = compress_bc1_texture(
+            extent,
+            &vec![bc1::Block::BLACK; count],
+            std::io::Cursor::new(&mut output),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            format!("blocks count does not match extent: expected 8, got {count}")
+        );
+        assert!(output.is_empty());
+    }
+
+    // Product is 2^32, which would wrap to zero in `u32`.
+    let extent = Extent::D3 {
+        width: 1 << 16,
+        height: 1 << 8,
+        depth: 1 << 8,
+    };
+    let mut output = Vec::new();
+    let err = compress_bc1_texture(extent, &[], std::io::Cursor::new(&mut output)).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("expected 4294967296, got 0"));
+}
+
+#[test]
+fn zero_extent() {
+    let extent = Extent::D2 {
+        width: 0,
+        height: 4,
+    };
+
+    let mut output = Vec::new();
+    let err = compress_bc1_texture(extent, &[], std::io::Cursor::new(&mut output)).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    // Header written by hand as compressor refuses empty extents.
+    let mut stream = Vec::new();
+    JackalHeader::new(Format::BC1, extent, MipLevels(1))
+        .write_to(&mut stream)
+        .unwrap();
+
+    let err = decompress_bc1_texture(std::io::Cursor::new(&stream)).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::InvalidExtent));
+}
+
+#[test]
+fn bc2_roundtrip() {
+    use crate::math::Rgba32F;
+
+    let red = Rgba32F::new(1.0, 0.0, 0.0, 1.0);
+    let blue = Rgba32F::new(0.0, 0.0, 1.0, 1.0);
+
+    // Sharp alpha edges: opaque and fully transparent texels side by side.
+    let blocks: Vec<bc2::Block> = (0..6 * 5)
+        .map(|i: u32| {
+            let pixels = std::array::from_fn(|y| {
+                std::array::from_fn(|x| {
+                    let color = Rgba32F::lerp(red, blue, ((i + y as u32) % 4) as f32 / 3.0);
+                    if (x as u32 + i).is_multiple_of(3) {
+                        color.rgb().with_alpha(0.0)
+                    } else {
+                        color
+                    }
+                })
+            });
+            bc2::Block::encode_with_alpha(pixels)
+        })
+        .collect();
+
+    let extent = Extent::D2 {
+        width: 6,
+        height: 5,
+    };
+
+    for interleaved_colors in [false, true] {
+        let options = CompressOptions {
+            interleaved_colors,
+            ..CompressOptions::default()
+        };
+
+        let mut output = Vec::new();
+        compress_bc2_texture_opts(extent, &blocks, options, std::io::Cursor::new(&mut output))
+            .unwrap();
+
+        let header = read_header(&output[..]).unwrap();
+        assert_eq!(header.format(), Format::BC2);
+
+        let (decoded_extent, decoded) =
+            decompress_bc2_texture(std::io::Cursor::new(&output)).unwrap();
+        assert_eq!(decoded_extent, extent);
+        assert_eq!(decoded, blocks);
+
+        // Format in the header must match requested blocks.
+        let err = decompress_bc1_texture(std::io::Cursor::new(&output)).unwrap_err();
+        assert_eq!(err.decode_error(), Some(DecodeError::Unsupported));
+    }
+}
+
+#[test]
+fn bc3_roundtrip() {
+    use crate::math::Rgba32F;
+
+    // Smooth alpha gradient across the texture over noisy colors.
+    let (width, height) = (7u32, 5u32);
+    let blocks: Vec<bc3::Block> = (0..width * height)
+        .map(|i| {
+            let (bx, by) = (i % width, i / width);
+            let pixels = std::array::from_fn(|y| {
+                std::array::from_fn(|x| {
+                    let px = bx * 4 + x as u32;
+                    let py = by * 4 + y as u32;
+                    let noise = (px * 7 + py * 13) % 5;
+                    Rgba32F::new(
+                        noise as f32 / 4.0,
+                        py as f32 / 19.0,
+                        0.5,
+                        (px + py) as f32 / 46.0,
+                    )
+                })
+            });
+            bc3::Block::encode_with_alpha(pixels)
+        })
+        .collect();
+
+    let extent = Extent::D2 { width, height };
+
+    for interleaved_colors in [false, true] {
+        let options = CompressOptions {
+            interleaved_colors,
+            ..CompressOptions::default()
+        };
+
+        let mut output = Vec::new();
+        compress_bc3_texture_opts(extent, &blocks, options, std::io::Cursor::new(&mut output))
+            .unwrap();
+
+        let header = read_header(&output[..]).unwrap();
+        assert_eq!(header.format(), Format::BC3);
+
+        let (decoded_extent, decoded) =
+            decompress_bc3_texture(std::io::Cursor::new(&output)).unwrap();
+        assert_eq!(decoded_extent, extent);
+        assert_eq!(decoded, blocks);
+
+        let (_, _, pixels) = decode_to_rgb8(std::io::Cursor::new(&output)).unwrap();
+        assert_eq!(pixels.len(), (width * height * 16) as usize);
+
+        let err = decompress_bc2_texture(std::io::Cursor::new(&output)).unwrap_err();
+        assert_eq!(err.decode_error(), Some(DecodeError::Unsupported));
+    }
+}
+
+#[test]
+fn varint() {
+    for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+        let mut bytes = Vec::new();
+        write_varint(&mut bytes, value).unwrap();
+
+        let mut rest = &bytes[..];
+        assert_eq!(read_varint(&mut rest), Some(value));
+        assert!(rest.is_empty());
+
+        let mut truncated = &bytes[..bytes.len() - 1];
+        assert_eq!(read_varint(&mut truncated), None);
+    }
+
+    // Eleventh byte would overflow 64 bits.
+    let mut overlong = &[0xFF; 11][..];
+    assert_eq!(read_varint(&mut overlong), None);
+}
+
+#[test]
+fn selective_aspect_decode() {
+    let extent = Extent::D2 {
+        width: 20,
+        height: 12,
+    };
+
+    let blocks: Vec<bc1::Block> = (0..20 * 12)
+        .map(|i: u32| bc1::Block {
+            color0: crate::math::Rgb565::from_bits((i * 37) as u16),
+            color1: crate::math::Rgb565::from_bits((i / 20) as u16),
+            texels: [(i % 7) as u8, 0xE4, (i % 3) as u8, i as u8],
+        })
+        .collect();
+
+    let super_block_size = SuperBlockSize {
+        width: 16,
+        height: 8,
+    };
+
+    // Blocks of super-block [1, 0, 0] in row-major order.
+    let super_block_blocks: Vec<bc1::Block> = (0..8)
+        .flat_map(|y| (16..20).map(move |x| x + y * 20))
+        .map(|index| blocks[index])
+        .collect();
+
+    for version in [
+        ContainerVersion::V2,
+        ContainerVersion::V3,
+        ContainerVersion::V4,
+    ] {
+        for interleaved_colors in [false, true] {
+            let options = CompressOptions {
+                checksums: false,
+                super_block_size: SuperBlockChoice::Fixed(super_block_size),
+                interleaved_colors,
+                ..CompressOptions::default()
+            };
+
+            let stream = compress_with_version(version, extent, &blocks, options);
+
+            let mut cursor = std::io::Cursor::new(&stream);
+            let (header, table) = read_container(&mut cursor, &DecodeLimits::default()).unwrap();
+            assert_eq!(header.version(), version);
+
+            let (_, decoded) = decompress_bc1_texture(std::io::Cursor::new(&stream)).unwrap();
+            assert_eq!(decoded, blocks);
+
+            let texels =
+                decompress_bc1_aspect::<4>(&header, [1, 0, 0], table[1], 2, &mut cursor).unwrap();
+            let expected: Vec<[u8; 4]> = super_block_blocks.iter().map(|b| b.texels).collect();
+            assert_eq!(texels, expected);
+
+            if interleaved_colors {
+                let low = decompress_bc1_aspect::<1>(&header, [1, 0, 0], table[1], 3, &mut cursor)
+                    .unwrap();
+                let expected: Vec<[u8; 1]> = super_block_blocks
+                    .iter()
+                    .map(|b| [b.color0.bits_interleaved() as u8])
+                    .collect();
+                assert_eq!(low, expected);
+            } else {
+                let colors =
+                    decompress_bc1_aspect::<2>(&header, [1, 0, 0], table[1], 1, &mut cursor)
+                        .unwrap();
+                let expected: Vec<[u8; 2]> = super_block_blocks
+                    .iter()
+                    .map(|b| b.color1.bits().to_le_bytes())
+                    .collect();
+                assert_eq!(colors, expected);
+            }
+
+            // Size of aspect elements must match.
+            let err = decompress_bc1_aspect::<2>(&header, [1, 0, 0], table[1], 2, &mut cursor)
+                .unwrap_err();
+            assert!(
+                matches!(err, DecompressError::Io(ref err) if err.kind() == std::io::ErrorKind::InvalidInput)
+            );
+        }
+    }
+
+    // Endpoints predicted from previous blocks are restored.
+    for interleaved_colors in [false, true] {
+        let options = CompressOptions {
+            super_block_size: SuperBlockChoice::Fixed(super_block_size),
+            interleaved_colors,
+            predictor: Predictor::Previous,
+            ..CompressOptions::default()
+        };
+
+        let mut stream = Vec::new();
+        compress_bc1_texture_opts(extent, &blocks, options, std::io::Cursor::new(&mut stream))
+            .unwrap();
+        let mut cursor = std::io::Cursor::new(&stream);
+        let (header, table) = read_container(&mut cursor, &DecodeLimits::default()).unwrap();
+
+        for aspect in 0..bc1::Block::ASPECTS {
+            let mut expected = Vec::new();
+            for block in &super_block_blocks {
+                block.compress(aspect, &header, &mut expected).unwrap();
+            }
+
+            let bytes: Vec<u8> = match bc1::Block::aspect_bytes(&header, aspect) {
+                0 => continue,
+                1 => decompress_bc1_aspect::<1>(&header, [1, 0, 0], table[1], aspect, &mut cursor)
+                    .unwrap()
+                    .concat(),
+                2 => decompress_bc1_aspect::<2>(&header, [1, 0, 0], table[1], aspect, &mut cursor)
+                    .unwrap()
+                    .concat(),
+                4 => decompress_bc1_aspect::<4>(&header, [1, 0, 0], table[1], aspect, &mut cursor)
+                    .unwrap()
+                    .concat(),
+                size => unreachable!("unexpected aspect size {size}"),
+            };
+            assert_eq!(bytes, expected, "aspect {aspect}");
+        }
+    }
+
+    // Constant super-blocks are restored too.
+    let options = CompressOptions {
+        predictor: Predictor::Previous,
+        ..CompressOptions::default()
+    };
+    let constant = vec![blocks[5]; 16];
+    let mut stream = Vec::new();
+    compress_bc1_texture_opts(
+        Extent::D2 {
+            width: 4,
+            height: 4,
+        },
+        &constant,
+        options,
+        std::io::Cursor::new(&mut stream),
+    )
+    .unwrap();
+    let mut cursor = std::io::Cursor::new(&stream);
+    let (header, table) = read_container(&mut cursor, &DecodeLimits::default()).unwrap();
+    let colors = decompress_bc1_aspect::<2>(&header, [0, 0, 0], table[0], 0, &mut cursor).unwrap();
+    assert_eq!(colors, vec![blocks[5].color0.bits().to_le_bytes(); 16]);
+
+    // Corrupted color streams do not prevent decoding texels in new container.
+    let options = CompressOptions {
+        checksums: false,
+        super_block_size: SuperBlockChoice::Fixed(super_block_size),
+        ..CompressOptions::default()
+    };
+    let mut stream = compress_with_version(ContainerVersion::V4, extent, &blocks, options);
+    let (header, table) =
+        read_container(std::io::Cursor::new(&stream), &DecodeLimits::default()).unwrap();
+
+    let payload_start = table[0].offset as usize;
+    let mut sizes = &stream[payload_start..];
+    let color0_size = read_varint(&mut sizes).unwrap() as usize;
+    for _ in 1..bc1::Block::ASPECTS {
+        read_varint(&mut sizes).unwrap();
+    }
+
+    // Color0 stream goes right after the sizes.
+    let color0_start = stream.len() - sizes.len();
+    stream[color0_start..color0_start + color0_size].fill(0xFF);
+
+    let texels = decompress_bc1_aspect::<4>(
+        &header,
+        [0, 0, 0],
+        table[0],
+        2,
+        std::io::Cursor::new(&stream),
+    )
+    .unwrap();
+    assert_eq!(texels.len(), 16 * 8);
+    assert_eq!(texels[17], blocks[21].texels);
+
+    let err = decompress_bc1_texture(std::io::Cursor::new(&stream)).unwrap_err();
+    assert_eq!(
+        err.decode_error(),
+        Some(DecodeError::InvalidData {
+            super_block: Some([0, 0, 0]),
+            aspect: Some(0),
+        })
+    );
+}
+
+#[test]
+fn decode_to_rgb8_pixels() {
+    use crate::encoder::{decode_bc1_image, encode_bc1_rgb8, EncodeEffort};
+
+    let (width, height) = (10, 7);
+    let pixels: Vec<Rgb8U> = (0..width * height)
+        .map(|i| Rgb8U::new((i * 3) as u8, (i * 7) as u8, 255 - i as u8))
+        .collect();
+
+    let (extent, blocks) = encode_bc1_rgb8(width, height, &pixels, EncodeEffort::Fast);
+
+    let mut output = Vec::new();
+    compress_bc1_texture(
+        extent.bc_block_extent(),
+        &blocks,
+        std::io::Cursor::new(&mut output),
+    )
+    .unwrap();
+
+    let (decoded_width, decoded_height, decoded) =
+        decode_to_rgb8(std::io::Cursor::new(&output)).unwrap();
+    assert_eq!((decoded_width, decoded_height), (12, 8));
+    assert_eq!(decoded.len(), 12 * 8);
+
+    // Cropping the block grid gives the same pixels as decoding at the true size.
+    let cropped = decode_bc1_image(extent, &blocks);
+    for y in 0..height {
+        for x in 0..width {
+            assert_eq!(
+                decoded[(x + y * decoded_width) as usize],
+                cropped[(x + y * width) as usize]
+            );
+        }
+    }
+
+    // Padding repeats the last column and row.
+    assert_eq!(decoded[11 + 7 * 12], decoded[9 + 6 * 12]);
+
+    // Encoder records pixel size, so partially used edge blocks are cropped.
+    for (width, height) in [(10, 7), (5, 3), (1, 1), (4, 9), (13, 4)] {
+        let pixels: Vec<Rgb8U> = (0..width * height)
+            .map(|i| Rgb8U::new((i * 5) as u8, (i * 11) as u8, 255 - (i * 3) as u8))
+            .collect();
+
+        let mut output = Vec::new();
+        crate::encoder::Encoder::new(Format::BC1)
+            .effort(EncodeEffort::Fast)
+            .encode_rgb8(width, height, &pixels, std::io::Cursor::new(&mut output))
+            .unwrap();
+
+        let (decoded_width, decoded_height, decoded) =
+            decode_to_rgb8(std::io::Cursor::new(&output)).unwrap();
+        assert_eq!((decoded_width, decoded_height), (width, height));
+        assert_eq!(decoded.len(), (width * height) as usize);
+
+        let (_, blocks) = decompress_bc1_texture(std::io::Cursor::new(&output)).unwrap();
+        assert_eq!(
+            decoded,
+            decode_bc1_image(Extent::D2 { width, height }, &blocks)
+        );
+    }
+
+    // Pixel size not covered by the block grid is rejected.
+    let mut output = Vec::new();
+    compress_bc1_texture_with_chunks(
+        extent.bc_block_extent(),
+        &blocks,
+        CompressOptions::default(),
+        &[MetaChunk::pixel_size(13, 7)],
+        std::io::Cursor::new(&mut output),
+    )
+    .unwrap();
+    let err = decode_to_rgb8(std::io::Cursor::new(&output)).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::INVALID_DATA));
+
+    let mut output = Vec::new();
+    compress_bc2_texture(
+        Extent::D2 {
+            width: 1,
+            height: 1,
+        },
+        &[bc2::Block::BLACK],
+        std::io::Cursor::new(&mut output),
+    )
+    .unwrap();
+    let (_, _, decoded) = decode_to_rgb8(std::io::Cursor::new(&output)).unwrap();
+    assert_eq!(decoded, [Rgb8U::BLACK; 16]);
+
+    let mut output = Vec::new();
+    compress_bc1_texture(
+        Extent::D3 {
+            width: 1,
+            height: 1,
+            depth: 2,
+        },
+        &[bc1::Block::BLACK; 2],
+        std::io::Cursor::new(&mut output),
+    )
+    .unwrap();
+    let err = decode_to_rgb8(std::io::Cursor::new(&output)).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::Unsupported));
+}
+
+#[test]
+fn array_layers_roundtrip() {
+    use crate::math::Rgb32F;
+
+    // Layers have unrelated content, blocks must not leak between them.
+    let red = bc1::Block::encode([[Rgb32F::new(1.0, 0.0, 0.0); 4]; 4]);
+    let gradient = bc1::Block::encode(std::array::from_fn(|i| {
+        std::array::from_fn(|j| Rgb32F::new(0.0, i as f32 / 3.0, j as f32 / 3.0))
+    }));
+
+    let (width, height) = (5, 3);
+    let layer = (width * height) as usize;
+
+    let blocks: Vec<bc1::Block> = (0..layer * 2)
+        .map(|i| if i < layer { red } else { gradient })
+        .collect();
+
+    for super_block_size in [
+        SuperBlockChoice::FromExtent,
+        SuperBlockChoice::Fixed(SuperBlockSize {
+            width: 2,
+            height: 2,
+        }),
+    ] {
+        let options = CompressOptions {
+            super_block_size,
+            ..CompressOptions::default()
+        };
+
+        let mut output = Vec::new();
+        compress_bc1_texture_opts(
+            Extent::D2Array {
+                width,
+                height,
+                layers: 2,
+            },
+            &blocks,
+            options,
+            std::io::Cursor::new(&mut output),
+        )
+        .unwrap();
+
+        let (_, decoded) = decompress_bc1_texture(std::io::Cursor::new(&output)).unwrap();
+        assert!(decoded[..layer].iter().all(|&block| block == red));
+        assert!(decoded[layer..].iter().all(|&block| block == gradient));
+    }
+}
+
+#[test]
+fn auto_super_block_size() {
+    use rand::{Rng, SeedableRng};
+
+    let extent = Extent::D2 {
+        width: 64,
+        height: 48,
+    };
+
+    let flat = vec![bc1::Block::WHITE; 64 * 48];
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(7);
+    let noise: Vec<bc1::Block> = (0..64 * 48)
+        .map(|_| bc1::Block::from_bytes(rng.gen()))
+        .collect();
+
+    assert_eq!(
+        choose_super_block_size(extent, &flat).unwrap(),
+        SuperBlockSize {
+            width: 64,
+            height: 64,
+        }
+    );
+
+    let size = choose_super_block_size(extent, &noise).unwrap();
+    assert!(size.width < 64 && size.height < 64, "{size:?}");
+
+    for blocks in [flat, noise] {
+        let options = CompressOptions {
+            super_block_size: SuperBlockChoice::Auto,
+            ..CompressOptions::default()
+        };
+
+        let mut output = Vec::new();
+        compress_bc1_texture_opts(extent, &blocks, options, std::io::Cursor::new(&mut output))
+            .unwrap();
+
+        let header = read_header(&output[..]).unwrap();
+        assert_eq!(
+            header.super_block_size(),
+            choose_super_block_size(extent, &blocks).unwrap()
+        );
+
+        let (_, decoded) = decompress_bc1_texture(std::io::Cursor::new(&output)).unwrap();
+        assert_eq!(decoded, blocks);
+    }
+
+    // Blocks not matching the extent are rejected like in compression.
+    let extent = Extent::D2 {
+        width: 2,
+        height: 2,
+    };
+    let err = choose_super_block_size(extent, &[bc1::Block::BLACK; 3]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let err =
+        choose_super_block_size(Extent::D1 { width: 0 }, &[bc1::Block::BLACK; 0]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn block_texture_roundtrip() {
+    let extent = Extent::D2Array {
+        width: 3,
+        height: 2,
+        layers: 2,
+    };
+
+    let mut texture = BlockTexture::filled(extent, bc1::Block::BLACK);
+    *texture.get_mut(2, 1, 1).unwrap() = bc1::Block::WHITE;
+
+    let mut output = Vec::new();
+    compress_block_texture(
+        &texture,
+        CompressOptions::default(),
+        std::io::Cursor::new(&mut output),
+    )
+    .unwrap();
+
+    let decoded: BlockTexture<bc1::Block> =
+        decompress_block_texture(std::io::Cursor::new(&output), &DecodeLimits::default()).unwrap();
+    assert_eq!(decoded, texture);
+
+    let err = decompress_block_texture::<bc2::Block>(
+        std::io::Cursor::new(&output),
+        &DecodeLimits::default(),
+    )
+    .unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::Unsupported));
+}
+
+#[test]
+fn read_jackal_blocks_table() {
+    let extent = Extent::D2 {
+        width: 40,
+        height: 20,
+    };
+    let blocks = vec![bc1::Block::WHITE; 40 * 20];
+    let options = CompressOptions {
+        super_block_size: SuperBlockChoice::Fixed(SuperBlockSize {
+            width: 16,
+            height: 16,
+        }),
+        ..CompressOptions::default()
+    };
+
+    let mut output = Vec::new();
+    compress_bc1_texture_opts(extent, &blocks, options, std::io::Cursor::new(&mut output)).unwrap();
+
+    let header = read_header(&output[..]).unwrap();
+    let table_start = header.bytes_size() + meta_chunks_bytes_size(header.version(), &[]);
+    let table_end = table_start + JackalBlock::BYTES_SIZE * header.jackal_blocks_count();
+
+    // Reading starts right after the header, empty chunk section is skipped.
+    let mut read = std::io::Cursor::new(&output);
+    read.set_position(header.bytes_size() as u64);
+    let table = read_jackal_blocks_vec(&header, &mut read).unwrap();
+    assert_eq!(table.len(), 6);
+    assert_eq!(read.position(), table_end as u64);
+
+    let mut slice_table = [JackalBlock::default(); 6];
+    read_jackal_blocks(&mut slice_table, &output[table_start..]).unwrap();
+    assert_eq!(table, slice_table);
+    assert!(table.iter().all(|entry| *entry != JackalBlock::default()));
+
+    // Truncated table is an error, not a partially filled table.
+    let truncated = &output[..table_end - 1];
+    let mut read = std::io::Cursor::new(truncated);
+    read.set_position(header.bytes_size() as u64);
+    assert!(read_jackal_blocks_vec(&header, &mut read).is_err());
+
+    // Only the first level has a table.
+    let mut read = std::io::Cursor::new(&output);
+    read.set_position(header.bytes_size() as u64);
+    assert_eq!(
+        read_level_jackal_blocks_vec(&header, 0, &mut read).unwrap(),
+        table
+    );
+    read.set_position(header.bytes_size() as u64);
+    let err = read_level_jackal_blocks_vec(&header, 1, &mut read).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::Unsupported));
+}
+
+#[test]
+fn slices_roundtrip() {
+    use crate::math::Rgb565;
+
+    // Every block records its position, so shifted or mixed slices are detected.
+    let positioned = |[width, height, depth]: [u32; 3]| -> Vec<bc1::Block> {
+        (0..depth)
+            .flat_map(|z| (0..height).flat_map(move |y| (0..width).map(move |x| (x, y, z))))
+            .map(|(x, y, z)| bc1::Block {
+                color0: Rgb565::from_bits((x | y << 4 | z << 8) as u16),
+                color1: Rgb565::from_bits(!(z as u16)),
+                texels: [x as u8, y as u8, z as u8, 0xA5],
+            })
+            .collect()
+    };
+
+    let extents = [
+        Extent::D3 {
+            width: 4,
+            height: 4,
+            depth: 3,
+        },
+        Extent::D2Array {
+            width: 4,
+            height: 4,
+            layers: 6,
+        },
+        Extent::D2Array {
+            width: 5,
+            height: 3,
+            layers: 6,
+        },
+    ];
+
+    for extent in extents {
+        let blocks = positioned(extent.raw_size());
+
+        for super_block_size in [
+            SuperBlockChoice::FromExtent,
+            SuperBlockChoice::Fixed(SuperBlockSize {
+                width: 2,
+                height: 2,
+            }),
+        ] {
+            let options = CompressOptions {
+                super_block_size,
+                ..CompressOptions::default()
+            };
+
+            let mut output = Vec::new();
+            compress_bc1_texture_opts(extent, &blocks, options, std::io::Cursor::new(&mut output))
+                .unwrap();
+
+            let (decoded_extent, decoded) =
+                decompress_bc1_texture(std::io::Cursor::new(&output)).unwrap();
+            assert_eq!(decoded_extent, extent);
+            assert_eq!(decoded, blocks, "{extent:?} {super_block_size:?}");
+        }
+    }
+}
+
+#[test]
+fn payloads_followed_by_garbage() {
+    let extent = Extent::D2 {
+        width: 40,
+        height: 20,
+    };
+    let blocks: Vec<bc1::Block> = (0..40 * 20)
+        .map(|i: u32| bc1::Block {
+            color0: crate::math::Rgb565::from_bits((i * 37) as u16),
+            color1: crate::math::Rgb565::from_bits((i / 40) as u16),
+            texels: [(i % 7) as u8, 0xE4, (i % 3) as u8, 0x55],
+        })
+        .collect();
+    let options = CompressOptions {
+        super_block_size: SuperBlockChoice::Fixed(SuperBlockSize {
+            width: 16,
+            height: 16,
+        }),
+        ..CompressOptions::default()
+    };
+
+    let mut output = Vec::new();
+    compress_bc1_texture_opts(extent, &blocks, options, std::io::Cursor::new(&mut output)).unwrap();
+
+    let (header, table) =
+        read_container(std::io::Cursor::new(&output), &DecodeLimits::default()).unwrap();
+    assert!(table.iter().all(|sb| !sb.is_empty()));
+
+    // Garbage after every payload, which looks like a valid brotli stream start.
+    let garbage = [0x1B, 0xFF, 0x00, 0x42, 0x8B, 0x3C, 0x00, 0x00, 0x07];
+    let table_start = header.bytes_size() + meta_chunks_bytes_size(header.version(), &[]);
+    let payload_start = table_start + JackalBlock::BYTES_SIZE * table.len();
+
+    let mut payloads = Vec::new();
+    let mut new_table = Vec::new();
+    for sb in &table {
+        new_table.push(JackalBlock {
+            offset: (payload_start + payloads.len()) as u64,
+            ..*sb
+        });
+        payloads.extend_from_slice(&output[sb.offset as usize..sb.end().unwrap() as usize]);
+        payloads.extend_from_slice(&garbage);
+    }
+
+    let mut padded = output[..table_start].to_vec();
+    for sb in &new_table {
+        sb.write_to(&mut padded).unwrap();
+    }
+    padded.extend_from_slice(&payloads);
+
+    let (_, decoded) = decompress_bc1_texture(std::io::Cursor::new(&padded)).unwrap();
+    assert_eq!(decoded, blocks);
+
+    // Payload extending past the end of the stream is rejected before reading.
+    let last = new_table.len() - 1;
+    let truncated = &padded[..new_table[last].end().unwrap() as usize - 1];
+    let err = decompress_bc1_blocks(
+        &header,
+        [2, 1, 0],
+        new_table[last],
+        &mut vec![bc1::Block::BLACK; 40 * 20],
+        std::io::Cursor::new(truncated),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.decode_error(),
+        Some(DecodeError::InvalidData {
+            super_block: Some([2, 1, 0]),
+            aspect: None,
+        })
+    );
+}
+
+#[test]
+fn z_curve_layout_reconstructs_row_major() {
+    use crate::math::Rgb565;
+
+    let extents = [
+        Extent::D2 {
+            width: 7,
+            height: 5,
+        },
+        Extent::D2Array {
+            width: 5,
+            height: 3,
+            layers: 2,
+        },
+    ];
+
+    for extent in extents {
+        let [width, height, depth] = extent.raw_size();
+        let blocks: Vec<bc1::Block> = (0..width * height * depth)
+            .map(|i| bc1::Block {
+                color0: Rgb565::from_bits(i as u16),
+                color1: Rgb565::from_bits(!(i as u16)),
+                texels: [i as u8, 0x1B, 0xE4, 0x55],
+            })
+            .collect();
+
+        // Edge super-blocks are clipped in both directions.
+        let super_block_size = SuperBlockSize {
+            width: 4,
+            height: 2,
+        };
+        let options = CompressOptions {
+            super_block_size: SuperBlockChoice::Fixed(super_block_size),
+            ..CompressOptions::default()
+        };
+
+        let mut output = Vec::new();
+        compress_bc1_texture_opts(extent, &blocks, options, std::io::Cursor::new(&mut output))
+            .unwrap();
+
+        let (_, row_major, bases) =
+            decompress_bc1_texture_layout(std::io::Cursor::new(&output), BlockLayout::RowMajor)
+                .unwrap();
+        assert_eq!(row_major, blocks);
+        assert!(bases.is_empty());
+
+        let (decoded_extent, z_curve, bases) = decompress_bc1_texture_layout(
+            std::io::Cursor::new(&output),
+            BlockLayout::ZCurvePerSuperBlock,
+        )
+        .unwrap();
+        assert_eq!(decoded_extent, extent);
+        assert_ne!(z_curve, row_major);
+        assert_eq!(
+            bases.len(),
+            read_header(&output[..]).unwrap().jackal_blocks_count()
+        );
+
+        let mut reconstructed = vec![bc1::Block::BLACK; z_curve.len()];
+        for z in 0..depth {
+            for y in 0..height {
+                for x in 0..width {
+                    let index = z_curve_block_index(extent, super_block_size, x, y, z);
+                    reconstructed[texture::block_index([width, height, depth], x, y, z)] =
+                        z_curve[index];
+                }
+            }
+        }
+        assert_eq!(reconstructed, row_major, "{extent:?}");
+
+        // First block of each super-block sits at its base.
+        let super_extent = read_header(&output[..]).unwrap().jackal_blocks_extent();
+        for (i, &base) in bases.iter().enumerate() {
+            let sx = i as u32 % super_extent[0];
+            let sy = i as u32 / super_extent[0] % super_extent[1];
+            let sz = i as u32 / super_extent[0] / super_extent[1];
+            assert_eq!(
+                z_curve_block_index(extent, super_block_size, sx * 4, sy * 2, sz),
+                base
+            );
+        }
+    }
+}
+
+#[test]
+fn endpoint_prediction() {
+    use crate::{bc4, bc5, math::Rgb565};
+
+    let extent = Extent::D2 {
+        width: 48,
+        height: 40,
+    };
+
+    // Smooth gradient where endpoints of neighbor blocks differ by small steps.
+    let blocks: Vec<bc1::Block> = (0..48 * 40)
+        .map(|i| {
+            let (x, y) = (i % 48, i / 48);
+            bc1::Block {
+                color0: Rgb565::new((x * 5 / 8) as u8, (y * 3 / 2) as u8, ((x + y) / 3) as u8),
+                color1: Rgb565::new((y * 3 / 4) as u8, x as u8, 31 - ((x + y) / 3) as u8),
+                texels: [(i % 5) as u8, 0xE4, 0x1B, (i % 3) as u8],
+            }
+        })
+        .collect();
+
+    let compress = |predictor, interleaved_colors| {
+        let mut output = Vec::new();
+        compress_bc1_texture_opts(
+            extent,
+            &blocks,
+            CompressOptions {
+                predictor,
+                interleaved_colors,
+                super_block_size: SuperBlockChoice::Fixed(SuperBlockSize {
+                    width: 16,
+                    height: 8,
+                }),
+                ..CompressOptions::default()
+            },
+            std::io::Cursor::new(&mut output),
+        )
+        .unwrap();
+        output
+    };
+
+    for interleaved_colors in [false, true] {
+        let plain = compress(Predictor::None, interleaved_colors);
+        let previous = compress(Predictor::Previous, interleaved_colors);
+
+        eprintln!(
+            "interleaved: {interleaved_colors}, none: {} bytes, previous: {} bytes",
+            plain.len(),
+            previous.len()
+        );
+        assert!(previous.len() < plain.len());
+
+        for (predictor, output) in [(Predictor::None, &plain), (Predictor::Previous, &previous)] {
+            assert_eq!(read_header(&output[..]).unwrap().predictor(), predictor);
+
+            let (_, decompressed) = decompress_bc1_texture(std::io::Cursor::new(output)).unwrap();
+            assert_eq!(decompressed, blocks, "{predictor:?}");
+
+            let (_, z_curve, _) = decompress_bc1_texture_layout(
+                std::io::Cursor::new(output),
+                BlockLayout::ZCurvePerSuperBlock,
+            )
+            .unwrap();
+            let size = read_header(&output[..]).unwrap().super_block_size();
+            for (i, block) in blocks.iter().enumerate() {
+                let index = z_curve_block_index(extent, size, i as u32 % 48, i as u32 / 48, 0);
+                assert_eq!(z_curve[index], *block, "{predictor:?}");
+            }
+        }
+    }
+
+    // Formats with single channel endpoints.
+    let bc5_blocks: Vec<bc5::Block> = blocks
+        .iter()
+        .map(|b| bc5::Block {
+            red: bc4::Block {
+                color0: crate::math::R8U::new(b.color0.bits() as u8),
+                color1: crate::math::R8U::new((b.color0.bits() >> 8) as u8),
+                texels: [b.texels[0], 0x24, 0x49, 0x92, 0x24, b.texels[3]],
+            },
+            green: bc4::Block {
+                color0: crate::math::R8U::new(b.color1.bits() as u8),
+                color1: crate::math::R8U::new((b.color1.bits() >> 8) as u8),
+                texels: [0x49; 6],
+            },
+        })
+        .collect();
+
+    for predictor in [Predictor::None, Predictor::Previous] {
+        let options = CompressOptions {
+            predictor,
+            ..CompressOptions::default()
+        };
+
+        let mut output = Vec::new();
+        compress_texture(
+            extent,
+            &bc5_blocks,
+            options,
+            std::io::Cursor::new(&mut output),
+        )
+        .unwrap();
+        let (_, decompressed) = decompress_texture::<bc5::Block>(
+            std::io::Cursor::new(&output),
+            &DecompressOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(decompressed, bc5_blocks, "{predictor:?}");
+
+        let bc3_blocks: Vec<bc3::Block> = blocks
+            .iter()
+            .zip(&bc5_blocks)
+            .map(|(rgb, alpha)| bc3::Block {
+                alpha: alpha.red,
+                rgb: *rgb,
+            })
+            .collect();
+
+        let mut output = Vec::new();
+        compress_bc3_texture_opts(
+            extent,
+            &bc3_blocks,
+            options,
+            std::io::Cursor::new(&mut output),
+        )
+        .unwrap();
+        let (_, decompressed) = decompress_bc3_texture(std::io::Cursor::new(&output)).unwrap();
+        assert_eq!(decompressed, bc3_blocks, "{predictor:?}");
+    }
+}
+
+#[test]
+fn predictor_per_super_block() {
+    use crate::math::Rgb565;
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(5);
+
+    let extent = Extent::D2 {
+        width: 32,
+        height: 16,
+    };
+
+    // Left super-block is a smooth gradient that benefits from prediction,
+    // right one picks endpoints from a small palette which prediction spreads.
+    let palette: [u16; 4] = std::array::from_fn(|_| rng.gen());
+    let blocks: Vec<bc1::Block> = (0..32 * 16)
+        .map(|i: u32| {
+            let (x, y) = (i % 32, i / 32);
+            let (color0, color1) = if x < 16 {
+                (
+                    Rgb565::new((x + y) as u8, (x * 2 + y) as u8, y as u8),
+                    Rgb565::new(y as u8, (x + y * 2) as u8, x as u8),
+                )
+            } else {
+                (
+                    Rgb565::from_bits(palette[rng.gen_range(0..4)]),
+                    Rgb565::from_bits(palette[rng.gen_range(0..4)]),
+                )
+            };
+            bc1::Block {
+                color0,
+                color1,
+                texels: [0xE4, 0x1B, 0x4E, 0xB1],
+            }
+        })
+        .collect();
+
+    let options = CompressOptions {
+        super_block_size: SuperBlockChoice::Fixed(SuperBlockSize {
+            width: 16,
+            height: 16,
+        }),
+        predictor: Predictor::Previous,
+        checksums: false,
+        ..CompressOptions::default()
+    };
+    let mut stream = Vec::new();
+    compress_bc1_texture_opts(extent, &blocks, options, std::io::Cursor::new(&mut stream)).unwrap();
+
+    let mut read = std::io::Cursor::new(&stream);
+    let (header, table) = read_container(&mut read, &DecodeLimits::default()).unwrap();
+    assert_eq!(header.predictor(), Predictor::Previous);
+
+    let modes: Vec<u8> = table
+        .iter()
+        .map(|entry| stream[entry.offset as usize])
+        .collect();
+    assert_eq!(modes, [PAYLOAD_ASPECTS, PAYLOAD_ASPECTS_UNPREDICTED]);
+
+    let (_, decoded) = decompress_bc1_texture(std::io::Cursor::new(&stream)).unwrap();
+    assert_eq!(decoded, blocks);
+
+    let (_, z_curve, _) = decompress_bc1_texture_layout(
+        std::io::Cursor::new(&stream),
+        BlockLayout::ZCurvePerSuperBlock,
+    )
+    .unwrap();
+    for (i, block) in blocks.iter().enumerate() {
+        let index = z_curve_block_index(
+            extent,
+            header.super_block_size(),
+            i as u32 % 32,
+            i as u32 / 32,
+            0,
+        );
+        assert_eq!(z_curve[index], *block);
+    }
+
+    let (_, endpoints) = decode_endpoints_preview(std::io::Cursor::new(&stream)).unwrap();
+    assert!(endpoints
+        .iter()
+        .zip(&blocks)
+        .all(|(&endpoints, block)| endpoints == (block.color0, block.color1)));
+
+    // Endpoints of both super-blocks are returned as is.
+    for (x, entry) in table.iter().enumerate() {
+        let color0 = decompress_bc1_aspect::<2>(
+            &header,
+            [x as u32, 0, 0],
+            *entry,
+            0,
+            std::io::Cursor::new(&stream),
+        )
+        .unwrap();
+
+        let expected: Vec<[u8; 2]> = blocks
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| i % 32 / 16 == x)
+            .map(|(_, block)| {
+                let mut bytes = Vec::new();
+                block.compress(0, &header, &mut bytes).unwrap();
+                bytes.try_into().unwrap()
+            })
+            .collect();
+        assert_eq!(color0, expected);
+    }
+
+    // Containers before V9 have no mode of unpredicted aspects.
+    let header = header.with_version(ContainerVersion::V8);
+    let mut stream = Vec::new();
+    write_texture(&header, &blocks, &[], std::io::Cursor::new(&mut stream)).unwrap();
+    let (_, table) =
+        read_container(std::io::Cursor::new(&stream), &DecodeLimits::default()).unwrap();
+    assert!(table
+        .iter()
+        .all(|entry| stream[entry.offset as usize] == PAYLOAD_ASPECTS));
+
+    stream[table[1].offset as usize] = PAYLOAD_ASPECTS_UNPREDICTED;
+    let err = decompress_bc1_texture(std::io::Cursor::new(&stream)).unwrap_err();
+    assert!(
+        matches!(err.decode_error(), Some(DecodeError::InvalidData { .. })),
+        "{err}"
+    );
+}
+
+#[test]
+fn canonical_indices() {
+    use crate::math::Rgb565;
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+
+    let extent = Extent::D2 {
+        width: 64,
+        height: 32,
+    };
+
+    // Row of index patterns repeated down the texture,
+    // with endpoints swapped and indices inverted in every other band of rows.
+    let patterns: [u32; 64] = std::array::from_fn(|_| rng.gen());
+    let color0 = Rgb565::new(20, 40, 10);
+    let color1 = Rgb565::new(3, 7, 25);
+    let blocks: Vec<bc1::Block> = (0..64 * 32)
+        .map(|i| {
+            let (x, y) = (i % 64, i / 64);
+            let word = patterns[x];
+            if (y / 3) % 2 == 1 {
+                bc1::Block {
+                    color0: color1,
+                    color1: color0,
+                    texels: (word ^ 0x5555_5555).to_le_bytes(),
+                }
+            } else {
+                bc1::Block {
+                    color0,
+                    color1,
+                    texels: word.to_le_bytes(),
+                }
+            }
+        })
+        .collect();
+
+    let compress = |blocks: &[bc1::Block], canonical_indices| {
+        let mut output = Vec::new();
+        compress_bc1_texture_opts(
+            extent,
+            blocks,
+            CompressOptions {
+                canonical_indices,
+                ..CompressOptions::default()
+            },
+            std::io::Cursor::new(&mut output),
+        )
+        .unwrap();
+        output
+    };
+
+    let plain = compress(&blocks, false);
+    let canonical = compress(&blocks, true);
+
+    assert!(canonical.len() < plain.len());
+    assert!(read_header(&canonical[..]).unwrap().canonical_indices());
+
+    let (_, decompressed) = decompress_bc1_texture(std::io::Cursor::new(&canonical)).unwrap();
+    assert_eq!(decompressed, blocks);
+
+    // Random blocks roundtrip exactly.
+    let random: Vec<bc1::Block> = (0..64 * 32)
+        .map(|_| bc1::Block::from_bytes(rng.gen()))
+        .collect();
+    let (_, decompressed) =
+        decompress_bc1_texture(std::io::Cursor::new(&compress(&random, true))).unwrap();
+    assert_eq!(decompressed, random);
+}
+
+#[test]
+fn progress_and_cancellation() {
+    let extent = Extent::D2 {
+        width: 6,
+        height: 5,
+    };
+    let blocks = checkerboard_blocks();
+    let options = CompressOptions {
+        super_block_size: SuperBlockChoice::Fixed(SuperBlockSize {
+            width: 4,
+            height: 4,
+        }),
+        ..CompressOptions::default()
+    };
+
+    let mut output = Vec::new();
+    let mut reports = Vec::new();
+    compress_bc1_texture_with_progress(
+        extent,
+        &blocks,
+        options,
+        std::io::Cursor::new(&mut output),
+        |progress| {
+            reports.push(progress);
+            ControlFlow::Continue(())
+        },
+    )
+    .unwrap();
+
+    let header = read_header(&output[..]).unwrap();
+    assert_eq!(reports.len(), header.jackal_blocks_count());
+    assert_eq!(
+        reports.last(),
+        Some(&Progress {
+            super_blocks_done: 4,
+            super_blocks_total: 4,
+            blocks_done: blocks.len(),
+            bytes: output.len() as u64,
+        })
+    );
+
+    let mut calls = 0;
+    let (_, decoded) = decompress_bc1_texture_with_progress(std::io::Cursor::new(&output), |p| {
+        calls += 1;
+        assert_eq!(p.super_blocks_done, calls);
+        ControlFlow::Continue(())
+    })
+    .unwrap();
+    assert_eq!(calls, header.jackal_blocks_count());
+    assert_eq!(decoded, blocks);
+
+    // Cancel after the first super-block.
+    let mut calls = 0;
+    let err = compress_bc1_texture_with_progress(
+        extent,
+        &blocks,
+        options,
+        std::io::Cursor::new(Vec::new()),
+        |_| {
+            calls += 1;
+            ControlFlow::Break(())
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, EncodeError::Cancelled));
+    assert_eq!(calls, 1);
+
+    let err = decompress_bc1_texture_with_progress(std::io::Cursor::new(&output), |_| {
+        ControlFlow::Break(())
+    })
+    .unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::Cancelled));
+}
+
+#[test]
+fn meta_chunks() {
+    let extent = Extent::D2 {
+        width: 6,
+        height: 5,
+    };
+    let blocks = checkerboard_blocks();
+
+    let compress = |chunks: &[MetaChunk]| {
+        let mut output = Vec::new();
+        compress_bc1_texture_with_chunks(
+            extent,
+            &blocks,
+            CompressOptions::default(),
+            chunks,
+            std::io::Cursor::new(&mut output),
+        )
+        .unwrap();
+        output
+    };
+
+    let cases: [&[MetaChunk]; 3] = [
+        &[],
+        &[MetaChunk::new(*b"GUID", [0xA5; 16])],
+        &[
+            MetaChunk::new(*b"AUTH", "someone"),
+            MetaChunk::new(*b"????", []),
+            MetaChunk::new(*b"HASH", [1, 2, 3, 4]),
+        ],
+    ];
+
+    for chunks in cases {
+        let output = compress(chunks);
+
+        let (decoded_extent, decoded) =
+            decompress_bc1_texture(std::io::Cursor::new(&output)).unwrap();
+        assert_eq!(decoded_extent, extent);
+        assert_eq!(decoded, blocks);
+
+        let mut read = std::io::Cursor::new(&output);
+        let header = read_header(&mut read).unwrap();
+        assert_eq!(read_meta_chunks(&mut read, &header).unwrap(), chunks);
+
+        // Chunks are followed by the table.
+        let (_, table) =
+            read_container(std::io::Cursor::new(&output), &DecodeLimits::default()).unwrap();
+        let mut entries = vec![JackalBlock::default(); table.len()];
+        read_jackal_blocks(&mut entries, &mut read).unwrap();
+        assert_eq!(entries, table);
+    }
+
+    // Chunk overrunning the table start, either past the end of the stream
+    // or leaving no room for the table.
+    let valid = compress(&[MetaChunk::new(*b"GUID", [0xA5; 16])]);
+    let len_pos = JackalHeader::BYTES_SIZE + 2 + 4;
+    let data_start = len_pos + 4;
+
+    for len in [valid.len() as u32, (valid.len() - data_start - 8) as u32] {
+        let mut output = valid.clone();
+        output[len_pos..data_start].copy_from_slice(&len.to_le_bytes());
+
+        let err = decompress_bc1_texture(std::io::Cursor::new(&output)).unwrap_err();
+        assert_eq!(err.decode_error(), Some(DecodeError::INVALID_DATA));
+
+        let mut read = std::io::Cursor::new(&output);
+        let header = read_header(&mut read).unwrap();
+        let err = read_meta_chunks(&mut read, &header).unwrap_err();
+        assert_eq!(err.decode_error(), Some(DecodeError::INVALID_DATA));
+    }
+}
+
+#[test]
+fn payload_alignment() {
+    let extent = Extent::D2 {
+        width: 6,
+        height: 5,
+    };
+    let blocks = checkerboard_blocks();
+
+    for alignment in [1, 16, 4096] {
+        let alignment = NonZeroU32::new(alignment).unwrap();
+        let options = CompressOptions {
+            super_block_size: SuperBlockChoice::Fixed(SuperBlockSize {
+                width: 2,
+                height: 2,
+            }),
+            payload_alignment: alignment,
+            ..CompressOptions::default()
+        };
+
+        let mut output = Vec::new();
+        compress_bc1_texture_opts(extent, &blocks, options, std::io::Cursor::new(&mut output))
+            .unwrap();
+
+        let (header, table) =
+            read_container(std::io::Cursor::new(&output), &DecodeLimits::default()).unwrap();
+        assert_eq!(header.payload_alignment(), alignment);
+        assert_eq!(table.len(), 9);
+        for sb in &table {
+            assert_eq!(sb.offset % alignment.get() as u64, 0);
+        }
+
+        let (_, decoded) = decompress_bc1_texture(std::io::Cursor::new(&output)).unwrap();
+        assert_eq!(decoded, blocks);
+    }
+
+    // Zero alignment is not representable.
+    let mut output = Vec::new();
+    compress_bc1_texture(extent, &blocks, std::io::Cursor::new(&mut output)).unwrap();
+    output[30..34].fill(0);
+    let err = read_header(&output[..]).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::InvalidHeader));
+}
+
+#[test]
+fn content_hash() {
+    let extent = Extent::D2 {
+        width: 6,
+        height: 5,
+    };
+    let blocks = checkerboard_blocks();
+    let expected = hash_blocks(extent, &blocks);
+
+    let compress = |blocks: &[bc1::Block], options: CompressOptions| {
+        let mut output = Vec::new();
+        compress_bc1_texture_opts(extent, blocks, options, std::io::Cursor::new(&mut output))
+            .unwrap();
+        output
+    };
+
+    // Options change compressed bytes, but not the content.
+    let variants = [
+        CompressOptions::default(),
+        CompressOptions {
+            checksums: false,
+            interleaved_colors: true,
+            predictor: Predictor::Previous,
+            canonical_indices: true,
+            ..CompressOptions::default()
+        },
+        CompressOptions {
+            super_block_size: SuperBlockChoice::Fixed(SuperBlockSize {
+                width: 2,
+                height: 4,
+            }),
+            payload_alignment: NonZeroU32::new(64).unwrap(),
+            ..CompressOptions::default()
+        },
+    ];
+
+    let streams: Vec<_> = variants
+        .iter()
+        .map(|&options| compress(&blocks, options))
+        .collect();
+    assert_ne!(streams[0], streams[1]);
+    assert_ne!(streams[0], streams[2]);
+
+    for stream in &streams {
+        let header = read_header(&stream[..]).unwrap();
+        assert_eq!(header.content_hash(), Some(expected));
+    }
+
+    // Single texel change.
+    let mut changed = blocks.clone();
+    let index = changed[17].texel_index(2, 3);
+    changed[17].set_texel_index(2, 3, (index + 1) % 4);
+    assert_ne!(hash_blocks(extent, &changed), expected);
+    let header = read_header(&compress(&changed, CompressOptions::default())[..]).unwrap();
+    assert_eq!(header.content_hash(), Some(hash_blocks(extent, &changed)));
+
+    // Stored hash that does not match is detected only with verification.
+    let mut corrupted = streams[0].clone();
+    corrupted[34] ^= 1;
+
+    let verify = DecompressOptions {
+        verify: true,
+        ..DecompressOptions::default()
+    };
+    let (_, decoded) =
+        decompress_bc1_texture_opts(std::io::Cursor::new(&streams[0]), &verify).unwrap();
+    assert_eq!(decoded, blocks);
+
+    let err = decompress_bc1_texture_opts(std::io::Cursor::new(&corrupted), &verify).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::ContentHashMismatch));
+
+    let (_, decoded) = decompress_bc1_texture(std::io::Cursor::new(&corrupted)).unwrap();
+    assert_eq!(decoded, blocks);
+}
+
+#[test]
+fn constant_super_blocks() {
+    use crate::math::Rgb565;
+
+    let super_block_size = SuperBlockSize {
+        width: 16,
+        height: 16,
+    };
+    let compress = |extent, blocks: &[bc1::Block], options| {
+        let mut output = Vec::new();
+        compress_bc1_texture_opts(extent, blocks, options, std::io::Cursor::new(&mut output))
+            .unwrap();
+        output
+    };
+
+    // Solid texture is stored as one raw block per super-block.
+    let extent = Extent::D2 {
+        width: 64,
+        height: 40,
+    };
+    let solid =
+        vec![bc1::Block::new(Rgb565::from_bits(0x1234), Rgb565::WHITE, [[2; 4]; 4]); 64 * 40];
+
+    for options in [
+        CompressOptions::default(),
+        CompressOptions {
+            interleaved_colors: true,
+            predictor: Predictor::Previous,
+            canonical_indices: true,
+            ..CompressOptions::default()
+        },
+    ] {
+        let options = CompressOptions {
+            super_block_size: SuperBlockChoice::Fixed(super_block_size),
+            ..options
+        };
+        let stream = compress(extent, &solid, options);
+
+        let header = read_header(&stream[..]).unwrap();
+        let table_start = header.bytes_size() + meta_chunks_bytes_size(header.version(), &[]);
+        let super_blocks = header.jackal_blocks_count();
+        assert_eq!(super_blocks, 4 * 3);
+        assert_eq!(
+            stream.len(),
+            table_start + super_blocks * (JackalBlock::BYTES_SIZE + 1 + 8)
+        );
+
+        let (_, decoded) = decompress_bc1_texture(std::io::Cursor::new(&stream)).unwrap();
+        assert_eq!(decoded, solid);
+    }
+
+    // Left super-blocks are constant, right ones are not.
+    let extent = Extent::D2 {
+        width: 32,
+        height: 16,
+    };
+    let mixed: Vec<bc1::Block> = (0..32 * 16)
+        .map(|i: u32| {
+            if i % 32 < 16 {
+                bc1::Block::BLACK
+            } else {
+                bc1::Block {
+                    color0: Rgb565::from_bits((i * 37) as u16),
+                    color1: Rgb565::from_bits(i as u16),
+                    texels: [i as u8, 0xE4, (i / 3) as u8, 0x1B],
+                }
+            }
+        })
+        .collect();
+
+    let options = CompressOptions {
+        super_block_size: SuperBlockChoice::Fixed(super_block_size),
+        predictor: Predictor::Previous,
+        checksums: false,
+        ..CompressOptions::default()
+    };
+    let stream = compress(extent, &mixed, options);
+
+    let mut read = std::io::Cursor::new(&stream);
+    let (header, table) = read_container(&mut read, &DecodeLimits::default()).unwrap();
+    let modes: Vec<u8> = table
+        .iter()
+        .map(|entry| stream[entry.offset as usize])
+        .collect();
+    assert_eq!(modes, [PAYLOAD_CONSTANT, PAYLOAD_ASPECTS]);
+    assert_eq!(table[0].size, 1 + 8);
+
+    let (_, decoded) = decompress_bc1_texture(std::io::Cursor::new(&stream)).unwrap();
+    assert_eq!(decoded, mixed);
+
+    let (_, endpoints) = decode_endpoints_preview(std::io::Cursor::new(&stream)).unwrap();
+    assert!(endpoints
+        .iter()
+        .zip(&mixed)
+        .all(|(&endpoints, block)| endpoints == (block.color0, block.color1)));
+
+    // Aspect of constant super-block is produced as if it was compressed.
+    let texels = decompress_bc1_aspect::<4>(
+        &header,
+        [0, 0, 0],
+        table[0],
+        2,
+        std::io::Cursor::new(&stream),
+    )
+    .unwrap();
+    assert_eq!(texels, vec![bc1::Block::BLACK.texels; 16 * 16]);
+
+    // Unknown mode and constant payload of wrong size are invalid data.
+    for (offset, byte) in [(table[0].offset, 7), (table[1].offset, PAYLOAD_CONSTANT)] {
+        let mut corrupted = stream.clone();
+        corrupted[offset as usize] = byte;
+        let err = decompress_bc1_texture(std::io::Cursor::new(&corrupted)).unwrap_err();
+        assert!(
+            matches!(err.decode_error(), Some(DecodeError::InvalidData { .. })),
+            "{err}"
+        );
+    }
+}
+
+#[test]
+fn decompress_from_slice() {
+    let extent = Extent::D2 {
+        width: 40,
+        height: 20,
+    };
+    let blocks: Vec<bc1::Block> = (0..40 * 20)
+        .map(|i: u32| bc1::Block {
+            color0: crate::math::Rgb565::from_bits((i * 37) as u16),
+            color1: crate::math::Rgb565::from_bits((i / 40) as u16),
+            texels: [(i % 7) as u8, 0xE4, (i % 3) as u8, 0x55],
+        })
+        .collect();
+    let options = CompressOptions {
+        super_block_size: SuperBlockChoice::Fixed(SuperBlockSize {
+            width: 16,
+            height: 16,
+        }),
+        ..CompressOptions::default()
+    };
+
+    let mut output = Vec::new();
+    compress_bc1_texture_opts(extent, &blocks, options, std::io::Cursor::new(&mut output)).unwrap();
+
+    let (decoded_extent, decoded) = decompress_bc1_texture_from_slice(&output).unwrap();
+    assert_eq!(decoded_extent, extent);
+    assert_eq!(decoded, blocks);
+
+    // Super-blocks decoded one by one.
+    let (header, table) =
+        read_container(std::io::Cursor::new(&output), &DecodeLimits::default()).unwrap();
+    let super_extent = header.jackal_blocks_extent();
+    let mut decoded = vec![bc1::Block::BLACK; blocks.len()];
+    for y in 0..super_extent[1] {
+        for x in 0..super_extent[0] {
+            let index = texture::block_index(super_extent, x, y, 0);
+            decompress_bc1_blocks_from_slice(
+                &header,
+                [x, y, 0],
+                table[index],
+                &output,
+                &mut decoded,
+            )
+            .unwrap();
+        }
+    }
+    assert_eq!(decoded, blocks);
+
+    // Payloads outside of the slice.
+    let last = *table.last().unwrap();
+    let invalid = DecodeError::InvalidData {
+        super_block: Some([2, 1, 0]),
+        aspect: None,
+    };
+    for jackal_block in [
+        JackalBlock {
+            offset: output.len() as u64,
+            ..last
+        },
+        JackalBlock {
+            offset: u64::MAX,
+            ..last
+        },
+        JackalBlock {
+            size: last.size + 1,
+            ..last
+        },
+    ] {
+        let err = decompress_bc1_blocks_from_slice(
+            &header,
+            [2, 1, 0],
+            jackal_block,
+            &output,
+            &mut decoded,
+        )
+        .unwrap_err();
+        assert_eq!(err.decode_error(), Some(invalid));
+    }
+
+    // Truncated data cuts off the last payload.
+    let truncated = &output[..output.len() - 1];
+    let err = decompress_bc1_texture_from_slice(truncated).unwrap_err();
+    assert_eq!(err.decode_error(), Some(invalid));
+    assert_eq!(err.stage(), Some(DecodeStage::SuperBlock(table.len() - 1)));
+}
+
+#[test]
+fn partial_decode() {
+    let extent = Extent::D2 {
+        width: 40,
+        height: 20,
+    };
+    let blocks = (0..40 * 20)
+        .map(|i: u32| bc1::Block {
+            color0: crate::math::Rgb565::from_bits((i * 37) as u16),
+            color1: crate::math::Rgb565::from_bits((i / 40) as u16),
+            texels: [(i % 7) as u8, 0xE4, (i % 3) as u8, 0x55],
+        })
+        .collect::<Vec<_>>();
+    let options = CompressOptions {
+        super_block_size: SuperBlockChoice::Fixed(SuperBlockSize {
+            width: 16,
+            height: 16,
+        }),
+        ..CompressOptions::default()
+    };
+
+    let mut output = Vec::new();
+    compress_bc1_texture_opts(extent, &blocks, options, std::io::Cursor::new(&mut output)).unwrap();
+
+    let (_, decoded, statuses) =
+        decompress_bc1_texture_partial(std::io::Cursor::new(&output), bc1::Block::BLACK).unwrap();
+    assert_eq!(decoded, blocks);
+    assert_eq!(statuses, [SuperBlockStatus::Ok; 6]);
+
+    // Corrupted payload of super-block [1, 0] is filled, others are decoded.
+    let (_, table) =
+        read_container(std::io::Cursor::new(&output), &DecodeLimits::default()).unwrap();
+    output[table[1].offset as usize + 1] ^= 0xFF;
+
+    let (_, decoded, statuses) =
+        decompress_bc1_texture_partial(std::io::Cursor::new(&output), bc1::Block::BLACK).unwrap();
+    assert_eq!(
+        statuses,
+        [
+            SuperBlockStatus::Ok,
+            SuperBlockStatus::Corrupt,
+            SuperBlockStatus::Ok,
+            SuperBlockStatus::Ok,
+            SuperBlockStatus::Ok,
+            SuperBlockStatus::Ok,
+        ]
+    );
+    for y in 0..20 {
+        for x in 0..40 {
+            let index = (x + y * 40) as usize;
+            if (16..32).contains(&x) && y < 16 {
+                assert_eq!(decoded[index], bc1::Block::BLACK);
+            } else {
+                assert_eq!(decoded[index], blocks[index]);
+            }
+        }
+    }
+
+    // Table cut short leaves remaining super-blocks unread.
+    let header = read_header(&output[..]).unwrap();
+    let truncated = &output[..header.bytes_size() + 2 * JackalBlock::BYTES_SIZE + 3];
+    let (_, decoded, statuses) =
+        decompress_bc1_texture_partial(std::io::Cursor::new(truncated), bc1::Block::BLACK).unwrap();
+    assert_eq!(statuses, [SuperBlockStatus::IoError; 6]);
+    assert!(decoded.iter().all(|&block| block == bc1::Block::BLACK));
+}
+
+#[test]
+fn aspect_bytes_report() {
+    let extent = Extent::D2 {
+        width: 32,
+        height: 32,
+    };
+
+    // Constant colors with pseudo-random indices.
+    let mut state = 0x9e37_79b9u32;
+    let blocks: Vec<bc1::Block> = (0..32 * 32)
+        .map(|_| {
+            let texels = std::array::from_fn(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            });
+            bc1::Block {
+                color0: crate::math::Rgb565::WHITE,
+                color1: crate::math::Rgb565::BLACK,
+                texels,
+            }
+        })
+        .collect();
+
+    let mut output = Vec::new();
+    let report = compress_bc1_texture_report(
+        extent,
+        &blocks,
+        CompressOptions::default(),
+        std::io::Cursor::new(&mut output),
+    )
+    .unwrap();
+
+    // Texel indices are aspect 2 of BC1 blocks.
+    let total: u64 = report.aspect_bytes.iter().sum();
+    assert!(report.aspect_bytes[2] * 10 > total * 9, "{report}");
+    assert!(total <= report.total_compressed_size());
+    assert_eq!(
+        report.aspect_bytes[2],
+        report
+            .super_blocks
+            .iter()
+            .map(|sb| sb.aspect_bytes[2])
+            .sum::<u64>()
+    );
+    assert_eq!(stat_file(std::io::Cursor::new(&output)).unwrap(), report);
+
+    let table = report.to_string();
+    assert!(table.starts_with("aspect"));
+    assert!(table.contains(&format!("\n2      {:>12}", report.aspect_bytes[2])));
+    assert!(table.lines().last().unwrap().starts_with("total"));
+
+    // Older containers keep all aspects in one stream.
+    let v2 = compress_with_version(
+        ContainerVersion::V2,
+        extent,
+        &blocks,
+        CompressOptions::default(),
+    );
+    let report = stat_file(std::io::Cursor::new(&v2)).unwrap();
+    assert_eq!(report.aspect_bytes, [0; MAX_ASPECTS]);
+}
+
+#[test]
+fn table_trailer() {
+    /// Writer without [`Seek`].
+    struct Plain(Vec<u8>);
+
+    impl Write for Plain {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let extent = Extent::D2 {
+        width: 6,
+        height: 5,
+    };
+    let blocks = checkerboard_blocks();
+
+    for table_location in [TableLocation::AfterHeader, TableLocation::Trailer] {
+        for payload_alignment in [1, 16] {
+            let options = CompressOptions {
+                super_block_size: SuperBlockChoice::Fixed(SuperBlockSize {
+                    width: 2,
+                    height: 2,
+                }),
+                payload_alignment: NonZeroU32::new(payload_alignment).unwrap(),
+                table_location,
+                ..CompressOptions::default()
+            };
+
+            let mut output = Vec::new();
+            compress_bc1_texture_opts(extent, &blocks, options, std::io::Cursor::new(&mut output))
+                .unwrap();
+
+            let (header, table) =
+                read_container(std::io::Cursor::new(&output), &DecodeLimits::default()).unwrap();
+            assert_eq!(header.table_location(), table_location);
+            assert_eq!(table.len(), 9);
+
+            let (_, decoded) = decompress_bc1_texture(std::io::Cursor::new(&output)).unwrap();
+            assert_eq!(decoded, blocks);
+
+            let (_, decoded) = decompress_bc1_texture_from_slice(&output).unwrap();
+            assert_eq!(decoded, blocks);
+
+            if table_location == TableLocation::Trailer {
+                assert_eq!(&output[output.len() - 16..][..8], b"JKLTABLE");
+
+                // Trailer layout never seeks, so any writer will do.
+                let mut plain = Plain(Vec::new());
+                compress_bc1_texture_to_stream(extent, &blocks, options, &mut plain).unwrap();
+                assert_eq!(plain.0, output);
+            } else {
+                let mut plain = Plain(Vec::new());
+                let err = compress_bc1_texture_to_stream(extent, &blocks, options, &mut plain)
+                    .unwrap_err();
+                assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+                assert!(plain.0.is_empty());
+            }
+        }
+    }
+
+    // Table cannot be found without valid trailer.
+    let options = CompressOptions {
+        table_location: TableLocation::Trailer,
+        ..CompressOptions::default()
+    };
+    let mut output = Vec::new();
+    compress_bc1_texture_opts(extent, &blocks, options, std::io::Cursor::new(&mut output)).unwrap();
+
+    let mut bad_magic = output.clone();
+    let len = bad_magic.len();
+    bad_magic[len - 16] ^= 0xFF;
+    let err = decompress_bc1_texture(std::io::Cursor::new(&bad_magic)).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::INVALID_DATA));
+
+    let mut bad_offset = output.clone();
+    bad_offset[len - 8..].copy_from_slice(&u64::MAX.to_le_bytes());
+    let err = decompress_bc1_texture(std::io::Cursor::new(&bad_offset)).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::INVALID_DATA));
+
+    // Truncated stream loses the trailer and with it the whole table.
+    let err = decompress_bc1_texture(std::io::Cursor::new(&output[..len - 1])).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::INVALID_DATA));
 }
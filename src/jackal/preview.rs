@@ -0,0 +1,362 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::{
+    bc1,
+    image::Image,
+    jackal::{
+        constant_aspects, decompress_any_block_aspect, decompress_aspect_stream,
+        decompress_aspects, has_payload_mode, parse_payload, payload_predictor, read_container,
+        read_payload, read_varint, restore_super_block_endpoints, super_block_bounds, texture,
+        AnyBlock, ContainerVersion, DecodeError, DecodeLimits, DecodeStage, DecompressError,
+        Extent, JackalBlock, JackalHeader, Payload, Predictor, MAX_ASPECTS, MAX_VARINT_BYTES,
+    },
+    math::{Rgb565, Rgb8U},
+};
+
+/// Aspects of BC1 block holding color endpoints.
+const ENDPOINT_ASPECTS: [usize; 4] = [0, 1, 3, 4];
+
+/// Decodes only color endpoints of BC1 texture, skipping texel indices.
+///
+/// Returns `(color0, color1)` of each block in the order accepted by [`compress_bc1_texture`].
+/// In [`ContainerVersion::V3`] and later containers only streams of endpoint aspects are read,
+/// so payload checksums are not verified.
+/// Older containers are read and decompressed whole.
+///
+/// [`compress_bc1_texture`]: crate::jackal::compress_bc1_texture
+pub fn decode_endpoints_preview(
+    mut read: impl Read + Seek,
+) -> Result<(Extent, Vec<(Rgb565, Rgb565)>), DecompressError> {
+    let (header, jackal_blocks) = read_container(&mut read, &DecodeLimits::default())?;
+
+    if header.format() != bc1::Block::FORMAT {
+        return Err(DecompressError::from(DecodeError::Unsupported).at(DecodeStage::Header));
+    }
+
+    let mut blocks = vec![bc1::Block::BLACK; header.blocks_count()];
+    let jackal_blocks_extent = header.jackal_blocks_extent();
+
+    for z in 0..jackal_blocks_extent[2] {
+        for y in 0..jackal_blocks_extent[1] {
+            for x in 0..jackal_blocks_extent[0] {
+                let index = texture::block_index(jackal_blocks_extent, x, y, z);
+
+                decode_super_block_endpoints(
+                    &header,
+                    [x, y, z],
+                    jackal_blocks[index],
+                    &mut blocks,
+                    &mut read,
+                )
+                .map_err(|err| err.at(DecodeStage::SuperBlock(index)))?;
+            }
+        }
+    }
+
+    let endpoints = blocks
+        .iter()
+        .map(|block| (block.color0, block.color1))
+        .collect();
+    Ok((header.extent(), endpoints))
+}
+
+/// Creates image with one pixel per block colored with average of its endpoints.
+///
+/// Image is `width` pixels wide, rows and slices or layers of the extent are stacked vertically.
+///
+/// # Panics
+///
+/// Panics if number of endpoints does not match the extent.
+pub fn endpoints_image(extent: Extent, endpoints: &[(Rgb565, Rgb565)]) -> Image<Rgb8U> {
+    let [width, height, depth] = extent.raw_size();
+    let pixels = endpoints
+        .iter()
+        .map(|&(color0, color1)| Rgb565::lerp_half(color0, color1))
+        .collect();
+    Image::from_pixels(width, height * depth, pixels)
+}
+
+/// Decodes endpoints of blocks of one super-block into `blocks` in row-major order of the texture.
+fn decode_super_block_endpoints(
+    header: &JackalHeader,
+    super_pos: [u32; 3],
+    jackal_block: JackalBlock,
+    blocks: &mut [bc1::Block],
+    read: impl Read + Seek,
+) -> Result<(), DecompressError> {
+    let raw_size = header.extent().raw_size();
+    let bounds = super_block_bounds(header, super_pos)?;
+
+    let index_of = |x0: u32, y0: u32| {
+        texture::block_index(
+            raw_size,
+            bounds.x_start + x0,
+            bounds.y_start + y0,
+            bounds.z(),
+        )
+    };
+
+    let (predictor, aspects) =
+        read_endpoint_aspects(header, super_pos, jackal_block, bounds.count(), read)?;
+
+    for aspect in ENDPOINT_ASPECTS {
+        decompress_any_block_aspect(bounds, &index_of, blocks, header, aspect, &aspects[aspect])?;
+    }
+
+    let header = header.with_predictor(predictor);
+    restore_super_block_endpoints(&header, bounds, &index_of, blocks);
+    Ok(())
+}
+
+/// Reads and decompresses endpoint aspects of super-block with `count` blocks.
+///
+/// Returns predictor of the endpoints along with the aspects.
+/// Streams of other aspects are skipped where container allows it.
+fn read_endpoint_aspects(
+    header: &JackalHeader,
+    super_pos: [u32; 3],
+    jackal_block: JackalBlock,
+    count: usize,
+    mut read: impl Read + Seek,
+) -> Result<(Predictor, [Vec<u8>; MAX_ASPECTS]), DecompressError> {
+    if let ContainerVersion::Legacy | ContainerVersion::V2 = header.version() {
+        // All aspects are in one stream, texels have to be decompressed too.
+        let payload = read_payload(header, super_pos, jackal_block, read)?;
+        let aspects = decompress_aspects::<bc1::Block>(header, super_pos, count, &payload, None)?;
+        return Ok((header.predictor(), aspects));
+    }
+
+    let invalid = |aspect| DecodeError::InvalidData {
+        super_block: Some(super_pos),
+        aspect,
+    };
+
+    // Payload must end within the stream.
+    let stream_len = read.seek(SeekFrom::End(0))?;
+    let end = match jackal_block.end() {
+        Some(end) if end <= stream_len => end,
+        _ => return Err(invalid(None).into()),
+    };
+
+    let mut offset = jackal_block.offset;
+    let mut predictor = header.predictor();
+    if has_payload_mode(header.version()) {
+        if jackal_block.is_empty() {
+            return Err(invalid(None).into());
+        }
+
+        let mut mode = [0];
+        read.seek(SeekFrom::Start(offset))?;
+        read.read_exact(&mut mode)?;
+
+        match payload_predictor(header, mode[0]) {
+            Some(mode_predictor) => predictor = mode_predictor,
+            None => {
+                // Payload of constant super-block is just one block.
+                let payload = read_payload(header, super_pos, jackal_block, read)?;
+                return match parse_payload::<bc1::Block>(header, super_pos, &payload)? {
+                    Payload::Constant(block) => {
+                        Ok((Predictor::None, constant_aspects(header, block, count)))
+                    }
+                    Payload::Aspects(..) => Err(invalid(None).into()),
+                };
+            }
+        }
+        offset += 1;
+    }
+
+    // Aspects start with varint sizes of their streams.
+    read.seek(SeekFrom::Start(offset))?;
+    let mut prefix = Vec::new();
+    (&mut read)
+        .take((end - offset).min(MAX_VARINT_BYTES * bc1::Block::ASPECTS as u64))
+        .read_to_end(&mut prefix)?;
+
+    let mut rest = &prefix[..];
+    let mut sizes = [0; MAX_ASPECTS];
+    for size in &mut sizes[..bc1::Block::ASPECTS] {
+        *size = read_varint(&mut rest).ok_or(invalid(None))?;
+    }
+
+    let mut stream_start = offset + (prefix.len() - rest.len()) as u64;
+    let mut aspects: [Vec<u8>; MAX_ASPECTS] = Default::default();
+
+    for (aspect, &size) in sizes.iter().enumerate().take(bc1::Block::ASPECTS) {
+        if size > end - stream_start {
+            return Err(invalid(Some(aspect)).into());
+        }
+
+        if size > 0 && ENDPOINT_ASPECTS.contains(&aspect) {
+            read.seek(SeekFrom::Start(stream_start))?;
+            let mut stream = Vec::new();
+            (&mut read).take(size).read_to_end(&mut stream)?;
+
+            aspects[aspect] =
+                decompress_aspect_stream::<bc1::Block>(header, super_pos, count, aspect, &stream)?;
+        }
+
+        stream_start += size;
+    }
+
+    Ok((predictor, aspects))
+}
+
+#[cfg(test)]
+struct CountingRead<R> {
+    inner: R,
+    bytes: u64,
+}
+
+#[cfg(test)]
+impl<R: Read> Read for CountingRead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+impl<R: Seek> Seek for CountingRead<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[test]
+fn test_endpoints_preview() {
+    use crate::jackal::{
+        compress_bc1_texture_opts, compress_with_version, decompress_bc1_texture, CompressOptions,
+        Predictor, SuperBlockChoice, SuperBlockSize,
+    };
+    use std::io::Cursor;
+
+    let extent = Extent::D2 {
+        width: 20,
+        height: 12,
+    };
+
+    // Color0 is greater than color1, so all four palette entries are interpolated
+    // and average of texels using each entry equally is average of endpoints.
+    let blocks: Vec<bc1::Block> = (0..20 * 12)
+        .map(|i: u32| bc1::Block {
+            color0: Rgb565::from_bits(0x8000 | (i * 91) as u16 & 0x7FFF),
+            color1: Rgb565::from_bits((i * 37) as u16 & 0x7FFF),
+            texels: [0xE4, 0x1B, 0x4E, 0xB1],
+        })
+        .collect();
+
+    let options = CompressOptions {
+        super_block_size: SuperBlockChoice::Fixed(SuperBlockSize {
+            width: 16,
+            height: 8,
+        }),
+        ..CompressOptions::default()
+    };
+
+    let mut streams = Vec::new();
+    for version in [
+        ContainerVersion::V2,
+        ContainerVersion::V3,
+        ContainerVersion::V7,
+    ] {
+        for interleaved_colors in [false, true] {
+            let options = CompressOptions {
+                interleaved_colors,
+                checksums: false,
+                ..options
+            };
+            streams.push(compress_with_version(version, extent, &blocks, options));
+        }
+    }
+
+    let mut predicted = Vec::new();
+    let options = CompressOptions {
+        predictor: Predictor::Previous,
+        ..options
+    };
+    compress_bc1_texture_opts(extent, &blocks, options, Cursor::new(&mut predicted)).unwrap();
+    streams.push(predicted);
+
+    for stream in &streams {
+        let (preview_extent, endpoints) = decode_endpoints_preview(Cursor::new(stream)).unwrap();
+        assert_eq!(preview_extent, extent);
+        assert_eq!(
+            endpoints,
+            blocks
+                .iter()
+                .map(|block| (block.color0, block.color1))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    // Preview matches fully decoded texture downsampled to block resolution.
+    let (_, decoded) = decompress_bc1_texture(Cursor::new(&streams[0])).unwrap();
+    let (_, endpoints) = decode_endpoints_preview(Cursor::new(&streams[0])).unwrap();
+    let image = endpoints_image(extent, &endpoints);
+    assert_eq!((image.width(), image.height()), (20, 12));
+
+    for (block, &preview) in decoded.iter().zip(image.pixels()) {
+        let mut sum = [0u32; 3];
+        for pixel in block.decode_rgb8().as_flattened() {
+            sum[0] += pixel.r() as u32;
+            sum[1] += pixel.g() as u32;
+            sum[2] += pixel.b() as u32;
+        }
+        let preview = [preview.r(), preview.g(), preview.b()];
+        for c in 0..3 {
+            assert!((sum[c] / 16).abs_diff(preview[c] as u32) <= 2);
+        }
+    }
+}
+
+#[test]
+fn test_endpoints_preview_reads_less() {
+    use crate::jackal::{compress_bc1_texture, decompress_bc1_texture};
+    use std::io::Cursor;
+
+    let extent = Extent::D2 {
+        width: 32,
+        height: 32,
+    };
+
+    // Pseudo-random texels do not compress, while endpoints form smooth gradient.
+    let mut state = 0x2545_f491u32;
+    let blocks: Vec<bc1::Block> = (0..32 * 32)
+        .map(|i: u32| {
+            let texels = std::array::from_fn(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            });
+            bc1::Block {
+                color0: Rgb565::from_bits((i / 32) as u16),
+                color1: Rgb565::from_bits((i % 32) as u16),
+                texels,
+            }
+        })
+        .collect();
+
+    let mut stream = Vec::new();
+    compress_bc1_texture(extent, &blocks, Cursor::new(&mut stream)).unwrap();
+
+    let mut full = CountingRead {
+        inner: Cursor::new(&stream),
+        bytes: 0,
+    };
+    decompress_bc1_texture(&mut full).unwrap();
+
+    let mut preview = CountingRead {
+        inner: Cursor::new(&stream),
+        bytes: 0,
+    };
+    decode_endpoints_preview(&mut preview).unwrap();
+
+    assert!(
+        preview.bytes * 2 < full.bytes,
+        "preview read {} bytes, full decode read {}",
+        preview.bytes,
+        full.bytes
+    );
+}
@@ -0,0 +1,192 @@
+use std::{
+    fmt,
+    io::{Read, Seek, SeekFrom},
+};
+
+use crate::jackal::{
+    payload_aspect_bytes, read_container, DecodeError, DecodeLimits, DecodeStage, DecompressError,
+    JackalBlock, JackalHeader, MAX_ASPECTS, MAX_ASPECT_SIZES_BYTES,
+};
+
+/// Progress of compression or decompression.
+///
+/// Reported after each super-block, in order of the super-block table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// Number of processed super-blocks.
+    pub super_blocks_done: usize,
+
+    /// Number of super-blocks in the texture.
+    pub super_blocks_total: usize,
+
+    /// Number of blocks in processed super-blocks.
+    pub blocks_done: usize,
+
+    /// Bytes written so far, including header and super-block table, when compressing.
+    /// Payload bytes read so far when decompressing.
+    pub bytes: u64,
+}
+
+/// Compressed size of one super-block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SuperBlockReport {
+    /// Position of the super-block in super-blocks.
+    pub position: [u32; 3],
+
+    /// Number of blocks in the super-block.
+    pub blocks: u32,
+
+    /// Size of the super-block payload in bytes.
+    pub compressed_size: u32,
+
+    /// Size of compressed stream of each block aspect in bytes.
+    ///
+    /// Only [`ContainerVersion::V3`] and later compress aspects separately,
+    /// sizes are zero for older containers and super-blocks of identical blocks.
+    ///
+    /// [`ContainerVersion::V3`]: crate::jackal::ContainerVersion::V3
+    pub aspect_bytes: [u64; MAX_ASPECTS],
+}
+
+/// Compressed sizes of all super-blocks of a texture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompressReport {
+    /// Super-blocks in order of the super-block table.
+    pub super_blocks: Vec<SuperBlockReport>,
+
+    /// Size of compressed streams of each block aspect summed over all super-blocks.
+    pub aspect_bytes: [u64; MAX_ASPECTS],
+}
+
+impl CompressReport {
+    /// Builds report from the header, super-block table
+    /// and sizes of aspect streams of each super-block.
+    pub(super) fn new(
+        header: &JackalHeader,
+        table: &[JackalBlock],
+        aspect_bytes: &[[u64; MAX_ASPECTS]],
+    ) -> Self {
+        let raw_size = header.extent().raw_size();
+        let super_block_size = header.super_block_size();
+        let extent = header.jackal_blocks_extent();
+
+        debug_assert_eq!(table.len(), header.jackal_blocks_count());
+        debug_assert_eq!(aspect_bytes.len(), table.len());
+
+        let positions = (0..extent[2]).flat_map(move |z| {
+            (0..extent[1]).flat_map(move |y| (0..extent[0]).map(move |x| [x, y, z]))
+        });
+
+        let super_blocks: Vec<_> = positions
+            .zip(table)
+            .zip(aspect_bytes)
+            .map(|((position, jackal_block), &aspect_bytes)| {
+                let width = (raw_size[0] - position[0] * super_block_size.width as u32)
+                    .min(super_block_size.width as u32);
+                let height = (raw_size[1] - position[1] * super_block_size.height as u32)
+                    .min(super_block_size.height as u32);
+
+                SuperBlockReport {
+                    position,
+                    blocks: width * height,
+                    compressed_size: jackal_block.size,
+                    aspect_bytes,
+                }
+            })
+            .collect();
+
+        let mut total = [0; MAX_ASPECTS];
+        for sb in &super_blocks {
+            for (total, bytes) in total.iter_mut().zip(sb.aspect_bytes) {
+                *total += bytes;
+            }
+        }
+
+        CompressReport {
+            super_blocks,
+            aspect_bytes: total,
+        }
+    }
+
+    /// Returns total number of blocks.
+    pub fn total_blocks(&self) -> u64 {
+        self.super_blocks.iter().map(|sb| sb.blocks as u64).sum()
+    }
+
+    /// Returns total size of super-block payloads in bytes.
+    pub fn total_compressed_size(&self) -> u64 {
+        self.super_blocks
+            .iter()
+            .map(|sb| sb.compressed_size as u64)
+            .sum()
+    }
+
+    /// Returns super-block with the largest payload.
+    pub fn largest(&self) -> Option<&SuperBlockReport> {
+        self.super_blocks.iter().max_by_key(|sb| sb.compressed_size)
+    }
+}
+
+/// Shows bytes of each aspect and their share of all payloads.
+///
+/// Bytes of payloads not attributed to any aspect, such as stream sizes
+/// and super-blocks of identical blocks, are shown as "other".
+impl fmt::Display for CompressReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total_compressed_size();
+        let share = |bytes: u64| {
+            if total == 0 {
+                0.0
+            } else {
+                bytes as f64 * 100.0 / total as f64
+            }
+        };
+
+        writeln!(f, "aspect        bytes   share")?;
+        for (aspect, &bytes) in self.aspect_bytes.iter().enumerate() {
+            if bytes > 0 {
+                writeln!(f, "{aspect:<6} {bytes:>12} {:>6.1}%", share(bytes))?;
+            }
+        }
+
+        let other = total.saturating_sub(self.aspect_bytes.iter().sum());
+        writeln!(f, "other  {other:>12} {:>6.1}%", share(other))?;
+        writeln!(f, "total  {total:>12} {:>6.1}%", share(total))
+    }
+}
+
+/// Reads header and super-block table of existing stream of any container version
+/// and reports compressed size of each super-block.
+///
+/// Sizes of aspect streams are read from the front of each payload.
+/// Payloads that extend past the end of the stream are reported as invalid data.
+pub fn stat_file(mut read: impl Read + Seek) -> Result<CompressReport, DecompressError> {
+    let (header, table) = read_container(&mut read, &DecodeLimits::default())?;
+
+    let stream_len = read.seek(SeekFrom::End(0))?;
+
+    let [width, height, _] = header.jackal_blocks_extent();
+
+    let mut aspect_bytes = Vec::with_capacity(table.len());
+    for (index, jackal_block) in table.iter().enumerate() {
+        let end = jackal_block.offset.checked_add(jackal_block.size as u64);
+        if end.is_none_or(|end| end > stream_len) {
+            let i = index as u32;
+            let err: DecompressError = DecodeError::InvalidData {
+                super_block: Some([i % width, i / width % height, i / (width * height)]),
+                aspect: None,
+            }
+            .into();
+            return Err(err.at(DecodeStage::SuperBlock(index)));
+        }
+
+        read.seek(SeekFrom::Start(jackal_block.offset))?;
+        let mut prefix = Vec::new();
+        (&mut read)
+            .take(jackal_block.len().min(MAX_ASPECT_SIZES_BYTES))
+            .read_to_end(&mut prefix)?;
+        aspect_bytes.push(payload_aspect_bytes(&header, &prefix));
+    }
+
+    Ok(CompressReport::new(&header, &table, &aspect_bytes))
+}
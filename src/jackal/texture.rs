@@ -0,0 +1,201 @@
+use crate::jackal::{AnyBlock, DecodeError, Extent, SuperBlockSize};
+
+/// Blocks of a texture together with its extent.
+///
+/// Blocks are stored row-major, x fastest, then y, then z.
+/// Extent is measured in blocks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockTexture<B> {
+    extent: Extent,
+    blocks: Vec<B>,
+}
+
+impl<B> BlockTexture<B>
+where
+    B: AnyBlock,
+{
+    /// Wraps blocks covering the extent.
+    ///
+    /// Returns [`DecodeError::InvalidExtent`] if extent is empty
+    /// or number of blocks does not match it.
+    pub fn new(extent: Extent, blocks: Vec<B>) -> Result<Self, DecodeError> {
+        let [width, height, depth] = extent.raw_size();
+
+        if extent.is_empty() || blocks.len() as u64 != width as u64 * height as u64 * depth as u64 {
+            return Err(DecodeError::InvalidExtent);
+        }
+
+        Ok(BlockTexture { extent, blocks })
+    }
+
+    /// Returns texture of the extent with all blocks set to `block`.
+    pub fn filled(extent: Extent, block: B) -> Self {
+        let [width, height, depth] = extent.raw_size();
+        let count = width as usize * height as usize * depth as usize;
+
+        BlockTexture {
+            extent,
+            blocks: vec![block; count],
+        }
+    }
+
+    pub fn extent(&self) -> Extent {
+        self.extent
+    }
+
+    pub fn as_slice(&self) -> &[B] {
+        &self.blocks
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [B] {
+        &mut self.blocks
+    }
+
+    pub fn into_parts(self) -> (Extent, Vec<B>) {
+        (self.extent, self.blocks)
+    }
+
+    /// Returns index of the block in [`BlockTexture::as_slice`]
+    /// or `None` if position is outside of the extent.
+    pub fn index(&self, x: u32, y: u32, z: u32) -> Option<usize> {
+        let [width, height, depth] = self.extent.raw_size();
+
+        if x >= width || y >= height || z >= depth {
+            return None;
+        }
+
+        Some(block_index(self.extent.raw_size(), x, y, z))
+    }
+
+    pub fn get(&self, x: u32, y: u32, z: u32) -> Option<&B> {
+        let index = self.index(x, y, z)?;
+        Some(&self.blocks[index])
+    }
+
+    pub fn get_mut(&mut self, x: u32, y: u32, z: u32) -> Option<&mut B> {
+        let index = self.index(x, y, z)?;
+        Some(&mut self.blocks[index])
+    }
+
+    /// Iterates over blocks of the super-block at `pos` row by row.
+    ///
+    /// Super-blocks at the right and bottom edges are clipped to the extent.
+    /// Yields nothing if `pos` is outside of the texture.
+    pub fn super_block_view(
+        &self,
+        super_block_size: SuperBlockSize,
+        pos: [u32; 3],
+    ) -> impl Iterator<Item = &B> + '_ {
+        let raw_size = self.extent.raw_size();
+
+        let range = |axis: usize, size: u16| {
+            let start = (pos[axis] as u64 * size as u64).min(raw_size[axis] as u64) as u32;
+            let end = (start as u64 + size as u64).min(raw_size[axis] as u64) as u32;
+            start..end
+        };
+
+        let xs = range(0, super_block_size.width);
+        let ys = if pos[2] < raw_size[2] {
+            range(1, super_block_size.height)
+        } else {
+            0..0
+        };
+        let z = pos[2];
+
+        ys.flat_map(move |y| {
+            let row = block_index(raw_size, xs.start, y, z);
+            &self.blocks[row..row + xs.len()]
+        })
+    }
+}
+
+/// Returns index of the block in row-major storage of blocks of `raw_size`.
+pub(crate) fn block_index(raw_size: [u32; 3], x: u32, y: u32, z: u32) -> usize {
+    let width = raw_size[0] as usize;
+    let height = raw_size[1] as usize;
+    x as usize + y as usize * width + z as usize * width * height
+}
+
+#[test]
+fn test_validation() {
+    use crate::bc1;
+
+    let extent = Extent::D2 {
+        width: 3,
+        height: 2,
+    };
+
+    assert!(BlockTexture::new(extent, vec![bc1::Block::BLACK; 6]).is_ok());
+    assert_eq!(
+        BlockTexture::new(extent, vec![bc1::Block::BLACK; 5]),
+        Err(DecodeError::InvalidExtent)
+    );
+    assert_eq!(
+        BlockTexture::new(
+            Extent::D2 {
+                width: 0,
+                height: 2,
+            },
+            Vec::<bc1::Block>::new(),
+        ),
+        Err(DecodeError::InvalidExtent)
+    );
+}
+
+#[test]
+fn test_3d_indexing() {
+    use crate::{bc1, math::Rgb565};
+
+    let extent = Extent::D3 {
+        width: 3,
+        height: 2,
+        depth: 4,
+    };
+
+    // Each block records its position in endpoints.
+    let blocks = (0..4)
+        .flat_map(|z| (0..2).flat_map(move |y| (0..3).map(move |x| (x, y, z))))
+        .map(|(x, y, z)| bc1::Block {
+            color0: Rgb565::from_bits((x + y * 16) as u16),
+            color1: Rgb565::from_bits(z as u16),
+            texels: [0; 4],
+        })
+        .collect();
+
+    let mut texture = BlockTexture::new(extent, blocks).unwrap();
+
+    for z in 0..4 {
+        for y in 0..2 {
+            for x in 0..3 {
+                let block = texture.get(x, y, z).unwrap();
+                assert_eq!(block.color0().bits(), (x + y * 16) as u16);
+                assert_eq!(block.color1().bits(), z as u16);
+            }
+        }
+    }
+
+    assert_eq!(texture.get(3, 0, 0), None);
+    assert_eq!(texture.get(0, 2, 0), None);
+    assert_eq!(texture.get(0, 0, 4), None);
+    assert_eq!(texture.index(2, 1, 3), Some(23));
+
+    texture.get_mut(1, 1, 2).unwrap().set_texel_index(3, 2, 3);
+    assert_eq!(texture.as_slice()[1 + 3 + 12].texel_index(3, 2), 3);
+
+    let size = SuperBlockSize {
+        width: 2,
+        height: 2,
+    };
+
+    // Right edge super-block is clipped to one column.
+    let view: Vec<_> = texture
+        .super_block_view(size, [1, 0, 3])
+        .map(|block| (block.color0().bits(), block.color1().bits()))
+        .collect();
+    assert_eq!(view, [(2, 3), (18, 3)]);
+
+    assert_eq!(texture.super_block_view(size, [0, 0, 0]).count(), 4);
+    assert_eq!(texture.super_block_view(size, [2, 0, 0]).count(), 0);
+    assert_eq!(texture.super_block_view(size, [0, 1, 0]).count(), 0);
+    assert_eq!(texture.super_block_view(size, [0, 0, 4]).count(), 0);
+}
@@ -0,0 +1,474 @@
+//! Minimal KTX2 container support.
+//!
+//! Block data is stored without supercompression.
+//! Levels are stored in the file starting from the smallest one,
+//! each aligned to least common multiple of block size and 4 bytes.
+
+use std::io::{Read, Write};
+
+use crate::jackal::{DecodeError, DecodeLimits, DecompressError, Extent, Format};
+
+const IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+const HEADER_SIZE: usize = 80;
+const LEVEL_INDEX_SIZE: usize = 24;
+
+const VK_FORMAT_BC1_RGB_UNORM_BLOCK: u32 = 131;
+const VK_FORMAT_BC2_UNORM_BLOCK: u32 = 135;
+const VK_FORMAT_BC3_UNORM_BLOCK: u32 = 137;
+const VK_FORMAT_BC4_UNORM_BLOCK: u32 = 139;
+const VK_FORMAT_BC5_UNORM_BLOCK: u32 = 141;
+
+const KHR_DF_MODEL_BC1A: u8 = 128;
+const KHR_DF_MODEL_BC2: u8 = 129;
+const KHR_DF_MODEL_BC3: u8 = 130;
+const KHR_DF_MODEL_BC4: u8 = 131;
+const KHR_DF_MODEL_BC5: u8 = 132;
+
+const KHR_DF_PRIMARIES_BT709: u8 = 1;
+const KHR_DF_TRANSFER_LINEAR: u8 = 1;
+
+fn vk_format(format: Format) -> Option<u32> {
+    match format {
+        Format::BC1 => Some(VK_FORMAT_BC1_RGB_UNORM_BLOCK),
+        Format::BC2 => Some(VK_FORMAT_BC2_UNORM_BLOCK),
+        Format::BC3 => Some(VK_FORMAT_BC3_UNORM_BLOCK),
+        Format::BC4 => Some(VK_FORMAT_BC4_UNORM_BLOCK),
+        Format::BC5 => Some(VK_FORMAT_BC5_UNORM_BLOCK),
+        Format::BC6 | Format::BC7 => None,
+    }
+}
+
+fn format_from_vk(vk_format: u32) -> Option<Format> {
+    match vk_format {
+        131..=134 => Some(Format::BC1),
+        135 | 136 => Some(Format::BC2),
+        137 | 138 => Some(Format::BC3),
+        139 | 140 => Some(Format::BC4),
+        141 | 142 => Some(Format::BC5),
+        143 | 144 => Some(Format::BC6),
+        145 | 146 => Some(Format::BC7),
+        _ => None,
+    }
+}
+
+/// Returns number of bytes of blocks covering the pixel extent or `None` on overflow.
+fn level_byte_size(extent: Extent, format: Format) -> Option<usize> {
+    format
+        .blocks_for_extent(extent)?
+        .checked_mul(format.block_byte_size())
+}
+
+/// Returns alignment of levels data.
+fn level_alignment(format: Format) -> usize {
+    // Least common multiple of block size and 4.
+    format.block_byte_size().max(4)
+}
+
+/// Builds data format descriptor for the format.
+fn build_dfd(format: Format) -> Vec<u8> {
+    // (bit offset, bit length, channel type) of each sample.
+    let (model, samples): (u8, &[(u16, u8, u8)]) = match format {
+        Format::BC1 => (KHR_DF_MODEL_BC1A, &[(0, 64, 0)]),
+        Format::BC2 => (KHR_DF_MODEL_BC2, &[(0, 64, 15), (64, 64, 0)]),
+        Format::BC3 => (KHR_DF_MODEL_BC3, &[(0, 64, 15), (64, 64, 0)]),
+        Format::BC4 => (KHR_DF_MODEL_BC4, &[(0, 64, 0)]),
+        Format::BC5 => (KHR_DF_MODEL_BC5, &[(0, 64, 0), (64, 64, 1)]),
+        Format::BC6 | Format::BC7 => unreachable!(),
+    };
+
+    let block_size = 24 + 16 * samples.len();
+    let mut dfd = Vec::with_capacity(4 + block_size);
+
+    dfd.extend_from_slice(&((4 + block_size) as u32).to_le_bytes());
+
+    // Vendor id and descriptor type are both zero for basic descriptor.
+    dfd.extend_from_slice(&0u32.to_le_bytes());
+    dfd.extend_from_slice(&2u16.to_le_bytes());
+    dfd.extend_from_slice(&(block_size as u16).to_le_bytes());
+
+    dfd.extend_from_slice(&[model, KHR_DF_PRIMARIES_BT709, KHR_DF_TRANSFER_LINEAR, 0]);
+
+    // Texel block dimensions minus one.
+    dfd.extend_from_slice(&[3, 3, 0, 0]);
+
+    // Bytes per plane.
+    dfd.extend_from_slice(&[format.block_byte_size() as u8, 0, 0, 0, 0, 0, 0, 0]);
+
+    for &(bit_offset, bit_length, channel) in samples {
+        dfd.extend_from_slice(&bit_offset.to_le_bytes());
+        dfd.extend_from_slice(&[bit_length - 1, channel]);
+        dfd.extend_from_slice(&[0; 4]);
+        dfd.extend_from_slice(&0u32.to_le_bytes());
+        dfd.extend_from_slice(&u32::MAX.to_le_bytes());
+    }
+
+    dfd
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut word = [0; 4];
+    word.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(word)
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut word = [0; 8];
+    word.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(word)
+}
+
+fn invalid_input(message: &'static str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
+
+/// Writes blocks of all mip levels into KTX2 container.
+///
+/// `extent` is the size of the base level in pixels.
+/// `levels` contains blocks bytes of each mip level starting from the base one.
+///
+/// Only BC1, BC3, BC4 and BC5 formats with 2D, 2D array and 3D extents are supported.
+pub fn export_ktx2(
+    extent: Extent,
+    format: Format,
+    levels: &[Vec<u8>],
+    mut write: impl Write,
+) -> std::io::Result<()> {
+    let vk_format = vk_format(format).ok_or_else(|| invalid_input("unsupported format"))?;
+
+    let (width, height, depth, layers) = match extent {
+        Extent::D2 { width, height } => (width, height, 0, 0),
+        Extent::D2Array {
+            width,
+            height,
+            layers,
+        } => (width, height, 0, layers),
+        Extent::D3 {
+            width,
+            height,
+            depth,
+        } => (width, height, depth, 0),
+        Extent::D1 { .. } | Extent::D1Array { .. } => {
+            return Err(invalid_input("1D extents cannot be exported to KTX2"))
+        }
+    };
+
+    if levels.is_empty() {
+        return Err(invalid_input("at least one level is required"));
+    }
+
+    for (level, bytes) in levels.iter().enumerate() {
+        if Some(bytes.len()) != level_byte_size(extent.mip_level(level as u32), format) {
+            return Err(invalid_input("level size does not match extent"));
+        }
+    }
+
+    let dfd = build_dfd(format);
+
+    let dfd_offset = HEADER_SIZE + LEVEL_INDEX_SIZE * levels.len();
+    let alignment = level_alignment(format);
+
+    // Place levels starting from the smallest one.
+    let mut offsets = vec![0; levels.len()];
+    let mut end = dfd_offset + dfd.len();
+    for (level, bytes) in levels.iter().enumerate().rev() {
+        let offset = end.next_multiple_of(alignment);
+        offsets[level] = offset;
+        end = offset + bytes.len();
+    }
+
+    let mut header = Vec::with_capacity(dfd_offset);
+    header.extend_from_slice(&IDENTIFIER);
+    header.extend_from_slice(&vk_format.to_le_bytes());
+    header.extend_from_slice(&1u32.to_le_bytes()); // typeSize
+    header.extend_from_slice(&width.to_le_bytes());
+    header.extend_from_slice(&height.to_le_bytes());
+    header.extend_from_slice(&depth.to_le_bytes());
+    header.extend_from_slice(&layers.to_le_bytes());
+    header.extend_from_slice(&1u32.to_le_bytes()); // faceCount
+    header.extend_from_slice(&(levels.len() as u32).to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes()); // supercompressionScheme
+
+    header.extend_from_slice(&(dfd_offset as u32).to_le_bytes());
+    header.extend_from_slice(&(dfd.len() as u32).to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes()); // kvdByteOffset
+    header.extend_from_slice(&0u32.to_le_bytes()); // kvdByteLength
+    header.extend_from_slice(&0u64.to_le_bytes()); // sgdByteOffset
+    header.extend_from_slice(&0u64.to_le_bytes()); // sgdByteLength
+
+    for (bytes, &offset) in levels.iter().zip(&offsets) {
+        header.extend_from_slice(&(offset as u64).to_le_bytes());
+        header.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+        header.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+    }
+
+    write.write_all(&header)?;
+    write.write_all(&dfd)?;
+
+    let mut pos = dfd_offset + dfd.len();
+    for (bytes, &offset) in levels.iter().zip(&offsets).rev() {
+        write.write_all(&[0; 16][..offset - pos])?;
+        write.write_all(bytes)?;
+        pos = offset + bytes.len();
+    }
+
+    Ok(())
+}
+
+/// Reads blocks of all mip levels from KTX2 container.
+///
+/// Returns extent of the base level in pixels, format of blocks
+/// and blocks bytes of each level starting from the base one.
+/// Extent is checked against default [`DecodeLimits`].
+pub fn import_ktx2(read: impl Read) -> Result<(Extent, Format, Vec<Vec<u8>>), DecompressError> {
+    import_ktx2_with_limits(read, &DecodeLimits::default())
+}
+
+/// Reads blocks of all mip levels from KTX2 container rejecting extents that exceed `limits`.
+///
+/// Header and level index are validated before any level is read,
+/// and each level reads no more bytes than its expected size.
+pub fn import_ktx2_with_limits(
+    mut read: impl Read,
+    limits: &DecodeLimits,
+) -> Result<(Extent, Format, Vec<Vec<u8>>), DecompressError> {
+    let mut bytes = [0; HEADER_SIZE];
+    read_exact_or(&mut read, &mut bytes, DecodeError::InvalidHeader)?;
+
+    if bytes[..12] != IDENTIFIER {
+        return Err(DecodeError::InvalidMagic.into());
+    }
+
+    let format = format_from_vk(read_u32(&bytes, 12)).ok_or(DecodeError::Unsupported)?;
+
+    if read_u32(&bytes, 16) != 1 {
+        return Err(DecodeError::InvalidHeader.into());
+    }
+
+    let width = read_u32(&bytes, 20);
+    let height = read_u32(&bytes, 24);
+    let depth = read_u32(&bytes, 28);
+    let layers = read_u32(&bytes, 32);
+    let faces = read_u32(&bytes, 36);
+    let level_count = read_u32(&bytes, 40).max(1) as usize;
+    let supercompression = read_u32(&bytes, 44);
+
+    if faces != 1 || supercompression != 0 {
+        return Err(DecodeError::Unsupported.into());
+    }
+
+    // Extent of `u32` pixels can't have more levels than bits in the side.
+    if level_count > u32::BITS as usize {
+        return Err(DecodeError::InvalidHeader.into());
+    }
+
+    let extent = match (height, depth, layers) {
+        (0, _, _) => return Err(DecodeError::Unsupported.into()),
+        (_, 0, 0) => Extent::D2 { width, height },
+        (_, 0, _) => Extent::D2Array {
+            width,
+            height,
+            layers,
+        },
+        (_, _, 0) => Extent::D3 {
+            width,
+            height,
+            depth,
+        },
+        _ => return Err(DecodeError::InvalidHeader.into()),
+    };
+
+    // Mip levels are never larger than the base one.
+    limits.check_pixel_extent(extent)?;
+
+    let mut index = vec![0; LEVEL_INDEX_SIZE * level_count];
+    read_exact_or(&mut read, &mut index, DecodeError::InvalidHeader)?;
+
+    // Levels as `(offset, length, level)`.
+    let mut order = Vec::with_capacity(level_count);
+
+    for level in 0..level_count {
+        let offset = read_u64(&index, LEVEL_INDEX_SIZE * level);
+        let length = read_u64(&index, LEVEL_INDEX_SIZE * level + 8);
+
+        let expected = level_byte_size(extent.mip_level(level as u32), format)
+            .ok_or(DecodeError::InvalidExtent)?;
+        if length != expected as u64 {
+            return Err(DecodeError::INVALID_DATA.into());
+        }
+
+        order.push((offset, expected, level));
+    }
+
+    // Levels are read in file order, skipping bytes in between.
+    order.sort_unstable();
+
+    let mut levels = vec![Vec::new(); level_count];
+    let mut pos = (HEADER_SIZE + index.len()) as u64;
+
+    for (offset, length, level) in order {
+        let gap = offset.checked_sub(pos).ok_or(DecodeError::INVALID_DATA)?;
+        if std::io::copy(&mut (&mut read).take(gap), &mut std::io::sink())? != gap {
+            return Err(DecodeError::INVALID_DATA.into());
+        }
+
+        let data = &mut levels[level];
+        data.reserve_exact(length);
+        (&mut read).take(length as u64).read_to_end(data)?;
+        if data.len() != length {
+            return Err(DecodeError::INVALID_DATA.into());
+        }
+
+        pos = offset + length as u64;
+    }
+
+    Ok((extent, format, levels))
+}
+
+/// Fills `bytes` from `read` reporting `error` if stream ends early.
+fn read_exact_or(
+    read: &mut impl Read,
+    bytes: &mut [u8],
+    error: DecodeError,
+) -> Result<(), DecompressError> {
+    read.read_exact(bytes).map_err(|err| match err.kind() {
+        std::io::ErrorKind::UnexpectedEof => error.into(),
+        _ => DecompressError::from(err),
+    })
+}
+
+#[test]
+fn test_ktx2_level_index() {
+    let extent = Extent::D2 {
+        width: 8,
+        height: 8,
+    };
+
+    let levels = vec![vec![0xAA; 32], vec![0xBB; 8]];
+
+    let mut output = Vec::new();
+    export_ktx2(extent, Format::BC1, &levels, &mut output).unwrap();
+
+    // 80 bytes header, 2 level index entries, DFD with one sample.
+    let dfd_offset = 80 + 2 * 24;
+    let dfd_length = 4 + 24 + 16;
+
+    assert_eq!(&output[..12], &IDENTIFIER);
+    assert_eq!(read_u32(&output, 12), 131);
+    assert_eq!(read_u32(&output, 40), 2);
+    assert_eq!(read_u32(&output, 48), dfd_offset as u32);
+    assert_eq!(read_u32(&output, 52), dfd_length as u32);
+
+    // Smallest level goes first, aligned to 8 bytes.
+    let expected_index: [u64; 6] = [184, 32, 32, 176, 8, 8];
+    for (i, &value) in expected_index.iter().enumerate() {
+        assert_eq!(read_u64(&output, 80 + i * 8), value);
+    }
+
+    assert_eq!(output.len(), 216);
+    assert_eq!(&output[176..184], &[0xBB; 8]);
+    assert_eq!(&output[184..216], &[0xAA; 32]);
+
+    let (imported_extent, format, imported) = import_ktx2(&output[..]).unwrap();
+    assert_eq!(imported_extent, extent);
+    assert_eq!(format, Format::BC1);
+    assert_eq!(imported, levels);
+}
+
+#[test]
+fn test_ktx2_alignment() {
+    let extent = Extent::D2Array {
+        width: 5,
+        height: 3,
+        layers: 2,
+    };
+
+    // 2x1 blocks per layer, then 1x1 blocks per layer.
+    let levels = vec![vec![1; 64], vec![2; 32], vec![3; 32]];
+
+    let mut output = Vec::new();
+    export_ktx2(extent, Format::BC3, &levels, &mut output).unwrap();
+
+    let offsets: Vec<u64> = (0..3)
+        .map(|level| read_u64(&output, 80 + level * 24))
+        .collect();
+
+    // DFD ends at 80 + 3 * 24 + 60 = 212, levels are 16 byte aligned.
+    assert_eq!(offsets, [288, 256, 224]);
+
+    let (imported_extent, format, imported) = import_ktx2(&output[..]).unwrap();
+    assert_eq!(imported_extent, extent);
+    assert_eq!(format, Format::BC3);
+    assert_eq!(imported, levels);
+}
+
+#[test]
+fn test_ktx2_invalid() {
+    let extent = Extent::D2 {
+        width: 4,
+        height: 4,
+    };
+
+    let err = export_ktx2(extent, Format::BC4, &[vec![0; 16]], std::io::sink()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    let mut output = Vec::new();
+    export_ktx2(extent, Format::BC4, &[vec![0; 8]], &mut output).unwrap();
+
+    // Truncate level data.
+    output.pop();
+    assert!(matches!(
+        import_ktx2(&output[..]),
+        Err(DecompressError::Decode(DecodeError::INVALID_DATA))
+    ));
+}
+
+#[test]
+fn test_ktx2_huge_extent() {
+    let extent = Extent::D2 {
+        width: 4,
+        height: 4,
+    };
+    let mut output = Vec::new();
+    export_ktx2(extent, Format::BC1, &[vec![0; 8]], &mut output).unwrap();
+
+    // Width, height, depth and layers.
+    for fields in [
+        [u32::MAX, u32::MAX, 0, u32::MAX],
+        [u32::MAX, u32::MAX, u32::MAX, 0],
+        [u32::MAX, u32::MAX, 0, 0],
+        [0, 4, 0, 0],
+    ] {
+        let mut bytes = output.clone();
+        for (i, value) in fields.into_iter().enumerate() {
+            bytes[20 + i * 4..24 + i * 4].copy_from_slice(&value.to_le_bytes());
+        }
+
+        let err = import_ktx2(&bytes[..]).unwrap_err();
+        assert_eq!(err.decode_error(), Some(DecodeError::InvalidExtent));
+    }
+
+    let limits = DecodeLimits {
+        max_blocks: 0,
+        ..DecodeLimits::default()
+    };
+    let err = import_ktx2_with_limits(&output[..], &limits).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::InvalidExtent));
+
+    // Limits are checked before the rest of the stream is read.
+    let endless = (&output[..]).chain(std::io::repeat(0));
+    let err = import_ktx2_with_limits(endless, &limits).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::InvalidExtent));
+
+    // Levels are read up to their size, bytes after the last level are never touched.
+    let endless = (&output[..]).chain(std::io::repeat(0));
+    let (_, _, levels) = import_ktx2(endless).unwrap();
+    assert_eq!(levels, [vec![0; 8]]);
+
+    let mut bytes = output.clone();
+    bytes[40..44].copy_from_slice(&u32::MAX.to_le_bytes());
+    let err = import_ktx2(&bytes[..]).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::InvalidHeader));
+}
@@ -3,11 +3,25 @@ pub mod bc2;
 pub mod bc3;
 pub mod bc4;
 pub mod bc5;
+#[doc(hidden)]
+pub mod bits;
 pub mod cluster_fit;
 pub mod encoder;
 pub mod filter;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod image;
+pub mod interop;
 pub mod jackal;
+pub mod ktx2;
+pub mod lzp;
+#[doc(hidden)]
+pub mod lzw;
 pub mod math;
+pub mod prelude;
+pub mod quality;
+mod simd;
+pub mod stats;
 pub mod z_curve;
 
-pub use jackal::{DecodeError, DecompressError, Extent};
+pub use jackal::{DecodeError, DecompressError, Extent, Format, JackalHeader};
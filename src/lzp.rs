@@ -0,0 +1,330 @@
+//! LZP byte stream codec.
+//!
+//! Each byte is predicted from a table indexed by hash of preceding bytes.
+//! Correctly predicted byte is coded with a single bit,
+//! otherwise the bit is followed by the literal byte.
+//!
+//! Stream starts with serialized [`LzpConfig`].
+//! Length of the data is not stored, caller must know it to decode.
+
+use std::io::{Read, Write};
+
+use crate::bits::{ReadBits, TruncatedStream, WriteBits};
+
+/// Error of decoding LZP stream.
+#[derive(Debug)]
+pub enum DecodeError {
+    Io(std::io::Error),
+
+    /// Serialized [`LzpConfig`] is out of range.
+    InvalidConfig,
+
+    /// Stream ended before requested number of bytes was decoded.
+    Truncated,
+}
+
+impl From<std::io::Error> for DecodeError {
+    #[inline(always)]
+    fn from(err: std::io::Error) -> Self {
+        DecodeError::Io(err)
+    }
+}
+
+/// Parameters of LZP coder.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LzpConfig {
+    /// Number of preceding bytes hashed to predict next one.
+    /// Must be in range `1..=8`.
+    pub context_len: u8,
+
+    /// Log2 of number of entries in prediction table.
+    /// Must be in range `1..=24`.
+    pub table_bits: u8,
+}
+
+impl Default for LzpConfig {
+    fn default() -> Self {
+        LzpConfig {
+            context_len: 3,
+            table_bits: 16,
+        }
+    }
+}
+
+impl LzpConfig {
+    pub const BYTES_SIZE: usize = 2;
+
+    pub fn is_valid(&self) -> bool {
+        (1..=8).contains(&self.context_len) && (1..=24).contains(&self.table_bits)
+    }
+
+    pub fn write_to(&self, mut write: impl Write) -> std::io::Result<()> {
+        write.write_all(&[self.context_len, self.table_bits])
+    }
+
+    pub fn read_from(mut read: impl Read) -> Result<Self, DecodeError> {
+        let mut bytes = [0; Self::BYTES_SIZE];
+        read.read_exact(&mut bytes)?;
+
+        let config = LzpConfig {
+            context_len: bytes[0],
+            table_bits: bytes[1],
+        };
+
+        if !config.is_valid() {
+            return Err(DecodeError::InvalidConfig);
+        }
+
+        Ok(config)
+    }
+}
+
+/// Prediction table shared by encoder and decoder.
+struct Model {
+    config: LzpConfig,
+    context: u64,
+    table: Vec<u8>,
+}
+
+impl Model {
+    fn new(config: LzpConfig) -> Self {
+        assert!(config.is_valid(), "Invalid LZP config {config:?}");
+
+        Model {
+            config,
+            context: 0,
+            table: vec![0; 1 << config.table_bits],
+        }
+    }
+
+    #[inline(always)]
+    fn slot(&self) -> usize {
+        let bits = self.config.context_len as u32 * 8;
+        let context = if bits < 64 {
+            self.context & ((1 << bits) - 1)
+        } else {
+            self.context
+        };
+
+        // Fibonacci hashing.
+        let hash = context.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        (hash >> (64 - self.config.table_bits)) as usize
+    }
+
+    #[inline(always)]
+    fn predict(&self) -> u8 {
+        self.table[self.slot()]
+    }
+
+    #[inline(always)]
+    fn update(&mut self, byte: u8) {
+        let slot = self.slot();
+        self.table[slot] = byte;
+        self.context = (self.context << 8) | byte as u64;
+    }
+}
+
+/// LZP encoder writing into underlying writer.
+pub struct Encoder<W: Write> {
+    model: Model,
+    write: WriteBits<W>,
+}
+
+impl<W> Encoder<W>
+where
+    W: Write,
+{
+    /// Creates encoder and writes config into the writer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if config is not valid.
+    pub fn new(config: LzpConfig, mut write: W) -> std::io::Result<Self> {
+        let model = Model::new(config);
+        config.write_to(&mut write)?;
+
+        Ok(Encoder {
+            model,
+            write: WriteBits::new(write),
+        })
+    }
+
+    /// Encodes bytes.
+    pub fn encode(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        for &byte in bytes {
+            if self.model.predict() == byte {
+                self.write_bits(&[1], 1)?;
+            } else {
+                self.write_bits(&[0], 1)?;
+                self.write_bits(&[byte], 8)?;
+            }
+
+            self.model.update(byte);
+        }
+
+        Ok(())
+    }
+
+    fn write_bits(&mut self, bits: &[u8], bit_len: usize) -> std::io::Result<()> {
+        if self.write.write_bits(bits, 0, bit_len)? != bit_len {
+            return Err(std::io::ErrorKind::WriteZero.into());
+        }
+        Ok(())
+    }
+
+    /// Writes remaining bits padded to a byte and returns underlying writer.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.write.finish()?;
+        Ok(self.write.into_inner())
+    }
+}
+
+impl<W> Write for Encoder<W>
+where
+    W: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.encode(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// LZP decoder reading from underlying reader.
+pub struct Decoder<R: Read> {
+    model: Model,
+    read: ReadBits<R>,
+}
+
+impl<R> Decoder<R>
+where
+    R: Read,
+{
+    /// Creates decoder and reads config from the reader.
+    pub fn new(mut read: R) -> Result<Self, DecodeError> {
+        let config = LzpConfig::read_from(&mut read)?;
+
+        Ok(Decoder {
+            model: Model::new(config),
+            read: ReadBits::new(read),
+        })
+    }
+
+    /// Returns config read from the stream.
+    pub fn config(&self) -> LzpConfig {
+        self.model.config
+    }
+
+    /// Decodes exactly `bytes.len()` bytes.
+    pub fn decode(&mut self, bytes: &mut [u8]) -> Result<(), DecodeError> {
+        for byte in bytes {
+            let hit = self.read_bits(1)?;
+
+            *byte = if hit != 0 {
+                self.model.predict()
+            } else {
+                self.read_bits(8)?
+            };
+
+            self.model.update(*byte);
+        }
+
+        Ok(())
+    }
+
+    fn read_bits(&mut self, bit_len: usize) -> Result<u8, DecodeError> {
+        let mut bits = [0];
+        match self.read.read_bits(&mut bits, 0, bit_len) {
+            Ok(n) if n == bit_len => Ok(bits[0]),
+            Ok(_) => Err(DecodeError::Truncated),
+            Err(err) if TruncatedStream::is_truncated(&err) => Err(DecodeError::Truncated),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Encodes bytes into LZP stream.
+pub fn compress(config: LzpConfig, bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = Encoder::new(config, Vec::new()).unwrap();
+    encoder.encode(bytes).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Decodes `len` bytes from LZP stream.
+pub fn decompress(stream: &[u8], len: usize) -> Result<Vec<u8>, DecodeError> {
+    let mut decoder = Decoder::new(stream)?;
+    let mut bytes = vec![0; len];
+    decoder.decode(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[test]
+fn test_lzp_roundtrip_random() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+
+    for context_len in 1..=8 {
+        for table_bits in [1, 8, 12, 24] {
+            let config = LzpConfig {
+                context_len,
+                table_bits,
+            };
+
+            let len = rng.gen_range(0..2000);
+
+            // Small alphabet makes predictions hit sometimes.
+            let bytes: Vec<u8> = (0..len).map(|_| rng.gen_range(0..4)).collect();
+
+            let stream = compress(config, &bytes);
+            assert_eq!(decompress(&stream, bytes.len()).unwrap(), bytes);
+        }
+    }
+}
+
+#[test]
+fn test_lzp_roundtrip_paeth() {
+    use crate::{filter, image::Image, math::Rgb8U};
+
+    let image = Image::from_fn(64, 48, |x, y| {
+        Rgb8U::new((x * 4) as u8, (y * 5) as u8, ((x + y) * 2) as u8)
+    });
+
+    let residuals = filter::paeth_forward(&image);
+    let bytes: Vec<u8> = residuals.pixels().iter().flat_map(|p| p.bytes()).collect();
+
+    let stream = compress(LzpConfig::default(), &bytes);
+
+    // Residuals of smooth image are highly repetitive.
+    assert!(stream.len() < bytes.len() / 4);
+    assert_eq!(decompress(&stream, bytes.len()).unwrap(), bytes);
+}
+
+#[test]
+fn test_lzp_invalid() {
+    assert!(matches!(
+        decompress(&[0, 16], 0),
+        Err(DecodeError::InvalidConfig)
+    ));
+    assert!(matches!(
+        decompress(&[3, 25], 0),
+        Err(DecodeError::InvalidConfig)
+    ));
+    assert!(matches!(decompress(&[3], 0), Err(DecodeError::Io(_))));
+
+    let stream = compress(LzpConfig::default(), &[1, 2, 3, 4, 5]);
+    assert!(matches!(
+        decompress(&stream, 10),
+        Err(DecodeError::Truncated)
+    ));
+
+    // Inside container any malformed LZP stream is invalid data.
+    let err = crate::jackal::DecompressError::from(DecodeError::InvalidConfig);
+    assert_eq!(
+        err.decode_error(),
+        Some(crate::jackal::DecodeError::INVALID_DATA)
+    );
+}
@@ -3,14 +3,17 @@
 //! This variation is adapted to allow efficient decoding on GPUs.
 //!
 //! Uses 8 and 16-bit alphabets.
-//! Indices in code grow by 1 bit.
+//! Indices in code grow by 1 bit, up to [`LzwConfig::max_code_bits`].
+//! When dictionary is full, it is either reset or frozen, see [`LzwConfig::reset_on_full`].
+//!
+//! Code right after the alphabet is reserved for reset symbol.
 //!
 
 use std::io::{Read, Write};
 
-use crate::bits::{ReadBits, WriteBits};
+use crate::bits::{ReadBits, TruncatedStream, WriteBits};
 
-pub(crate) trait Element: Copy + Eq {
+pub trait Element: Copy + Eq {
     // This parameter is related to the maximum size of the input data.
     // With maximum superblock size of 256x256 there's 65536 entries per block field.
     //
@@ -32,7 +35,7 @@ impl Element for u8 {
     // With 8-bit fields and maximum input size 256x256x16,
     // There can be all symbols, pairs, triples, quadruples, quintuples and some sextuples.
     // This means that the dictionary upper bound size is 2^8 * 6;
-    const MAX_INPUT_SIZE: usize = 6 * 1 << 8;
+    const MAX_INPUT_SIZE: usize = 6 * (1 << 8);
 
     const MAX_VALUE: u32 = u8::MAX as u32;
 
@@ -56,7 +59,7 @@ impl Element for u16 {
     // With 16-bit fields and maximum input size 256x256x8,
     // there can be all symbols, pairs, triples and some quadruples at the end.
     // This means that the dictionary upper bound size is 2^16 * 4;
-    const MAX_INPUT_SIZE: usize = 4 * 1 << 16;
+    const MAX_INPUT_SIZE: usize = 4 * (1 << 16);
 
     #[inline(always)]
     fn into_u32(self) -> u32 {
@@ -76,6 +79,73 @@ impl Element for u16 {
     }
 }
 
+/// Parameters of LZW coder.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LzwConfig {
+    /// Maximum number of bits in a code.
+    /// Dictionary is full when it has `2^max_code_bits` codes.
+    ///
+    /// Must be greater than number of bits in element and not greater than 24.
+    pub max_code_bits: u8,
+
+    /// If `true`, full dictionary is cleared and reset symbol is emitted.
+    /// Otherwise full dictionary is frozen.
+    pub reset_on_full: bool,
+}
+
+impl Default for LzwConfig {
+    fn default() -> Self {
+        LzwConfig {
+            max_code_bits: 20,
+            reset_on_full: true,
+        }
+    }
+}
+
+impl LzwConfig {
+    pub const MAX_CODE_BITS: u8 = 24;
+
+    const RESET_ON_FULL_BIT: u8 = 0x80;
+
+    /// Checks that config can be used with elements of type `T`.
+    pub fn is_valid_for<T: Element>(&self) -> bool {
+        let element_bits = 32 - T::MAX_VALUE.leading_zeros();
+        (element_bits + 1..=Self::MAX_CODE_BITS as u32).contains(&(self.max_code_bits as u32))
+    }
+
+    /// Encodes config into one byte.
+    pub fn encode(&self) -> u8 {
+        let mut byte = self.max_code_bits;
+        if self.reset_on_full {
+            byte |= Self::RESET_ON_FULL_BIT;
+        }
+        byte
+    }
+
+    /// Decodes config from one byte.
+    pub fn decode<T: Element>(byte: u8) -> Result<Self, DecodeError> {
+        let config = LzwConfig {
+            max_code_bits: byte & !Self::RESET_ON_FULL_BIT,
+            reset_on_full: byte & Self::RESET_ON_FULL_BIT != 0,
+        };
+
+        if !config.is_valid_for::<T>() {
+            return Err(DecodeError::InvalidConfig);
+        }
+
+        Ok(config)
+    }
+}
+
+/// Number of bits to write code for dictionary with `codes` codes.
+#[inline(always)]
+fn code_bits(codes: u32, config: &LzwConfig) -> u32 {
+    codes
+        .next_power_of_two()
+        .trailing_zeros()
+        .min(config.max_code_bits as u32)
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 struct Entry<T> {
     prefix: u32,
@@ -83,20 +153,17 @@ struct Entry<T> {
 }
 
 pub struct Encoder<T> {
+    config: LzwConfig,
     entries: Vec<Entry<T>>,
     prefix: Option<u32>,
-    subbits: u128,
-    subbits_magnitude: u128,
 }
 
-impl<T> Encoder<T> {
-    pub fn new() -> Self {
-        Encoder {
-            entries: Vec::new(),
-            prefix: None,
-            subbits: 0,
-            subbits_magnitude: 1,
-        }
+impl<T> Default for Encoder<T>
+where
+    T: Element,
+{
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -104,75 +171,53 @@ impl<T> Encoder<T>
 where
     T: Element,
 {
-    const T_ENTRIES: u32 = T::MAX_VALUE + 1;
+    const RESET: u32 = T::MAX_VALUE + 1;
+    const FIRST_ENTRY: u32 = T::MAX_VALUE + 2;
 
-    fn lookup(&self, entry: Entry<T>) -> Option<u32> {
-        for i in entry.prefix.saturating_sub(Self::T_ENTRIES)..self.entries.len() as u32 {
-            if self.entries[i as usize] == entry {
-                return Some(i + Self::T_ENTRIES);
-            }
+    pub fn new() -> Self {
+        Self::with_config(LzwConfig::default())
+    }
 
-            // if self.entries[i as usize] == entry
-            //     && self.entries[i as usize].element.close(&entry.element)
-            // {
-            //     return Some(i + Self::T_ENTRIES);
-            // }
-        }
+    /// Creates encoder with specified config.
+    ///
+    /// # Panics
+    ///
+    /// Panics if config is not valid for `T`.
+    pub fn with_config(config: LzwConfig) -> Self {
+        assert!(config.is_valid_for::<T>(), "Invalid LZW config {config:?}");
 
-        None
+        Encoder {
+            config,
+            entries: Vec::new(),
+            prefix: None,
+        }
     }
 
-    fn insert(&mut self, entry: Entry<T>) {
-        self.entries.push(entry);
+    pub fn config(&self) -> LzwConfig {
+        self.config
     }
 
-    fn flush_subbits(&mut self, writer: &mut WriteBits<impl Write>) -> std::io::Result<()> {
-        if self.subbits_magnitude > 0 {
-            let bits = 128 - self.subbits_magnitude.leading_zeros();
-            let subbit_bytes = self.subbits.to_le_bytes();
-            writer.write_bits(&subbit_bytes, 0, bits as usize)?;
+    fn max_entries(&self) -> usize {
+        (1usize << self.config.max_code_bits) - Self::FIRST_ENTRY as usize
+    }
 
-            self.subbits_magnitude = 1;
-            self.subbits = 0;
+    fn lookup(&self, entry: Entry<T>) -> Option<u32> {
+        // Entry is always inserted after its prefix.
+        for i in entry.prefix.saturating_sub(Self::FIRST_ENTRY)..self.entries.len() as u32 {
+            if self.entries[i as usize] == entry {
+                return Some(i + Self::FIRST_ENTRY);
+            }
         }
-        Ok(())
+
+        None
     }
 
     fn write(&mut self, index: u32, writer: &mut WriteBits<impl Write>) -> std::io::Result<()> {
-        let bits = (self.entries.len() + Self::T_ENTRIES as usize)
-            .next_power_of_two()
-            .trailing_zeros();
+        let bits = code_bits(self.entries.len() as u32 + Self::FIRST_ENTRY, &self.config);
 
         debug_assert!(1 << bits > index);
 
-        let index_bytes = index.to_le_bytes();
-        writer.write_bits(&index_bytes, 0, bits as usize)?;
-
-        // eprintln!("{} - {}", bits, index);
-
-        Ok(())
-    }
-
-    fn write2(&mut self, index: u32, writer: &mut WriteBits<impl Write>) -> std::io::Result<()> {
-        let size = self.entries.len() as u128 + Self::T_ENTRIES as u128;
-        debug_assert!((index as u128) < size);
-
-        match size.checked_mul(self.subbits_magnitude) {
-            None => {
-                self.flush_subbits(writer)?;
-                self.subbits = index as u128;
-                self.subbits_magnitude = size;
-            }
-            Some(next_magnitude) => {
-                self.subbits *= size;
-                self.subbits += index as u128;
-                self.subbits_magnitude = next_magnitude;
-            }
-        }
-
-        // eprintln!("{} - {}", bits, index);
-
-        Ok(())
+        writer.write_u64_bits(index as u64, bits as usize)
     }
 
     pub fn encode(&mut self, input: T, writer: &mut WriteBits<impl Write>) -> std::io::Result<()> {
@@ -189,8 +234,15 @@ where
 
         match index {
             None => {
-                self.write2(prefix, writer)?;
-                self.insert(entry);
+                self.write(prefix, writer)?;
+
+                if self.entries.len() < self.max_entries() {
+                    self.entries.push(entry);
+                } else if self.config.reset_on_full {
+                    self.write(Self::RESET, writer)?;
+                    self.entries.clear();
+                }
+
                 self.prefix = Some(input.into_u32());
             }
             Some(index) => {
@@ -205,8 +257,7 @@ where
         let Some(prefix) = self.prefix else {
             return Ok(());
         };
-        self.write2(prefix, writer)?;
-        self.flush_subbits(writer)?;
+        self.write(prefix, writer)?;
         Ok(())
     }
 }
@@ -215,6 +266,10 @@ where
 pub enum DecodeError {
     Io(std::io::Error),
     InvalidIndex,
+    InvalidConfig,
+
+    /// Decoding stopped in the middle of a phrase.
+    TrailingOutput,
 }
 
 impl From<std::io::Error> for DecodeError {
@@ -230,28 +285,28 @@ enum Output<T> {
 }
 
 pub struct Decoder<T> {
+    config: LzwConfig,
     scratch: Vec<T>,
     entries: Vec<(u32, u32)>,
     output: Output<T>,
     last: Option<Output<T>>,
 }
 
-impl<T> Decoder<T> {
-    pub fn new() -> Self {
-        Decoder {
-            scratch: Vec::new(),
-            entries: Vec::new(),
-            output: Output::Range(0, 0),
-            last: None,
-        }
+impl<T> Default for Decoder<T>
+where
+    T: Element,
+{
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    pub fn finish(&self) {
+impl<T> Decoder<T> {
+    /// Checks that all elements of the last decoded phrase are consumed.
+    pub fn finish(&self) -> Result<(), DecodeError> {
         match self.output {
-            Output::Range(start, end) if start == end => {}
-            _ => {
-                panic!("Decoder output was not consumed.");
-            }
+            Output::Range(start, end) if start == end => Ok(()),
+            _ => Err(DecodeError::TrailingOutput),
         }
     }
 }
@@ -260,116 +315,121 @@ impl<T> Decoder<T>
 where
     T: Element,
 {
-    const T_ENTRIES: u32 = T::MAX_VALUE + 1;
-
-    fn read_index(&mut self, reader: &mut ReadBits<impl Read>) -> std::io::Result<u32> {
-        let bits = (self.entries.len() as u32 + Self::T_ENTRIES + self.last.is_some() as u32)
-            .next_power_of_two()
-            .trailing_zeros();
+    const RESET: u32 = T::MAX_VALUE + 1;
+    const FIRST_ENTRY: u32 = T::MAX_VALUE + 2;
 
-        let mut index_bytes = [0; 4];
-        reader.read_bits(&mut index_bytes, 0, bits as usize)?;
+    pub fn new() -> Self {
+        Self::with_config(LzwConfig::default())
+    }
 
-        let index = u32::from_le_bytes(index_bytes);
+    /// Creates decoder with specified config.
+    /// It must match config used by the encoder.
+    ///
+    /// # Panics
+    ///
+    /// Panics if config is not valid for `T`.
+    pub fn with_config(config: LzwConfig) -> Self {
+        assert!(config.is_valid_for::<T>(), "Invalid LZW config {config:?}");
 
-        // eprintln!("{} - {}", bits, index);
+        Decoder {
+            config,
+            scratch: Vec::new(),
+            entries: Vec::new(),
+            output: Output::Range(0, 0),
+            last: None,
+        }
+    }
 
-        Ok(index)
+    pub fn config(&self) -> LzwConfig {
+        self.config
     }
 
-    fn decode_next_range<'a>(
-        &'a mut self,
-        reader: &mut ReadBits<impl Read>,
-    ) -> Result<(), DecodeError> {
-        let index = self.read_index(reader)?;
+    fn max_entries(&self) -> usize {
+        (1usize << self.config.max_code_bits) - Self::FIRST_ENTRY as usize
+    }
 
-        if index < Self::T_ENTRIES {
-            let element = T::from_u32(index);
-            // One element.
+    fn read_index(&mut self, reader: &mut ReadBits<impl Read>) -> std::io::Result<u32> {
+        // Entry for the last output is added only after next code is read.
+        let pending = self.last.is_some() && self.entries.len() < self.max_entries();
 
-            match self.last {
-                None => {}
-                Some(Output::Element(last_element)) => {
-                    let new_start = self.scratch.len() as u32;
+        let bits = code_bits(
+            self.entries.len() as u32 + Self::FIRST_ENTRY + pending as u32,
+            &self.config,
+        );
 
-                    self.scratch.push(last_element);
-                    self.scratch.push(element);
+        let index = reader.read_u64_bits(bits as usize)?;
+        Ok(index as u32)
+    }
 
-                    let new_end = self.scratch.len() as u32;
-                    self.entries.push((new_start, new_end));
-                }
-                Some(Output::Range(last_start, last_end)) => {
-                    let new_start = self.scratch.len() as u32;
+    /// Adds entry of last output followed by the element.
+    fn push_entry(&mut self, element: T) {
+        if self.entries.len() >= self.max_entries() {
+            return;
+        }
 
-                    self.scratch
-                        .extend_from_within(last_start as usize..last_end as usize);
-                    self.scratch.push(element);
+        let new_start = self.scratch.len() as u32;
 
-                    let new_end = self.scratch.len() as u32;
-                    self.entries.push((new_start, new_end));
-                }
+        match self.last {
+            None => return,
+            Some(Output::Element(last_element)) => {
+                self.scratch.push(last_element);
             }
+            Some(Output::Range(last_start, last_end)) => {
+                self.scratch
+                    .extend_from_within(last_start as usize..last_end as usize);
+            }
+        }
+        self.scratch.push(element);
 
-            self.last = Some(Output::Element(element));
-            self.output = Output::Element(element);
-        } else if index - Self::T_ENTRIES < self.entries.len() as u32 {
-            let (start, end) = self.entries[(index - Self::T_ENTRIES) as usize];
-            let element = self.scratch[start as usize];
+        let new_end = self.scratch.len() as u32;
+        self.entries.push((new_start, new_end));
+    }
 
-            match self.last {
-                None => {}
-                Some(Output::Element(last_element)) => {
-                    let new_start = self.scratch.len() as u32;
+    fn decode_next_range(&mut self, reader: &mut ReadBits<impl Read>) -> Result<(), DecodeError> {
+        let mut index = self.read_index(reader)?;
 
-                    self.scratch.push(last_element);
-                    self.scratch.push(element);
+        while index == Self::RESET {
+            if !self.config.reset_on_full {
+                return Err(DecodeError::InvalidIndex);
+            }
 
-                    let new_end = self.scratch.len() as u32;
-                    self.entries.push((new_start, new_end));
-                }
-                Some(Output::Range(last_start, last_end)) => {
-                    let new_start = self.scratch.len() as u32;
+            self.scratch.clear();
+            self.entries.clear();
+            self.last = None;
 
-                    self.scratch
-                        .extend_from_within(last_start as usize..last_end as usize);
-                    self.scratch.push(element);
+            index = self.read_index(reader)?;
+        }
 
-                    let new_end = self.scratch.len() as u32;
-                    self.entries.push((new_start, new_end));
-                }
-            }
+        if index < Self::RESET {
+            // One element.
+            let element = T::from_u32(index);
+            self.push_entry(element);
+
+            self.last = Some(Output::Element(element));
+            self.output = Output::Element(element);
+        } else if index - Self::FIRST_ENTRY < self.entries.len() as u32 {
+            let (start, end) = self.entries[(index - Self::FIRST_ENTRY) as usize];
+            let element = self.scratch[start as usize];
+            self.push_entry(element);
 
             self.last = Some(Output::Range(start, end));
             self.output = Output::Range(start, end);
-        } else {
-            match self.last {
+        } else if index - Self::FIRST_ENTRY == self.entries.len() as u32
+            && self.entries.len() < self.max_entries()
+        {
+            // Code for the entry being added right now.
+            let element = match self.last {
                 None => return Err(DecodeError::InvalidIndex),
-                Some(Output::Element(last_element)) => {
-                    let new_start = self.scratch.len() as u32;
-
-                    self.scratch.push(last_element);
-                    self.scratch.push(last_element);
-
-                    let new_end = self.scratch.len() as u32;
-                    self.entries.push((new_start, new_end));
+                Some(Output::Element(last_element)) => last_element,
+                Some(Output::Range(last_start, _)) => self.scratch[last_start as usize],
+            };
+            self.push_entry(element);
 
-                    self.last = Some(Output::Range(new_start, new_end));
-                    self.output = Output::Range(new_start, new_end);
-                }
-                Some(Output::Range(last_start, last_end)) => {
-                    let new_start = self.scratch.len() as u32;
-
-                    self.scratch
-                        .extend_from_within(last_start as usize..last_end as usize);
-                    self.scratch.push(self.scratch[last_start as usize]);
-
-                    let new_end = self.scratch.len() as u32;
-                    self.entries.push((new_start, new_end));
-
-                    self.last = Some(Output::Range(new_start, new_end));
-                    self.output = Output::Range(new_start, new_end);
-                }
-            }
+            let (start, end) = *self.entries.last().unwrap();
+            self.last = Some(Output::Range(start, end));
+            self.output = Output::Range(start, end);
+        } else {
+            return Err(DecodeError::InvalidIndex);
         }
 
         Ok(())
@@ -394,6 +454,185 @@ where
     }
 }
 
+/// Byte-oriented LZW compressor implementing [`Write`].
+///
+/// Config is not written, reader must be created with the same config.
+/// [`LzwWriter::finish`] must be called to write the last code and padding bits.
+pub struct LzwWriter<W: Write> {
+    encoder: Option<Encoder<u8>>,
+    writer: Option<WriteBits<W>>,
+}
+
+impl<W> LzwWriter<W>
+where
+    W: Write,
+{
+    pub fn new(write: W) -> Self {
+        Self::with_config(LzwConfig::default(), write)
+    }
+
+    /// Creates writer with specified config.
+    ///
+    /// # Panics
+    ///
+    /// Panics if config is not valid for bytes.
+    pub fn with_config(config: LzwConfig, write: W) -> Self {
+        LzwWriter {
+            encoder: Some(Encoder::with_config(config)),
+            writer: Some(WriteBits::new(write)),
+        }
+    }
+
+    /// Writes pending code and pads last byte with zeros.
+    ///
+    /// Returns underlying writer.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        let encoder = self.encoder.take().unwrap();
+        let mut writer = self.writer.take().unwrap();
+
+        encoder.finish(&mut writer)?;
+        writer.finish()?;
+        writer.flush()?;
+        Ok(writer.into_inner())
+    }
+}
+
+impl<W> Write for LzwWriter<W>
+where
+    W: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let encoder = self.encoder.as_mut().unwrap();
+        let writer = self.writer.as_mut().unwrap();
+
+        for &byte in buf {
+            encoder.encode(byte, writer)?;
+        }
+        Ok(buf.len())
+    }
+
+    /// Flushes whole bytes of written codes.
+    ///
+    /// Pending code is kept, as following bytes may extend it.
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.as_mut().unwrap().flush()
+    }
+}
+
+impl<W> Drop for LzwWriter<W>
+where
+    W: Write,
+{
+    fn drop(&mut self) {
+        if cfg!(debug_assertions) && self.encoder.is_some() && !std::thread::panicking() {
+            panic!("LzwWriter dropped without calling `finish`");
+        }
+    }
+}
+
+/// Byte-oriented LZW decompressor implementing [`Read`].
+///
+/// Stream ends when fewer bits than the next code remain,
+/// which is the case for padding written by [`LzwWriter::finish`].
+/// Streams must not be followed by other data.
+pub struct LzwReader<R> {
+    decoder: Decoder<u8>,
+    reader: ReadBits<R>,
+}
+
+impl<R> LzwReader<R>
+where
+    R: Read,
+{
+    pub fn new(read: R) -> Self {
+        Self::with_config(LzwConfig::default(), read)
+    }
+
+    /// Creates reader with specified config.
+    /// It must match config used by the writer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if config is not valid for bytes.
+    pub fn with_config(config: LzwConfig, read: R) -> Self {
+        LzwReader {
+            decoder: Decoder::with_config(config),
+            reader: ReadBits::new(read),
+        }
+    }
+}
+
+impl<R> Read for LzwReader<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        for (i, byte) in buf.iter_mut().enumerate() {
+            match self.decoder.decode_next(&mut self.reader) {
+                Ok(element) => *byte = element,
+                Err(DecodeError::Io(err)) if is_padding(&err) => return Ok(i),
+                Err(DecodeError::Io(err)) => return Err(err),
+                Err(
+                    DecodeError::InvalidIndex
+                    | DecodeError::InvalidConfig
+                    | DecodeError::TrailingOutput,
+                ) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "invalid LZW code",
+                    ));
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+}
+
+/// Returns `true` if stream ended with less than a byte of bits left.
+fn is_padding(err: &std::io::Error) -> bool {
+    err.get_ref()
+        .and_then(|err| err.downcast_ref::<TruncatedStream>())
+        .is_some_and(|truncated| truncated.bits_read < 8)
+}
+
+/// Encodes elements into payload that starts with config byte.
+pub fn compress<T>(config: LzwConfig, input: &[T]) -> Vec<u8>
+where
+    T: Element,
+{
+    let mut encoder = Encoder::with_config(config);
+    let mut payload = vec![config.encode()];
+
+    let mut writer = WriteBits::new(&mut payload);
+    for &element in input {
+        encoder.encode(element, &mut writer).unwrap();
+    }
+    encoder.finish(&mut writer).unwrap();
+    writer.finish().unwrap();
+
+    payload
+}
+
+/// Decodes `len` elements from payload produced by [`compress`].
+pub fn decompress<T>(payload: &[u8], len: usize) -> Result<Vec<T>, DecodeError>
+where
+    T: Element,
+{
+    let (&config, payload) = payload.split_first().ok_or(DecodeError::InvalidConfig)?;
+    let config = LzwConfig::decode::<T>(config)?;
+
+    let mut decoder = Decoder::with_config(config);
+    let mut reader = ReadBits::new(payload);
+
+    let mut output = Vec::with_capacity(len);
+    for _ in 0..len {
+        output.push(decoder.decode_next(&mut reader)?);
+    }
+    decoder.finish()?;
+
+    Ok(output)
+}
+
 #[test]
 fn test_u16() {
     let mut encoder = Encoder::<u16>::new();
@@ -423,3 +662,168 @@ fn test_u16() {
         decoded += 1;
     }
 }
+
+#[test]
+fn test_small_dictionary() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(5);
+
+    // Small alphabet repeats often and fills 9-bit dictionary many times.
+    let data: Vec<u8> = (0..20000).map(|_| rng.gen_range(0..6)).collect();
+
+    for reset_on_full in [true, false] {
+        let config = LzwConfig {
+            max_code_bits: 9,
+            reset_on_full,
+        };
+
+        let payload = compress(config, &data);
+        assert_eq!(payload[0], config.encode());
+        assert_eq!(decompress::<u8>(&payload, data.len()).unwrap(), data);
+    }
+
+    let config = LzwConfig {
+        max_code_bits: 17,
+        reset_on_full: true,
+    };
+    let data: Vec<u16> = (0..20000).map(|i| (i % 7 * 1000) as u16).collect();
+    let payload = compress(config, &data);
+    assert_eq!(decompress::<u16>(&payload, data.len()).unwrap(), data);
+}
+
+#[test]
+fn test_reset_count() {
+    let config = LzwConfig {
+        max_code_bits: 9,
+        reset_on_full: true,
+    };
+
+    // Count reset symbols in the stream.
+    let data: Vec<u8> = (0..5000u32).map(|i| (i * i % 251) as u8).collect();
+
+    let mut encoder = Encoder::with_config(config);
+    let mut compressed = Vec::new();
+    let mut writer = WriteBits::new(&mut compressed);
+    let mut resets = 0;
+    for &byte in &data {
+        let before = encoder.entries.len();
+        encoder.encode(byte, &mut writer).unwrap();
+        if before > 0 && encoder.entries.is_empty() {
+            resets += 1;
+        }
+    }
+    encoder.finish(&mut writer).unwrap();
+    writer.finish().unwrap();
+
+    assert!(resets > 2, "Expected multiple resets, got {resets}");
+
+    let mut decoder = Decoder::<u8>::with_config(config);
+    let mut reader = ReadBits::new(&compressed[..]);
+    for &byte in &data {
+        assert_eq!(decoder.decode_next(&mut reader).unwrap(), byte);
+    }
+}
+
+#[test]
+fn test_invalid_config() {
+    assert!(matches!(
+        LzwConfig::decode::<u8>(8),
+        Err(DecodeError::InvalidConfig)
+    ));
+    assert!(matches!(
+        LzwConfig::decode::<u16>(16 | 0x80),
+        Err(DecodeError::InvalidConfig)
+    ));
+    assert!(matches!(
+        LzwConfig::decode::<u8>(25),
+        Err(DecodeError::InvalidConfig)
+    ));
+    assert_eq!(
+        LzwConfig::decode::<u8>(9 | 0x80).unwrap(),
+        LzwConfig {
+            max_code_bits: 9,
+            reset_on_full: true,
+        }
+    );
+    assert!(matches!(
+        decompress::<u8>(&[], 0),
+        Err(DecodeError::InvalidConfig)
+    ));
+}
+
+#[test]
+fn test_adapters_odd_chunks() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(7);
+
+    // Runs of few symbols compress well and fill the dictionary.
+    let data: Vec<u8> = (0..30000)
+        .map(|i| {
+            if i % 97 < 60 {
+                rng.gen_range(0..4)
+            } else {
+                rng.gen()
+            }
+        })
+        .collect();
+
+    let config = LzwConfig {
+        max_code_bits: 10,
+        reset_on_full: true,
+    };
+
+    for chunk in [1, 3, 7, 13, 1000] {
+        let mut writer = LzwWriter::with_config(config, Vec::new());
+        for part in data.chunks(chunk) {
+            writer.write_all(part).unwrap();
+        }
+        writer.flush().unwrap();
+        let compressed = writer.finish().unwrap();
+
+        // Same codes as element-wise encoder.
+        assert_eq!(compressed[..], compress(config, &data)[1..]);
+
+        let mut reader = LzwReader::with_config(config, &compressed[..]);
+        let mut decoded = Vec::new();
+        let mut buf = [0; 13];
+        loop {
+            let len = reader.read(&mut buf[..chunk.min(13)]).unwrap();
+            if len == 0 {
+                break;
+            }
+            decoded.extend_from_slice(&buf[..len]);
+        }
+        assert_eq!(decoded, data, "chunk {chunk}");
+    }
+
+    // Empty stream.
+    let compressed = LzwWriter::new(Vec::new()).finish().unwrap();
+    assert!(compressed.is_empty());
+    let mut decoded = Vec::new();
+    LzwReader::new(&compressed[..])
+        .read_to_end(&mut decoded)
+        .unwrap();
+    assert!(decoded.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "finish")]
+#[cfg(debug_assertions)]
+fn test_writer_unfinished() {
+    let mut writer = LzwWriter::new(Vec::new());
+    writer.write_all(b"abc").unwrap();
+}
+
+#[test]
+fn test_decompress_mid_phrase() {
+    let compressed = compress(LzwConfig::default(), &[1u8; 8]);
+    assert_eq!(decompress::<u8>(&compressed, 8).unwrap(), [1; 8]);
+
+    // Runs are encoded as growing phrases, so shorter lengths stop inside one.
+    assert!(matches!(
+        decompress::<u8>(&compressed, 5),
+        Err(DecodeError::TrailingOutput)
+    ));
+}
@@ -1,8 +1,15 @@
 //! All the math functions are implemented here.
+//!
+//! With `serde` feature vectors and colors are serialized as their raw
+//! representation: `Rgb565` as a single `u16`, `R8U` and `R32F` as a number,
+//! other types as a sequence of channels in memory order, e.g. `Rgb8U` as `[r, g, b]`.
+//! `Region3` is serialized as a struct with `min` and `max` fields.
 
 use std::{
+    fmt,
     hash::Hash,
-    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign},
+    ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign},
+    sync::OnceLock,
 };
 
 #[inline(always)]
@@ -17,6 +24,45 @@ pub fn lerp(a: f32, b: f32, t: f32) -> f32 {
     }
 }
 
+/// Average of two bytes rounded down, computed without overflow.
+#[inline(always)]
+pub const fn avg_floor(a: u8, b: u8) -> u8 {
+    (a & b) + ((a ^ b) >> 1)
+}
+
+/// Decodes sRGB-encoded value into linear light.
+#[inline]
+pub fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encodes linear light value with sRGB transfer function.
+#[inline]
+pub fn linear_to_srgb(v: f32) -> f32 {
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Decodes 8-bit sRGB value into linear light using lookup table.
+#[inline]
+pub fn srgb8_to_linear(v: u8) -> f32 {
+    static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| std::array::from_fn(|v| srgb_to_linear(v as f32 / 255.0)))[v as usize]
+}
+
+/// Encodes linear light value into the nearest 8-bit sRGB value.
+#[inline]
+pub fn linear_to_srgb8(v: f32) -> u8 {
+    (linear_to_srgb(v.clamp(0.0, 1.0)) * 255.0).round() as u8
+}
+
 /// Trait to create additive identity element.
 pub trait Zero {
     fn zero() -> Self;
@@ -30,6 +76,11 @@ impl Zero for f32 {
 
 /// A 2D vector.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 #[repr(transparent)]
 pub struct Vec2([f32; 2]);
 
@@ -107,6 +158,48 @@ impl DivAssign<f32> for Vec2 {
     }
 }
 
+impl Mul for Vec2 {
+    type Output = Vec2;
+
+    #[inline(always)]
+    fn mul(self, rhs: Vec2) -> Vec2 {
+        Vec2([self.x() * rhs.x(), self.y() * rhs.y()])
+    }
+}
+
+impl MulAssign for Vec2 {
+    #[inline(always)]
+    fn mul_assign(&mut self, rhs: Self) {
+        self.0[0] *= rhs.x();
+        self.0[1] *= rhs.y();
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Vec2;
+
+    #[inline(always)]
+    fn neg(self) -> Vec2 {
+        Vec2([-self.x(), -self.y()])
+    }
+}
+
+impl Index<usize> for Vec2 {
+    type Output = f32;
+
+    #[inline(always)]
+    fn index(&self, index: usize) -> &f32 {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<usize> for Vec2 {
+    #[inline(always)]
+    fn index_mut(&mut self, index: usize) -> &mut f32 {
+        &mut self.0[index]
+    }
+}
+
 impl Vec2 {
     pub const ZERO: Vec2 = Vec2([0.0, 0.0]);
 
@@ -120,6 +213,30 @@ impl Vec2 {
         Vec2([value, value])
     }
 
+    /// Returns componentwise minimum of two vectors.
+    #[inline(always)]
+    pub const fn min(a: Self, b: Self) -> Self {
+        Vec2([a.x().min(b.x()), a.y().min(b.y())])
+    }
+
+    /// Returns componentwise maximum of two vectors.
+    #[inline(always)]
+    pub const fn max(a: Self, b: Self) -> Self {
+        Vec2([a.x().max(b.x()), a.y().max(b.y())])
+    }
+
+    /// Clamps each component between corresponding components of `min` and `max`.
+    #[inline(always)]
+    pub const fn clamp(self, min: Self, max: Self) -> Self {
+        Vec2::min(Vec2::max(self, min), max)
+    }
+
+    /// Returns vector of absolute values of components.
+    #[inline(always)]
+    pub const fn abs(self) -> Self {
+        Vec2([self.x().abs(), self.y().abs()])
+    }
+
     #[inline(always)]
     pub const fn dot(self, rhs: Vec2) -> f32 {
         self.x() * rhs.x() + self.y() * rhs.y()
@@ -313,6 +430,11 @@ impl Vec2 {
 
 /// A 3D vector.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 #[repr(transparent)]
 pub struct Vec3([f32; 3]);
 
@@ -394,6 +516,58 @@ impl DivAssign<f32> for Vec3 {
     }
 }
 
+impl Mul for Vec3 {
+    type Output = Vec3;
+
+    #[inline(always)]
+    fn mul(self, rhs: Vec3) -> Vec3 {
+        Vec3([self.x() * rhs.x(), self.y() * rhs.y(), self.z() * rhs.z()])
+    }
+}
+
+impl MulAssign for Vec3 {
+    #[inline(always)]
+    fn mul_assign(&mut self, rhs: Self) {
+        self.0[0] *= rhs.x();
+        self.0[1] *= rhs.y();
+        self.0[2] *= rhs.z();
+    }
+}
+
+impl Neg for Vec3 {
+    type Output = Vec3;
+
+    #[inline(always)]
+    fn neg(self) -> Vec3 {
+        Vec3([-self.x(), -self.y(), -self.z()])
+    }
+}
+
+/// Indexes components of the vector.
+///
+/// Index out of range panics.
+///
+/// ```should_panic
+/// # use jkl::math::Vec3;
+/// let v = Vec3::new(1.0, 2.0, 3.0);
+/// let _ = v[3];
+/// ```
+impl Index<usize> for Vec3 {
+    type Output = f32;
+
+    #[inline(always)]
+    fn index(&self, index: usize) -> &f32 {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<usize> for Vec3 {
+    #[inline(always)]
+    fn index_mut(&mut self, index: usize) -> &mut f32 {
+        &mut self.0[index]
+    }
+}
+
 impl Vec3 {
     pub const ZERO: Vec3 = Vec3([0.0, 0.0, 0.0]);
 
@@ -406,6 +580,30 @@ impl Vec3 {
         Vec3([value, value, value])
     }
 
+    /// Returns componentwise minimum of two vectors.
+    #[inline(always)]
+    pub const fn min(a: Self, b: Self) -> Self {
+        Vec3([a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z())])
+    }
+
+    /// Returns componentwise maximum of two vectors.
+    #[inline(always)]
+    pub const fn max(a: Self, b: Self) -> Self {
+        Vec3([a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z())])
+    }
+
+    /// Clamps each component between corresponding components of `min` and `max`.
+    #[inline(always)]
+    pub const fn clamp(self, min: Self, max: Self) -> Self {
+        Vec3::min(Vec3::max(self, min), max)
+    }
+
+    /// Returns vector of absolute values of components.
+    #[inline(always)]
+    pub const fn abs(self) -> Self {
+        Vec3([self.x().abs(), self.y().abs(), self.z().abs()])
+    }
+
     #[inline(always)]
     pub const fn dot(self, rhs: Vec3) -> f32 {
         self.x() * rhs.x() + self.y() * rhs.y() + self.z() * rhs.z()
@@ -1048,6 +1246,11 @@ impl Vec3 {
 
 /// A 4D vector.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 #[repr(transparent)]
 pub struct Vec4([f32; 4]);
 
@@ -1153,6 +1356,55 @@ impl DivAssign<f32> for Vec4 {
     }
 }
 
+impl Mul for Vec4 {
+    type Output = Vec4;
+
+    #[inline(always)]
+    fn mul(self, rhs: Vec4) -> Vec4 {
+        Vec4([
+            self.x() * rhs.x(),
+            self.y() * rhs.y(),
+            self.z() * rhs.z(),
+            self.w() * rhs.w(),
+        ])
+    }
+}
+
+impl MulAssign for Vec4 {
+    #[inline(always)]
+    fn mul_assign(&mut self, rhs: Self) {
+        self.0[0] *= rhs.x();
+        self.0[1] *= rhs.y();
+        self.0[2] *= rhs.z();
+        self.0[3] *= rhs.w();
+    }
+}
+
+impl Neg for Vec4 {
+    type Output = Vec4;
+
+    #[inline(always)]
+    fn neg(self) -> Vec4 {
+        Vec4([-self.x(), -self.y(), -self.z(), -self.w()])
+    }
+}
+
+impl Index<usize> for Vec4 {
+    type Output = f32;
+
+    #[inline(always)]
+    fn index(&self, index: usize) -> &f32 {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<usize> for Vec4 {
+    #[inline(always)]
+    fn index_mut(&mut self, index: usize) -> &mut f32 {
+        &mut self.0[index]
+    }
+}
+
 impl Vec4 {
     pub const ZERO: Vec4 = Vec4([0.0, 0.0, 0.0, 0.0]);
 
@@ -1166,6 +1418,45 @@ impl Vec4 {
         Vec4([value, value, value, value])
     }
 
+    /// Returns componentwise minimum of two vectors.
+    #[inline(always)]
+    pub const fn min(a: Self, b: Self) -> Self {
+        Vec4([
+            a.x().min(b.x()),
+            a.y().min(b.y()),
+            a.z().min(b.z()),
+            a.w().min(b.w()),
+        ])
+    }
+
+    /// Returns componentwise maximum of two vectors.
+    #[inline(always)]
+    pub const fn max(a: Self, b: Self) -> Self {
+        Vec4([
+            a.x().max(b.x()),
+            a.y().max(b.y()),
+            a.z().max(b.z()),
+            a.w().max(b.w()),
+        ])
+    }
+
+    /// Clamps each component between corresponding components of `min` and `max`.
+    #[inline(always)]
+    pub const fn clamp(self, min: Self, max: Self) -> Self {
+        Vec4::min(Vec4::max(self, min), max)
+    }
+
+    /// Returns vector of absolute values of components.
+    #[inline(always)]
+    pub const fn abs(self) -> Self {
+        Vec4([
+            self.x().abs(),
+            self.y().abs(),
+            self.z().abs(),
+            self.w().abs(),
+        ])
+    }
+
     #[inline(always)]
     pub const fn dot(self, rhs: Vec4) -> f32 {
         self.x() * rhs.x() + self.y() * rhs.y() + self.z() * rhs.z() + self.w() * rhs.w()
@@ -2899,6 +3190,7 @@ impl Vec4 {
 
 /// A region in 3D space defined by a points on a diagonal.
 #[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Region3 {
     pub min: Vec3,
     pub max: Vec3,
@@ -2906,14 +3198,12 @@ pub struct Region3 {
 
 impl Region3 {
     pub fn new(points: impl Iterator<Item = Vec3>) -> Self {
-        let mut min = Vec3([f32::INFINITY; 3]);
-        let mut max = Vec3([f32::NEG_INFINITY; 3]);
+        let mut min = Vec3::splat(f32::INFINITY);
+        let mut max = Vec3::splat(f32::NEG_INFINITY);
 
         for point in points {
-            for i in 0..3 {
-                min.0[i] = min.0[i].min(point.0[i]);
-                max.0[i] = max.0[i].max(point.0[i]);
-            }
+            min = Vec3::min(min, point);
+            max = Vec3::max(max, point);
         }
 
         Region3 { min, max }
@@ -2996,13 +3286,38 @@ impl Region3 {
         self.min.x() <= self.max.x() && self.min.y() <= self.max.y() && self.min.z() <= self.max.z()
     }
 
+    /// Returns size of the region along each axis.
+    ///
+    /// Empty regions have zero extent.
+    pub fn extent(&self) -> Vec3 {
+        Vec3::max(self.max - self.min, Vec3::ZERO)
+    }
+
+    /// Returns volume of the region, zero for empty regions.
     pub fn volume(&self) -> f32 {
-        let diff = self.max - self.min;
-        diff.x().min(0.0) * diff.y().min(0.0) * diff.z().min(0.0)
+        if self.is_empty() {
+            return 0.0;
+        }
+        let extent = self.extent();
+        extent.x() * extent.y() * extent.z()
+    }
+
+    /// Returns surface area of the region, zero for empty regions.
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let extent = self.extent();
+        2.0 * (extent.x() * extent.y() + extent.y() * extent.z() + extent.z() * extent.x())
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 #[repr(transparent)]
 pub struct R8U(u8);
 
@@ -3035,9 +3350,10 @@ impl R8U {
         R32F(self.0 as f32 / 255.0)
     }
 
+    /// Rounds to the nearest representable value.
     #[inline(always)]
     pub const fn from_f32(luma: R32F) -> R8U {
-        let clamped = (luma.r() * 255.0).clamp(0.0, 255.0);
+        let clamped = (luma.r() * 255.0 + 0.5).clamp(0.0, 255.0);
         R8U(clamped as u8)
     }
 
@@ -3051,6 +3367,27 @@ impl R8U {
         R8U(self.0.wrapping_sub(other.0))
     }
 
+    #[inline(always)]
+    pub const fn saturating_add(self, other: Self) -> Self {
+        R8U(self.0.saturating_add(other.0))
+    }
+
+    #[inline(always)]
+    pub const fn saturating_sub(self, other: Self) -> Self {
+        R8U(self.0.saturating_sub(other.0))
+    }
+
+    /// Returns average of two values rounded down.
+    #[inline(always)]
+    pub const fn avg_floor(self, other: Self) -> Self {
+        R8U(avg_floor(self.0, other.0))
+    }
+
+    #[inline(always)]
+    pub const fn abs_diff(self, other: Self) -> Self {
+        R8U(self.0.abs_diff(other.0))
+    }
+
     #[inline(always)]
     pub const fn diff(a: Self, b: Self) -> f32 {
         a.r() as f32 - b.r() as f32
@@ -3064,12 +3401,57 @@ impl R8U {
 
     #[inline(always)]
     pub fn distance(a: Self, b: Self) -> f32 {
-        Self::diff(a, b)
+        Self::diff(a, b).abs()
+    }
+}
+
+/// Two-channel color represented as 2 bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+#[repr(transparent)]
+pub struct Rg8U([u8; 2]);
+
+impl Rg8U {
+    pub const WHITE: Rg8U = Rg8U([255, 255]);
+    pub const BLACK: Rg8U = Rg8U([0, 0]);
+
+    #[inline(always)]
+    pub const fn new(r: u8, g: u8) -> Self {
+        Rg8U([r, g])
+    }
+
+    #[inline(always)]
+    pub const fn r(&self) -> u8 {
+        self.0[0]
+    }
+
+    #[inline(always)]
+    pub const fn g(&self) -> u8 {
+        self.0[1]
+    }
+
+    #[inline(always)]
+    pub const fn bytes(&self) -> [u8; 2] {
+        self.0
+    }
+
+    #[inline(always)]
+    pub const fn into_f32(self) -> Rg32F {
+        Rg32F([self.0[0] as f32 / 255.0, self.0[1] as f32 / 255.0])
     }
 }
 
 /// An RGB color represented as 3 floats.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 #[repr(transparent)]
 pub struct R32F(f32);
 
@@ -3110,7 +3492,7 @@ impl R32F {
 
     #[inline(always)]
     pub fn distance(a: Self, b: Self) -> f32 {
-        Self::diff(a, b)
+        Self::diff(a, b).abs()
     }
 
     #[inline(always)]
@@ -3121,6 +3503,11 @@ impl R32F {
 
 /// An RGB color represented as 3 floats.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 #[repr(transparent)]
 pub struct Rg32F([f32; 2]);
 
@@ -3133,6 +3520,18 @@ impl Rg32F {
         Rg32F([r, g])
     }
 
+    /// Creates color from channels in memory order.
+    #[inline(always)]
+    pub const fn from_array(channels: [f32; 2]) -> Self {
+        Rg32F(channels)
+    }
+
+    /// Returns channels in memory order.
+    #[inline(always)]
+    pub const fn to_array(self) -> [f32; 2] {
+        self.0
+    }
+
     #[inline(always)]
     pub const fn r(&self) -> f32 {
         self.0[0]
@@ -3176,7 +3575,12 @@ impl Rg32F {
 }
 
 /// An RGB color with 8 bit unsigned normalized integers per channel.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 #[repr(transparent)]
 pub struct Rgb8U([u8; 3]);
 
@@ -3246,6 +3650,12 @@ impl Rgb8U {
         Rgb32F([r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0])
     }
 
+    /// Decodes sRGB-encoded color into linear light.
+    #[inline(always)]
+    pub fn to_linear_f32(self) -> Rgb32F {
+        Rgb32F(self.0.map(srgb8_to_linear))
+    }
+
     #[inline(always)]
     pub fn from_f32(rgb: Rgb32F) -> Self {
         let r = (rgb.r() * 255.0).clamp(0.0, 255.0) as u8;
@@ -3270,6 +3680,40 @@ impl Rgb8U {
         Rgb8U::new(r, g, b)
     }
 
+    #[inline(always)]
+    pub const fn saturating_add(lhs: Self, rhs: Self) -> Self {
+        let r = lhs.r().saturating_add(rhs.r());
+        let g = lhs.g().saturating_add(rhs.g());
+        let b = lhs.b().saturating_add(rhs.b());
+        Rgb8U::new(r, g, b)
+    }
+
+    #[inline(always)]
+    pub const fn saturating_sub(lhs: Self, rhs: Self) -> Self {
+        let r = lhs.r().saturating_sub(rhs.r());
+        let g = lhs.g().saturating_sub(rhs.g());
+        let b = lhs.b().saturating_sub(rhs.b());
+        Rgb8U::new(r, g, b)
+    }
+
+    /// Returns per-channel average of two colors rounded down.
+    #[inline(always)]
+    pub const fn avg_floor(lhs: Self, rhs: Self) -> Self {
+        let r = avg_floor(lhs.r(), rhs.r());
+        let g = avg_floor(lhs.g(), rhs.g());
+        let b = avg_floor(lhs.b(), rhs.b());
+        Rgb8U::new(r, g, b)
+    }
+
+    /// Returns per-channel absolute difference of two colors.
+    #[inline(always)]
+    pub const fn abs_diff(lhs: Self, rhs: Self) -> Self {
+        let r = lhs.r().abs_diff(rhs.r());
+        let g = lhs.g().abs_diff(rhs.g());
+        let b = lhs.b().abs_diff(rhs.b());
+        Rgb8U::new(r, g, b)
+    }
+
     #[inline(always)]
     pub const fn diff(lhs: Self, rhs: Self) -> Vec3 {
         Vec3([
@@ -3293,6 +3737,11 @@ impl Rgb8U {
 
 /// An RGB color represented as 3 floats.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 #[repr(transparent)]
 pub struct Rgb32F([f32; 3]);
 
@@ -3305,6 +3754,23 @@ impl Rgb32F {
         Rgb32F([r, g, b])
     }
 
+    /// Creates color from channels in memory order.
+    #[inline(always)]
+    pub const fn from_array(channels: [f32; 3]) -> Self {
+        Rgb32F(channels)
+    }
+
+    /// Returns channels in memory order.
+    #[inline(always)]
+    pub const fn to_array(self) -> [f32; 3] {
+        self.0
+    }
+
+    #[inline(always)]
+    pub const fn splat(value: f32) -> Self {
+        Rgb32F([value; 3])
+    }
+
     #[inline(always)]
     pub const fn r(&self) -> f32 {
         self.0[0]
@@ -3330,6 +3796,24 @@ impl Rgb32F {
         Rgba32F([self.r(), self.g(), self.b(), 1.0])
     }
 
+    /// Decodes sRGB-encoded color into linear light.
+    #[inline(always)]
+    pub fn srgb_to_linear(self) -> Self {
+        Rgb32F(self.0.map(srgb_to_linear))
+    }
+
+    /// Encodes linear light color with sRGB transfer function.
+    #[inline(always)]
+    pub fn linear_to_srgb(self) -> Self {
+        Rgb32F(self.0.map(linear_to_srgb))
+    }
+
+    /// Encodes linear light color into the nearest 8-bit sRGB color.
+    #[inline(always)]
+    pub fn linear_to_srgb8(self) -> Rgb8U {
+        Rgb8U(self.0.map(linear_to_srgb8))
+    }
+
     #[inline(always)]
     pub fn lerp(lhs: Self, rhs: Self, t: f32) -> Self {
         Rgb32F([
@@ -3366,7 +3850,12 @@ impl Rgb32F {
 }
 
 /// An RGBA color with 8 bit unsigned normalized integers per channel.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 #[repr(transparent)]
 pub struct Rgba8U([u8; 4]);
 
@@ -3442,6 +3931,14 @@ impl Rgba8U {
         ])
     }
 
+    /// Decodes sRGB-encoded color into linear light, alpha is kept linear.
+    #[inline(always)]
+    pub fn to_linear_f32(self) -> Rgba32F {
+        self.rgb()
+            .to_linear_f32()
+            .with_alpha(self.a() as f32 / 255.0)
+    }
+
     #[inline(always)]
     pub fn from_f32(rgb: Rgba32F) -> Self {
         let r = (rgb.r() * 255.0).clamp(0.0, 255.0) as u8;
@@ -3474,10 +3971,53 @@ impl Rgba8U {
 
         Rgba8U::new(r, g, b, a)
     }
+
+    #[inline(always)]
+    pub const fn saturating_add(lhs: Self, rhs: Self) -> Self {
+        let r = lhs.r().saturating_add(rhs.r());
+        let g = lhs.g().saturating_add(rhs.g());
+        let b = lhs.b().saturating_add(rhs.b());
+        let a = lhs.a().saturating_add(rhs.a());
+        Rgba8U::new(r, g, b, a)
+    }
+
+    #[inline(always)]
+    pub const fn saturating_sub(lhs: Self, rhs: Self) -> Self {
+        let r = lhs.r().saturating_sub(rhs.r());
+        let g = lhs.g().saturating_sub(rhs.g());
+        let b = lhs.b().saturating_sub(rhs.b());
+        let a = lhs.a().saturating_sub(rhs.a());
+        Rgba8U::new(r, g, b, a)
+    }
+
+    /// Returns per-channel average of two colors rounded down.
+    #[inline(always)]
+    pub const fn avg_floor(lhs: Self, rhs: Self) -> Self {
+        let r = avg_floor(lhs.r(), rhs.r());
+        let g = avg_floor(lhs.g(), rhs.g());
+        let b = avg_floor(lhs.b(), rhs.b());
+        let a = avg_floor(lhs.a(), rhs.a());
+        Rgba8U::new(r, g, b, a)
+    }
+
+    /// Returns per-channel absolute difference of two colors.
+    #[inline(always)]
+    pub const fn abs_diff(lhs: Self, rhs: Self) -> Self {
+        let r = lhs.r().abs_diff(rhs.r());
+        let g = lhs.g().abs_diff(rhs.g());
+        let b = lhs.b().abs_diff(rhs.b());
+        let a = lhs.a().abs_diff(rhs.a());
+        Rgba8U::new(r, g, b, a)
+    }
 }
 
 /// An RGB color represented as 3 floats.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 #[repr(transparent)]
 pub struct Rgba32F([f32; 4]);
 
@@ -3491,6 +4031,24 @@ impl Rgba32F {
         Rgba32F([r, g, b, a])
     }
 
+    /// Creates color from channels in memory order.
+    #[inline(always)]
+    pub const fn from_array(channels: [f32; 4]) -> Self {
+        Rgba32F(channels)
+    }
+
+    /// Returns channels in memory order.
+    #[inline(always)]
+    pub const fn to_array(self) -> [f32; 4] {
+        self.0
+    }
+
+    /// Returns color with all channels, including alpha, set to `value`.
+    #[inline(always)]
+    pub const fn splat(value: f32) -> Self {
+        Rgba32F([value; 4])
+    }
+
     #[inline(always)]
     pub const fn r(&self) -> f32 {
         self.0[0]
@@ -3511,6 +4069,13 @@ impl Rgba32F {
         self.0[3]
     }
 
+    /// Encodes linear light color into the nearest 8-bit sRGB color, alpha is kept linear.
+    #[inline(always)]
+    pub fn linear_to_srgb8(self) -> Rgba8U {
+        let a = (self.a() * 255.0).round().clamp(0.0, 255.0) as u8;
+        self.rgb().linear_to_srgb8().with_alpha(a)
+    }
+
     #[inline(always)]
     pub const fn rgb(&self) -> Rgb32F {
         Rgb32F([self.r(), self.g(), self.b()])
@@ -3525,10 +4090,41 @@ impl Rgba32F {
             lerp(a.a(), b.a(), t),
         ])
     }
+
+    #[inline(always)]
+    pub const fn diff(a: Self, b: Self) -> Vec4 {
+        Vec4([a.r() - b.r(), a.g() - b.g(), a.b() - b.b(), a.a() - b.a()])
+    }
+
+    #[inline(always)]
+    pub const fn distance_squared(a: Self, b: Self) -> f32 {
+        let diff = Self::diff(a, b);
+        diff.dot(diff)
+    }
+
+    #[inline(always)]
+    pub fn distance(a: Self, b: Self) -> f32 {
+        Self::distance_squared(a, b).sqrt()
+    }
+
+    #[inline(always)]
+    pub const fn offset(self, offset: Vec4) -> Self {
+        Rgba32F([
+            self.r() + offset.x(),
+            self.g() + offset.y(),
+            self.b() + offset.z(),
+            self.a() + offset.w(),
+        ])
+    }
 }
 
 /// An RGB color with 5,6 and 5 bits unsigned normalized integers per channel.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 #[repr(transparent)]
 pub struct Rgb565(u16);
 
@@ -3536,6 +4132,14 @@ impl Rgb565 {
     pub const WHITE: Rgb565 = Rgb565(0b11111_111111_11111);
     pub const BLACK: Rgb565 = Rgb565(0);
 
+    /// Creates color from channels.
+    ///
+    /// Intended for literal constants, use [`Rgb565::new_clamped`] or [`Rgb565::new_masked`]
+    /// for channels computed at runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any channel is out of range.
     pub const fn new(r: u8, g: u8, b: u8) -> Self {
         assert!(r <= 31, "Red channel must be in range 0..=31");
         assert!(g <= 63, "Green channel must be in range 0..=63");
@@ -3547,7 +4151,23 @@ impl Rgb565 {
         Rgb565((r << 11) | (g << 5) | b)
     }
 
+    /// Creates color from channels clamping them to their ranges.
+    #[inline(always)]
+    pub const fn new_clamped(r: u8, g: u8, b: u8) -> Self {
+        let r = if r > 31 { 31 } else { r };
+        let g = if g > 63 { 63 } else { g };
+        let b = if b > 31 { 31 } else { b };
+        Rgb565::new(r, g, b)
+    }
+
+    /// Creates color from channels discarding bits above their widths.
+    #[inline(always)]
+    pub const fn new_masked(r: u8, g: u8, b: u8) -> Self {
+        Rgb565::new(r & 0b11111, g & 0b111111, b & 0b11111)
+    }
+
     /// Return the raw bits of the encoded color.
+    /// Colors are ordered by their raw bits.
     #[inline(always)]
     pub const fn bits(&self) -> u16 {
         self.0
@@ -3608,7 +4228,7 @@ impl Rgb565 {
         r |= (bits & 0b100000000000000) >> 10;
         g |= (bits & 0b1000000000000000) >> 10;
 
-        Rgb565::new(r as u8, g as u8, b as u8)
+        Rgb565::new_masked(r as u8, g as u8, b as u8)
     }
 
     /// Return color from raw bytes.
@@ -3673,25 +4293,79 @@ impl Rgb565 {
         Rgb565((r << 11) | (g << 5) | b)
     }
 
+    /// Expands color to 8 bits per channel replicating high bits into low bits.
+    #[inline(always)]
+    pub const fn into_rgb8(self) -> Rgb8U {
+        let r = self.r();
+        let g = self.g();
+        let b = self.b();
+        Rgb8U([
+            (r << 3) | (r >> 2),
+            (g << 2) | (g >> 4),
+            (b << 3) | (b >> 2),
+        ])
+    }
+
+    /// Quantizes 8-bit color rounding each channel to the nearest level.
+    #[inline(always)]
+    pub const fn from_rgb8(rgb: Rgb8U) -> Self {
+        let [r, g, b] = rgb.0;
+        let r = (r as u16 * 31 + 127) / 255;
+        let g = (g as u16 * 63 + 127) / 255;
+        let b = (b as u16 * 31 + 127) / 255;
+        Rgb565((r << 11) | (g << 5) | b)
+    }
+
+    /// Returns BC1 palette color two thirds of the way from `b` to `a`,
+    /// that is `(2 * a + b) / 3` computed on expanded 8-bit channels.
+    #[inline(always)]
+    pub const fn lerp_third(a: Self, b: Self) -> Rgb8U {
+        let [ar, ag, ab] = a.into_rgb8().0;
+        let [br, bg, bb] = b.into_rgb8().0;
+        Rgb8U([
+            ((2 * ar as u16 + br as u16) / 3) as u8,
+            ((2 * ag as u16 + bg as u16) / 3) as u8,
+            ((2 * ab as u16 + bb as u16) / 3) as u8,
+        ])
+    }
+
+    /// Returns BC1 palette color half way between `a` and `b`
+    /// computed on expanded 8-bit channels.
+    #[inline(always)]
+    pub const fn lerp_half(a: Self, b: Self) -> Rgb8U {
+        let [ar, ag, ab] = a.into_rgb8().0;
+        let [br, bg, bb] = b.into_rgb8().0;
+        Rgb8U([
+            ((ar as u16 + br as u16) / 2) as u8,
+            ((ag as u16 + bg as u16) / 2) as u8,
+            ((ab as u16 + bb as u16) / 2) as u8,
+        ])
+    }
+
     #[inline(always)]
     pub fn wrapping_add(a: Self, b: Self) -> Self {
-        let r = a.r().wrapping_add(b.r()) & 31;
-        let g = a.g().wrapping_add(b.g()) & 63;
-        let b = a.b().wrapping_add(b.b()) & 31;
-        Rgb565::new(r, g, b)
+        let r = a.r().wrapping_add(b.r());
+        let g = a.g().wrapping_add(b.g());
+        let b = a.b().wrapping_add(b.b());
+        Rgb565::new_masked(r, g, b)
     }
 
     #[inline(always)]
     pub fn wrapping_sub(a: Self, b: Self) -> Self {
-        let r = a.r().wrapping_sub(b.r()) & 31;
-        let g = a.g().wrapping_sub(b.g()) & 63;
-        let b = a.b().wrapping_sub(b.b()) & 31;
-        Rgb565::new(r, g, b)
+        let r = a.r().wrapping_sub(b.r());
+        let g = a.g().wrapping_sub(b.g());
+        let b = a.b().wrapping_sub(b.b());
+        Rgb565::new_masked(r, g, b)
     }
 }
 
 /// An YIQ color represented as 3 floats.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 #[repr(transparent)]
 pub struct Yiq32F([f32; 3]);
 
@@ -3704,6 +4378,18 @@ impl Yiq32F {
         Yiq32F([y, i, q])
     }
 
+    /// Creates color from channels in memory order.
+    #[inline(always)]
+    pub const fn from_array(channels: [f32; 3]) -> Self {
+        Yiq32F(channels)
+    }
+
+    /// Returns channels in memory order.
+    #[inline(always)]
+    pub const fn to_array(self) -> [f32; 3] {
+        self.0
+    }
+
     #[inline(always)]
     pub const fn y(&self) -> f32 {
         self.0[0]
@@ -3839,6 +4525,162 @@ impl From<Vec4> for Rgba32F {
     }
 }
 
+impl From<f32> for R32F {
+    #[inline(always)]
+    fn from(value: f32) -> Self {
+        R32F(value)
+    }
+}
+
+impl From<R32F> for f32 {
+    #[inline(always)]
+    fn from(value: R32F) -> Self {
+        value.0
+    }
+}
+
+impl From<[f32; 2]> for Rg32F {
+    #[inline(always)]
+    fn from(value: [f32; 2]) -> Self {
+        Rg32F(value)
+    }
+}
+
+impl From<Rg32F> for [f32; 2] {
+    #[inline(always)]
+    fn from(value: Rg32F) -> Self {
+        value.0
+    }
+}
+
+impl From<[f32; 3]> for Rgb32F {
+    #[inline(always)]
+    fn from(value: [f32; 3]) -> Self {
+        Rgb32F(value)
+    }
+}
+
+impl From<Rgb32F> for [f32; 3] {
+    #[inline(always)]
+    fn from(value: Rgb32F) -> Self {
+        value.0
+    }
+}
+
+impl From<[f32; 4]> for Rgba32F {
+    #[inline(always)]
+    fn from(value: [f32; 4]) -> Self {
+        Rgba32F(value)
+    }
+}
+
+impl From<Rgba32F> for [f32; 4] {
+    #[inline(always)]
+    fn from(value: Rgba32F) -> Self {
+        value.0
+    }
+}
+
+impl From<[f32; 3]> for Yiq32F {
+    #[inline(always)]
+    fn from(value: [f32; 3]) -> Self {
+        Yiq32F(value)
+    }
+}
+
+impl From<Yiq32F> for [f32; 3] {
+    #[inline(always)]
+    fn from(value: Yiq32F) -> Self {
+        value.0
+    }
+}
+
+/// Formats color as `#RR`.
+impl fmt::Display for R8U {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02X}", self.0)
+    }
+}
+
+/// Formats color as `#RRGG`.
+impl fmt::Display for Rg8U {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [r, g] = self.0;
+        write!(f, "#{r:02X}{g:02X}")
+    }
+}
+
+/// Formats color as `#RRGGBB`.
+impl fmt::Display for Rgb8U {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [r, g, b] = self.0;
+        write!(f, "#{r:02X}{g:02X}{b:02X}")
+    }
+}
+
+/// Formats color as `#RRGGBBAA`.
+impl fmt::Display for Rgba8U {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [r, g, b, a] = self.0;
+        write!(f, "#{r:02X}{g:02X}{b:02X}{a:02X}")
+    }
+}
+
+/// Formats color as `rgb565(r,g,b)` with channels in their own ranges.
+impl fmt::Display for Rgb565 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rgb565({},{},{})", self.r(), self.g(), self.b())
+    }
+}
+
+/// Writes `name(c0,c1,...)` with precision of the formatter, 3 digits by default.
+fn write_channels(f: &mut fmt::Formatter<'_>, name: &str, channels: &[f32]) -> fmt::Result {
+    let precision = f.precision().unwrap_or(3);
+    write!(f, "{name}(")?;
+    for (i, channel) in channels.iter().enumerate() {
+        if i > 0 {
+            f.write_str(",")?;
+        }
+        write!(f, "{channel:.precision$}")?;
+    }
+    f.write_str(")")
+}
+
+/// Formats color as `r(0.500)`.
+impl fmt::Display for R32F {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_channels(f, "r", &[self.0])
+    }
+}
+
+/// Formats color as `rg(1.000,0.500)`.
+impl fmt::Display for Rg32F {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_channels(f, "rg", &self.0)
+    }
+}
+
+/// Formats color as `rgb(1.000,0.500,0.000)`.
+impl fmt::Display for Rgb32F {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_channels(f, "rgb", &self.0)
+    }
+}
+
+/// Formats color as `rgba(1.000,0.500,0.000,1.000)`.
+impl fmt::Display for Rgba32F {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_channels(f, "rgba", &self.0)
+    }
+}
+
+/// Formats color as `yiq(1.000,0.000,0.000)`.
+impl fmt::Display for Yiq32F {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_channels(f, "yiq", &self.0)
+    }
+}
+
 pub fn max_variance_diagonal_axis(samples: &[Vec3]) -> Vec3 {
     let region = Region3::new(samples.iter().copied());
     let center = region.center();
@@ -3875,7 +4717,7 @@ pub fn pca_axis(v: &[Vec3]) -> Vec3 {
         let d = *p - mean;
         for i in 0..3 {
             for j in 0..3 {
-                cov[i][j] += d.0[i] * d.0[j];
+                cov[i][j] += d[i] * d[j];
             }
         }
     }
@@ -3893,7 +4735,7 @@ pub fn pca_axis(v: &[Vec3]) -> Vec3 {
         let mut next_axis = Vec3::ZERO;
         for i in 0..3 {
             for j in 0..3 {
-                next_axis.0[i] += cov[i][j] * axis.0[j];
+                next_axis[i] += cov[i][j] * axis[j];
             }
         }
 
@@ -3908,3 +4750,413 @@ pub fn pca_axis(v: &[Vec3]) -> Vec3 {
 
     axis
 }
+
+// All types are `repr(transparent)` over plain numbers and arrays of them.
+#[cfg(feature = "bytemuck")]
+mod pod {
+    use super::*;
+
+    unsafe impl bytemuck::Zeroable for Vec2 {}
+    unsafe impl bytemuck::Pod for Vec2 {}
+    unsafe impl bytemuck::Zeroable for Vec3 {}
+    unsafe impl bytemuck::Pod for Vec3 {}
+    unsafe impl bytemuck::Zeroable for Vec4 {}
+    unsafe impl bytemuck::Pod for Vec4 {}
+    unsafe impl bytemuck::Zeroable for R8U {}
+    unsafe impl bytemuck::Pod for R8U {}
+    unsafe impl bytemuck::Zeroable for Rg8U {}
+    unsafe impl bytemuck::Pod for Rg8U {}
+    unsafe impl bytemuck::Zeroable for R32F {}
+    unsafe impl bytemuck::Pod for R32F {}
+    unsafe impl bytemuck::Zeroable for Rg32F {}
+    unsafe impl bytemuck::Pod for Rg32F {}
+    unsafe impl bytemuck::Zeroable for Rgb8U {}
+    unsafe impl bytemuck::Pod for Rgb8U {}
+    unsafe impl bytemuck::Zeroable for Rgb32F {}
+    unsafe impl bytemuck::Pod for Rgb32F {}
+    unsafe impl bytemuck::Zeroable for Rgba8U {}
+    unsafe impl bytemuck::Pod for Rgba8U {}
+    unsafe impl bytemuck::Zeroable for Rgba32F {}
+    unsafe impl bytemuck::Pod for Rgba32F {}
+    unsafe impl bytemuck::Zeroable for Rgb565 {}
+    unsafe impl bytemuck::Pod for Rgb565 {}
+    unsafe impl bytemuck::Zeroable for Yiq32F {}
+    unsafe impl bytemuck::Pod for Yiq32F {}
+}
+
+#[test]
+fn test_rgb565_bits_interleaved_roundtrip() {
+    for bits in 0..=u16::MAX {
+        let color = Rgb565::from_bits(bits);
+        assert_eq!(
+            Rgb565::from_bits_interleaved(color.bits_interleaved()),
+            color
+        );
+
+        let color = Rgb565::from_bits_interleaved(bits);
+        assert_eq!(color.bits_interleaved(), bits);
+    }
+}
+
+#[test]
+fn test_rgb565_out_of_range() {
+    for v in [32, 64, 255] {
+        let clamped = Rgb565::new_clamped(v, v, v);
+        assert_eq!((clamped.r(), clamped.g(), clamped.b()), (31, v.min(63), 31));
+
+        let masked = Rgb565::new_masked(v, v, v);
+        assert_eq!(
+            (masked.r(), masked.g(), masked.b()),
+            (v & 31, v & 63, v & 31)
+        );
+    }
+
+    assert_eq!(Rgb565::new_clamped(31, 63, 31), Rgb565::WHITE);
+    assert_eq!(Rgb565::new_masked(31, 63, 31), Rgb565::WHITE);
+    assert_eq!(Rgb565::new_clamped(4, 5, 6), Rgb565::new(4, 5, 6));
+    assert_eq!(Rgb565::new_masked(4, 5, 6), Rgb565::new(4, 5, 6));
+
+    let wrapped = Rgb565::wrapping_sub(Rgb565::BLACK, Rgb565::new(1, 1, 1));
+    assert_eq!(wrapped, Rgb565::WHITE);
+    assert_eq!(
+        Rgb565::wrapping_add(wrapped, Rgb565::new(1, 1, 1)),
+        Rgb565::BLACK
+    );
+}
+
+#[test]
+fn test_rgb565_rgb8_exhaustive() {
+    for v in 0..32u8 {
+        let color = Rgb565::new(v, 0, v);
+        let expanded = color.into_rgb8();
+        let reference = (v as f32 * 255.0 / 31.0).round() as u8;
+        assert_eq!(expanded.r(), (v << 3) | (v >> 2));
+        assert_eq!(expanded.b(), (v << 3) | (v >> 2));
+        assert!(expanded.r().abs_diff(reference) <= 1);
+        assert_eq!(Rgb565::from_rgb8(expanded), color);
+    }
+
+    for v in 0..64u8 {
+        let color = Rgb565::new(0, v, 0);
+        let expanded = color.into_rgb8();
+        let reference = (v as f32 * 255.0 / 63.0).round() as u8;
+        assert_eq!(expanded.g(), (v << 2) | (v >> 4));
+        assert!(expanded.g().abs_diff(reference) <= 1);
+        assert_eq!(Rgb565::from_rgb8(expanded), color);
+    }
+
+    for x in 0..=255u8 {
+        let color = Rgb565::from_rgb8(Rgb8U::new(x, x, x));
+        assert_eq!(color.r(), (x as f32 * 31.0 / 255.0).round() as u8);
+        assert_eq!(color.g(), (x as f32 * 63.0 / 255.0).round() as u8);
+        assert_eq!(color.b(), (x as f32 * 31.0 / 255.0).round() as u8);
+    }
+}
+
+#[test]
+fn test_rgb565_lerp_exhaustive() {
+    for a in 0..64u8 {
+        for b in 0..64u8 {
+            let ca = Rgb565::new(a & 31, a, a & 31);
+            let cb = Rgb565::new(b & 31, b, b & 31);
+            let (ea, eb) = (ca.into_rgb8(), cb.into_rgb8());
+
+            let third = Rgb565::lerp_third(ca, cb);
+            assert_eq!(third.r() as u16, (2 * ea.r() as u16 + eb.r() as u16) / 3);
+            assert_eq!(third.g() as u16, (2 * ea.g() as u16 + eb.g() as u16) / 3);
+            assert_eq!(third.b() as u16, (2 * ea.b() as u16 + eb.b() as u16) / 3);
+            assert_eq!(Rgb565::lerp_third(ca, ca), ea);
+
+            let half = Rgb565::lerp_half(ca, cb);
+            assert_eq!(half.g() as u16, (ea.g() as u16 + eb.g() as u16) / 2);
+            assert_eq!(half, Rgb565::lerp_half(cb, ca));
+        }
+    }
+}
+
+#[test]
+fn test_vec_componentwise() {
+    let a = Vec3::new(1.0, -2.0, 3.0);
+    let b = Vec3::new(-1.0, 4.0, 2.0);
+
+    assert_eq!(Vec3::min(a, b), Vec3::new(-1.0, -2.0, 2.0));
+    assert_eq!(Vec3::max(a, b), Vec3::new(1.0, 4.0, 3.0));
+    assert_eq!(a.abs(), Vec3::new(1.0, 2.0, 3.0));
+    assert_eq!(-a, Vec3::new(-1.0, 2.0, -3.0));
+    assert_eq!(a * b, Vec3::new(-1.0, -8.0, 6.0));
+    assert_eq!(
+        a.clamp(Vec3::splat(0.0), Vec3::splat(2.0)),
+        Vec3::new(1.0, 0.0, 2.0)
+    );
+
+    let mut c = a;
+    c *= b;
+    c[1] = 5.0;
+    assert_eq!(c, Vec3::new(-1.0, 5.0, 6.0));
+    assert_eq!((c[0], c[1], c[2]), (c.x(), c.y(), c.z()));
+
+    let a = Vec2::new(1.0, -2.0);
+    let b = Vec2::new(-1.0, 4.0);
+    assert_eq!(Vec2::min(a, b), Vec2::new(-1.0, -2.0));
+    assert_eq!(Vec2::max(a, b), Vec2::new(1.0, 4.0));
+    assert_eq!(a.abs(), Vec2::new(1.0, 2.0));
+    assert_eq!(-a * b, Vec2::new(1.0, 8.0));
+    assert_eq!(a.clamp(Vec2::ZERO, Vec2::splat(0.5)), Vec2::new(0.5, 0.0));
+    assert_eq!(a[1], -2.0);
+
+    let a = Vec4::new(1.0, -2.0, 3.0, -4.0);
+    let b = Vec4::new(-1.0, 4.0, 2.0, 0.5);
+    assert_eq!(Vec4::min(a, b), Vec4::new(-1.0, -2.0, 2.0, -4.0));
+    assert_eq!(Vec4::max(a, b), Vec4::new(1.0, 4.0, 3.0, 0.5));
+    assert_eq!(a.abs(), Vec4::new(1.0, 2.0, 3.0, 4.0));
+    assert_eq!(-a * b, Vec4::new(1.0, 8.0, -6.0, 2.0));
+    assert_eq!(
+        a.clamp(Vec4::splat(-1.0), Vec4::splat(1.0)),
+        Vec4::new(1.0, -1.0, 1.0, -1.0)
+    );
+    let mut c = a;
+    c[3] = 0.0;
+    assert_eq!(c.w(), 0.0);
+}
+
+#[test]
+fn test_region3_new() {
+    let region = Region3::new(
+        [
+            Vec3::new(0.5, 0.1, 0.9),
+            Vec3::new(0.2, 0.7, 0.3),
+            Vec3::new(0.4, 0.0, 1.0),
+        ]
+        .into_iter(),
+    );
+    assert_eq!(region.min, Vec3::new(0.2, 0.0, 0.3));
+    assert_eq!(region.max, Vec3::new(0.5, 0.7, 1.0));
+    assert!(Region3::new(std::iter::empty()).is_empty());
+}
+
+#[test]
+fn test_region3_volume() {
+    let cube = Region3 {
+        min: Vec3::ZERO,
+        max: Vec3::splat(1.0),
+    };
+    assert_eq!(cube.extent(), Vec3::splat(1.0));
+    assert_eq!(cube.volume(), 1.0);
+    assert_eq!(cube.surface_area(), 6.0);
+
+    let plane = Region3::new([Vec3::ZERO, Vec3::new(2.0, 0.0, 3.0)].into_iter());
+    assert_eq!(plane.volume(), 0.0);
+    assert_eq!(plane.surface_area(), 12.0);
+
+    let empty = Region3::new(std::iter::empty());
+    assert!(empty.is_empty());
+    assert!(!empty.is_real());
+    assert_eq!(empty.extent(), Vec3::ZERO);
+    assert_eq!(empty.volume(), 0.0);
+    assert_eq!(empty.surface_area(), 0.0);
+}
+
+#[test]
+fn test_saturating_and_average() {
+    let max = Rgba8U::new(255, 255, 254, 0);
+    let one = Rgba8U::new(1, 0, 1, 1);
+
+    assert_eq!(
+        Rgba8U::saturating_add(max, one),
+        Rgba8U::new(255, 255, 255, 1)
+    );
+    assert_eq!(Rgba8U::saturating_sub(one, max), Rgba8U::new(0, 0, 0, 1));
+    assert_eq!(Rgba8U::wrapping_add(max, one), Rgba8U::new(0, 255, 255, 1));
+    assert_eq!(Rgba8U::avg_floor(max, max), max);
+    assert_eq!(Rgba8U::avg_floor(max, one), Rgba8U::new(128, 127, 127, 0));
+    assert_eq!(Rgba8U::abs_diff(max, one), Rgba8U::new(254, 255, 253, 1));
+    assert_eq!(Rgba8U::abs_diff(one, max), Rgba8U::abs_diff(max, one));
+
+    assert_eq!(
+        Rgb8U::saturating_add(max.rgb(), one.rgb()),
+        Rgb8U::new(255, 255, 255)
+    );
+    assert_eq!(
+        Rgb8U::saturating_sub(one.rgb(), max.rgb()),
+        Rgb8U::new(0, 0, 0)
+    );
+    assert_eq!(
+        Rgb8U::avg_floor(Rgb8U::new(255, 254, 0), Rgb8U::new(254, 254, 1)),
+        Rgb8U::new(254, 254, 0)
+    );
+    assert_eq!(
+        Rgb8U::abs_diff(Rgb8U::new(0, 255, 10), Rgb8U::new(255, 0, 10)),
+        Rgb8U::new(255, 255, 0)
+    );
+
+    assert_eq!(R8U::new(255).saturating_add(R8U::new(1)), R8U::new(255));
+    assert_eq!(R8U::new(0).saturating_sub(R8U::new(1)), R8U::new(0));
+    assert_eq!(R8U::new(255).avg_floor(R8U::new(254)), R8U::new(254));
+    assert_eq!(R8U::new(0).abs_diff(R8U::new(255)), R8U::new(255));
+
+    for a in 0..=255u8 {
+        for b in 0..=255u8 {
+            assert_eq!(avg_floor(a, b) as u16, (a as u16 + b as u16) >> 1);
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_roundtrip() {
+    fn roundtrip<T>(value: T, json: &str)
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + PartialEq + std::fmt::Debug,
+    {
+        assert_eq!(serde_json::to_string(&value).unwrap(), json);
+        assert_eq!(serde_json::from_str::<T>(json).unwrap(), value);
+    }
+
+    roundtrip(Vec2::new(1.0, 2.0), "[1.0,2.0]");
+    roundtrip(Vec3::new(1.0, 2.0, 3.0), "[1.0,2.0,3.0]");
+    roundtrip(Vec4::new(1.0, 2.0, 3.0, 4.0), "[1.0,2.0,3.0,4.0]");
+    roundtrip(R8U::new(7), "7");
+    roundtrip(Rgb8U::new(1, 2, 3), "[1,2,3]");
+    roundtrip(Rgba8U::new(1, 2, 3, 4), "[1,2,3,4]");
+    roundtrip(Rgb32F::new(0.5, 0.25, 1.0), "[0.5,0.25,1.0]");
+    roundtrip(Rgba32F::new(0.5, 0.25, 1.0, 0.0), "[0.5,0.25,1.0,0.0]");
+    roundtrip(Rgb565::new(31, 0, 1), "63489");
+    roundtrip(Yiq32F::new(1.0, 0.0, 0.5), "[1.0,0.0,0.5]");
+
+    let region = Region3 {
+        min: Vec3::ZERO,
+        max: Vec3::splat(1.0),
+    };
+    let json = serde_json::to_string(&region).unwrap();
+    assert_eq!(json, r#"{"min":[0.0,0.0,0.0],"max":[1.0,1.0,1.0]}"#);
+    let decoded: Region3 = serde_json::from_str(&json).unwrap();
+    assert_eq!((decoded.min, decoded.max), (region.min, region.max));
+}
+
+#[test]
+fn test_float_color_ops() {
+    let a = Rgba32F::new(1.0, 0.5, 0.25, 1.0);
+    let b = Rgba32F::new(0.0, 0.5, 0.25, 0.0);
+    assert_eq!(Rgba32F::diff(a, b), Vec4::new(1.0, 0.0, 0.0, 1.0));
+    assert_eq!(Rgba32F::distance_squared(a, b), 2.0);
+    assert_eq!(Rgba32F::distance(a, b), 2.0f32.sqrt());
+    assert_eq!(b.offset(Rgba32F::diff(a, b)), a);
+    assert_eq!(Rgba32F::splat(0.0), Rgba32F::TRANSPARENT);
+    assert_eq!(Rgba32F::splat(1.0), Rgba32F::WHITE);
+
+    let a = Rgb32F::new(1.0, 0.0, 0.5);
+    let b = Rgb32F::splat(0.5);
+    assert_eq!(Rgb32F::diff(a, b), Vec3::new(0.5, -0.5, 0.0));
+    assert_eq!(Rgb32F::distance_squared(a, b), 0.5);
+    assert_eq!(Rgb32F::distance(a, b), 0.5f32.sqrt());
+    assert_eq!(b.offset(Rgb32F::diff(a, b)), a);
+
+    assert_eq!(
+        Rgba32F::from([0.0, 0.25, 0.5, 1.0]),
+        Rgba32F::new(0.0, 0.25, 0.5, 1.0)
+    );
+    assert_eq!(<[f32; 4]>::from(Rgba32F::WHITE), [1.0; 4]);
+    assert_eq!(Rgb32F::from([0.0, 0.25, 0.5]), Rgb32F::new(0.0, 0.25, 0.5));
+    assert_eq!(<[f32; 3]>::from(Rgb32F::WHITE), [1.0; 3]);
+    assert_eq!(Rg32F::from([0.25, 0.5]), Rg32F::new(0.25, 0.5));
+    assert_eq!(<[f32; 2]>::from(Rg32F::WHITE), [1.0; 2]);
+    assert_eq!(
+        <[f32; 3]>::from(Yiq32F::from([1.0, 0.5, 0.25])),
+        [1.0, 0.5, 0.25]
+    );
+    assert_eq!(f32::from(R32F::from(0.5)), 0.5);
+}
+
+#[test]
+fn test_srgb_conversions() {
+    for v in 0..=255u8 {
+        let linear = Rgb8U::new(v, v, v).to_linear_f32();
+        assert_eq!(linear.r(), srgb_to_linear(v as f32 / 255.0));
+        assert_eq!(linear.linear_to_srgb8(), Rgb8U::new(v, v, v));
+
+        let srgb = linear_to_srgb(linear.r()) * 255.0;
+        assert!((srgb - v as f32).abs() < 1.0);
+    }
+
+    assert!((srgb8_to_linear(128) - 0.2158).abs() < 1e-4);
+    assert_eq!(srgb8_to_linear(0), 0.0);
+    assert_eq!(srgb8_to_linear(255), 1.0);
+    assert_eq!(linear_to_srgb(0.0031308 / 2.0), 0.0031308 * 12.92 / 2.0);
+    assert_eq!(linear_to_srgb8(0.5), 188);
+    assert_eq!(linear_to_srgb8(-1.0), 0);
+    assert_eq!(linear_to_srgb8(2.0), 255);
+
+    let color = Rgba8U::new(128, 0, 255, 128);
+    let linear = color.to_linear_f32();
+    assert_eq!(linear.a(), 128.0 / 255.0);
+    assert_eq!(linear.linear_to_srgb8(), color);
+
+    let color = Rgb32F::new(0.2, 0.5, 0.8);
+    let roundtrip = color.srgb_to_linear().linear_to_srgb();
+    assert!(Rgb32F::distance(color, roundtrip) < 1e-5);
+}
+
+#[test]
+fn test_color_display() {
+    assert_eq!(R8U::new(0x0A).to_string(), "#0A");
+    assert_eq!(Rg8U::new(0xFF, 0x10).to_string(), "#FF10");
+    assert_eq!(Rgb8U::new(255, 128, 0).to_string(), "#FF8000");
+    assert_eq!(Rgba8U::new(1, 2, 3, 255).to_string(), "#010203FF");
+    assert_eq!(Rgb565::WHITE.to_string(), "rgb565(31,63,31)");
+    assert_eq!(Rgb565::new(1, 2, 3).to_string(), "rgb565(1,2,3)");
+    assert_eq!(R32F::new(0.5).to_string(), "r(0.500)");
+    assert_eq!(Rg32F::new(1.0, 0.25).to_string(), "rg(1.000,0.250)");
+    assert_eq!(
+        Rgb32F::new(1.0, 0.5, 0.0).to_string(),
+        "rgb(1.000,0.500,0.000)"
+    );
+    assert_eq!(
+        Rgba32F::new(1.0, 0.5, 0.0, 1.0).to_string(),
+        "rgba(1.000,0.500,0.000,1.000)"
+    );
+    assert_eq!(Yiq32F::WHITE.to_string(), "yiq(1.000,0.000,0.000)");
+    assert_eq!(
+        format!("{:.1}", Rgb32F::new(1.0, 0.5, 0.0)),
+        "rgb(1.0,0.5,0.0)"
+    );
+}
+
+#[test]
+fn test_color_keys() {
+    use std::collections::HashMap;
+
+    let mut counts: HashMap<Rgb565, usize> = HashMap::new();
+    for bits in [3, 1, 3, 2, 3, 1] {
+        *counts.entry(Rgb565::from_bits(bits)).or_default() += 1;
+    }
+    assert_eq!(counts.len(), 3);
+    assert_eq!(counts[&Rgb565::from_bits(3)], 3);
+    assert_eq!(counts.get(&Rgb565::WHITE), None);
+
+    // Sorting by raw bits is deterministic regardless of map order.
+    let mut palette: Vec<Rgb565> = counts.into_keys().collect();
+    palette.sort();
+    assert_eq!(palette, [1, 2, 3].map(Rgb565::from_bits).to_vec());
+    assert!(Rgb565::BLACK < Rgb565::new(0, 0, 1));
+    assert!(Rgb565::new(0, 63, 31) < Rgb565::new(1, 0, 0));
+
+    assert!(Rgb8U::new(1, 0, 0) > Rgb8U::new(0, 255, 255));
+    let _: HashMap<Rgba8U, usize> = HashMap::new();
+    let _: HashMap<R8U, usize> = HashMap::new();
+    let _: HashMap<Rg8U, usize> = HashMap::new();
+}
+
+#[test]
+fn test_float_color_arrays() {
+    const COLOR: Rgb32F = Rgb32F::from_array([1.0, 0.5, 0.0]);
+    const CHANNELS: [f32; 3] = COLOR.to_array();
+    assert_eq!(COLOR, Rgb32F::new(1.0, 0.5, 0.0));
+    assert_eq!(CHANNELS, [1.0, 0.5, 0.0]);
+
+    assert_eq!(Rg32F::from_array([0.5, 0.25]).to_array(), [0.5, 0.25]);
+    assert_eq!(
+        Rgba32F::from_array([0.1, 0.2, 0.3, 0.4]),
+        Rgba32F::new(0.1, 0.2, 0.3, 0.4)
+    );
+    assert_eq!(Yiq32F::WHITE.to_array(), [1.0, 0.0, 0.0]);
+}
@@ -0,0 +1,26 @@
+//! Types and functions needed to encode, compress and decompress textures.
+//!
+//! ```
+//! use jkl::prelude::*;
+//! ```
+//!
+//! Block types of all formats are named `Block` in their modules,
+//! so they are exported here with format prefix.
+
+pub use crate::{
+    bc1::Block as Bc1Block,
+    bc2::Block as Bc2Block,
+    bc3::Block as Bc3Block,
+    bc4::Block as Bc4Block,
+    bc5::Block as Bc5Block,
+    encoder::{EncodeEffort, EncodeError, EncodeStats, Encoder, QualityRegion},
+    image::Image,
+    jackal::{
+        compress_bc1_texture, compress_bc1_texture_opts, compress_block_texture, decode_to_rgb8,
+        decompress_bc1_texture, decompress_bc1_texture_opts, decompress_block_texture, AnyBlock,
+        BlockTexture, CompressOptions, DecodeError, DecodeLimits, DecompressError,
+        DecompressOptions, Extent, Format, JackalHeader, MipLevels, Predictor, SuperBlockChoice,
+        SuperBlockSize, TableLocation, TextureMeta,
+    },
+    math::{Rgb32F, Rgb565, Rgb8U, Rgba32F, Rgba8U},
+};
@@ -0,0 +1,286 @@
+//! Quality metrics for compressed textures.
+//!
+//! All functions operate on arrays of blocks stored row-major
+//! (x fastest, then y, then z) and take the extent of the image in pixels,
+//! so texels of edge blocks that lie outside of the image are not counted.
+
+use crate::{bc1, math::Rgb32F, Extent};
+
+/// Calls `f` for every pixel of the image with the original and decoded colors.
+fn for_each_pixel(
+    original: &[bc1::Block],
+    decoded: &[bc1::Block],
+    extent: Extent,
+    mut f: impl FnMut(Rgb32F, Rgb32F),
+) {
+    let [width, height, depth] = extent.raw_size();
+    let blocks_width = width.div_ceil(4);
+    let blocks_height = height.div_ceil(4);
+    let blocks_count = blocks_width as usize * blocks_height as usize * depth as usize;
+
+    assert_eq!(original.len(), blocks_count);
+    assert_eq!(decoded.len(), blocks_count);
+
+    for z in 0..depth {
+        for by in 0..blocks_height {
+            for bx in 0..blocks_width {
+                let index = bx as usize
+                    + by as usize * blocks_width as usize
+                    + z as usize * blocks_width as usize * blocks_height as usize;
+
+                let a = original[index].decode();
+                let b = decoded[index].decode();
+
+                let rows = (height - by * 4).min(4) as usize;
+                let columns = (width - bx * 4).min(4) as usize;
+
+                for i in 0..rows {
+                    for j in 0..columns {
+                        f(a[i][j], b[i][j]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn psnr_from_mse(mse: f32) -> f32 {
+    if mse == 0.0 {
+        return f32::INFINITY;
+    }
+    10.0 * (1.0 / mse).log10()
+}
+
+/// Returns mean squared error of each RGB channel.
+pub fn mse_per_channel(
+    original: &[bc1::Block],
+    decoded: &[bc1::Block],
+    extent: Extent,
+) -> [f32; 3] {
+    let mut sum = [0.0f64; 3];
+    let mut count = 0u64;
+
+    for_each_pixel(original, decoded, extent, |a, b| {
+        let diff = Rgb32F::diff(a, b);
+        sum[0] += (diff.x() * diff.x()) as f64;
+        sum[1] += (diff.y() * diff.y()) as f64;
+        sum[2] += (diff.z() * diff.z()) as f64;
+        count += 1;
+    });
+
+    if count == 0 {
+        return [0.0; 3];
+    }
+
+    sum.map(|s| (s / count as f64) as f32)
+}
+
+/// Returns mean squared error averaged over RGB channels.
+pub fn mse(original: &[bc1::Block], decoded: &[bc1::Block], extent: Extent) -> f32 {
+    let [r, g, b] = mse_per_channel(original, decoded, extent);
+    (r + g + b) / 3.0
+}
+
+/// Returns peak signal-to-noise ratio in decibels.
+///
+/// Identical images yield infinity.
+pub fn psnr(original: &[bc1::Block], decoded: &[bc1::Block], extent: Extent) -> f32 {
+    psnr_from_mse(mse(original, decoded, extent))
+}
+
+/// Returns peak signal-to-noise ratio of each RGB channel in decibels.
+pub fn psnr_per_channel(
+    original: &[bc1::Block],
+    decoded: &[bc1::Block],
+    extent: Extent,
+) -> [f32; 3] {
+    mse_per_channel(original, decoded, extent).map(psnr_from_mse)
+}
+
+fn luma(rgb: Rgb32F) -> f32 {
+    0.299 * rgb.r() + 0.587 * rgb.g() + 0.114 * rgb.b()
+}
+
+/// Decodes blocks into a luma plane cropped to the pixel extent.
+fn luma_plane(blocks: &[bc1::Block], extent: Extent) -> Vec<f32> {
+    let [width, height, depth] = extent.raw_size();
+    let mut plane = vec![0.0; width as usize * height as usize * depth as usize];
+
+    let blocks_width = width.div_ceil(4);
+    let blocks_height = height.div_ceil(4);
+
+    for z in 0..depth {
+        for by in 0..blocks_height {
+            for bx in 0..blocks_width {
+                let index = bx as usize
+                    + by as usize * blocks_width as usize
+                    + z as usize * blocks_width as usize * blocks_height as usize;
+
+                let texels = blocks[index].decode();
+
+                for y in by * 4..(by * 4 + 4).min(height) {
+                    for x in bx * 4..(bx * 4 + 4).min(width) {
+                        let pixel = texels[(y % 4) as usize][(x % 4) as usize];
+
+                        plane[x as usize
+                            + y as usize * width as usize
+                            + z as usize * (width * height) as usize] = luma(pixel);
+                    }
+                }
+            }
+        }
+    }
+
+    plane
+}
+
+/// Returns mean structural similarity index of luma.
+///
+/// The image is split into 8x8 windows, windows at the right and bottom edges are cropped
+/// to the image. Identical images yield 1.0.
+pub fn ssim(original: &[bc1::Block], decoded: &[bc1::Block], extent: Extent) -> f32 {
+    const C1: f64 = 0.01 * 0.01;
+    const C2: f64 = 0.03 * 0.03;
+
+    let [width, height, depth] = extent.raw_size();
+    let a = luma_plane(original, extent);
+    let b = luma_plane(decoded, extent);
+
+    let mut total = 0.0f64;
+    let mut windows = 0u64;
+
+    for z in 0..depth {
+        for wy in (0..height).step_by(8) {
+            for wx in (0..width).step_by(8) {
+                let mut sum_a = 0.0f64;
+                let mut sum_b = 0.0f64;
+                let mut sum_aa = 0.0f64;
+                let mut sum_bb = 0.0f64;
+                let mut sum_ab = 0.0f64;
+                let mut n = 0.0f64;
+
+                for y in wy..(wy + 8).min(height) {
+                    for x in wx..(wx + 8).min(width) {
+                        let index = x as usize
+                            + y as usize * width as usize
+                            + z as usize * (width * height) as usize;
+
+                        let va = a[index] as f64;
+                        let vb = b[index] as f64;
+
+                        sum_a += va;
+                        sum_b += vb;
+                        sum_aa += va * va;
+                        sum_bb += vb * vb;
+                        sum_ab += va * vb;
+                        n += 1.0;
+                    }
+                }
+
+                let mean_a = sum_a / n;
+                let mean_b = sum_b / n;
+                let var_a = sum_aa / n - mean_a * mean_a;
+                let var_b = sum_bb / n - mean_b * mean_b;
+                let cov = sum_ab / n - mean_a * mean_b;
+
+                let ssim = ((2.0 * mean_a * mean_b + C1) * (2.0 * cov + C2))
+                    / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2));
+
+                total += ssim;
+                windows += 1;
+            }
+        }
+    }
+
+    if windows == 0 {
+        return 1.0;
+    }
+
+    (total / windows as f64) as f32
+}
+
+#[cfg(test)]
+fn solid(color: crate::math::Rgb565) -> bc1::Block {
+    bc1::Block::new(color, color, [[0; 4]; 4])
+}
+
+#[test]
+fn test_psnr_8x4() {
+    use crate::math::Rgb565;
+
+    let extent = Extent::D2 {
+        width: 8,
+        height: 4,
+    };
+
+    let original = [solid(Rgb565::WHITE), solid(Rgb565::WHITE)];
+    let decoded = [solid(Rgb565::WHITE), solid(Rgb565::BLACK)];
+
+    // Half of the pixels differ by 1.0 in every channel.
+    assert_eq!(mse(&original, &decoded, extent), 0.5);
+    assert!((psnr(&original, &decoded, extent) - 3.0103).abs() < 1e-4);
+
+    assert_eq!(psnr(&original, &original, extent), f32::INFINITY);
+}
+
+#[test]
+fn test_psnr_cropped() {
+    use crate::math::Rgb565;
+
+    // Only 2 columns of the second block are inside the image.
+    let extent = Extent::D2 {
+        width: 6,
+        height: 4,
+    };
+
+    let original = [solid(Rgb565::WHITE), solid(Rgb565::WHITE)];
+    let decoded = [solid(Rgb565::WHITE), solid(Rgb565::BLACK)];
+
+    // 8 of 24 pixels differ.
+    assert!((mse(&original, &decoded, extent) - 1.0 / 3.0).abs() < 1e-6);
+    assert!((psnr(&original, &decoded, extent) - 4.7712).abs() < 1e-4);
+}
+
+#[test]
+fn test_psnr_per_channel() {
+    use crate::math::Rgb565;
+
+    let extent = Extent::D2 {
+        width: 8,
+        height: 4,
+    };
+
+    let original = [solid(Rgb565::BLACK), solid(Rgb565::new(31, 0, 0))];
+    let decoded = [solid(Rgb565::BLACK), solid(Rgb565::BLACK)];
+
+    assert_eq!(
+        mse_per_channel(&original, &decoded, extent),
+        [0.5, 0.0, 0.0]
+    );
+
+    let [r, g, b] = psnr_per_channel(&original, &decoded, extent);
+    assert!((r - 3.0103).abs() < 1e-4);
+    assert_eq!(g, f32::INFINITY);
+    assert_eq!(b, f32::INFINITY);
+}
+
+#[test]
+fn test_ssim() {
+    use crate::math::Rgb565;
+
+    let extent = Extent::D2 {
+        width: 8,
+        height: 4,
+    };
+
+    let original = [solid(Rgb565::WHITE), solid(Rgb565::BLACK)];
+    assert!((ssim(&original, &original, extent) - 1.0).abs() < 1e-6);
+
+    // Constant window of luma 1.0 against constant window of luma 0.0:
+    // (2 * 0 * 1 + C1) / (1 + C1) ~= 1e-4.
+    let decoded = [solid(Rgb565::BLACK), solid(Rgb565::BLACK)];
+    let value = ssim(&[solid(Rgb565::WHITE); 2], &decoded, extent);
+    assert!((value - 1e-4 / 1.0001).abs() < 1e-6);
+
+    assert!(ssim(&original, &decoded, extent) < 1.0);
+}
@@ -0,0 +1,280 @@
+//! Hot loops of BC1 endpoint fitting.
+//!
+//! Scalar implementations are used by default.
+//! With `simd` feature enabled, SSE2 implementations are used on x86_64.
+//!
+//! Only iterative refinement of [`cluster_fit::fit`] uses these kernels,
+//! so only [`EncodeEffort::High`] benefits from the feature.
+//! [`cluster_fit::cluster_fit`] used by [`EncodeEffort::Default`] stays scalar.
+//!
+//! [`cluster_fit::fit`]: crate::cluster_fit::fit
+//! [`cluster_fit::cluster_fit`]: crate::cluster_fit::cluster_fit
+//! [`EncodeEffort::High`]: crate::encoder::EncodeEffort::High
+//! [`EncodeEffort::Default`]: crate::encoder::EncodeEffort::Default
+
+use crate::math::Vec3;
+
+/// Sums accumulated for least squares solution of endpoints.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Moments {
+    /// Sum of `(1 - w)^2`.
+    pub a: f32,
+    /// Sum of `(1 - w) * w`.
+    pub b: f32,
+    /// Sum of `w^2`.
+    pub c: f32,
+    /// Sum of `point * (1 - w)`.
+    pub x: Vec3,
+    /// Sum of `point * w`.
+    pub y: Vec3,
+}
+
+/// Finds nearest palette color for each point.
+///
+/// Returns index of nearest color for each point
+/// and sum of squared distances to them.
+/// On ties, lower index wins.
+#[inline]
+pub(crate) fn nearest4(points: &[Vec3; 16], palette: &[Vec3; 4]) -> ([u8; 16], f32) {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    {
+        // SAFETY: SSE2 is always available on x86_64.
+        unsafe { sse2::nearest4(points, palette) }
+    }
+
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    {
+        scalar::nearest4(points, palette)
+    }
+}
+
+/// Accumulates least squares sums for points with interpolation weights.
+#[inline]
+pub(crate) fn least_squares(weights: &[f32; 16], points: &[Vec3; 16]) -> Moments {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    {
+        // SAFETY: SSE2 is always available on x86_64.
+        unsafe { sse2::least_squares(weights, points) }
+    }
+
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    {
+        scalar::least_squares(weights, points)
+    }
+}
+
+// Kept for comparison against SIMD implementations.
+#[cfg_attr(all(feature = "simd", target_arch = "x86_64"), allow(dead_code))]
+mod scalar {
+    use super::Moments;
+    use crate::math::Vec3;
+
+    pub fn nearest4(points: &[Vec3; 16], palette: &[Vec3; 4]) -> ([u8; 16], f32) {
+        let mut indices = [0; 16];
+        let mut sse = 0.0;
+
+        for (index, &point) in indices.iter_mut().zip(points) {
+            let mut best = (0, f32::MAX);
+
+            for (i, &color) in palette.iter().enumerate() {
+                let d = point - color;
+                let e = d.dot(d);
+                if e < best.1 {
+                    best = (i as u8, e);
+                }
+            }
+
+            *index = best.0;
+            sse += best.1;
+        }
+
+        (indices, sse)
+    }
+
+    pub fn least_squares(weights: &[f32; 16], points: &[Vec3; 16]) -> Moments {
+        let mut m = Moments {
+            a: 0.0,
+            b: 0.0,
+            c: 0.0,
+            x: Vec3::ZERO,
+            y: Vec3::ZERO,
+        };
+
+        for (&w, &p) in weights.iter().zip(points) {
+            let u = 1.0 - w;
+
+            m.a += u * u;
+            m.b += u * w;
+            m.c += w * w;
+
+            m.x += p * u;
+            m.y += p * w;
+        }
+
+        m
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod sse2 {
+    use core::arch::x86_64::*;
+
+    use super::Moments;
+    use crate::math::Vec3;
+
+    /// Splits points into separate channel arrays.
+    #[inline(always)]
+    fn transpose(points: &[Vec3; 16]) -> [[f32; 16]; 3] {
+        let mut channels = [[0.0; 16]; 3];
+        for (i, p) in points.iter().enumerate() {
+            channels[0][i] = p.x();
+            channels[1][i] = p.y();
+            channels[2][i] = p.z();
+        }
+        channels
+    }
+
+    #[target_feature(enable = "sse2")]
+    fn load(values: &[f32; 16], chunk: usize) -> __m128 {
+        let v = &values[chunk * 4..chunk * 4 + 4];
+        _mm_setr_ps(v[0], v[1], v[2], v[3])
+    }
+
+    #[target_feature(enable = "sse2")]
+    fn store(v: __m128) -> [f32; 4] {
+        let mut out = [0.0; 4];
+        // SAFETY: `out` has room for 4 floats and unaligned store is used.
+        unsafe { _mm_storeu_ps(out.as_mut_ptr(), v) };
+        out
+    }
+
+    #[target_feature(enable = "sse2")]
+    fn hsum(v: __m128) -> f32 {
+        let [a, b, c, d] = store(v);
+        (a + b) + (c + d)
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub fn nearest4(points: &[Vec3; 16], palette: &[Vec3; 4]) -> ([u8; 16], f32) {
+        let [r, g, b] = transpose(points);
+
+        let mut indices = [0; 16];
+        let mut errors = [0.0f32; 16];
+
+        for chunk in 0..4 {
+            let pr = load(&r, chunk);
+            let pg = load(&g, chunk);
+            let pb = load(&b, chunk);
+
+            let mut best = _mm_set1_ps(f32::MAX);
+            let mut best_index = _mm_setzero_ps();
+
+            for (i, color) in palette.iter().enumerate() {
+                let dr = _mm_sub_ps(pr, _mm_set1_ps(color.x()));
+                let dg = _mm_sub_ps(pg, _mm_set1_ps(color.y()));
+                let db = _mm_sub_ps(pb, _mm_set1_ps(color.z()));
+
+                // Same operation order as `Vec3::dot`.
+                let e = _mm_add_ps(
+                    _mm_add_ps(_mm_mul_ps(dr, dr), _mm_mul_ps(dg, dg)),
+                    _mm_mul_ps(db, db),
+                );
+
+                let closer = _mm_cmplt_ps(e, best);
+                best = _mm_or_ps(_mm_and_ps(closer, e), _mm_andnot_ps(closer, best));
+                best_index = _mm_or_ps(
+                    _mm_and_ps(closer, _mm_set1_ps(i as f32)),
+                    _mm_andnot_ps(closer, best_index),
+                );
+            }
+
+            let best = store(best);
+            let best_index = store(best_index);
+
+            for k in 0..4 {
+                errors[chunk * 4 + k] = best[k];
+                indices[chunk * 4 + k] = best_index[k] as u8;
+            }
+        }
+
+        // Sum in texel order to match scalar implementation exactly.
+        let sse = errors.iter().sum();
+
+        (indices, sse)
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub fn least_squares(weights: &[f32; 16], points: &[Vec3; 16]) -> Moments {
+        let [r, g, b] = transpose(points);
+
+        let one = _mm_set1_ps(1.0);
+
+        let mut a = _mm_setzero_ps();
+        let mut ab = _mm_setzero_ps();
+        let mut c = _mm_setzero_ps();
+        let mut x = [_mm_setzero_ps(); 3];
+        let mut y = [_mm_setzero_ps(); 3];
+
+        for chunk in 0..4 {
+            let w = load(weights, chunk);
+            let u = _mm_sub_ps(one, w);
+
+            a = _mm_add_ps(a, _mm_mul_ps(u, u));
+            ab = _mm_add_ps(ab, _mm_mul_ps(u, w));
+            c = _mm_add_ps(c, _mm_mul_ps(w, w));
+
+            for (channel, values) in [&r, &g, &b].into_iter().enumerate() {
+                let p = load(values, chunk);
+                x[channel] = _mm_add_ps(x[channel], _mm_mul_ps(p, u));
+                y[channel] = _mm_add_ps(y[channel], _mm_mul_ps(p, w));
+            }
+        }
+
+        Moments {
+            a: hsum(a),
+            b: hsum(ab),
+            c: hsum(c),
+            x: Vec3::new(hsum(x[0]), hsum(x[1]), hsum(x[2])),
+            y: Vec3::new(hsum(y[0]), hsum(y[1]), hsum(y[2])),
+        }
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+#[test]
+fn test_simd_matches_scalar() {
+    use rand::{Rng, SeedableRng};
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() <= 1e-4 * a.abs().max(1.0)
+    }
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(7);
+
+    for _ in 0..1000 {
+        let points: [Vec3; 16] =
+            std::array::from_fn(|_| Vec3::new(rng.gen(), rng.gen(), rng.gen()));
+        let palette: [Vec3; 4] =
+            std::array::from_fn(|_| Vec3::new(rng.gen(), rng.gen(), rng.gen()));
+        let weights: [f32; 16] = std::array::from_fn(|_| rng.gen_range(0..4) as f32 / 3.0);
+
+        assert_eq!(
+            scalar::nearest4(&points, &palette),
+            // SAFETY: SSE2 is always available on x86_64.
+            unsafe { sse2::nearest4(&points, &palette) }
+        );
+
+        let s = scalar::least_squares(&weights, &points);
+        // SAFETY: SSE2 is always available on x86_64.
+        let v = unsafe { sse2::least_squares(&weights, &points) };
+
+        assert!(close(s.a, v.a));
+        assert!(close(s.b, v.b));
+        assert!(close(s.c, v.c));
+        for (s, v) in [(s.x, v.x), (s.y, v.y)] {
+            assert!(close(s.x(), v.x()));
+            assert!(close(s.y(), v.y()));
+            assert!(close(s.z(), v.z()));
+        }
+    }
+}
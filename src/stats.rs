@@ -0,0 +1,162 @@
+//! Byte statistics used to estimate compressed sizes.
+
+/// Number of occurrences of each byte value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Histogram256 {
+    counts: [u64; 256],
+    total: u64,
+}
+
+impl Default for Histogram256 {
+    fn default() -> Self {
+        Histogram256::new()
+    }
+}
+
+impl Histogram256 {
+    pub const fn new() -> Self {
+        Histogram256 {
+            counts: [0; 256],
+            total: 0,
+        }
+    }
+
+    /// Returns histogram of the bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut histogram = Histogram256::new();
+        histogram.extend(bytes);
+        histogram
+    }
+
+    #[inline(always)]
+    pub fn push(&mut self, byte: u8) {
+        self.counts[byte as usize] += 1;
+        self.total += 1;
+    }
+
+    pub fn extend(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.push(byte);
+        }
+    }
+
+    /// Adds counts of `other` histogram.
+    pub fn merge(&mut self, other: &Histogram256) {
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+        self.total += other.total;
+    }
+
+    pub fn count(&self, byte: u8) -> u64 {
+        self.counts[byte as usize]
+    }
+
+    /// Returns number of bytes counted.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    /// Returns Shannon entropy in bits per byte.
+    /// Empty histogram has zero entropy.
+    pub fn entropy_bits(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.total_bits() / self.total as f64
+    }
+
+    /// Estimates number of bytes needed to encode counted bytes with order-0 entropy coder.
+    pub fn estimated_size_bytes(&self) -> f64 {
+        self.total_bits() / 8.0
+    }
+
+    /// Returns number of bits needed to encode all counted bytes.
+    fn total_bits(&self) -> f64 {
+        let total = self.total as f64;
+        self.counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let count = count as f64;
+                -count * (count / total).log2()
+            })
+            .sum()
+    }
+}
+
+/// Returns Shannon entropy of the bytes in bits per byte.
+pub fn entropy_of(bytes: &[u8]) -> f64 {
+    Histogram256::from_bytes(bytes).entropy_bits()
+}
+
+/// Returns average number of bits per byte needed to encode `data`
+/// with code built for the `model` distribution.
+///
+/// Never less than entropy of `data`.
+/// Infinite if `data` contains bytes that never occur in `model`.
+pub fn cross_entropy(model: &Histogram256, data: &Histogram256) -> f64 {
+    if data.total == 0 {
+        return 0.0;
+    }
+
+    let model_total = model.total as f64;
+    let bits: f64 = data
+        .counts
+        .iter()
+        .zip(&model.counts)
+        .filter(|&(&count, _)| count > 0)
+        .map(|(&count, &model_count)| {
+            if model_count == 0 {
+                return f64::INFINITY;
+            }
+            -(count as f64) * (model_count as f64 / model_total).log2()
+        })
+        .sum();
+
+    bits / data.total as f64
+}
+
+#[test]
+fn test_known_distributions() {
+    let uniform: Vec<u8> = (0..=255).collect();
+    assert_eq!(entropy_of(&uniform), 8.0);
+    assert_eq!(entropy_of(&uniform.repeat(3)), 8.0);
+
+    assert_eq!(entropy_of(&[42; 100]), 0.0);
+    assert_eq!(entropy_of(&[]), 0.0);
+
+    let two = [1, 2].repeat(50);
+    assert_eq!(entropy_of(&two), 1.0);
+
+    let histogram = Histogram256::from_bytes(&two);
+    assert_eq!(histogram.total(), 100);
+    assert_eq!(histogram.count(1), 50);
+    assert_eq!(histogram.estimated_size_bytes(), 12.5);
+
+    // 1/2, 1/4, 1/4.
+    assert_eq!(entropy_of(&[0, 0, 1, 2]), 1.5);
+}
+
+#[test]
+fn test_merge_and_cross_entropy() {
+    let mut a = Histogram256::from_bytes(&[1, 1, 2]);
+    let b = Histogram256::from_bytes(&[2, 3]);
+    a.merge(&b);
+    assert_eq!(a, Histogram256::from_bytes(&[1, 1, 2, 2, 3]));
+
+    let two = Histogram256::from_bytes(&[1, 2]);
+    assert_eq!(cross_entropy(&two, &two), two.entropy_bits());
+
+    // Coding with mismatched model costs more than entropy.
+    let skewed = Histogram256::from_bytes(&[1, 1, 1, 2]);
+    assert!(cross_entropy(&skewed, &two) > two.entropy_bits());
+    assert_eq!(cross_entropy(&two, &skewed), 1.0);
+
+    assert_eq!(cross_entropy(&two, &b), f64::INFINITY);
+    assert_eq!(cross_entropy(&two, &Histogram256::new()), 0.0);
+}
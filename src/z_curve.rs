@@ -35,72 +35,144 @@ fn test_even_odd_split_squash() {
     );
 }
 
+/// Inverse of [`even_odd_split_squash`].
+pub fn even_odd_spread(even: u16, odd: u16) -> u32 {
+    fn spread(value: u16) -> u32 {
+        let mut v = value as u32;
+        v = (v | (v << 8)) & 0x00FF00FF;
+        v = (v | (v << 4)) & 0x0F0F0F0F;
+        v = (v | (v << 2)) & 0x33333333;
+        v = (v | (v << 1)) & 0x55555555;
+        v
+    }
+
+    spread(even) | (spread(odd) << 1)
+}
+
 /// Iterator that outputs 2D coordinates in z-order.
 pub struct BoundZCurve {
     width: u16,
     height: u16,
-    next_index: u32,
+    next_index: u64,
+    end_index: u64,
 }
 
 impl BoundZCurve {
     pub fn new(width: u16, height: u16) -> Self {
-        BoundZCurve {
+        let mut curve = BoundZCurve {
             width,
             height,
             next_index: 0,
+            end_index: 0,
+        };
+
+        if width > 0 && height > 0 {
+            curve.end_index = 1 << (curve.levels() * 2);
         }
+
+        curve
     }
 }
 
-impl Iterator for BoundZCurve {
-    type Item = (u16, u16);
+impl BoundZCurve {
+    /// Returns number of levels in the quad-tree covering the rectangle.
+    fn levels(&self) -> u32 {
+        let side = self.width.max(self.height) as u32;
+        side.next_power_of_two().trailing_zeros()
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            let index = self.next_index;
+    /// Returns number of cells of the square at the origin with the size inside the rectangle.
+    #[inline(always)]
+    fn cells_in(&self, x: u16, y: u16, size: u32) -> u32 {
+        let w = (self.width as u32).saturating_sub(x as u32).min(size);
+        let h = (self.height as u32).saturating_sub(y as u32).min(size);
+        w * h
+    }
+
+    /// Returns position of the cell in the iteration order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if cell is out of bounds.
+    pub fn index_of(&self, x: u16, y: u16) -> u32 {
+        assert!(
+            x < self.width && y < self.height,
+            "Cell ({x}, {y}) is out of bounds"
+        );
 
-            let (x, y) = even_odd_split_squash(index);
+        // Root square of 16 levels spans all 32 bits, shifts are done in `u64`.
+        let target = even_odd_spread(x, y) as u64;
 
-            let x_out = x >= self.width;
-            let y_out = y >= self.height;
+        // Walk from the root square down to the cell,
+        // counting cells in quadrants that precede the one containing it.
+        let mut count = 0;
+        for level in (0..self.levels()).rev() {
+            let quadrant = (target >> (level * 2)) & 3;
+            let base = target >> ((level + 1) * 2) << ((level + 1) * 2);
 
-            if x_out && y_out {
-                return None;
+            for q in 0..quadrant {
+                let (qx, qy) = even_odd_split_squash((base | (q << (level * 2))) as u32);
+                count += self.cells_in(qx, qy, 1 << level);
             }
+        }
 
-            if x_out {
-                if x > (self.width + 1).next_power_of_two() || self.width == 0 {
-                    return None;
-                }
+        count
+    }
 
-                let tz = x.trailing_zeros();
-                let msb = 1u32 << (tz * 2 + 1);
-                let mask = msb - 1;
-                let until_next = msb - (index & mask);
+    /// Returns cell at the position in the iteration order.
+    /// Inverse of [`BoundZCurve::index_of`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if index is not less than number of cells.
+    pub fn position_at(&self, index: u32) -> (u16, u16) {
+        assert!(
+            (index as u64) < self.width as u64 * self.height as u64,
+            "Index {index} is out of bounds"
+        );
 
-                // Skip until next x < width
-                self.next_index += until_next as u32;
-                continue;
-            }
+        // Descend into the quadrant containing the index.
+        let mut rest = index;
+        let mut base = 0;
+        for level in (0..self.levels()).rev() {
+            for q in 0..4 {
+                let code = base | (q << (level * 2));
+                let (qx, qy) = even_odd_split_squash(code);
+                let cells = self.cells_in(qx, qy, 1 << level);
 
-            if y_out {
-                if y > (self.height + 1).next_power_of_two() || self.height == 0 {
-                    return None;
+                if rest < cells {
+                    base = code;
+                    break;
                 }
+                rest -= cells;
+            }
+        }
 
-                let tz = y.trailing_zeros();
-                let msb = 1u32 << (tz * 2 + 2);
-                let mask = msb - 1;
-                let until_next = msb - (index & mask);
+        debug_assert_eq!(rest, 0);
+        even_odd_split_squash(base)
+    }
+}
 
-                // Skip until next y < height
-                self.next_index += until_next as u32;
-                continue;
+impl Iterator for BoundZCurve {
+    type Item = (u16, u16);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.next_index < self.end_index {
+            let index = self.next_index;
+            let (x, y) = even_odd_split_squash(index as u32);
+
+            if x < self.width && y < self.height {
+                self.next_index += 1;
+                return Some((x, y));
             }
 
-            self.next_index += 1;
-            return Some((x, y));
+            // Cell is the minimal corner of every aligned square that starts at it,
+            // so the largest such square is out of bounds entirely.
+            let level = index.trailing_zeros().min(63) / 2;
+            self.next_index += 1 << (level * 2);
         }
+
+        None
     }
 }
 
@@ -161,3 +233,266 @@ fn test_rect_z_order() {
         ]
     );
 }
+
+#[test]
+fn test_bound_z_curve_random_access() {
+    let sides = [1, 2, 3, 5, 17, 64, 255, 1024];
+
+    for width in sides {
+        for height in sides {
+            let curve = BoundZCurve::new(width, height);
+            let mut count = 0;
+
+            for (i, (x, y)) in BoundZCurve::new(width, height).enumerate() {
+                assert_eq!(curve.index_of(x, y), i as u32, "size {width}x{height}");
+                assert_eq!(curve.position_at(i as u32), (x, y), "size {width}x{height}");
+                count += 1;
+            }
+
+            assert_eq!(count, width as u32 * height as u32, "size {width}x{height}");
+        }
+    }
+
+    for (x, y) in [(0, 0), (1, 0), (0, 1), (12345, 321), (u16::MAX, u16::MAX)] {
+        assert_eq!(even_odd_split_squash(even_odd_spread(x, y)), (x, y));
+    }
+
+    // Sides above 32768 need all 16 levels.
+    for (width, height) in [(40000, 1), (3, 40000), (40000, 40000), (u16::MAX, u16::MAX)] {
+        let curve = BoundZCurve::new(width, height);
+
+        for (i, (x, y)) in BoundZCurve::new(width, height).take(1000).enumerate() {
+            assert_eq!(curve.index_of(x, y), i as u32, "size {width}x{height}");
+            assert_eq!(curve.position_at(i as u32), (x, y), "size {width}x{height}");
+        }
+
+        let last = width as u32 * height as u32 - 1;
+        for (x, y) in [(width - 1, height - 1), (width / 2, height / 3), (39999, 0)] {
+            if x >= width || y >= height {
+                continue;
+            }
+            let index = curve.index_of(x, y);
+            assert!(index <= last, "size {width}x{height}");
+            assert_eq!(curve.position_at(index), (x, y), "size {width}x{height}");
+        }
+    }
+
+    assert_eq!(BoundZCurve::new(40000, 1).index_of(39999, 0), 39999);
+}
+
+/// Spreads bits of the value so that there are two zero bits between each bit.
+#[inline(always)]
+fn spread_by_3(value: u16) -> u64 {
+    let mut v = value as u64;
+    v = (v | (v << 32)) & 0x001F_0000_0000_FFFF;
+    v = (v | (v << 16)) & 0x001F_0000_FF00_00FF;
+    v = (v | (v << 8)) & 0x100F_00F0_0F00_F00F;
+    v = (v | (v << 4)) & 0x10C3_0C30_C30C_30C3;
+    v = (v | (v << 2)) & 0x1249_2492_4924_9249;
+    v
+}
+
+/// Inverse of [`spread_by_3`], ignores other bits.
+#[inline(always)]
+fn squash_by_3(value: u64) -> u16 {
+    let mut v = value & 0x1249_2492_4924_9249;
+    v = (v | (v >> 2)) & 0x10C3_0C30_C30C_30C3;
+    v = (v | (v >> 4)) & 0x100F_00F0_0F00_F00F;
+    v = (v | (v >> 8)) & 0x001F_0000_FF00_00FF;
+    v = (v | (v >> 16)) & 0x001F_0000_0000_FFFF;
+    v = (v | (v >> 32)) & 0xFFFF;
+    v as u16
+}
+
+/// Interleaves bits of 3D coordinates, `x` in lowest bit.
+pub fn morton3(x: u16, y: u16, z: u16) -> u64 {
+    spread_by_3(x) | (spread_by_3(y) << 1) | (spread_by_3(z) << 2)
+}
+
+/// Inverse of [`morton3`].
+pub fn morton3_split(index: u64) -> (u16, u16, u16) {
+    (
+        squash_by_3(index),
+        squash_by_3(index >> 1),
+        squash_by_3(index >> 2),
+    )
+}
+
+#[test]
+fn test_morton3() {
+    assert_eq!(morton3(1, 0, 0), 0b001);
+    assert_eq!(morton3(0, 1, 0), 0b010);
+    assert_eq!(morton3(0, 0, 1), 0b100);
+    assert_eq!(morton3(3, 2, 1), 0b011_101);
+    assert_eq!(morton3(u16::MAX, u16::MAX, u16::MAX), (1 << 48) - 1);
+
+    for (x, y, z) in [(0, 0, 0), (1, 2, 3), (255, 17, 1024), (u16::MAX, 0, 12345)] {
+        assert_eq!(morton3_split(morton3(x, y, z)), (x, y, z));
+    }
+}
+
+/// Iterator that outputs 3D coordinates in z-order,
+/// restricted to the bounding box.
+pub struct BoundZCurve3 {
+    width: u16,
+    height: u16,
+    depth: u16,
+    next_index: u64,
+    end_index: u64,
+}
+
+impl BoundZCurve3 {
+    pub fn new(width: u16, height: u16, depth: u16) -> Self {
+        let side = width.max(height).max(depth) as u64;
+        let levels = side.next_power_of_two().trailing_zeros();
+
+        let end_index = if width == 0 || height == 0 || depth == 0 {
+            0
+        } else {
+            1 << (levels * 3)
+        };
+
+        BoundZCurve3 {
+            width,
+            height,
+            depth,
+            next_index: 0,
+            end_index,
+        }
+    }
+
+    #[inline(always)]
+    fn contains(&self, x: u16, y: u16, z: u16) -> bool {
+        x < self.width && y < self.height && z < self.depth
+    }
+
+    /// Returns number of cells in the box.
+    pub fn len(&self) -> u64 {
+        self.width as u64 * self.height as u64 * self.depth as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns position of the cell in the iteration order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if cell is out of bounds.
+    pub fn index_of(&self, x: u16, y: u16, z: u16) -> u64 {
+        assert!(
+            self.contains(x, y, z),
+            "Cell ({x}, {y}, {z}) is out of bounds"
+        );
+
+        let target = morton3(x, y, z);
+        let levels = self.end_index.trailing_zeros() / 3;
+
+        // Walk from the root cube down to the cell,
+        // counting cells in octants that precede the one containing it.
+        let mut count = 0;
+        for level in (0..levels).rev() {
+            let size = 1u32 << level;
+            let octant = (target >> (level * 3)) & 7;
+            let base = target >> ((level + 1) * 3) << ((level + 1) * 3);
+
+            for o in 0..octant {
+                let (ox, oy, oz) = morton3_split(base | (o << (level * 3)));
+                count += clip(ox, self.width, size)
+                    * clip(oy, self.height, size)
+                    * clip(oz, self.depth, size);
+            }
+        }
+
+        count
+    }
+}
+
+/// Length of range `origin..origin + size` that is below `bound`.
+#[inline(always)]
+fn clip(origin: u16, bound: u16, size: u32) -> u64 {
+    (bound as u32).saturating_sub(origin as u32).min(size) as u64
+}
+
+impl Iterator for BoundZCurve3 {
+    type Item = (u16, u16, u16);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.next_index < self.end_index {
+            let index = self.next_index;
+            let (x, y, z) = morton3_split(index);
+
+            if self.contains(x, y, z) {
+                self.next_index += 1;
+                return Some((x, y, z));
+            }
+
+            // Cell is the minimal corner of every aligned cube that starts at it,
+            // so the largest such cube is out of bounds entirely.
+            let level = index.trailing_zeros().min(63) / 3;
+            self.next_index += 1 << (level * 3);
+        }
+
+        None
+    }
+}
+
+#[test]
+fn test_bound_z_curve3() {
+    let sizes = [
+        (1, 1, 1),
+        (2, 2, 2),
+        (3, 1, 1),
+        (1, 5, 1),
+        (1, 1, 7),
+        (3, 5, 7),
+        (8, 3, 2),
+        (17, 4, 9),
+        (2, 33, 3),
+        (0, 4, 4),
+        (4, 0, 4),
+        (4, 4, 0),
+    ];
+
+    for (width, height, depth) in sizes {
+        let curve = BoundZCurve3::new(width, height, depth);
+        let len = curve.len();
+        let cells: Vec<_> = curve.collect();
+
+        assert_eq!(cells.len() as u64, len, "size {width}x{height}x{depth}");
+
+        let mut seen = vec![false; len as usize];
+        let curve = BoundZCurve3::new(width, height, depth);
+
+        for (i, &(x, y, z)) in cells.iter().enumerate() {
+            assert!(x < width && y < height && z < depth);
+
+            let flat = x as usize + (y as usize + z as usize * height as usize) * width as usize;
+            assert!(!seen[flat], "cell ({x}, {y}, {z}) visited twice");
+            seen[flat] = true;
+
+            assert_eq!(curve.index_of(x, y, z), i as u64);
+        }
+
+        // Order is z-order.
+        assert!(cells
+            .windows(2)
+            .all(|w| morton3(w[0].0, w[0].1, w[0].2) < morton3(w[1].0, w[1].1, w[1].2)));
+    }
+
+    let cells: Vec<_> = BoundZCurve3::new(2, 2, 2).collect();
+    assert_eq!(
+        cells,
+        [
+            (0, 0, 0),
+            (1, 0, 0),
+            (0, 1, 0),
+            (1, 1, 0),
+            (0, 0, 1),
+            (1, 0, 1),
+            (0, 1, 1),
+            (1, 1, 1)
+        ]
+    );
+}
@@ -0,0 +1,126 @@
+//! Public API reachable through the prelude.
+//!
+//! Breaking this test means breaking users, change it only deliberately.
+
+use std::io::Cursor;
+
+use jkl::prelude::*;
+
+fn gradient(width: u32, height: u32) -> Image<Rgb8U> {
+    Image::from_fn(width, height, |x, y| {
+        Rgb8U::new((x * 255 / width) as u8, (y * 255 / height) as u8, 128)
+    })
+}
+
+#[test]
+fn encode_and_decode() {
+    let image = gradient(32, 16);
+
+    let mut stream = Vec::new();
+    let stats: EncodeStats = Encoder::new(Format::BC1)
+        .effort(EncodeEffort::Fast)
+        .checksums(true)
+        .encode_image(&image, Cursor::new(&mut stream))
+        .unwrap();
+    assert_eq!(stats.blocks_count, 8 * 4);
+
+    let header: JackalHeader = jkl::jackal::read_header(&stream[..]).unwrap();
+    assert_eq!(header.format(), Format::BC1);
+    assert_eq!(header.levels(), MipLevels(1));
+
+    let (extent, blocks): (Extent, Vec<Bc1Block>) =
+        decompress_bc1_texture(Cursor::new(&stream)).unwrap();
+    assert_eq!(
+        extent,
+        Extent::D2 {
+            width: 8,
+            height: 4
+        }
+    );
+
+    let (width, height, pixels) = decode_to_rgb8(Cursor::new(&stream)).unwrap();
+    assert_eq!((width, height), (32, 16));
+    assert_eq!(pixels.len(), image.pixels().len());
+
+    let options = CompressOptions {
+        super_block_size: SuperBlockChoice::Fixed(SuperBlockSize {
+            width: 4,
+            height: 4,
+        }),
+        predictor: Predictor::Previous,
+        meta: TextureMeta::default(),
+        ..CompressOptions::default()
+    };
+    let mut recompressed = Vec::new();
+    compress_bc1_texture_opts(extent, &blocks, options, Cursor::new(&mut recompressed)).unwrap();
+
+    let decompress_options = DecompressOptions {
+        limits: DecodeLimits::default(),
+        verify: true,
+    };
+    let (_, decoded) =
+        decompress_bc1_texture_opts(Cursor::new(&recompressed), &decompress_options).unwrap();
+    assert_eq!(decoded, blocks);
+
+    let mut default = Vec::new();
+    compress_bc1_texture(extent, &blocks, Cursor::new(&mut default)).unwrap();
+}
+
+#[test]
+fn block_textures() {
+    fn roundtrip<B: AnyBlock>(blocks: Vec<B>) {
+        let extent = Extent::D1 {
+            width: blocks.len() as u32,
+        };
+        let texture = BlockTexture::new(extent, blocks).unwrap();
+
+        let mut stream = Vec::new();
+        compress_block_texture(
+            &texture,
+            CompressOptions::default(),
+            Cursor::new(&mut stream),
+        )
+        .unwrap();
+
+        let decoded: BlockTexture<B> =
+            decompress_block_texture(Cursor::new(&stream), &DecodeLimits::default()).unwrap();
+        assert_eq!(decoded.extent(), extent);
+    }
+
+    let white = [[Rgba32F::new(1.0, 1.0, 1.0, 1.0); 4]; 4];
+
+    roundtrip(vec![
+        Bc1Block::new(Rgb565::WHITE, Rgb565::BLACK, [[0; 4]; 4]);
+        3
+    ]);
+    roundtrip(vec![Bc2Block::encode_with_alpha(white); 2]);
+    roundtrip(vec![Bc3Block::encode_with_alpha(white); 2]);
+    roundtrip(vec![
+        Bc4Block::encode([[jkl::math::R32F::new(0.5); 4]; 4]);
+        2
+    ]);
+    roundtrip(vec![
+        Bc5Block::encode(
+            [[jkl::math::Rg32F::new(0.5, 0.25); 4]; 4]
+        );
+        2
+    ]);
+
+    let _ = Rgb32F::new(0.0, 0.0, 0.0);
+    let _ = Rgba8U::new(0, 0, 0, 0);
+}
+
+#[test]
+fn errors() {
+    let err: DecompressError = decompress_bc1_texture(Cursor::new(b"JKL0")).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::InvalidMagic));
+
+    let err: EncodeError = Encoder::new(Format::BC1)
+        .encode_rgb8(0, 0, &[], Cursor::new(Vec::new()))
+        .unwrap_err();
+    assert!(matches!(err, EncodeError::InvalidExtent));
+
+    // Crate root re-exports.
+    let _: Option<jkl::Format> = None;
+    let _: Option<jkl::JackalHeader> = None;
+}
@@ -0,0 +1,303 @@
+//! Golden files pin exact bytes of Jackal streams.
+//!
+//! Intentional format changes must bump container version
+//! and regenerate fixtures by running tests with `JKL_REGENERATE_GOLDENS=1`.
+//! Fixtures of previous versions are kept in subdirectories and must still decode.
+
+use std::{io::Cursor, path::PathBuf};
+
+use jkl::{
+    bc1, bc2,
+    encoder::{EncodeEffort, Encoder},
+    image::Image,
+    jackal::{
+        self, BlockTexture, CompressOptions, ContainerVersion, Format, SuperBlockChoice,
+        SuperBlockSize,
+    },
+    math::{Rgb565, Rgb8U},
+    Extent,
+};
+
+/// Deterministic pseudo-random sequence independent of external crates.
+fn xorshift(state: &mut u32) -> u32 {
+    *state ^= *state << 13;
+    *state ^= *state >> 17;
+    *state ^= *state << 5;
+    *state
+}
+
+/// Blocks with smooth colors and noisy indices.
+fn fixture_blocks(width: u32, height: u32, seed: u32) -> Vec<bc1::Block> {
+    let mut state = seed;
+    let mut blocks = Vec::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            let r = (x * 31 / width.max(1)) as u8;
+            let g = (y * 63 / height.max(1)) as u8;
+            let word = xorshift(&mut state);
+            let indices = std::array::from_fn(|y| {
+                std::array::from_fn(|x| ((word >> (2 * (x + 4 * y))) & 0b11) as u8)
+            });
+            blocks.push(bc1::Block::new(
+                Rgb565::new(r, g, 31 - r),
+                Rgb565::new(r / 2, g / 2, 0),
+                indices,
+            ));
+        }
+    }
+
+    blocks
+}
+
+fn check_golden(name: &str, bytes: &[u8]) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+
+    if std::env::var_os("JKL_REGENERATE_GOLDENS").is_some() {
+        std::fs::write(&path, bytes).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read(&path)
+        .unwrap_or_else(|err| panic!("failed to read golden file {}: {err}", path.display()));
+    assert!(
+        expected == bytes,
+        "output differs from golden file {}, bump container version and regenerate",
+        path.display()
+    );
+}
+
+fn compress(extent: Extent, blocks: &[bc1::Block], options: CompressOptions) -> Vec<u8> {
+    let mut output = Vec::new();
+    jackal::compress_bc1_texture_opts(extent, blocks, options, Cursor::new(&mut output)).unwrap();
+    output
+}
+
+#[test]
+fn golden_default() {
+    let extent = Extent::D2 {
+        width: 8,
+        height: 4,
+    };
+    let blocks = fixture_blocks(8, 4, 1);
+    let bytes = compress(extent, &blocks, CompressOptions::default());
+    check_golden("default.jkl", &bytes);
+
+    let (decoded_extent, decoded) = jackal::decompress_bc1_texture(Cursor::new(&bytes)).unwrap();
+    assert_eq!(decoded_extent, extent);
+    assert_eq!(decoded, blocks);
+}
+
+#[test]
+fn golden_interleaved_without_checksums() {
+    let extent = Extent::D2 {
+        width: 5,
+        height: 7,
+    };
+    let blocks = fixture_blocks(5, 7, 2);
+    let options = CompressOptions {
+        checksums: false,
+        interleaved_colors: true,
+        ..CompressOptions::default()
+    };
+    check_golden("interleaved.jkl", &compress(extent, &blocks, options));
+}
+
+#[test]
+fn golden_many_super_blocks() {
+    let extent = Extent::D2Array {
+        width: 20,
+        height: 9,
+        layers: 2,
+    };
+    let blocks = fixture_blocks(20, 18, 3);
+    let options = CompressOptions {
+        super_block_size: SuperBlockChoice::Fixed(SuperBlockSize {
+            width: 16,
+            height: 4,
+        }),
+        ..CompressOptions::default()
+    };
+    check_golden("super_blocks.jkl", &compress(extent, &blocks, options));
+}
+
+#[test]
+fn golden_bc2_interleaved() {
+    let extent = Extent::D2 {
+        width: 6,
+        height: 5,
+    };
+    let mut state = 4;
+    let blocks = fixture_blocks(6, 5, 4)
+        .into_iter()
+        .map(|block| {
+            let mut alpha = [0; 8];
+            alpha[..4].copy_from_slice(&xorshift(&mut state).to_le_bytes());
+            alpha[4..].copy_from_slice(&xorshift(&mut state).to_le_bytes());
+            bc2::Block {
+                alpha,
+                color0: block.color0(),
+                color1: block.color1(),
+                texels: block.indices_word().to_le_bytes(),
+            }
+        })
+        .collect();
+    let texture = BlockTexture::new(extent, blocks).unwrap();
+    let options = CompressOptions {
+        interleaved_colors: true,
+        ..CompressOptions::default()
+    };
+
+    let mut bytes = Vec::new();
+    jackal::compress_block_texture(&texture, options, Cursor::new(&mut bytes)).unwrap();
+    check_golden("bc2_interleaved.jkl", &bytes);
+
+    let decoded =
+        jackal::decompress_block_texture::<bc2::Block>(Cursor::new(&bytes), &Default::default())
+            .unwrap();
+    assert_eq!(decoded, texture);
+}
+
+#[test]
+fn encoder_is_deterministic() {
+    let image = Image::from_fn(37, 21, |x, y| {
+        Rgb8U::new((x * 7) as u8, (y * 12) as u8, ((x ^ y) * 5) as u8)
+    });
+
+    let encode = |parallel: bool| {
+        let mut output = Vec::new();
+        Encoder::new(Format::BC1)
+            .effort(EncodeEffort::High)
+            .dither(true)
+            .parallel(parallel)
+            .encode_image(&image, Cursor::new(&mut output))
+            .unwrap();
+        output
+    };
+
+    let first = encode(false);
+    assert_eq!(encode(false), first);
+    assert_eq!(encode(true), first);
+}
+
+#[test]
+fn previous_versions_decode() {
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+
+    let cases = [
+        (
+            "default.jkl",
+            Extent::D2 {
+                width: 8,
+                height: 4,
+            },
+            fixture_blocks(8, 4, 1),
+        ),
+        (
+            "interleaved.jkl",
+            Extent::D2 {
+                width: 5,
+                height: 7,
+            },
+            fixture_blocks(5, 7, 2),
+        ),
+        (
+            "super_blocks.jkl",
+            Extent::D2Array {
+                width: 20,
+                height: 9,
+                layers: 2,
+            },
+            fixture_blocks(20, 18, 3),
+        ),
+    ];
+
+    for (dir, version) in [
+        ("v2", ContainerVersion::V2),
+        ("v3", ContainerVersion::V3),
+        ("v4", ContainerVersion::V4),
+        ("v5", ContainerVersion::V5),
+        ("v6", ContainerVersion::V6),
+        ("v7", ContainerVersion::V7),
+        ("v8", ContainerVersion::V8),
+    ] {
+        for (name, extent, blocks) in &cases {
+            let bytes = std::fs::read(fixtures.join(dir).join(name)).unwrap();
+            assert_eq!(jackal::sniff(Cursor::new(&bytes)).unwrap(), version);
+
+            let (decoded_extent, decoded) =
+                jackal::decompress_bc1_texture(Cursor::new(&bytes)).unwrap();
+            assert_eq!(decoded_extent, *extent, "{dir}/{name}");
+            assert_eq!(decoded, *blocks, "{dir}/{name}");
+        }
+    }
+}
+
+#[test]
+fn slice_decode_matches_stream_decode() {
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+
+    for dir in ["", "v2", "v3", "v4", "v5", "v6", "v7", "v8"] {
+        for name in ["default.jkl", "interleaved.jkl", "super_blocks.jkl"] {
+            let bytes = std::fs::read(fixtures.join(dir).join(name)).unwrap();
+
+            let from_stream = jackal::decompress_bc1_texture(Cursor::new(&bytes)).unwrap();
+            let from_slice = jackal::decompress_bc1_texture_from_slice(&bytes).unwrap();
+            assert_eq!(from_slice, from_stream, "{dir}/{name}");
+        }
+    }
+}
+
+#[test]
+fn truncated_stream_decodes_partially() {
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let bytes = std::fs::read(fixtures.join("super_blocks.jkl")).unwrap();
+    let (extent, expected) = jackal::decompress_bc1_texture(Cursor::new(&bytes)).unwrap();
+
+    let gray = bc1::Block::new(
+        Rgb565::new(16, 32, 16),
+        Rgb565::new(16, 32, 16),
+        [[0; 4]; 4],
+    );
+
+    let header_size = jackal::read_header(&bytes[..]).unwrap().bytes_size();
+    let mut coverage = 0;
+    for len in (header_size..=bytes.len()).step_by(97).chain([bytes.len()]) {
+        let (decoded_extent, decoded, statuses) =
+            jackal::decompress_bc1_texture_partial(Cursor::new(&bytes[..len]), gray).unwrap();
+        assert_eq!(decoded_extent, extent);
+
+        for (block, expected) in decoded.iter().zip(&expected) {
+            assert!(block == expected || *block == gray, "truncated at {len}");
+        }
+
+        // Longer prefix never decodes less.
+        let decoded_count = decoded
+            .iter()
+            .zip(&expected)
+            .filter(|(a, b)| a == b)
+            .count();
+        assert!(decoded_count >= coverage, "truncated at {len}");
+        coverage = decoded_count;
+
+        // Payloads follow the table in order, so only a tail of super-blocks is missing.
+        let ok = statuses
+            .iter()
+            .take_while(|&&status| status == jackal::SuperBlockStatus::Ok)
+            .count();
+        assert!(
+            statuses[ok..]
+                .iter()
+                .all(|&status| status == jackal::SuperBlockStatus::IoError),
+            "truncated at {len}: {statuses:?}"
+        );
+    }
+
+    assert_eq!(coverage, expected.len());
+
+    let err = jackal::decompress_bc1_texture_partial(Cursor::new(&bytes[..header_size - 1]), gray)
+        .unwrap_err();
+    assert_eq!(err.stage(), Some(jackal::DecodeStage::Header));
+}
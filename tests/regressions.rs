@@ -0,0 +1,129 @@
+//! Inputs that used to crash decoders.
+//!
+//! Every file in `tests/fixtures/regressions` must be rejected with an error, never a panic.
+//! `.dds` and `.ktx2` files are passed to the matching importer, others to the decoder.
+//! Crashes found by fuzz targets in `fuzz/` are added here once fixed.
+
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use jkl::{
+    jackal::{self, DecodeLimits},
+    ktx2, DecodeError,
+};
+
+fn regression(name: &str) -> Vec<u8> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/regressions")
+        .join(name);
+    std::fs::read(&path).unwrap_or_else(|err| panic!("failed to read {}: {err}", path.display()))
+}
+
+/// Runs importer of the container matching extension of `path` on the input.
+fn import(path: &Path, bytes: &[u8]) -> Option<DecodeError> {
+    let limits = DecodeLimits {
+        max_blocks: 1 << 16,
+        max_super_blocks: 1 << 12,
+    };
+
+    let err = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("dds") => jackal::import_dds_with_limits(bytes, &limits).err(),
+        Some("ktx2") => ktx2::import_ktx2_with_limits(bytes, &limits).err(),
+        _ => return decode_all(bytes),
+    };
+    err.and_then(|err| err.decode_error())
+}
+
+/// Runs all decode entry points on the input.
+fn decode_all(bytes: &[u8]) -> Option<DecodeError> {
+    if let Ok(header) = jackal::read_header(bytes) {
+        let mut read = Cursor::new(bytes);
+        read.set_position(header.bytes_size() as u64);
+        let _ = jackal::read_jackal_blocks_vec(&header, read);
+    }
+
+    let _ = jackal::stat_file(Cursor::new(bytes));
+
+    let limits = DecodeLimits {
+        max_blocks: 1 << 16,
+        max_super_blocks: 1 << 12,
+    };
+    jackal::decompress_bc1_texture_with_limits(Cursor::new(bytes), &limits)
+        .err()
+        .and_then(|err| err.decode_error())
+}
+
+#[test]
+fn all_regressions_are_rejected() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/regressions");
+
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(
+            import(&path, &bytes).is_some(),
+            "{} is not rejected",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn payload_offset_overflow() {
+    // Offset + size overflowed `u64` when checking payload bounds.
+    let bytes = regression("offset_overflow.jkl");
+
+    let err = jackal::stat_file(Cursor::new(&bytes)).unwrap_err();
+    assert!(matches!(
+        err.decode_error(),
+        Some(DecodeError::InvalidData { .. })
+    ));
+
+    assert!(matches!(
+        decode_all(&bytes),
+        Some(DecodeError::InvalidData { .. })
+    ));
+}
+
+#[test]
+fn huge_payload_size() {
+    // Buffer of the size from the table was allocated before reading the payload.
+    let bytes = regression("huge_payload_size.jkl");
+
+    assert_eq!(
+        decode_all(&bytes),
+        Some(DecodeError::InvalidData {
+            super_block: Some([0, 0, 0]),
+            aspect: None,
+        })
+    );
+}
+
+#[test]
+fn dds_huge_extent() {
+    // Buffer for blocks of the extent from the header was allocated before reading them.
+    let bytes = regression("huge_extent.dds");
+
+    let err = jackal::import_dds(&bytes[..]).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::InvalidExtent));
+}
+
+#[test]
+fn ktx2_huge_extent() {
+    // Level size overflowed `usize` for extent from the header.
+    let bytes = regression("huge_extent.ktx2");
+
+    let err = ktx2::import_ktx2(&bytes[..]).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::InvalidExtent));
+}
+
+#[test]
+fn ktx2_level_offset_overflow() {
+    // Offset + length of the level overflowed when slicing level data.
+    let bytes = regression("level_offset_overflow.ktx2");
+
+    let err = ktx2::import_ktx2(&bytes[..]).unwrap_err();
+    assert_eq!(err.decode_error(), Some(DecodeError::INVALID_DATA));
+}